@@ -0,0 +1,45 @@
+use divan::{AllocProfiler, Bencher};
+use rodio::buffer::SamplesBuffer;
+use rodio::mixer::mixer;
+use rodio::Sink;
+
+#[global_allocator]
+static ALLOC: AllocProfiler = AllocProfiler::system();
+
+fn main() {
+    divan::main();
+}
+
+/// One second of output from a sink playing a source that needs resampling. The allocations
+/// counted here should not grow with the number of spans played.
+#[divan::bench]
+fn sink_playback(bencher: Bencher) {
+    bencher
+        .with_inputs(|| {
+            let (controller, output) = mixer::<f32>(2, 48_000);
+            let sink = Sink::connect_new(&controller);
+            let samples = vec![0.5f32; 2 * 44_100];
+            sink.append(SamplesBuffer::new(2, 44_100, samples));
+            (sink, output)
+        })
+        .bench_values(|(sink, output)| {
+            output.take(2 * 48_000).for_each(divan::black_box_drop);
+            sink
+        })
+}
+
+/// One second of output from an idle sink, which plays filler silence.
+#[divan::bench]
+fn idle_sink(bencher: Bencher) {
+    bencher
+        .with_inputs(|| {
+            let (controller, output) = mixer::<f32>(2, 48_000);
+            let sink = Sink::connect_new(&controller);
+            sink.append(SamplesBuffer::new(2, 48_000, vec![0.0f32; 2]));
+            (sink, output)
+        })
+        .bench_values(|(sink, output)| {
+            output.take(2 * 48_000).for_each(divan::black_box_drop);
+            sink
+        })
+}
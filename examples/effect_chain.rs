@@ -0,0 +1,43 @@
+//! Builds a chain of effects chosen at runtime. For example:
+//! `cargo run --example effect_chain -- low_pass reverb speed`
+
+use rodio::source::{BoxedSource, SineWave, Source};
+use std::error::Error;
+use std::time::Duration;
+
+fn apply(source: BoxedSource, effect: &str) -> BoxedSource {
+    match effect {
+        "low_pass" => source.low_pass(800).boxed(),
+        "high_pass" => source.high_pass(2000).boxed(),
+        "reverb" => source
+            .buffered()
+            .reverb(Duration::from_millis(40), 0.7)
+            .boxed(),
+        "speed" => source.speed(1.5).boxed(),
+        "fade_in" => source.fade_in(Duration::from_secs(1)).boxed(),
+        "quiet" => source.amplify(0.3).boxed(),
+        other => {
+            eprintln!("unknown effect `{other}`, skipping it");
+            source
+        }
+    }
+}
+
+fn main() -> Result<(), Box<dyn Error>> {
+    let stream_handle = rodio::OutputStreamBuilder::open_default_stream()?;
+    let sink = rodio::Sink::connect_new(&stream_handle.mixer());
+
+    let source = SineWave::new(440.0)
+        .take_duration(Duration::from_secs(3))
+        .amplify(0.2)
+        .boxed();
+    let source = std::env::args().skip(1).fold(source, |source, effect| {
+        println!("adding {effect}");
+        apply(source, &effect)
+    });
+
+    sink.append(source);
+    sink.sleep_until_end();
+
+    Ok(())
+}
@@ -0,0 +1,17 @@
+use rodio::source::{SineWave, Source};
+use std::error::Error;
+use std::time::Duration;
+
+fn main() -> Result<(), Box<dyn Error>> {
+    // The sink keeps the output stream open, no other binding is needed.
+    let sink = rodio::Player::open_default()?.sink();
+
+    let source = SineWave::new(440.0)
+        .take_duration(Duration::from_secs(1))
+        .amplify(0.20);
+    sink.append(source);
+
+    sink.sleep_until_end();
+
+    Ok(())
+}
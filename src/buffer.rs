@@ -23,6 +23,7 @@ pub struct SamplesBuffer<S> {
     channels: ChannelCount,
     sample_rate: SampleRate,
     duration: Duration,
+    looping: bool,
 }
 
 impl<S> SamplesBuffer<S>
@@ -60,8 +61,40 @@ where
             channels,
             sample_rate,
             duration,
+            looping: false,
         }
     }
+
+    /// Plays the samples over and over again instead of ending after the last one.
+    ///
+    /// The end of the buffer is directly followed by its start, so make sure it contains a
+    /// whole number of frames and that the sound ends where it begins to avoid clicks.
+    #[inline]
+    pub fn loop_buffer(mut self) -> SamplesBuffer<S> {
+        self.looping = true;
+        self
+    }
+
+    /// Jumps to the sample at `pos`.
+    ///
+    /// The position is rounded down to the nearest sample. Channels stay in order: if the
+    /// last sample returned was for the first channel, the next one is for the second channel
+    /// at the new position. Positions past the end saturate at the end, unless the buffer
+    /// loops in which case they wrap around.
+    pub fn seek(&mut self, pos: Duration) {
+        let channels = self.channels as usize;
+        let frames = self.data.len() / channels;
+        let curr_channel = self.pos % channels;
+
+        let frame = pos.as_nanos() * self.sample_rate as u128 / 1_000_000_000;
+        let frame = if self.looping && frames > 0 {
+            (frame % frames as u128) as usize
+        } else {
+            frame.min(frames as u128) as usize
+        };
+
+        self.pos = frame * channels + curr_channel;
+    }
 }
 
 impl<S> Source for SamplesBuffer<S>
@@ -83,29 +116,34 @@ where
         self.sample_rate
     }
 
+    /// Reports `None` if the buffer loops.
     #[inline]
     fn total_duration(&self) -> Option<Duration> {
-        Some(self.duration)
+        if self.looping {
+            None
+        } else {
+            Some(self.duration)
+        }
+    }
+
+    /// Reports `None` if the buffer loops.
+    #[inline]
+    fn total_samples(&self) -> Option<u64> {
+        if self.looping {
+            None
+        } else {
+            Some(self.data.len() as u64)
+        }
     }
 
     // this is fast because all the samples are in memory already
     // and due to the constant sample_rate we can jump to the right
     // sample directly
     //
-    /// This jumps in memory till the sample for `pos`.
+    /// This jumps in memory till the sample for `pos`, see [`SamplesBuffer::seek`].
     #[inline]
     fn try_seek(&mut self, pos: Duration) -> Result<(), SeekError> {
-        let curr_channel = self.pos % self.channels() as usize;
-        let new_pos = pos.as_secs_f32() * self.sample_rate() as f32 * self.channels() as f32;
-        // saturate pos at the end of the source
-        let new_pos = new_pos as usize;
-        let new_pos = new_pos.min(self.data.len());
-
-        // make sure the next sample is for the right channel
-        let new_pos = new_pos.next_multiple_of(self.channels() as usize);
-        let new_pos = new_pos - curr_channel;
-
-        self.pos = new_pos;
+        self.seek(pos);
         Ok(())
     }
 }
@@ -118,6 +156,9 @@ where
 
     #[inline]
     fn next(&mut self) -> Option<S> {
+        if self.looping && self.pos >= self.data.len() {
+            self.pos = 0;
+        }
         let sample = self.data.get(self.pos)?;
         self.pos += 1;
         Some(*sample)
@@ -125,7 +166,12 @@ where
 
     #[inline]
     fn size_hint(&self) -> (usize, Option<usize>) {
-        (self.data.len(), Some(self.data.len()))
+        if self.looping && !self.data.is_empty() {
+            (usize::MAX, None)
+        } else {
+            let remaining = self.data.len().saturating_sub(self.pos);
+            (remaining, Some(remaining))
+        }
     }
 }
 
@@ -134,6 +180,22 @@ mod tests {
     use crate::buffer::SamplesBuffer;
     use crate::source::Source;
 
+    #[test]
+    fn collect_samples_round_trip() {
+        use crate::source::{chirp, SineWave};
+        use std::time::Duration;
+
+        let sweep = || chirp(8000, 100.0, 1000.0, Duration::from_millis(50));
+        let expected: Vec<i16> = sweep().convert_samples().collect();
+        let buffer = sweep().collect_samples::<i16>().unwrap();
+        assert_eq!(buffer.channels(), 1);
+        assert_eq!(buffer.sample_rate(), 8000);
+        assert_eq!(buffer.total_duration(), Some(Duration::from_millis(50)));
+        assert_eq!(buffer.collect::<Vec<_>>(), expected);
+
+        assert!(SineWave::new(440.0).collect_samples::<f32>().is_err());
+    }
+
     #[test]
     fn basic() {
         let _ = SamplesBuffer::new(1, 44100, vec![0i16, 0, 0, 0, 0, 0]);
@@ -159,6 +221,15 @@ mod tests {
         assert_eq!(dur.subsec_nanos(), 500_000_000);
     }
 
+    #[test]
+    fn total_samples_matches_data() {
+        // 7 samples at 3 Hz do not have a duration of a whole number of nanoseconds
+        let buf = SamplesBuffer::new(1, 3, vec![0i16; 7]);
+        assert_eq!(buf.total_samples(), Some(7));
+        assert_eq!(buf.clone().count(), 7);
+        assert_eq!(buf.loop_buffer().total_samples(), None);
+    }
+
     #[test]
     fn iteration() {
         let mut buf = SamplesBuffer::new(1, 44100, vec![1i16, 2, 3, 4, 5, 6]);
@@ -198,5 +269,31 @@ mod tests {
             buf.try_seek(Duration::from_secs(6)).unwrap();
             assert!(buf.next().is_some_and(|s| s % 2 == 1),);
         }
+
+        #[test]
+        fn next_sample_matches_index() {
+            let data: Vec<i16> = (0..1000).collect();
+            let mut buf = SamplesBuffer::new(2, 100, data.clone());
+
+            buf.seek(Duration::from_millis(1230));
+            assert_eq!(buf.next(), Some(data[246]));
+
+            // past the end saturates
+            buf.seek(Duration::from_secs(60));
+            assert_eq!(buf.next(), None);
+        }
+
+        #[test]
+        fn looping_wraps_around() {
+            let mut buf = SamplesBuffer::new(1, 10, vec![0i16, 1, 2, 3, 4]).loop_buffer();
+            assert_eq!(buf.total_duration(), None);
+
+            let looped: Vec<i16> = buf.by_ref().take(12).collect();
+            assert_eq!(looped, [0, 1, 2, 3, 4, 0, 1, 2, 3, 4, 0, 1]);
+
+            // 1.3 seconds is 13 samples, which is 3 samples into the third loop
+            buf.seek(Duration::from_millis(1300));
+            assert_eq!(buf.next(), Some(3));
+        }
     }
 }
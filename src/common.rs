@@ -3,3 +3,13 @@ pub type SampleRate = u32;
 
 /// Number of channels in a stream.
 pub type ChannelCount = u16;
+
+/// Returns the shortest duration that covers `frames` frames at `sample_rate`.
+///
+/// Converting the result back to frames, rounding either down or to the nearest frame, gives
+/// exactly `frames` again.
+pub(crate) fn frames_to_duration(frames: u64, sample_rate: SampleRate) -> std::time::Duration {
+    let sample_rate = sample_rate.max(1) as u128;
+    let nanos = (frames as u128 * 1_000_000_000).div_ceil(sample_rate);
+    std::time::Duration::from_nanos(nanos as u64)
+}
@@ -0,0 +1,140 @@
+/// Dithering applied when the output stream uses an integer sample format.
+///
+/// Without dithering the samples are truncated, which turns the quantization error into
+/// distortion that is correlated with the signal and audible on quiet passages. Dithering
+/// adds a small amount of noise before quantizing so that the error becomes a constant, much
+/// less objectionable noise floor.
+///
+/// Only 8 and 16 bit formats are dithered, the error of wider formats is far below what can
+/// be heard.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum DitherMode {
+    /// Truncate the samples, the output is bit-identical to a plain conversion.
+    #[default]
+    None,
+    /// Triangular probability density function dither of ±1 LSB.
+    Tpdf,
+    /// TPDF dither with first order noise shaping, which moves the noise towards high
+    /// frequencies where the ear is less sensitive to it.
+    TpdfShaped,
+}
+
+/// Applies a [`DitherMode`] to a stream of interleaved samples.
+#[derive(Clone, Debug)]
+pub(crate) struct Ditherer {
+    mode: DitherMode,
+    // Number of quantization steps per unit of amplitude.
+    scale: f32,
+    rng: u32,
+    // Quantization error of the previous sample of each channel, used for noise shaping.
+    errors: Vec<f32>,
+    channel: usize,
+}
+
+impl Ditherer {
+    /// `bits` is the width of the integer output format. Formats wider than 16 bits are
+    /// never dithered.
+    pub(crate) fn new(mode: DitherMode, bits: u32, channels: usize) -> Ditherer {
+        let mode = if bits > 16 { DitherMode::None } else { mode };
+        Ditherer {
+            mode,
+            scale: (1u32 << bits.clamp(1, 16).saturating_sub(1)) as f32,
+            rng: 0x9e37_79b9,
+            errors: vec![0.0; channels.max(1)],
+            channel: 0,
+        }
+    }
+
+    /// Returns the sample to convert to the output format in place of `sample`.
+    ///
+    /// The result is a multiple of the quantization step, so the truncating conversion to
+    /// the output format does not change it any further.
+    #[inline]
+    pub(crate) fn process(&mut self, sample: f32) -> f32 {
+        if self.mode == DitherMode::None {
+            return sample;
+        }
+
+        let channel = self.channel;
+        self.channel = (self.channel + 1) % self.errors.len();
+
+        let value = sample * self.scale - self.errors[channel];
+        let noise = self.uniform() + self.uniform() - 1.0;
+        let quantized = (value + noise).round().clamp(-self.scale, self.scale - 1.0);
+        if self.mode == DitherMode::TpdfShaped {
+            self.errors[channel] = quantized - value;
+        }
+        quantized / self.scale
+    }
+
+    /// Uniformly distributed value in `[0, 1)` from a xorshift generator.
+    #[inline]
+    fn uniform(&mut self) -> f32 {
+        self.rng ^= self.rng << 13;
+        self.rng ^= self.rng >> 17;
+        self.rng ^= self.rng << 5;
+        (self.rng >> 8) as f32 / (1u32 << 24) as f32
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{DitherMode, Ditherer};
+    use dasp_sample::Sample;
+
+    /// Converts a quiet signal to i16 and returns the result.
+    fn render(mode: DitherMode) -> Vec<i16> {
+        let mut ditherer = Ditherer::new(mode, 16, 1);
+        (0..48_000)
+            .map(|i| {
+                // sine wave with an amplitude of 0.8 LSB
+                let sample = 0.8 / 32768.0 * (i as f32 * 0.05).sin();
+                ditherer.process(sample).to_sample::<i16>()
+            })
+            .collect()
+    }
+
+    fn rms(samples: &[i16]) -> f32 {
+        let sum: f32 = samples.iter().map(|&s| (s as f32).powi(2)).sum();
+        (sum / samples.len() as f32).sqrt()
+    }
+
+    #[test]
+    fn disabled_is_bit_identical() {
+        let mut ditherer = Ditherer::new(DitherMode::None, 16, 2);
+        for i in 0..1000 {
+            let sample = (i as f32 * 0.013).sin() * 0.7;
+            assert_eq!(
+                ditherer.process(sample).to_sample::<i16>(),
+                sample.to_sample::<i16>()
+            );
+        }
+        // the quiet signal is truncated away entirely
+        assert!(render(DitherMode::None).iter().all(|&s| s == 0));
+    }
+
+    #[test]
+    fn tpdf_adds_noise_floor() {
+        let output = render(DitherMode::Tpdf);
+        // the signal (0.57 LSB rms) plus TPDF dither and rounding (0.5 LSB rms)
+        let rms = rms(&output);
+        assert!((0.6..0.9).contains(&rms), "{rms}");
+        assert!(output.iter().all(|s| s.abs() <= 2));
+    }
+
+    #[test]
+    fn noise_shaping_moves_noise_up() {
+        // the difference between neighbouring samples emphasizes high frequencies
+        let high = |samples: &[i16]| {
+            let diff: Vec<i16> = samples.windows(2).map(|w| w[1] - w[0]).collect();
+            rms(&diff) / rms(samples)
+        };
+        assert!(high(&render(DitherMode::TpdfShaped)) > high(&render(DitherMode::Tpdf)));
+    }
+
+    #[test]
+    fn wide_formats_are_not_dithered() {
+        let mut ditherer = Ditherer::new(DitherMode::Tpdf, 32, 1);
+        assert_eq!(ditherer.process(0.123), 0.123);
+    }
+}
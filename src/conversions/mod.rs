@@ -6,10 +6,23 @@ This includes conversion between sample formats, channels or sample rates.
 */
 
 pub use self::channels::ChannelCountConverter;
+#[cfg(feature = "playback")]
+pub use self::dither::DitherMode;
+#[cfg(feature = "playback")]
+pub(crate) use self::dither::Ditherer;
+pub(crate) use self::pool::BufferPool;
+pub(crate) use self::resampler::{from_f32, Custom, Resampling};
+pub use self::resampler::{Interpolation, Resampler};
 pub use self::sample::DataConverter;
 pub use self::sample::Sample;
 pub use self::sample_rate::SampleRateConverter;
+pub use self::slice::{convert_samples_slice, remap_channels_slice, resample_slice};
 
 mod channels;
+#[cfg(feature = "playback")]
+mod dither;
+mod pool;
+mod resampler;
 mod sample;
 mod sample_rate;
+mod slice;
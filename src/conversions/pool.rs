@@ -0,0 +1,54 @@
+/// Maximum number of buffers kept around, the rest is freed.
+const MAX_BUFFERS: usize = 8;
+
+/// Recycles sample buffers so steady state playback does not allocate.
+///
+/// Buffers keep their capacity but are always empty when taken from the pool.
+#[derive(Clone, Debug)]
+pub(crate) struct BufferPool<T> {
+    buffers: Vec<Vec<T>>,
+}
+
+impl<T> Default for BufferPool<T> {
+    fn default() -> Self {
+        BufferPool {
+            buffers: Vec::new(),
+        }
+    }
+}
+
+impl<T> BufferPool<T> {
+    /// Returns an empty buffer, reusing a recycled one if there is any.
+    #[inline]
+    pub(crate) fn take(&mut self) -> Vec<T> {
+        self.buffers.pop().unwrap_or_default()
+    }
+
+    /// Returns a buffer to the pool. Its content is dropped right away.
+    #[inline]
+    pub(crate) fn recycle(&mut self, mut buffer: Vec<T>) {
+        buffer.clear();
+        if buffer.capacity() > 0 && self.buffers.len() < MAX_BUFFERS {
+            self.buffers.push(buffer);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::BufferPool;
+
+    #[test]
+    fn recycled_buffers_are_cleared() {
+        let mut pool = BufferPool::default();
+        let mut buffer = pool.take();
+        buffer.extend([1.0f32, 2.0, 3.0]);
+        let capacity = buffer.capacity();
+        pool.recycle(buffer);
+
+        let buffer = pool.take();
+        assert!(buffer.is_empty());
+        assert_eq!(buffer.capacity(), capacity);
+        assert!(pool.take().is_empty());
+    }
+}
@@ -0,0 +1,407 @@
+use dasp_sample::{FromSample, Sample as DaspSample};
+
+use crate::common::{ChannelCount, SampleRate};
+use crate::conversions::{BufferPool, Sample, SampleRateConverter};
+
+/// A sample rate converter that can be plugged into a
+/// [`UniformSourceIterator`](crate::source::UniformSourceIterator) with
+/// [`with_resampler`](crate::source::UniformSourceIterator::with_resampler), for example to
+/// wrap a higher quality converter from another crate.
+///
+/// The resampler produces one output frame at a time and pulls as many input frames as it
+/// needs for that, about `to / from` output frames per input frame. Samples are converted to
+/// `f32` on the way in and back on the way out.
+pub trait Resampler: Send {
+    /// Prepares the conversion of a span from `from` to `to` Hz with `channels` channels.
+    /// Called before the first frame of every span, spans can follow each other in the same
+    /// format, in which case the history may be kept.
+    fn reset(&mut self, from: SampleRate, to: SampleRate, channels: ChannelCount);
+
+    /// Writes the next output frame to `output`, which holds a sample per channel, and returns
+    /// true. Returns false once the span is done.
+    ///
+    /// `input` fills the frame passed to it with the next input frame, it returns false if
+    /// the span has ended and then leaves the frame untouched.
+    fn next_frame(&mut self, input: &mut dyn FnMut(&mut [f32]) -> bool, output: &mut [f32])
+        -> bool;
+
+    /// Returns a copy of this resampler in the same state, used when the source it converts
+    /// is cloned. Returns `None` by default, in which case cloning the source panics.
+    fn try_clone(&self) -> Option<Box<dyn Resampler>> {
+        None
+    }
+
+    /// Number of input frames that were read ahead of the last output frame, reported as
+    /// part of [`Source::latency`](crate::Source::latency). Zero by default.
+    fn latency(&self) -> usize {
+        0
+    }
+}
+
+/// How samples between two input samples are computed when a signal is played at another
+/// speed, see [`Speed::with_interpolation`](crate::source::Speed::with_interpolation). Later
+/// variants sound cleaner but take more work.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Interpolation {
+    /// Repeats or skips samples, the cheapest and the harshest.
+    Nearest,
+    /// Draws a straight line between two samples, like the built-in sample rate conversion.
+    Linear,
+    /// Fits a curve through four samples (Catmull-Rom). A good middle ground.
+    #[default]
+    Cubic,
+    /// A windowed sinc over 16 samples, which also filters out the frequencies that would
+    /// alias when speeding up.
+    Sinc,
+}
+
+/// Zero crossings of the sinc on either side of the interpolated position.
+const SINC_HALF_TAPS: usize = 8;
+
+impl Interpolation {
+    /// Input frames the interpolation looks at around a position.
+    #[inline]
+    pub(crate) fn taps(self) -> usize {
+        match self {
+            Interpolation::Nearest | Interpolation::Linear => 2,
+            Interpolation::Cubic => 4,
+            Interpolation::Sinc => 2 * SINC_HALF_TAPS,
+        }
+    }
+
+    /// Fills `weights` with the weight of every tap for a position `fraction` past tap
+    /// `taps() / 2 - 1`. `cutoff` is the highest frequency to keep as a share of the Nyquist
+    /// frequency, it is only used by the sinc.
+    pub(crate) fn weights(self, fraction: f32, cutoff: f32, weights: &mut [f32]) {
+        let t = fraction;
+        match self {
+            Interpolation::Nearest => {
+                weights[0] = if t < 0.5 { 1.0 } else { 0.0 };
+                weights[1] = 1.0 - weights[0];
+            }
+            Interpolation::Linear => {
+                weights[0] = 1.0 - t;
+                weights[1] = t;
+            }
+            Interpolation::Cubic => {
+                let (t2, t3) = (t * t, t * t * t);
+                weights[0] = (-t3 + 2.0 * t2 - t) / 2.0;
+                weights[1] = (3.0 * t3 - 5.0 * t2 + 2.0) / 2.0;
+                weights[2] = (-3.0 * t3 + 4.0 * t2 + t) / 2.0;
+                weights[3] = (t3 - t2) / 2.0;
+            }
+            Interpolation::Sinc => {
+                let half = SINC_HALF_TAPS as f32;
+                let mut sum = 0.0;
+                for (tap, weight) in weights.iter_mut().enumerate() {
+                    let x = tap as f32 - (half - 1.0) - t;
+                    // Lanczos window
+                    *weight = cutoff * sinc(cutoff * x) * sinc(x / half);
+                    sum += *weight;
+                }
+                // Keeps the gain at 1 for a constant signal.
+                for weight in weights.iter_mut() {
+                    *weight /= sum;
+                }
+            }
+        }
+    }
+}
+
+#[inline]
+fn sinc(x: f32) -> f32 {
+    if x.abs() < 1e-6 {
+        1.0
+    } else {
+        let x = std::f32::consts::PI * x;
+        x.sin() / x
+    }
+}
+
+/// Converts the sample rate of `input`, with the [`SampleRateConverter`] unless a
+/// [`Resampler`] was given.
+pub(crate) enum Resampling<I>
+where
+    I: Iterator,
+{
+    Linear(SampleRateConverter<I>),
+    Custom(I, Custom),
+}
+
+/// A [`Resampler`] and the output frame it is writing to, kept from one span to the next.
+pub(crate) struct Custom {
+    resampler: Box<dyn Resampler>,
+    // Format of the span, to scale the size hint.
+    from: SampleRate,
+    to: SampleRate,
+    // The output frame being returned and the position of the next sample in it.
+    frame: Vec<f32>,
+    pos: usize,
+}
+
+impl Custom {
+    pub(crate) fn new(resampler: Box<dyn Resampler>) -> Custom {
+        Custom {
+            resampler,
+            from: 1,
+            to: 1,
+            frame: Vec::new(),
+            pos: 0,
+        }
+    }
+
+    fn reset(&mut self, from: SampleRate, to: SampleRate, channels: ChannelCount) {
+        self.resampler.reset(from, to, channels);
+        self.from = from;
+        self.to = to;
+        self.frame.clear();
+        self.frame.resize(channels.max(1) as usize, 0.0);
+        self.pos = self.frame.len();
+    }
+}
+
+impl Clone for Custom {
+    fn clone(&self) -> Self {
+        Custom {
+            resampler: self.resampler.try_clone().expect(
+                "the resampler of this source can not be cloned, see `Resampler::try_clone`",
+            ),
+            from: self.from,
+            to: self.to,
+            frame: self.frame.clone(),
+            pos: self.pos,
+        }
+    }
+}
+
+impl<I> Clone for Resampling<I>
+where
+    I: Iterator + Clone,
+    I::Item: Sample,
+{
+    fn clone(&self) -> Self {
+        match self {
+            Resampling::Linear(converter) => Resampling::Linear(converter.clone()),
+            Resampling::Custom(input, custom) => Resampling::Custom(input.clone(), custom.clone()),
+        }
+    }
+}
+
+impl<I> Resampling<I>
+where
+    I: Iterator,
+    I::Item: Sample,
+{
+    /// Takes the buffers of the [`SampleRateConverter`] from `pool`, see
+    /// [`recycle`](Resampling::recycle) to give them back. `custom` is used instead if given.
+    pub(crate) fn with_pool(
+        input: I,
+        from: SampleRate,
+        to: SampleRate,
+        channels: ChannelCount,
+        custom: Option<Custom>,
+        pool: &mut BufferPool<I::Item>,
+    ) -> Resampling<I> {
+        match custom {
+            None => Resampling::Linear(SampleRateConverter::with_pool(
+                input, from, to, channels, pool,
+            )),
+            Some(mut custom) => {
+                custom.reset(from, to, channels);
+                Resampling::Custom(input, custom)
+            }
+        }
+    }
+
+    #[inline]
+    pub(crate) fn inner(&self) -> &I {
+        match self {
+            Resampling::Linear(converter) => converter.inner(),
+            Resampling::Custom(input, _) => input,
+        }
+    }
+
+    #[inline]
+    pub(crate) fn inner_mut(&mut self) -> &mut I {
+        match self {
+            Resampling::Linear(converter) => converter.inner_mut(),
+            Resampling::Custom(input, _) => input,
+        }
+    }
+
+    #[inline]
+    pub(crate) fn latency(&self) -> usize {
+        match self {
+            Resampling::Linear(converter) => converter.latency(),
+            Resampling::Custom(_, custom) => custom.resampler.latency(),
+        }
+    }
+
+    /// Gives the buffers back to `pool` and returns the input and the custom resampler, to
+    /// convert the next span with.
+    pub(crate) fn recycle(self, pool: &mut BufferPool<I::Item>) -> (I, Option<Custom>) {
+        match self {
+            Resampling::Linear(converter) => (converter.recycle(pool), None),
+            Resampling::Custom(input, custom) => (input, Some(custom)),
+        }
+    }
+}
+
+impl<I> Iterator for Resampling<I>
+where
+    I: Iterator,
+    I::Item: Sample,
+{
+    type Item = I::Item;
+
+    #[inline]
+    fn next(&mut self) -> Option<I::Item> {
+        let (input, custom) = match self {
+            Resampling::Linear(converter) => return converter.next(),
+            Resampling::Custom(input, custom) => (input, custom),
+        };
+        if custom.pos >= custom.frame.len() {
+            // An incomplete frame at the end is dropped.
+            let mut read = |frame: &mut [f32]| {
+                for sample in frame.iter_mut() {
+                    match input.next() {
+                        Some(value) => *sample = value.to_f32(),
+                        None => return false,
+                    }
+                }
+                true
+            };
+            if !custom.resampler.next_frame(&mut read, &mut custom.frame) {
+                return None;
+            }
+            custom.pos = 0;
+        }
+        let sample = custom.frame[custom.pos];
+        custom.pos += 1;
+        Some(from_f32(sample))
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let (input, custom) = match self {
+            Resampling::Linear(converter) => return converter.size_hint(),
+            Resampling::Custom(input, custom) => (input, custom),
+        };
+        // Whole input frames scaled by the rate ratio, after the rest of the current frame.
+        // Frames the resampler holds on to are not known, so there is no upper bound.
+        let channels = custom.frame.len();
+        let frames =
+            (input.size_hint().0 / channels) as u64 * custom.to as u64 / custom.from as u64;
+        let samples = usize::try_from(frames)
+            .ok()
+            .and_then(|frames| frames.checked_mul(channels))
+            .and_then(|samples| samples.checked_add(channels - custom.pos));
+        (samples.unwrap_or(usize::MAX), None)
+    }
+}
+
+// Converts back to the sample type of the source.
+#[inline]
+pub(crate) fn from_f32<S: Sample>(value: f32) -> S {
+    let float = <S as DaspSample>::Float::from_sample_(value);
+    float.to_sample()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Custom, Resampler, Resampling};
+    use crate::common::{ChannelCount, SampleRate};
+    use crate::conversions::{BufferPool, SampleRateConverter};
+
+    // Repeats every input frame `to / from` times.
+    #[derive(Default)]
+    struct Repeat {
+        times: u32,
+        left: u32,
+        frame: Vec<f32>,
+    }
+
+    impl Resampler for Repeat {
+        fn reset(&mut self, from: SampleRate, to: SampleRate, channels: ChannelCount) {
+            self.times = to / from;
+            self.left = 0;
+            self.frame = vec![0.0; channels as usize];
+        }
+
+        fn next_frame(
+            &mut self,
+            input: &mut dyn FnMut(&mut [f32]) -> bool,
+            output: &mut [f32],
+        ) -> bool {
+            if self.left == 0 {
+                if !input(&mut self.frame) {
+                    return false;
+                }
+                self.left = self.times;
+            }
+            self.left -= 1;
+            output.copy_from_slice(&self.frame);
+            true
+        }
+    }
+
+    fn repeat() -> Option<Custom> {
+        Some(Custom::new(Box::new(Repeat::default())))
+    }
+
+    #[test]
+    fn default_is_the_converter() {
+        let input: Vec<f32> = (0..300).map(|i| (i as f32 * 0.37).sin()).collect();
+        for (from, to, channels) in [(44_100, 48_000, 2), (48_000, 8000, 1), (8000, 8000, 3)] {
+            let expected: Vec<f32> =
+                SampleRateConverter::new(input.clone().into_iter(), from, to, channels).collect();
+            let mut pool = BufferPool::default();
+            let output = Resampling::with_pool(
+                input.clone().into_iter(),
+                from,
+                to,
+                channels,
+                None,
+                &mut pool,
+            );
+            assert!(matches!(output, Resampling::Linear(_)));
+            assert_eq!(output.collect::<Vec<_>>(), expected, "{from} to {to}");
+        }
+    }
+
+    #[test]
+    fn incomplete_last_frame_is_kept_without_resampler() {
+        let mut pool = BufferPool::default();
+        let output: Vec<u16> =
+            Resampling::with_pool(vec![1u16, 2, 3].into_iter(), 1000, 1000, 2, None, &mut pool)
+                .collect();
+        assert_eq!(output, [1, 2, 3]);
+    }
+
+    #[test]
+    fn custom_size_hint_is_scaled() {
+        let mut pool = BufferPool::default();
+        let mut output = Resampling::with_pool(
+            vec![0.5f32; 12].into_iter(),
+            1000,
+            3000,
+            2,
+            repeat(),
+            &mut pool,
+        );
+        assert_eq!(output.size_hint(), (36, None));
+        output.next();
+        // the second and third copy of the first frame are held by the resampler
+        assert_eq!(output.size_hint(), (31, None));
+        assert_eq!(output.count(), 35);
+    }
+
+    #[test]
+    #[should_panic(expected = "can not be cloned")]
+    fn cloning_a_resampler_without_try_clone_panics() {
+        let mut pool = BufferPool::default();
+        let output =
+            Resampling::with_pool(vec![0.5f32; 4].into_iter(), 1, 2, 1, repeat(), &mut pool);
+        let _ = output.clone();
+    }
+}
@@ -24,6 +24,12 @@ impl<I, O> DataConverter<I, O> {
         self.input
     }
 
+    /// Get access to the iterator
+    #[inline]
+    pub fn inner(&self) -> &I {
+        &self.input
+    }
+
     /// get mutable access to the iterator
     #[inline]
     pub fn inner_mut(&mut self) -> &mut I {
@@ -1,4 +1,4 @@
-use crate::conversions::Sample;
+use crate::conversions::{BufferPool, Sample};
 
 use crate::common::{ChannelCount, SampleRate};
 use num_rational::Ratio;
@@ -36,7 +36,19 @@ where
     I: Iterator,
     I::Item: Sample,
 {
-    /// Create new sample rate converter.
+    /// Same as [`with_pool`](SampleRateConverter::with_pool) without reusing any buffers.
+    #[inline]
+    pub fn new(
+        input: I,
+        from: SampleRate,
+        to: SampleRate,
+        num_channels: ChannelCount,
+    ) -> SampleRateConverter<I> {
+        Self::with_pool(input, from, to, num_channels, &mut BufferPool::default())
+    }
+
+    /// Create new sample rate converter, taking its internal buffers from `pool`. See
+    /// [`recycle`](SampleRateConverter::recycle) to give them back.
     ///
     /// The converter uses simple linear interpolation for up-sampling
     /// and discards samples for down-sampling. This may introduce audible
@@ -49,31 +61,26 @@ where
     ///
     /// # Panic
     /// Panics if `from`, `to` or `num_channels` are 0.
-    #[inline]
-    pub fn new(
+    pub(crate) fn with_pool(
         mut input: I,
         from: SampleRate,
         to: SampleRate,
         num_channels: ChannelCount,
+        pool: &mut BufferPool<I::Item>,
     ) -> SampleRateConverter<I> {
         assert!(num_channels >= 1);
         assert!(from >= 1);
         assert!(to >= 1);
 
-        let (first_samples, next_samples) = if from == to {
-            // if `from` == `to` == 1, then we just pass through
-            (Vec::new(), Vec::new())
-        } else {
-            let first = input
-                .by_ref()
-                .take(num_channels as usize)
-                .collect::<Vec<_>>();
-            let next = input
-                .by_ref()
-                .take(num_channels as usize)
-                .collect::<Vec<_>>();
-            (first, next)
-        };
+        let mut first_samples = pool.take();
+        let mut next_samples = pool.take();
+        let mut output_buffer = pool.take();
+        // if `from` == `to` == 1, then we just pass through
+        if from != to {
+            first_samples.extend(input.by_ref().take(num_channels as usize));
+            next_samples.extend(input.by_ref().take(num_channels as usize));
+            output_buffer.reserve(num_channels as usize - 1);
+        }
 
         // Reducing numerator to avoid numeric overflows during interpolation.
         let (to, from) = Ratio::new(to, from).into_raw();
@@ -87,16 +94,40 @@ where
             next_output_span_pos_in_chunk: 0,
             current_span: first_samples,
             next_span: next_samples,
-            output_buffer: Vec::with_capacity(num_channels as usize - 1),
+            output_buffer,
         }
     }
 
-    /// Destroys this iterator and returns the underlying iterator.
-    #[inline]
-    pub fn into_inner(self) -> I {
+    /// Destroys this iterator, returns its buffers to `pool` and the underlying iterator.
+    pub(crate) fn recycle(self, pool: &mut BufferPool<I::Item>) -> I {
+        pool.recycle(self.current_span);
+        pool.recycle(self.next_span);
+        pool.recycle(self.output_buffer);
         self.input
     }
 
+    /// Number of input frames read ahead of the output.
+    ///
+    /// Every output frame is interpolated between the current input frame and the one after
+    /// it, so that next frame has already been read while converting. Nothing is read ahead
+    /// when the rates are the same or once the input has ended. The output is aligned on the
+    /// input and the last input frame is held until the output has caught up with the end of
+    /// the input, so rendering `n` input frames yields `ceil(n * to / from)` output frames.
+    #[inline]
+    pub fn latency(&self) -> usize {
+        if self.from == self.to {
+            0
+        } else {
+            self.next_span.len() / self.channels as usize
+        }
+    }
+
+    /// Get access to the iterator
+    #[inline]
+    pub fn inner(&self) -> &I {
+        &self.input
+    }
+
     /// get mutable access to the iterator
     #[inline]
     pub fn inner_mut(&mut self) -> &mut I {
@@ -166,14 +197,16 @@ where
         // Merging `self.current_span` and `self.next_span` into `self.output_buffer`.
         // Note that `self.output_buffer` can be truncated if there is not enough data in
         // `self.next_span`.
+        // Once the input has ended the last frame is held, so that the output lasts exactly
+        // as long as the input instead of stopping at the last input frame.
         let mut result = None;
         let numerator = (self.from * self.next_output_span_pos_in_chunk) % self.to;
-        for (off, (cur, next)) in self
-            .current_span
-            .iter()
-            .zip(self.next_span.iter())
-            .enumerate()
-        {
+        let next_span = if self.next_span.is_empty() {
+            &self.current_span
+        } else {
+            &self.next_span
+        };
+        for (off, (cur, next)) in self.current_span.iter().zip(next_span.iter()).enumerate() {
             let sample = Sample::lerp(*cur, *next, numerator, self.to);
 
             if off == 0 {
@@ -186,19 +219,7 @@ where
         // Incrementing the counter for the next iteration.
         self.next_output_span_pos_in_chunk += 1;
 
-        if result.is_some() {
-            result
-        } else {
-            // draining `self.current_span`
-            if !self.current_span.is_empty() {
-                let r = Some(self.current_span.remove(0));
-                mem::swap(&mut self.output_buffer, &mut self.current_span);
-                self.current_span.clear();
-                r
-            } else {
-                None
-            }
-        }
+        result
     }
 
     #[inline]
@@ -250,6 +271,7 @@ where
 mod test {
     use super::SampleRateConverter;
     use crate::common::{ChannelCount, SampleRate};
+    use crate::conversions::BufferPool;
     use core::time::Duration;
     use quickcheck::{quickcheck, TestResult};
 
@@ -339,16 +361,17 @@ mod test {
                          .step_by(k as usize).collect::<Vec<_>>().concat())
         }
 
-        #[ignore]
         /// Check that resampling does not change the audio duration,
         ///  except by a negligible amount (± 1ms).  Reproduces #316.
-        /// Ignored, pending a bug fix.
-        fn preserve_durations(d: Duration, freq: f32, to: SampleRate) -> TestResult {
-            if to == 0 { return TestResult::discard(); }
-
+        fn preserve_durations(millis: u16, to: u16) -> TestResult {
             use crate::source::{SineWave, Source};
 
-            let source = SineWave::new(freq).take_duration(d);
+            // Up to two seconds, at rates high enough for one output frame to be shorter than
+            // the tolerance and low enough for the interpolation not to overflow.
+            let d = Duration::from_millis(millis as u64 % 2000);
+            let to = 2000 + to as SampleRate % 62_000;
+
+            let source = SineWave::new(440.0).take_duration(d);
             let from = source.sample_rate();
 
             let resampled =
@@ -377,7 +400,9 @@ mod test {
         let output = SampleRateConverter::new(input.into_iter(), 1000, 7000, 1);
         let size_estimation = output.len();
         let output = output.collect::<Vec<_>>();
-        assert_eq!(output, [1, 2, 4, 6, 8, 10, 12, 14]);
+        // Two input frames last 2 ms, which is 14 frames at 7 kHz. The last input frame is
+        // held for its whole duration as there is nothing after it to interpolate towards.
+        assert_eq!(output, [1, 2, 4, 6, 8, 10, 12, 14, 14, 14, 14, 14, 14, 14]);
         assert!((size_estimation as f32 / output.len() as f32).abs() < 2.0);
     }
 
@@ -390,4 +415,39 @@ mod test {
         assert_eq!(output, [0, 5, 10, 15]);
         assert!((size_estimation as f32 / output.len() as f32).abs() < 2.0);
     }
+
+    #[test]
+    fn output_length_matches_input_duration() {
+        for (from, to) in [
+            (44100, 48000),
+            (48000, 44100),
+            (8000, 44100),
+            (22050, 16000),
+        ] {
+            for frames in [1u32, 2, 999, 1000, 4410] {
+                let input = vec![0.5f32; frames as usize * 2];
+                let output = SampleRateConverter::new(input.into_iter(), from, to, 2);
+                assert_eq!(output.latency(), usize::from(frames > 1));
+
+                let expected = (frames as u64 * to as u64).div_ceil(from as u64) as usize * 2;
+                assert_eq!(output.count(), expected, "{frames} frames {from} -> {to}");
+            }
+        }
+    }
+
+    #[test]
+    fn reused_buffers_start_empty() {
+        let mut pool = BufferPool::default();
+        let mut first =
+            SampleRateConverter::with_pool(vec![7u16; 9].into_iter(), 1, 3, 3, &mut pool);
+        // stop half way so that samples are left in every buffer
+        let _ = first.by_ref().take(4).count();
+        let _ = first.recycle(&mut pool);
+
+        let input = vec![2u16, 16, 4, 18, 6, 20, 8, 22];
+        let output = SampleRateConverter::with_pool(input.clone().into_iter(), 1, 2, 2, &mut pool)
+            .collect::<Vec<_>>();
+        let expected = SampleRateConverter::new(input.into_iter(), 1, 2, 2).collect::<Vec<_>>();
+        assert_eq!(output, expected);
+    }
 }
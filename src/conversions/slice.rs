@@ -0,0 +1,90 @@
+use dasp_sample::FromSample;
+
+use crate::common::{ChannelCount, SampleRate};
+use crate::conversions::{ChannelCountConverter, DataConverter, Sample, SampleRateConverter};
+
+/// Converts a buffer of samples to another sample type, the same way
+/// [`Source::convert_samples`](crate::Source::convert_samples) does.
+///
+/// # Example
+///
+/// ```
+/// let converted: Vec<i16> = rodio::convert_samples_slice(&[0.0f32, 0.5, -1.0]);
+/// assert_eq!(converted, [0, 16384, i16::MIN]);
+/// ```
+pub fn convert_samples_slice<I, O>(input: &[I]) -> Vec<O>
+where
+    I: Sample,
+    O: FromSample<I> + Sample,
+{
+    DataConverter::new(input.iter().copied()).collect()
+}
+
+/// Converts a buffer of interleaved samples with `channels` channels from the sample rate
+/// `from` to `to`, the same way sources are converted for playback.
+///
+/// Up-sampling interpolates linearly between samples and down-sampling drops samples, which
+/// may introduce audible distortions. The result has `to / from` times as many frames as the
+/// input, rounded to whole frames.
+///
+/// # Panics
+///
+/// If `from`, `to` or `channels` is zero.
+pub fn resample_slice(
+    input: &[f32],
+    channels: ChannelCount,
+    from: SampleRate,
+    to: SampleRate,
+) -> Vec<f32> {
+    SampleRateConverter::new(input.iter().copied(), from, to, channels).collect()
+}
+
+/// Converts a buffer of interleaved samples from `from` channels to `to` channels, the same
+/// way sources are converted for playback.
+///
+/// A mono input is copied to the first two channels. Otherwise channels that the input does
+/// not have are silent and channels that the output does not have are dropped. An incomplete
+/// frame at the end of the input is dropped.
+///
+/// # Panics
+///
+/// If `from` or `to` is zero.
+pub fn remap_channels_slice(input: &[f32], from: ChannelCount, to: ChannelCount) -> Vec<f32> {
+    assert!(from >= 1);
+    let whole_frames = input.len() - input.len() % from as usize;
+    ChannelCountConverter::new(input[..whole_frames].iter().copied(), from, to).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{convert_samples_slice, remap_channels_slice, resample_slice};
+
+    #[test]
+    fn converts_sample_type() {
+        let converted: Vec<u16> = convert_samples_slice(&[0i16, i16::MIN, i16::MAX]);
+        assert_eq!(converted, [32768, 0, u16::MAX]);
+    }
+
+    #[test]
+    fn resamples() {
+        // stereo, doubling the rate interpolates every other frame
+        let input = [0.0, 1.0, 0.5, -1.0, 1.0, 0.0];
+        let output = resample_slice(&input, 2, 1000, 2000);
+        assert_eq!(output[..8], [0.0, 1.0, 0.25, 0.0, 0.5, -1.0, 0.75, -0.5]);
+        assert_eq!(resample_slice(&[1.0; 8], 1, 44_100, 44_100), [1.0; 8]);
+        assert_eq!(resample_slice(&[1.0; 8], 1, 2000, 1000).len(), 4);
+    }
+
+    #[test]
+    fn remaps_channels() {
+        assert_eq!(
+            remap_channels_slice(&[1.0, 2.0], 1, 2),
+            [1.0, 1.0, 2.0, 2.0]
+        );
+        assert_eq!(
+            remap_channels_slice(&[1.0, 2.0, 3.0, 4.0, 5.0, 6.0, 7.0], 3, 2),
+            [1.0, 2.0, 4.0, 5.0]
+        );
+        assert_eq!(remap_channels_slice(&[1.0, 2.0], 2, 3), [1.0, 2.0, 0.0]);
+    }
+}
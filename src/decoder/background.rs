@@ -0,0 +1,446 @@
+//! Decodes a file on a thread of its own and streams the samples to the audio thread, see
+//! [`Sink::append_async`](crate::Sink::append_async).
+
+use std::collections::VecDeque;
+use std::io::{Read, Seek};
+use std::sync::mpsc::{self, Receiver, Sender, SyncSender, TryRecvError, TrySendError};
+use std::sync::{Arc, Condvar, Mutex};
+use std::thread;
+use std::time::Duration;
+
+use super::{Decoder, DecoderError};
+use crate::common::{ChannelCount, SampleRate};
+use crate::source::SeekError;
+use crate::{Sample, Source};
+
+/// Samples per chunk sent to the audio thread, rounded down to whole frames.
+const CHUNK_LEN: usize = 4096;
+
+/// Chunks decoded ahead of playback.
+const CHUNKS_AHEAD: usize = 16;
+
+/// Frames of silence played when the decoding thread falls behind.
+const SILENCE_FRAMES: usize = 256;
+
+/// Chunks a worker of a [`DecodePool`] decodes for one sound before it moves on to the next.
+const CHUNKS_PER_TURN: usize = 4;
+
+/// How long the workers of a [`DecodePool`] wait when every sound is decoded far enough
+/// ahead.
+const POOL_POLL: Duration = Duration::from_millis(5);
+
+// Decoded samples that share a format.
+struct Chunk {
+    channels: ChannelCount,
+    sample_rate: SampleRate,
+    samples: Vec<f32>,
+}
+
+enum Span {
+    Chunk { chunk: Chunk, pos: usize },
+    // Samples of silence left, in the format of the previous chunk.
+    Silence(usize),
+    End,
+}
+
+/// Plays the samples decoded by a background thread. Silence is played in the format of the
+/// last chunk when the thread falls behind.
+pub(crate) struct BackgroundDecoded {
+    chunks: Receiver<Chunk>,
+    span: Span,
+    channels: ChannelCount,
+    sample_rate: SampleRate,
+}
+
+/// Starts decoding `data` on a new thread and waits for the first chunk, so the source knows
+/// its format from the start. The returned receiver gets the result once all samples are
+/// decoded, or once decoding stops because the source was dropped.
+pub(crate) fn decode_in_background<R>(
+    data: R,
+) -> (BackgroundDecoded, Receiver<Result<(), DecoderError>>)
+where
+    R: Read + Seek + Send + Sync + 'static,
+{
+    let (chunk_tx, chunk_rx) = mpsc::sync_channel(CHUNKS_AHEAD);
+    let (result_tx, result_rx) = mpsc::channel();
+    thread::Builder::new()
+        .name("rodio-decoder".into())
+        .spawn(move || {
+            let result = Decoder::new(data).map(|decoder| decode(decoder, chunk_tx));
+            let _ = result_tx.send(result);
+        })
+        .expect("failed to spawn the decoding thread");
+    (BackgroundDecoded::new(chunk_rx), result_rx)
+}
+
+// Sends the samples of `decoder` in chunks until it ends or the receiver is dropped.
+fn decode<R>(mut decoder: Decoder<R>, chunks: SyncSender<Chunk>)
+where
+    R: Read + Seek,
+{
+    loop {
+        let (chunk, ended) = next_chunk(&mut decoder);
+        if let Some(chunk) = chunk {
+            if chunks.send(chunk).is_err() {
+                return;
+            }
+        }
+        if ended {
+            return;
+        }
+    }
+}
+
+// Decodes the next chunk, returns whether the decoder has ended.
+fn next_chunk<R>(decoder: &mut Decoder<R>) -> (Option<Chunk>, bool)
+where
+    R: Read + Seek,
+{
+    let channels = decoder.channels().max(1);
+    let sample_rate = decoder.sample_rate();
+    let frame_len = channels as usize;
+    let frames = (CHUNK_LEN / frame_len).max(1);
+    let mut samples = Vec::with_capacity(frames * frame_len);
+    let mut ended = false;
+    for _ in 0..frames {
+        // A chunk ends early where the format changes, only checked between frames.
+        let format = (decoder.channels().max(1), decoder.sample_rate());
+        if !samples.is_empty() && format != (channels, sample_rate) {
+            break;
+        }
+        let len = samples.len();
+        samples.extend(decoder.by_ref().take(frame_len).map(|s| s.to_f32()));
+        if samples.len() < len + frame_len {
+            ended = true;
+            break;
+        }
+    }
+    let chunk = (!samples.is_empty()).then_some(Chunk {
+        channels,
+        sample_rate,
+        samples,
+    });
+    (chunk, ended)
+}
+
+/// A fixed number of threads that decode sounds appended with
+/// [`Sink::append_async`](crate::Sink::append_async), for apps that play many sounds at once.
+/// Set it up with [`SinkBuilder::decode_pool`](crate::SinkBuilder::decode_pool).
+///
+/// Without a pool every sound is decoded on a thread of its own. The threads of a pool take
+/// turns on the sounds instead, a few chunks at a time, and skip sounds that are decoded far
+/// enough ahead of playback. The pool can be cloned to share it between sinks, its threads
+/// stop once every clone is dropped and the sounds they were decoding are done.
+///
+/// # Example
+///
+/// ```no_run
+/// use std::fs::File;
+/// use rodio::decoder::DecodePool;
+/// use rodio::{OutputStreamBuilder, SinkBuilder};
+///
+/// let stream_handle = OutputStreamBuilder::open_default_stream().unwrap();
+/// let pool = DecodePool::new(2);
+/// let sinks: Vec<_> = (0..10)
+///     .map(|_| SinkBuilder::new().decode_pool(pool.clone()).build(&stream_handle.mixer()))
+///     .collect();
+/// for sink in &sinks {
+///     sink.append_async(File::open("music.flac").unwrap());
+/// }
+/// ```
+#[derive(Clone)]
+pub struct DecodePool {
+    handle: Arc<PoolHandle>,
+}
+
+// Stops the workers once the last clone of the pool is dropped.
+struct PoolHandle {
+    shared: Arc<PoolShared>,
+    threads: usize,
+}
+
+struct PoolShared {
+    state: Mutex<PoolState>,
+    // Notified when a sound is added and when the pool is dropped.
+    wake: Condvar,
+}
+
+struct PoolState {
+    jobs: VecDeque<Box<dyn Job>>,
+    // Turns in a row that did not decode anything, once every sound had one the workers wait.
+    stalled: usize,
+    shutdown: bool,
+}
+
+trait Job: Send {
+    fn run(&mut self) -> Turn;
+}
+
+enum Turn {
+    Progress,
+    // Enough is decoded ahead of playback.
+    Blocked,
+    Done,
+}
+
+// A sound decoded by a `DecodePool`.
+struct PooledDecode<R>
+where
+    R: Read + Seek,
+{
+    // Taken to create `decoder` on the first turn.
+    data: Option<R>,
+    decoder: Option<Decoder<R>>,
+    chunks: SyncSender<Chunk>,
+    // A chunk that did not fit in the channel yet.
+    pending: Option<Chunk>,
+    ended: bool,
+    result: Sender<Result<(), DecoderError>>,
+}
+
+impl DecodePool {
+    /// Starts a pool with `threads` decoding threads, at least one.
+    pub fn new(threads: usize) -> DecodePool {
+        let threads = threads.max(1);
+        let shared = Arc::new(PoolShared {
+            state: Mutex::new(PoolState {
+                jobs: VecDeque::new(),
+                stalled: 0,
+                shutdown: false,
+            }),
+            wake: Condvar::new(),
+        });
+        for index in 0..threads {
+            let shared = shared.clone();
+            thread::Builder::new()
+                .name(format!("rodio-decoder-{index}"))
+                .spawn(move || work(&shared))
+                .expect("failed to spawn a decoding thread");
+        }
+        DecodePool {
+            handle: Arc::new(PoolHandle { shared, threads }),
+        }
+    }
+
+    /// Returns the number of decoding threads.
+    #[inline]
+    pub fn threads(&self) -> usize {
+        self.handle.threads
+    }
+
+    /// Like [`decode_in_background`], but decodes on the threads of the pool. Also waits for
+    /// the first chunk.
+    pub(crate) fn decode<R>(
+        &self,
+        data: R,
+    ) -> (BackgroundDecoded, Receiver<Result<(), DecoderError>>)
+    where
+        R: Read + Seek + Send + Sync + 'static,
+    {
+        let (chunk_tx, chunk_rx) = mpsc::sync_channel(CHUNKS_AHEAD);
+        let (result_tx, result_rx) = mpsc::channel();
+        let job = PooledDecode {
+            data: Some(data),
+            decoder: None,
+            chunks: chunk_tx,
+            pending: None,
+            ended: false,
+            result: result_tx,
+        };
+        let shared = &self.handle.shared;
+        let mut state = shared.state.lock().unwrap();
+        state.jobs.push_back(Box::new(job));
+        state.stalled = 0;
+        drop(state);
+        shared.wake.notify_one();
+        (BackgroundDecoded::new(chunk_rx), result_rx)
+    }
+}
+
+impl Drop for PoolHandle {
+    fn drop(&mut self) {
+        self.shared.state.lock().unwrap().shutdown = true;
+        self.shared.wake.notify_all();
+    }
+}
+
+// Runs the sounds of the pool in turns until it is dropped and no sound is left.
+fn work(shared: &PoolShared) {
+    loop {
+        let mut job = {
+            let mut state = shared.state.lock().unwrap();
+            loop {
+                if state.stalled < state.jobs.len() {
+                    break state.jobs.pop_front().expect("there are jobs");
+                }
+                if state.jobs.is_empty() {
+                    if state.shutdown {
+                        return;
+                    }
+                    state = shared.wake.wait(state).unwrap();
+                } else {
+                    state = shared.wake.wait_timeout(state, POOL_POLL).unwrap().0;
+                    state.stalled = 0;
+                }
+            }
+        };
+        let turn = job.run();
+        let mut state = shared.state.lock().unwrap();
+        match turn {
+            Turn::Progress => state.stalled = 0,
+            Turn::Blocked => state.stalled += 1,
+            Turn::Done => {
+                state.stalled = 0;
+                continue;
+            }
+        }
+        state.jobs.push_back(job);
+    }
+}
+
+impl<R> PooledDecode<R>
+where
+    R: Read + Seek,
+{
+    fn finish(&mut self, result: Result<(), DecoderError>) -> Turn {
+        let _ = self.result.send(result);
+        Turn::Done
+    }
+}
+
+impl<R> Job for PooledDecode<R>
+where
+    R: Read + Seek + Send + Sync + 'static,
+{
+    fn run(&mut self) -> Turn {
+        let mut progress = false;
+        if let Some(data) = self.data.take() {
+            match Decoder::new(data) {
+                Ok(decoder) => self.decoder = Some(decoder),
+                Err(err) => return self.finish(Err(err)),
+            }
+            progress = true;
+        }
+        let Some(decoder) = self.decoder.as_mut() else {
+            return Turn::Done;
+        };
+        for _ in 0..CHUNKS_PER_TURN {
+            let chunk = match self.pending.take() {
+                Some(chunk) => chunk,
+                None if self.ended => return self.finish(Ok(())),
+                None => {
+                    let (chunk, ended) = next_chunk(decoder);
+                    self.ended = ended;
+                    match chunk {
+                        Some(chunk) => chunk,
+                        None => return self.finish(Ok(())),
+                    }
+                }
+            };
+            match self.chunks.try_send(chunk) {
+                Ok(()) => progress = true,
+                Err(TrySendError::Full(chunk)) => {
+                    self.pending = Some(chunk);
+                    break;
+                }
+                // The sound was dropped.
+                Err(TrySendError::Disconnected(_)) => return self.finish(Ok(())),
+            }
+        }
+        if progress {
+            Turn::Progress
+        } else {
+            Turn::Blocked
+        }
+    }
+}
+
+impl BackgroundDecoded {
+    // Blocks until the first chunk is decoded, the source is empty if decoding failed.
+    fn new(chunks: Receiver<Chunk>) -> BackgroundDecoded {
+        let mut decoded = BackgroundDecoded {
+            chunks,
+            span: Span::End,
+            channels: 1,
+            sample_rate: 44100,
+        };
+        if let Ok(chunk) = decoded.chunks.recv() {
+            decoded.start(chunk);
+        }
+        decoded
+    }
+
+    fn start(&mut self, chunk: Chunk) {
+        self.channels = chunk.channels;
+        self.sample_rate = chunk.sample_rate;
+        self.span = Span::Chunk { chunk, pos: 0 };
+    }
+
+    // Picks what to play next once the current span is done, without waiting.
+    fn next_span(&mut self) {
+        match self.chunks.try_recv() {
+            Ok(chunk) => self.start(chunk),
+            Err(TryRecvError::Empty) => {
+                self.span = Span::Silence(SILENCE_FRAMES * self.channels as usize);
+            }
+            Err(TryRecvError::Disconnected) => self.span = Span::End,
+        }
+    }
+}
+
+impl Iterator for BackgroundDecoded {
+    type Item = f32;
+
+    #[inline]
+    fn next(&mut self) -> Option<f32> {
+        let (sample, done) = match &mut self.span {
+            Span::Chunk { chunk, pos } => {
+                let sample = chunk.samples[*pos];
+                *pos += 1;
+                (sample, *pos >= chunk.samples.len())
+            }
+            Span::Silence(left) => {
+                *left -= 1;
+                (0.0, *left == 0)
+            }
+            Span::End => return None,
+        };
+        if done {
+            self.next_span();
+        }
+        Some(sample)
+    }
+}
+
+impl Source for BackgroundDecoded {
+    #[inline]
+    fn current_span_len(&self) -> Option<usize> {
+        match &self.span {
+            Span::Chunk { chunk, pos } => Some(chunk.samples.len() - pos),
+            Span::Silence(left) => Some(*left),
+            Span::End => Some(0),
+        }
+    }
+
+    #[inline]
+    fn channels(&self) -> ChannelCount {
+        self.channels
+    }
+
+    #[inline]
+    fn sample_rate(&self) -> SampleRate {
+        self.sample_rate
+    }
+
+    #[inline]
+    fn total_duration(&self) -> Option<Duration> {
+        None
+    }
+
+    #[inline]
+    fn try_seek(&mut self, _: Duration) -> Result<(), SeekError> {
+        Err(SeekError::NotSupported {
+            underlying_source: std::any::type_name::<Self>(),
+        })
+    }
+}
@@ -0,0 +1,85 @@
+//! Reads the markers in the `cue ` chunk of WAV files, which hound and Symphonia skip.
+
+use std::io::{Read, Seek, SeekFrom};
+
+/// Returns the frames and labels of the markers in the `cue ` chunk of the WAV in `data`,
+/// ordered by position, then resets `data` to where it was. Returns nothing if the chunks
+/// cannot be read.
+pub(crate) fn read_cues<R>(mut data: R) -> Vec<(u64, Option<String>)>
+where
+    R: Read + Seek,
+{
+    let Ok(stream_pos) = data.stream_position() else {
+        return Vec::new();
+    };
+    let mut cues = Vec::new();
+    let mut labels = Vec::new();
+    read_cue_chunks(data.by_ref(), &mut cues, &mut labels);
+    let _ = data.seek(SeekFrom::Start(stream_pos));
+
+    let mut cues: Vec<(u64, Option<String>)> = cues
+        .into_iter()
+        .map(|(id, frame)| {
+            let label = labels
+                .iter()
+                .find(|(label_id, _)| *label_id == id)
+                .map(|(_, label)| label.clone());
+            (frame, label)
+        })
+        .collect();
+    cues.sort_by_key(|(frame, _)| *frame);
+    cues
+}
+
+/// Collects the ids and frames of the cue points and the ids and names of the labels, up to
+/// the first chunk that cannot be read.
+fn read_cue_chunks<R>(mut data: R, cues: &mut Vec<(u32, u64)>, labels: &mut Vec<(u32, String)>)
+where
+    R: Read + Seek,
+{
+    let read_u32 = |bytes: &[u8], at: usize| {
+        u32::from_le_bytes([bytes[at], bytes[at + 1], bytes[at + 2], bytes[at + 3]])
+    };
+    let mut riff = [0; 12];
+    if data.read_exact(&mut riff).is_err() || &riff[..4] != b"RIFF" || &riff[8..] != b"WAVE" {
+        return;
+    }
+    let mut chunk = [0; 8];
+    while data.read_exact(&mut chunk).is_ok() {
+        let len = read_u32(&chunk, 4) as u64 + (read_u32(&chunk, 4) & 1) as u64;
+        match &chunk[..4] {
+            // The length of a file still being written, the rest is not chunks.
+            b"data" if len == 0 => return,
+            b"cue " | b"LIST" => {
+                let mut body = Vec::new();
+                if data.by_ref().take(len).read_to_end(&mut body).ok() != Some(len as usize) {
+                    return;
+                }
+                if &chunk[..4] == b"cue " {
+                    // dwName, dwPosition, fccChunk, dwChunkStart, dwBlockStart, dwSampleOffset
+                    for point in body.get(4..).unwrap_or_default().chunks_exact(24) {
+                        cues.push((read_u32(point, 0), read_u32(point, 20) as u64));
+                    }
+                } else if body.starts_with(b"adtl") {
+                    let mut rest = &body[4..];
+                    while rest.len() >= 8 {
+                        let sub_len = read_u32(rest, 4) as usize;
+                        let Some(sub) = rest.get(8..8 + sub_len) else {
+                            break;
+                        };
+                        if &rest[..4] == b"labl" && sub.len() >= 4 {
+                            let name = sub[4..].split(|&b| b == 0).next().unwrap_or_default();
+                            labels.push((read_u32(sub, 0), String::from_utf8_lossy(name).into()));
+                        }
+                        rest = rest.get(8 + sub_len + (sub_len & 1)..).unwrap_or_default();
+                    }
+                }
+            }
+            _ => {
+                if data.seek(SeekFrom::Current(len as i64)).is_err() {
+                    return;
+                }
+            }
+        }
+    }
+}
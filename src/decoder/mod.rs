@@ -3,24 +3,29 @@
 use std::error::Error;
 use std::fmt;
 #[allow(unused_imports)]
-use std::io::{Read, Seek, SeekFrom};
+use std::io::{Cursor, Read, Seek, SeekFrom};
 use std::mem;
 use std::str::FromStr;
+use std::sync::Arc;
 use std::time::Duration;
 
-use crate::source::SeekError;
+use crate::source::{SeekError, TakeDuration};
 use crate::Source;
 
 #[cfg(feature = "symphonia")]
 use self::read_seek_source::ReadSeekSource;
-use crate::common::{ChannelCount, SampleRate};
+use crate::common::{frames_to_duration, ChannelCount, SampleRate};
 #[cfg(feature = "symphonia")]
-use ::symphonia::core::io::{MediaSource, MediaSourceStream};
+use ::symphonia::core::io::{MediaSource, MediaSourceStream, MediaSourceStreamOptions};
 
+pub(crate) mod background;
+#[cfg(any(feature = "wav", feature = "symphonia-wav"))]
+mod cues;
 #[cfg(all(feature = "flac", not(feature = "symphonia-flac")))]
 mod flac;
 #[cfg(all(feature = "minimp3", not(feature = "symphonia-mp3")))]
 mod mp3;
+mod pcm;
 #[cfg(feature = "symphonia")]
 mod read_seek_source;
 #[cfg(feature = "symphonia")]
@@ -31,6 +36,9 @@ mod vorbis;
 #[cfg(all(feature = "wav", not(feature = "symphonia-wav")))]
 mod wav;
 
+pub use self::background::DecodePool;
+pub use self::pcm::{PcmReader, SampleFormat};
+
 #[cfg(feature = "integer-decoder")]
 /// Output format of the decoders.
 pub type DecoderSample = i16;
@@ -53,6 +61,33 @@ pub struct LoopedDecoder<R>(DecoderImpl<R>)
 where
     R: Read + Seek;
 
+/// Describes an audio track of a file, see [`Decoder::tracks`].
+///
+/// What is known before decoding depends on the format, so everything but the id is optional.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TrackInfo {
+    /// Identifies the track within the file, pass it to [`Decoder::new_with_track`].
+    pub id: u32,
+    /// The number of channels.
+    pub channels: Option<ChannelCount>,
+    /// The sample rate.
+    pub sample_rate: Option<SampleRate>,
+    /// How long the track plays.
+    pub total_duration: Option<Duration>,
+    /// The language of the track, for example `"eng"`.
+    pub language: Option<String>,
+}
+
+/// A marker in a file, such as a chapter of an audiobook or a track of a DJ set, see
+/// [`Decoder::cue_points`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CuePoint {
+    /// Where the marker is, from the start of the file.
+    pub timestamp: Duration,
+    /// The name of the marker, if it has one.
+    pub label: Option<String>,
+}
+
 // Cannot really reduce the size of the VorbisDecoder. There are not any
 // arrays just a lot of struct fields.
 #[allow(clippy::large_enum_variant)]
@@ -70,6 +105,7 @@ where
     Mp3(mp3::Mp3Decoder<R>),
     #[cfg(feature = "symphonia")]
     Symphonia(symphonia::SymphoniaDecoder),
+    Pcm(pcm::PcmDecoder<R>),
     None(::std::marker::PhantomData<R>),
 }
 
@@ -87,6 +123,7 @@ impl<R: Read + Seek> DecoderImpl<R> {
             DecoderImpl::Mp3(source) => source.next(),
             #[cfg(feature = "symphonia")]
             DecoderImpl::Symphonia(source) => source.next(),
+            DecoderImpl::Pcm(source) => source.next(),
             DecoderImpl::None(_) => None,
         }
     }
@@ -104,6 +141,7 @@ impl<R: Read + Seek> DecoderImpl<R> {
             DecoderImpl::Mp3(source) => source.size_hint(),
             #[cfg(feature = "symphonia")]
             DecoderImpl::Symphonia(source) => source.size_hint(),
+            DecoderImpl::Pcm(source) => source.size_hint(),
             DecoderImpl::None(_) => (0, None),
         }
     }
@@ -121,6 +159,7 @@ impl<R: Read + Seek> DecoderImpl<R> {
             DecoderImpl::Mp3(source) => source.current_span_len(),
             #[cfg(feature = "symphonia")]
             DecoderImpl::Symphonia(source) => source.current_span_len(),
+            DecoderImpl::Pcm(source) => source.current_span_len(),
             DecoderImpl::None(_) => Some(0),
         }
     }
@@ -138,6 +177,7 @@ impl<R: Read + Seek> DecoderImpl<R> {
             DecoderImpl::Mp3(source) => source.channels(),
             #[cfg(feature = "symphonia")]
             DecoderImpl::Symphonia(source) => source.channels(),
+            DecoderImpl::Pcm(source) => source.channels(),
             DecoderImpl::None(_) => 0,
         }
     }
@@ -155,6 +195,7 @@ impl<R: Read + Seek> DecoderImpl<R> {
             DecoderImpl::Mp3(source) => source.sample_rate(),
             #[cfg(feature = "symphonia")]
             DecoderImpl::Symphonia(source) => source.sample_rate(),
+            DecoderImpl::Pcm(source) => source.sample_rate(),
             DecoderImpl::None(_) => 1,
         }
     }
@@ -172,10 +213,29 @@ impl<R: Read + Seek> DecoderImpl<R> {
             DecoderImpl::Mp3(source) => source.total_duration(),
             #[cfg(feature = "symphonia")]
             DecoderImpl::Symphonia(source) => source.total_duration(),
+            DecoderImpl::Pcm(source) => source.total_duration(),
             DecoderImpl::None(_) => Some(Duration::default()),
         }
     }
 
+    #[inline]
+    fn total_samples(&self) -> Option<u64> {
+        match self {
+            #[cfg(all(feature = "wav", not(feature = "symphonia-wav")))]
+            DecoderImpl::Wav(source) => source.total_samples(),
+            #[cfg(all(feature = "vorbis", not(feature = "symphonia-vorbis")))]
+            DecoderImpl::Vorbis(source) => source.total_samples(),
+            #[cfg(all(feature = "flac", not(feature = "symphonia-flac")))]
+            DecoderImpl::Flac(source) => source.total_samples(),
+            #[cfg(all(feature = "minimp3", not(feature = "symphonia-mp3")))]
+            DecoderImpl::Mp3(source) => source.total_samples(),
+            #[cfg(feature = "symphonia")]
+            DecoderImpl::Symphonia(source) => source.total_samples(),
+            DecoderImpl::Pcm(source) => source.total_samples(),
+            DecoderImpl::None(_) => Some(0),
+        }
+    }
+
     #[inline]
     fn try_seek(&mut self, pos: Duration) -> Result<(), SeekError> {
         match self {
@@ -189,11 +249,25 @@ impl<R: Read + Seek> DecoderImpl<R> {
             DecoderImpl::Mp3(source) => source.try_seek(pos),
             #[cfg(feature = "symphonia")]
             DecoderImpl::Symphonia(source) => source.try_seek(pos),
+            DecoderImpl::Pcm(source) => source.try_seek(pos),
             DecoderImpl::None(_) => Err(SeekError::NotSupported {
                 underlying_source: "DecoderImpl::None",
             }),
         }
     }
+
+    #[inline]
+    fn try_seek_frames(&mut self, frame: u64) -> Result<(), SeekError> {
+        match self {
+            #[cfg(all(feature = "wav", not(feature = "symphonia-wav")))]
+            DecoderImpl::Wav(source) => source.try_seek_frames(frame),
+            DecoderImpl::Pcm(source) => source.try_seek_frames(frame),
+            _ => {
+                let pos = frames_to_duration(frame, self.sample_rate());
+                self.try_seek(pos)
+            }
+        }
+    }
 }
 
 impl<R> Decoder<R>
@@ -203,8 +277,78 @@ where
     /// Builds a new decoder.
     ///
     /// Attempts to automatically detect the format of the source of data.
-    #[allow(unused_variables)]
     pub fn new(data: R) -> Result<Decoder<R>, DecoderError> {
+        Self::detect(data, None)
+    }
+
+    /// Builds a new decoder that reads at most about `bytes` ahead, to save memory on small
+    /// targets at the cost of reading the data in more, smaller pieces.
+    ///
+    /// Attempts to automatically detect the format of the source of data like
+    /// [`new`](Decoder::new). Only the formats decoded by Symphonia buffer the data they read
+    /// ahead in a buffer of a configurable size. Its length is rounded down to a power of
+    /// two and can not be smaller than 64 KiB, which is also the default, so a cap only saves
+    /// memory when it was raised before. The other decoders use buffers of a fixed size or
+    /// none at all, there the buffering is up to the reader that is passed in, for example
+    /// the capacity of a [`BufReader`](std::io::BufReader). The smallest buffers per format
+    /// are:
+    ///
+    /// | Format | Decoder | Read-ahead |
+    /// |--------|---------|------------|
+    /// | WAV    | hound   | none, reads from `data` directly |
+    /// | FLAC   | claxon  | 2 KiB |
+    /// | Vorbis | lewton  | the Ogg page being read, up to about 64 KiB |
+    /// | MP3    | minimp3 | about 34 KiB |
+    /// | any    | Symphonia | 64 KiB |
+    ///
+    /// The decoded samples of one packet are buffered in addition, how many depends on the
+    /// format and the file and not on `bytes`.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use std::fs::File;
+    /// use std::io::BufReader;
+    /// use rodio::Decoder;
+    ///
+    /// let file = BufReader::with_capacity(4096, File::open("music.mp3").unwrap());
+    /// let decoder = Decoder::with_max_buffer(file, 64 * 1024).unwrap();
+    /// ```
+    pub fn with_max_buffer(data: R, bytes: usize) -> Result<Decoder<R>, DecoderError> {
+        Self::detect(data, Some(bytes))
+    }
+
+    /// Builds a new decoder that plays the track with the id `track`, see
+    /// [`tracks`](Decoder::tracks).
+    ///
+    /// [`new`](Decoder::new) plays the first track it can decode. The format is detected
+    /// automatically, always with Symphonia. Returns [`DecoderError::UnknownTrack`] if there is
+    /// no such track or its codec is not supported.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use std::fs::File;
+    /// use std::io::BufReader;
+    /// use rodio::Decoder;
+    ///
+    /// let open = || BufReader::new(File::open("film.m4a").unwrap());
+    /// let tracks = Decoder::new(open()).unwrap().tracks();
+    /// let commentary = tracks.last().unwrap().id;
+    /// let decoder = Decoder::new_with_track(open(), commentary).unwrap();
+    /// ```
+    #[cfg(feature = "symphonia")]
+    pub fn new_with_track(data: R, track: u32) -> Result<Decoder<R>, DecoderError> {
+        let mss = MediaSourceStream::new(
+            Box::new(ReadSeekSource::new(data)) as Box<dyn MediaSource>,
+            Default::default(),
+        );
+        symphonia::SymphoniaDecoder::new(mss, None, Some(track))
+            .map(|decoder| Decoder(DecoderImpl::Symphonia(decoder)))
+    }
+
+    #[allow(unused_variables)]
+    fn detect(data: R, max_buffer: Option<usize>) -> Result<Decoder<R>, DecoderError> {
         #[cfg(all(feature = "wav", not(feature = "symphonia-wav")))]
         let data = match wav::WavDecoder::new(data) {
             Err(data) => data,
@@ -238,21 +382,42 @@ where
         };
 
         #[cfg(feature = "symphonia")]
-        {
-            let mss = MediaSourceStream::new(
-                Box::new(ReadSeekSource::new(data)) as Box<dyn MediaSource>,
-                Default::default(),
-            );
-
-            match symphonia::SymphoniaDecoder::new(mss, None) {
-                Err(e) => Err(e),
-                Ok(decoder) => Ok(Decoder(DecoderImpl::Symphonia(decoder))),
-            }
-        }
+        return Decoder::new_symphonia(data, None, symphonia_options(max_buffer));
         #[cfg(not(feature = "symphonia"))]
         Err(DecoderError::UnrecognizedFormat)
     }
 
+    /// Builds a new decoder that only plays the data from `start` to `end`, for example to
+    /// preview part of a file.
+    ///
+    /// The decoder seeks to `start` right away, so formats that support seeking do not decode
+    /// what comes before it. The source ends at `end`, or at the end of the data if that comes
+    /// first. Returns [`DecoderError::SeekError`] if the format does not support seeking.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use std::fs::File;
+    /// use std::io::BufReader;
+    /// use std::time::Duration;
+    /// use rodio::Decoder;
+    ///
+    /// let file = BufReader::new(File::open("music.wav").unwrap());
+    /// let preview =
+    ///     Decoder::new_range(file, Duration::from_secs(30), Duration::from_secs(45)).unwrap();
+    /// ```
+    pub fn new_range(
+        data: R,
+        start: Duration,
+        end: Duration,
+    ) -> Result<TakeDuration<Decoder<R>>, DecoderError> {
+        let mut decoder = Self::new(data)?;
+        decoder
+            .try_seek(start)
+            .map_err(|e| DecoderError::SeekError(e.to_string()))?;
+        Ok(decoder.take_duration(end.saturating_sub(start)))
+    }
+
     /// Builds a new looped decoder.
     ///
     /// Attempts to automatically detect the format of the source of data.
@@ -272,7 +437,7 @@ where
     /// Builds a new decoder from wav data.
     #[cfg(feature = "symphonia-wav")]
     pub fn new_wav(data: R) -> Result<Decoder<R>, DecoderError> {
-        Decoder::new_symphonia(data, "wav")
+        Decoder::new_symphonia(data, Some("wav"), Default::default())
     }
 
     /// Builds a new decoder from flac data.
@@ -287,7 +452,7 @@ where
     /// Builds a new decoder from flac data.
     #[cfg(feature = "symphonia-flac")]
     pub fn new_flac(data: R) -> Result<Decoder<R>, DecoderError> {
-        Decoder::new_symphonia(data, "flac")
+        Decoder::new_symphonia(data, Some("flac"), Default::default())
     }
 
     /// Builds a new decoder from vorbis data.
@@ -302,7 +467,7 @@ where
     /// Builds a new decoder from vorbis data.
     #[cfg(feature = "symphonia-vorbis")]
     pub fn new_vorbis(data: R) -> Result<Decoder<R>, DecoderError> {
-        Decoder::new_symphonia(data, "ogg")
+        Decoder::new_symphonia(data, Some("ogg"), Default::default())
     }
 
     /// Builds a new decoder from mp3 data.
@@ -317,35 +482,156 @@ where
     /// Builds a new decoder from mp3 data.
     #[cfg(feature = "symphonia-mp3")]
     pub fn new_mp3(data: R) -> Result<Decoder<R>, DecoderError> {
-        Decoder::new_symphonia(data, "mp3")
+        Decoder::new_symphonia(data, Some("mp3"), Default::default())
     }
 
     /// Builds a new decoder from aac data.
     #[cfg(feature = "symphonia-aac")]
     pub fn new_aac(data: R) -> Result<Decoder<R>, DecoderError> {
-        Decoder::new_symphonia(data, "aac")
+        Decoder::new_symphonia(data, Some("aac"), Default::default())
     }
 
     /// Builds a new decoder from mp4 data.
     #[cfg(feature = "symphonia-isomp4")]
     pub fn new_mp4(data: R, hint: Mp4Type) -> Result<Decoder<R>, DecoderError> {
-        Decoder::new_symphonia(data, &hint.to_string())
+        Decoder::new_symphonia(data, Some(&hint.to_string()), Default::default())
     }
 
+    // Opens `data` with Symphonia, guessing the format if there is no `hint`.
     #[cfg(feature = "symphonia")]
-    fn new_symphonia(data: R, hint: &str) -> Result<Decoder<R>, DecoderError> {
+    fn new_symphonia(
+        data: R,
+        hint: Option<&str>,
+        options: MediaSourceStreamOptions,
+    ) -> Result<Decoder<R>, DecoderError> {
+        #[cfg(feature = "symphonia-wav")]
+        let (data, cues) = {
+            let mut data = data;
+            let cues = cues::read_cues(&mut data);
+            (data, cues)
+        };
         let mss = MediaSourceStream::new(
             Box::new(ReadSeekSource::new(data)) as Box<dyn MediaSource>,
-            Default::default(),
+            options,
         );
 
-        match symphonia::SymphoniaDecoder::new(mss, Some(hint)) {
-            Err(e) => Err(e),
-            Ok(decoder) => Ok(Decoder(DecoderImpl::Symphonia(decoder))),
+        match symphonia::SymphoniaDecoder::init(mss, hint, None) {
+            Err(e) => Err(DecoderError::Backend {
+                format: hint.map_or("unknown", format_name),
+                source: Arc::new(e),
+            }),
+            #[cfg(feature = "symphonia-wav")]
+            Ok(Some(mut decoder)) => {
+                decoder.add_cues(cues);
+                Ok(Decoder(DecoderImpl::Symphonia(decoder)))
+            }
+            #[cfg(not(feature = "symphonia-wav"))]
+            Ok(Some(decoder)) => Ok(Decoder(DecoderImpl::Symphonia(decoder))),
+            Ok(None) => Err(DecoderError::NoStreams),
         }
     }
 }
 
+impl<R: Read> Decoder<PcmReader<R>> {
+    /// Builds a new decoder from headerless PCM data.
+    ///
+    /// The data is read as interleaved samples for `channels` channels in the given `format`,
+    /// without looking for any container. An incomplete frame at the end of the data is
+    /// ignored. The data is only read, so the decoder can only seek forward. Returns
+    /// [`DecoderError::ZeroRateOrChannels`] if `sample_rate` or `channels` is zero.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rodio::decoder::{Decoder, SampleFormat};
+    /// use rodio::Source;
+    ///
+    /// let bytes: Vec<u8> = [0i16, 1000, -1000, 0]
+    ///     .iter()
+    ///     .flat_map(|s| s.to_le_bytes())
+    ///     .collect();
+    /// let decoder = Decoder::new_pcm(&bytes[..], 44100, 2, SampleFormat::I16Le).unwrap();
+    /// assert_eq!(decoder.channels(), 2);
+    /// assert_eq!(decoder.count(), 4);
+    /// ```
+    pub fn new_pcm(
+        data: R,
+        sample_rate: SampleRate,
+        channels: ChannelCount,
+        format: SampleFormat,
+    ) -> Result<Decoder<PcmReader<R>>, DecoderError> {
+        match pcm::PcmDecoder::from_reader(data, sample_rate, channels, format) {
+            None => Err(DecoderError::ZeroRateOrChannels),
+            Some(decoder) => Ok(Decoder(DecoderImpl::Pcm(decoder))),
+        }
+    }
+}
+
+impl Decoder<Cursor<Vec<u8>>> {
+    /// Builds a new decoder for audio held in memory, detecting its format like
+    /// [`new`](Decoder::new). Saves wrapping the bytes in a [`Cursor`] yourself.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use rodio::Decoder;
+    ///
+    /// let decoder = Decoder::new_from_bytes(include_bytes!("../../assets/beep.wav").to_vec());
+    /// ```
+    pub fn new_from_bytes(data: Vec<u8>) -> Result<Self, DecoderError> {
+        Self::new(Cursor::new(data))
+    }
+}
+
+impl Decoder<Cursor<Arc<[u8]>>> {
+    /// Builds a new decoder for audio held in memory that is shared, detecting its format like
+    /// [`new`](Decoder::new). The bytes are not copied, so one asset can be decoded by many
+    /// decoders at the same time.
+    pub fn new_from_slice(data: Arc<[u8]>) -> Result<Self, DecoderError> {
+        Self::new(Cursor::new(data))
+    }
+}
+
+/// Smallest read-ahead buffer Symphonia accepts, a power of two larger than the 32 KiB it
+/// reads at once.
+#[cfg(feature = "symphonia")]
+const MIN_SYMPHONIA_BUFFER: usize = 64 * 1024;
+
+/// Options for a read-ahead buffer of at most about `bytes`, or the default without a cap.
+#[cfg(feature = "symphonia")]
+fn symphonia_options(max_buffer: Option<usize>) -> MediaSourceStreamOptions {
+    match max_buffer {
+        Some(bytes) => MediaSourceStreamOptions {
+            buffer_len: symphonia_buffer_len(bytes),
+        },
+        None => Default::default(),
+    }
+}
+
+/// Largest buffer length Symphonia accepts that is at most `bytes`, or the smallest it accepts.
+#[cfg(feature = "symphonia")]
+fn symphonia_buffer_len(bytes: usize) -> usize {
+    if bytes <= MIN_SYMPHONIA_BUFFER {
+        MIN_SYMPHONIA_BUFFER
+    } else {
+        1 << (usize::BITS - 1 - bytes.leading_zeros())
+    }
+}
+
+/// Name of the format a file extension stands for, as used in error messages.
+#[cfg(feature = "symphonia")]
+fn format_name(extension: &str) -> &'static str {
+    match extension {
+        "wav" => "WAV",
+        "flac" => "FLAC",
+        "ogg" => "Vorbis",
+        "mp3" => "MP3",
+        "aac" => "AAC",
+        "mp4" | "m4a" | "m4p" | "m4b" | "m4r" | "m4v" | "mov" => "MP4",
+        _ => "unknown",
+    }
+}
+
 #[allow(missing_docs)] // Reason: will be removed, see: #612
 #[derive(Debug)]
 pub enum Mp4Type {
@@ -399,6 +685,59 @@ where
     }
 }
 
+impl<R> Decoder<R>
+where
+    R: Read + Seek,
+{
+    /// Attempts to seek to the frame at index `frame`, counting from the start of the data.
+    ///
+    /// A frame holds one sample for every channel, so `frame` is the number of samples per
+    /// channel to skip. Unlike [`try_seek`](Source::try_seek) this does not go through a
+    /// [`Duration`], which can be off by one sample. WAV and raw PCM data seek exactly, other
+    /// formats are as accurate as their seek implementation.
+    #[inline]
+    pub fn try_seek_samples(&mut self, frame: u64) -> Result<(), SeekError> {
+        self.0.try_seek_frames(frame)
+    }
+
+    /// Lists the audio tracks of the file that can be decoded, for example the languages of a
+    /// film. Containers such as MP4 can hold several, select one with
+    /// [`new_with_track`](Decoder::new_with_track).
+    ///
+    /// Formats that only hold a single track list it with the id `0`.
+    pub fn tracks(&self) -> Vec<TrackInfo> {
+        #[cfg(feature = "symphonia")]
+        if let DecoderImpl::Symphonia(decoder) = &self.0 {
+            return decoder.tracks();
+        }
+        vec![TrackInfo {
+            id: 0,
+            channels: Some(self.channels()),
+            sample_rate: Some(self.sample_rate()),
+            total_duration: self.total_duration(),
+            language: None,
+        }]
+    }
+
+    /// Lists the markers stored in the file, ordered by their position, for example to offer
+    /// chapter navigation with [`try_seek`](Source::try_seek).
+    ///
+    /// Markers are read from the `cue ` chunk of WAV files, with the names from their `labl`
+    /// chunks, and from the cues Symphonia reads, such as FLAC cue sheets, with their title.
+    /// Other formats, or files without markers, have none. That includes the chapters of Ogg
+    /// files, kept in `CHAPTERxxx` comments, and the chapter tracks of MP4 files, which are
+    /// not read yet.
+    pub fn cue_points(&self) -> Vec<CuePoint> {
+        match &self.0 {
+            #[cfg(all(feature = "wav", not(feature = "symphonia-wav")))]
+            DecoderImpl::Wav(source) => source.cue_points().to_vec(),
+            #[cfg(feature = "symphonia")]
+            DecoderImpl::Symphonia(source) => source.cue_points(),
+            _ => Vec::new(),
+        }
+    }
+}
+
 impl<R> Iterator for Decoder<R>
 where
     R: Read + Seek,
@@ -439,10 +778,20 @@ where
         self.0.total_duration()
     }
 
+    #[inline]
+    fn total_samples(&self) -> Option<u64> {
+        self.0.total_samples()
+    }
+
     #[inline]
     fn try_seek(&mut self, pos: Duration) -> Result<(), SeekError> {
         self.0.try_seek(pos)
     }
+
+    #[inline]
+    fn try_seek_frames(&mut self, frame: u64) -> Result<(), SeekError> {
+        self.0.try_seek_frames(frame)
+    }
 }
 
 impl<R> Iterator for LoopedDecoder<R>
@@ -495,12 +844,19 @@ where
                 }
                 #[cfg(feature = "symphonia")]
                 DecoderImpl::Symphonia(source) => {
+                    let track = source.track_id();
                     let mut reader = source.into_inner();
                     reader.seek(SeekFrom::Start(0)).ok()?;
-                    let mut source = symphonia::SymphoniaDecoder::new(reader, None).ok()?;
+                    let mut source =
+                        symphonia::SymphoniaDecoder::new(reader, None, Some(track)).ok()?;
                     let sample = source.next();
                     (DecoderImpl::Symphonia(source), sample)
                 }
+                DecoderImpl::Pcm(mut source) => {
+                    source.try_seek(Duration::ZERO).ok()?;
+                    let sample = source.next();
+                    (DecoderImpl::Pcm(source), sample)
+                }
                 none @ DecoderImpl::None(_) => (none, None),
             };
             self.0 = decoder;
@@ -541,12 +897,19 @@ where
     fn try_seek(&mut self, pos: Duration) -> Result<(), SeekError> {
         self.0.try_seek(pos)
     }
+
+    #[inline]
+    fn try_seek_frames(&mut self, frame: u64) -> Result<(), SeekError> {
+        self.0.try_seek_frames(frame)
+    }
 }
 
 /// Error that can happen when creating a decoder.
 #[derive(Debug, Clone)]
 pub enum DecoderError {
-    /// The format of the data has not been recognized.
+    /// The format of the data has not been recognized. With Symphonia enabled,
+    /// [`Decoder::new`] returns [`Backend`](DecoderError::Backend) instead, with the error
+    /// Symphonia gave up with.
     UnrecognizedFormat,
 
     /// An IO error occurred while reading, writing, or seeking the stream.
@@ -569,6 +932,28 @@ pub enum DecoderError {
     /// No streams were found by the decoder
     #[cfg(feature = "symphonia")]
     NoStreams,
+
+    /// The track passed to [`Decoder::new_with_track`] does not exist or its codec is not
+    /// supported. Contains the id of the track.
+    #[cfg(feature = "symphonia")]
+    UnknownTrack(u32),
+
+    /// Seeking to the start of the range given to [`Decoder::new_range`] failed, usually
+    /// because the format does not support seeking. Contains the description of the error.
+    SeekError(String),
+
+    /// The sample rate or the channel count given to [`Decoder::new_pcm`] is zero.
+    ZeroRateOrChannels,
+
+    /// The decoder for a specific format failed to open the data, for example because the
+    /// file is truncated. The error of the decoder is available through [`Error::source`].
+    Backend {
+        /// Name of the format that was tried, for example `"MP3"`, or `"unknown"` if the format
+        /// was being detected.
+        format: &'static str,
+        /// The error reported by the decoder.
+        source: Arc<dyn Error + Send + Sync>,
+    },
 }
 
 impl fmt::Display for DecoderError {
@@ -585,9 +970,23 @@ impl fmt::Display for DecoderError {
             DecoderError::ResetRequired => "Reset required",
             #[cfg(feature = "symphonia")]
             DecoderError::NoStreams => "No streams",
+            #[cfg(feature = "symphonia")]
+            DecoderError::UnknownTrack(id) => return write!(f, "No supported track with id {id}"),
+            DecoderError::SeekError(msg) => &msg[..],
+            DecoderError::ZeroRateOrChannels => "Sample rate and channel count must not be zero",
+            DecoderError::Backend { format, source } => {
+                return write!(f, "Failed to open {format} data: {source}");
+            }
         };
         write!(f, "{text}")
     }
 }
 
-impl Error for DecoderError {}
+impl Error for DecoderError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            DecoderError::Backend { source, .. } => Some(source.as_ref()),
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,506 @@
+use std::io::{self, ErrorKind, Read, Seek, SeekFrom};
+use std::time::Duration;
+
+use crate::source::SeekError;
+use crate::Source;
+
+use crate::common::{ChannelCount, SampleRate};
+
+use dasp_sample::{Sample, I24};
+
+use super::DecoderSample;
+
+/// Number of frames read from the underlying reader at once.
+const FRAMES_PER_READ: usize = 1024;
+
+/// Encoding of the samples in headerless PCM data.
+///
+/// Used with [`Decoder::new_pcm`](super::Decoder::new_pcm).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SampleFormat {
+    /// Unsigned 8 bit, silence is 128.
+    U8,
+    /// Signed 16 bit, little endian.
+    I16Le,
+    /// Signed 16 bit, big endian.
+    I16Be,
+    /// Signed 24 bit packed in 3 bytes, little endian.
+    I24Le,
+    /// Signed 24 bit packed in 3 bytes, big endian.
+    I24Be,
+    /// Signed 32 bit, little endian.
+    I32Le,
+    /// Signed 32 bit, big endian.
+    I32Be,
+    /// 32 bit float, little endian.
+    F32Le,
+    /// 32 bit float, big endian.
+    F32Be,
+    /// 64 bit float, little endian.
+    F64Le,
+    /// 64 bit float, big endian.
+    F64Be,
+    /// 8 bit G.711 A-law, the companding used by European telephony.
+    ALaw,
+    /// 8 bit G.711 mu-law, the companding used by North American and Japanese telephony.
+    MuLaw,
+}
+
+impl SampleFormat {
+    /// Number of bytes used by a single sample.
+    #[inline]
+    pub fn sample_size(self) -> usize {
+        match self {
+            SampleFormat::U8 | SampleFormat::ALaw | SampleFormat::MuLaw => 1,
+            SampleFormat::I16Le | SampleFormat::I16Be => 2,
+            SampleFormat::I24Le | SampleFormat::I24Be => 3,
+            SampleFormat::I32Le
+            | SampleFormat::I32Be
+            | SampleFormat::F32Le
+            | SampleFormat::F32Be => 4,
+            SampleFormat::F64Le | SampleFormat::F64Be => 8,
+        }
+    }
+
+    /// Decodes one sample, `bytes` must be exactly `sample_size` long.
+    #[inline]
+    fn decode(self, bytes: &[u8]) -> DecoderSample {
+        match self {
+            SampleFormat::U8 => bytes[0].to_sample(),
+            SampleFormat::I16Le => i16::from_le_bytes([bytes[0], bytes[1]]).to_sample(),
+            SampleFormat::I16Be => i16::from_be_bytes([bytes[0], bytes[1]]).to_sample(),
+            SampleFormat::I24Le => {
+                // sign extend by placing the sample in the upper bytes and shifting back
+                let value = i32::from_le_bytes([0, bytes[0], bytes[1], bytes[2]]) >> 8;
+                I24::new_unchecked(value).to_sample()
+            }
+            SampleFormat::I24Be => {
+                let value = i32::from_be_bytes([bytes[0], bytes[1], bytes[2], 0]) >> 8;
+                I24::new_unchecked(value).to_sample()
+            }
+            SampleFormat::I32Le => {
+                i32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]).to_sample()
+            }
+            SampleFormat::I32Be => {
+                i32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]).to_sample()
+            }
+            SampleFormat::F32Le => {
+                f32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]).to_sample()
+            }
+            SampleFormat::F32Be => {
+                f32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]).to_sample()
+            }
+            SampleFormat::F64Le => f64::from_le_bytes(bytes.try_into().unwrap()).to_sample(),
+            SampleFormat::F64Be => f64::from_be_bytes(bytes.try_into().unwrap()).to_sample(),
+            SampleFormat::ALaw => a_law_to_linear(bytes[0]).to_sample(),
+            SampleFormat::MuLaw => mu_law_to_linear(bytes[0]).to_sample(),
+        }
+    }
+}
+
+/// Expands an A-law code to the 16 bit sample it stands for, following G.711.
+#[inline]
+fn a_law_to_linear(code: u8) -> i16 {
+    // even bits are inverted on the wire
+    let code = code ^ 0x55;
+    let exponent = (code >> 4) & 0x07;
+    let mut magnitude = (((code & 0x0f) as i16) << 4) + 8;
+    if exponent > 0 {
+        magnitude = (magnitude + 0x100) << (exponent - 1);
+    }
+    if code & 0x80 != 0 {
+        magnitude
+    } else {
+        -magnitude
+    }
+}
+
+/// Expands a mu-law code to the 16 bit sample it stands for, following G.711.
+#[inline]
+fn mu_law_to_linear(code: u8) -> i16 {
+    // the bias makes the segments line up, it is removed again below
+    const BIAS: i16 = 0x84;
+    // all bits are inverted on the wire
+    let code = !code;
+    let exponent = (code >> 4) & 0x07;
+    let magnitude = ((((code & 0x0f) as i16) << 3) + BIAS) << exponent;
+    if code & 0x80 != 0 {
+        BIAS - magnitude
+    } else {
+        magnitude - BIAS
+    }
+}
+
+/// Reader given to [`Decoder::new_pcm`](super::Decoder::new_pcm), which only needs to read
+/// the data. Seeking in it always fails, so the decoder can only skip forward.
+pub struct PcmReader<R>(R);
+
+impl<R> PcmReader<R> {
+    /// Returns the wrapped reader.
+    #[inline]
+    pub fn into_inner(self) -> R {
+        self.0
+    }
+}
+
+impl<R: Read> Read for PcmReader<R> {
+    #[inline]
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.0.read(buf)
+    }
+}
+
+impl<R> Seek for PcmReader<R> {
+    #[inline]
+    fn seek(&mut self, _: SeekFrom) -> io::Result<u64> {
+        Err(io::Error::from(ErrorKind::Unsupported))
+    }
+}
+
+impl<R: Read> PcmDecoder<PcmReader<R>> {
+    /// Like [`PcmDecoder::new`] for data that can only be read.
+    pub fn from_reader(
+        data: R,
+        sample_rate: SampleRate,
+        channels: ChannelCount,
+        format: SampleFormat,
+    ) -> Option<PcmDecoder<PcmReader<R>>> {
+        PcmDecoder::new(PcmReader(data), sample_rate, channels, format).ok()
+    }
+}
+
+/// Decoder for raw interleaved PCM without any header.
+pub struct PcmDecoder<R>
+where
+    R: Read + Seek,
+{
+    reader: R,
+    format: SampleFormat,
+    sample_rate: SampleRate,
+    channels: ChannelCount,
+    // Whether the reader could tell its position, otherwise it can not seek.
+    seekable: bool,
+    // Position of the first sample in the reader.
+    start: u64,
+    // Number of whole frames in the data, if the end could be found.
+    total_frames: Option<u64>,
+    // Position in the reader where the data ends, if it does not go on to the end.
+    end: Option<u64>,
+    // Position in the reader of the next byte to read.
+    read_pos: u64,
+    // Raw bytes read but not decoded yet, never more than one partial frame between reads.
+    bytes: Vec<u8>,
+    decoded: Vec<DecoderSample>,
+    // Position of the next sample to return in `decoded`.
+    pos: usize,
+}
+
+impl<R> PcmDecoder<R>
+where
+    R: Read + Seek,
+{
+    /// Wraps `data` which must be positioned at the first sample. Returns the data back if
+    /// `sample_rate` or `channels` are zero.
+    pub fn new(
+        mut data: R,
+        sample_rate: SampleRate,
+        channels: ChannelCount,
+        format: SampleFormat,
+    ) -> Result<PcmDecoder<R>, R> {
+        if sample_rate == 0 || channels == 0 {
+            return Err(data);
+        }
+
+        let position = data.stream_position();
+        let seekable = position.is_ok();
+        let start = position.unwrap_or(0);
+        let total_frames = data.seek(SeekFrom::End(0)).ok().and_then(|end| {
+            data.seek(SeekFrom::Start(start)).ok()?;
+            let frame_len = (format.sample_size() * channels as usize) as u64;
+            Some(end.saturating_sub(start) / frame_len)
+        });
+
+        Ok(PcmDecoder {
+            reader: data,
+            format,
+            sample_rate,
+            channels,
+            seekable,
+            start,
+            total_frames,
+            end: None,
+            read_pos: start,
+            bytes: Vec::new(),
+            decoded: Vec::new(),
+            pos: 0,
+        })
+    }
+
+    /// Stops decoding after `len` bytes of data, for data that is followed by something else.
+    #[cfg(all(feature = "wav", not(feature = "symphonia-wav")))]
+    pub(crate) fn with_data_len(mut self, len: u64) -> PcmDecoder<R> {
+        let end = self.start + len;
+        self.end = Some(end);
+        let frame_len = self.frame_len() as u64;
+        self.total_frames = Some(
+            self.total_frames
+                .map_or(len, |frames| (frames * frame_len).min(len))
+                / frame_len,
+        );
+        self
+    }
+
+    /// Returns the underlying reader.
+    #[cfg(all(feature = "wav", not(feature = "symphonia-wav")))]
+    #[inline]
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+
+    // Reads up to `offset` and drops what was read, for readers that can not seek.
+    fn skip_to(&mut self, offset: u64) -> Result<(), SeekError> {
+        let size = self.format.sample_size();
+        let decoded = (self.decoded.len() - self.pos) * size;
+        let current = self.read_pos - (decoded + self.bytes.len()) as u64;
+        if offset < current {
+            return Err(SeekError::NotSupported {
+                underlying_source: std::any::type_name::<Self>(),
+            });
+        }
+
+        let mut skip = offset - current;
+        if skip < decoded as u64 {
+            self.pos += skip as usize / size;
+            return Ok(());
+        }
+        skip -= decoded as u64;
+        self.decoded.clear();
+        self.pos = 0;
+        if skip < self.bytes.len() as u64 {
+            self.bytes.drain(..skip as usize);
+            return Ok(());
+        }
+        skip -= self.bytes.len() as u64;
+        self.bytes.clear();
+
+        let skipped = io::copy(&mut self.reader.by_ref().take(skip), &mut io::sink())
+            .map_err(|e| SeekError::Other(Box::new(e)))?;
+        self.read_pos += skipped;
+        Ok(())
+    }
+
+    #[inline]
+    fn frame_len(&self) -> usize {
+        self.format.sample_size() * self.channels as usize
+    }
+
+    /// Reads and decodes at least one whole frame. Returns false at the end of the data, a
+    /// partial frame remaining at that point is discarded.
+    fn refill(&mut self) -> bool {
+        let frame_len = self.frame_len();
+        let mut filled = self.bytes.len();
+        let mut len = frame_len * FRAMES_PER_READ;
+        if let Some(end) = self.end {
+            let left = end.saturating_sub(self.read_pos) as usize;
+            len = len.min(filled + left);
+        }
+        self.bytes.resize(len, 0);
+
+        // Stop as soon as a whole frame is available so that slow readers such as sockets
+        // do not have to fill the entire buffer before anything plays.
+        while filled < frame_len {
+            match self.reader.read(&mut self.bytes[filled..]) {
+                Ok(0) => break,
+                Ok(n) => {
+                    filled += n;
+                    self.read_pos += n as u64;
+                }
+                Err(e) if e.kind() == ErrorKind::Interrupted => {}
+                Err(_) => break,
+            }
+        }
+
+        let complete = filled - filled % frame_len;
+        let size = self.format.sample_size();
+        let format = self.format;
+        self.decoded.clear();
+        self.decoded.extend(
+            self.bytes[..complete]
+                .chunks_exact(size)
+                .map(|bytes| format.decode(bytes)),
+        );
+        self.pos = 0;
+
+        self.bytes.copy_within(complete..filled, 0);
+        self.bytes.truncate(filled - complete);
+        complete > 0
+    }
+}
+
+impl<R> Iterator for PcmDecoder<R>
+where
+    R: Read + Seek,
+{
+    type Item = DecoderSample;
+
+    #[inline]
+    fn next(&mut self) -> Option<DecoderSample> {
+        if self.pos == self.decoded.len() && !self.refill() {
+            self.bytes.clear();
+            return None;
+        }
+
+        let sample = self.decoded[self.pos];
+        self.pos += 1;
+        Some(sample)
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.decoded.len() - self.pos, None)
+    }
+}
+
+impl<R> Source for PcmDecoder<R>
+where
+    R: Read + Seek,
+{
+    #[inline]
+    fn current_span_len(&self) -> Option<usize> {
+        None
+    }
+
+    #[inline]
+    fn channels(&self) -> ChannelCount {
+        self.channels
+    }
+
+    #[inline]
+    fn sample_rate(&self) -> SampleRate {
+        self.sample_rate
+    }
+
+    #[inline]
+    fn total_duration(&self) -> Option<Duration> {
+        let frames = self.total_frames?;
+        Some(Duration::from_secs_f64(
+            frames as f64 / self.sample_rate as f64,
+        ))
+    }
+
+    #[inline]
+    fn total_samples(&self) -> Option<u64> {
+        Some(self.total_frames? * self.channels as u64)
+    }
+
+    #[inline]
+    fn try_seek(&mut self, pos: Duration) -> Result<(), SeekError> {
+        let frame = pos.as_nanos() * self.sample_rate as u128 / 1_000_000_000;
+        self.try_seek_frames(frame as u64)
+    }
+
+    /// Seeks to `frame`. Seeking past the end leaves nothing to play. A reader that can not
+    /// seek can only skip forward.
+    fn try_seek_frames(&mut self, frame: u64) -> Result<(), SeekError> {
+        let offset = self.start + frame * self.frame_len() as u64;
+        if !self.seekable {
+            return self.skip_to(offset);
+        }
+        self.reader
+            .seek(SeekFrom::Start(offset))
+            .map_err(|e| SeekError::Other(Box::new(e)))?;
+        self.read_pos = offset;
+
+        self.bytes.clear();
+        self.decoded.clear();
+        self.pos = 0;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+    use std::time::Duration;
+
+    use super::{PcmDecoder, SampleFormat};
+    use crate::decoder::DecoderSample;
+    use crate::Source;
+    use dasp_sample::Sample;
+
+    fn decode(bytes: Vec<u8>, channels: u16, format: SampleFormat) -> Vec<DecoderSample> {
+        PcmDecoder::new(Cursor::new(bytes), 8000, channels, format)
+            .ok()
+            .unwrap()
+            .collect()
+    }
+
+    #[test]
+    fn decodes_declared_format() {
+        let samples = [0i16, i16::MAX, i16::MIN, 256];
+        let le: Vec<u8> = samples.iter().flat_map(|s| s.to_le_bytes()).collect();
+        let be: Vec<u8> = samples.iter().flat_map(|s| s.to_be_bytes()).collect();
+        let expected: Vec<DecoderSample> = samples.iter().map(|s| s.to_sample()).collect();
+        assert_eq!(decode(le, 2, SampleFormat::I16Le), expected);
+        assert_eq!(decode(be, 2, SampleFormat::I16Be), expected);
+
+        let floats = [0.0f32, 0.5, -0.25, 1.0];
+        let le: Vec<u8> = floats.iter().flat_map(|s| s.to_le_bytes()).collect();
+        let expected: Vec<DecoderSample> = floats.iter().map(|s| s.to_sample()).collect();
+        assert_eq!(decode(le, 1, SampleFormat::F32Le), expected);
+        let be: Vec<u8> = floats
+            .iter()
+            .flat_map(|s| (*s as f64).to_be_bytes())
+            .collect();
+        assert_eq!(decode(be, 1, SampleFormat::F64Be), expected);
+
+        // -2 and 1 in 24 bit
+        let packed = vec![0xfe, 0xff, 0xff, 0x01, 0x00, 0x00];
+        let expected: Vec<DecoderSample> =
+            [-2i32 << 8, 1 << 8].iter().map(|s| s.to_sample()).collect();
+        assert_eq!(decode(packed, 1, SampleFormat::I24Le), expected);
+    }
+
+    #[test]
+    fn partial_trailing_frame_is_dropped() {
+        // two stereo frames of 16 bit samples followed by three stray bytes
+        let mut bytes: Vec<u8> = [1i16, 2, 3, 4]
+            .iter()
+            .flat_map(|s| s.to_le_bytes())
+            .collect();
+        bytes.extend([9, 9, 9]);
+        assert_eq!(decode(bytes, 2, SampleFormat::I16Le).len(), 4);
+    }
+
+    #[test]
+    fn reads_data_that_can_not_seek() {
+        let bytes: Vec<u8> = [1i16, 2, 3].iter().flat_map(|s| s.to_le_bytes()).collect();
+        let mut decoder =
+            PcmDecoder::from_reader(&bytes[..], 8000, 1, SampleFormat::I16Le).unwrap();
+        assert_eq!(decoder.total_duration(), None);
+        assert_eq!(decoder.next(), Some(1i16.to_sample()));
+        // only skipping forward is possible
+        decoder.try_seek(Duration::from_secs(1) / 4000).unwrap();
+        assert!(decoder.try_seek(Duration::ZERO).is_err());
+        assert_eq!(decoder.count(), 1);
+    }
+
+    #[test]
+    fn zero_rate_or_channels_is_rejected() {
+        assert!(PcmDecoder::from_reader(&[0u8; 4][..], 0, 1, SampleFormat::I16Le).is_none());
+        assert!(PcmDecoder::from_reader(&[0u8; 4][..], 8000, 0, SampleFormat::I16Le).is_none());
+    }
+
+    #[test]
+    fn seek_and_duration() {
+        let bytes: Vec<u8> = (0..8000i16).flat_map(|s| s.to_le_bytes()).collect();
+        let mut decoder = PcmDecoder::new(Cursor::new(bytes), 8000, 1, SampleFormat::I16Le)
+            .ok()
+            .unwrap();
+        assert_eq!(decoder.total_duration(), Some(Duration::from_secs(1)));
+        assert_eq!(decoder.total_samples(), Some(8000));
+
+        decoder.try_seek(Duration::from_millis(500)).unwrap();
+        assert_eq!(decoder.next(), Some(4000i16.to_sample()));
+    }
+}
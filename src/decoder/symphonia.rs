@@ -5,17 +5,17 @@ use symphonia::{
         audio::{AudioBufferRef, SampleBuffer, SignalSpec},
         codecs::{Decoder, DecoderOptions, CODEC_TYPE_NULL},
         errors::Error,
-        formats::{FormatOptions, FormatReader, SeekedTo},
+        formats::{FormatOptions, FormatReader, Packet, SeekedTo},
         io::MediaSourceStream,
-        meta::MetadataOptions,
+        meta::{MetadataOptions, StandardTagKey},
         probe::Hint,
         units::{self, Time},
     },
     default::get_probe,
 };
 
-use super::{DecoderError, DecoderSample};
-use crate::common::{ChannelCount, SampleRate};
+use super::{CuePoint, DecoderError, DecoderSample, TrackInfo};
+use crate::common::{frames_to_duration, ChannelCount, SampleRate};
 use crate::{source, Source};
 
 // Decoder errors are not considered fatal.
@@ -27,17 +27,21 @@ pub(crate) struct SymphoniaDecoder {
     decoder: Box<dyn Decoder>,
     current_span_offset: usize,
     format: Box<dyn FormatReader>,
+    track_id: u32,
     total_duration: Option<Time>,
     buffer: SampleBuffer<DecoderSample>,
     spec: SignalSpec,
+    // Markers Symphonia does not read, such as those of the `cue ` chunk of a WAV file.
+    extra_cues: Vec<CuePoint>,
 }
 
 impl SymphoniaDecoder {
     pub(crate) fn new(
         mss: MediaSourceStream,
         extension: Option<&str>,
+        track: Option<u32>,
     ) -> Result<Self, DecoderError> {
-        match SymphoniaDecoder::init(mss, extension) {
+        match SymphoniaDecoder::init(mss, extension, track) {
             Err(e) => match e {
                 Error::IoError(e) => Err(DecoderError::IoError(e.to_string())),
                 Error::DecodeError(e) => Err(DecoderError::DecodeError(e)),
@@ -49,18 +53,29 @@ impl SymphoniaDecoder {
                 Error::ResetRequired => Err(DecoderError::ResetRequired),
             },
             Ok(Some(decoder)) => Ok(decoder),
-            Ok(None) => Err(DecoderError::NoStreams),
+            Ok(None) => match track {
+                Some(id) => Err(DecoderError::UnknownTrack(id)),
+                None => Err(DecoderError::NoStreams),
+            },
         }
     }
 
+    #[inline]
+    pub(crate) fn track_id(&self) -> u32 {
+        self.track_id
+    }
+
     #[inline]
     pub(crate) fn into_inner(self) -> MediaSourceStream {
         self.format.into_inner()
     }
 
-    fn init(
+    /// Decodes the track with the id `track`, or the first track with a supported codec.
+    /// Returns `None` if there is no such track.
+    pub(crate) fn init(
         mss: MediaSourceStream,
         extension: Option<&str>,
+        track: Option<u32>,
     ) -> symphonia::core::errors::Result<Option<SymphoniaDecoder>> {
         let mut hint = Hint::new();
         if let Some(ext) = extension {
@@ -73,38 +88,38 @@ impl SymphoniaDecoder {
         let metadata_opts: MetadataOptions = Default::default();
         let mut probed = get_probe().format(&hint, mss, &format_opts, &metadata_opts)?;
 
-        let stream = match probed.format.default_track() {
-            Some(stream) => stream,
-            None => return Ok(None),
+        if probed.format.tracks().is_empty() {
+            return Ok(None);
+        }
+        let track = match track {
+            Some(id) => probed
+                .format
+                .tracks()
+                .iter()
+                .find(|t| t.id == id && t.codec_params.codec != CODEC_TYPE_NULL),
+            // Select the first supported track
+            None => Some(
+                probed
+                    .format
+                    .tracks()
+                    .iter()
+                    .find(|t| t.codec_params.codec != CODEC_TYPE_NULL)
+                    .ok_or(symphonia::core::errors::Error::Unsupported(
+                        "No track with supported codec",
+                    ))?,
+            ),
         };
-
-        // Select the first supported track
-        let track_id = probed
-            .format
-            .tracks()
-            .iter()
-            .find(|t| t.codec_params.codec != CODEC_TYPE_NULL)
-            .ok_or(symphonia::core::errors::Error::Unsupported(
-                "No track with supported codec",
-            ))?
-            .id;
-
-        let track = match probed
-            .format
-            .tracks()
-            .iter()
-            .find(|track| track.id == track_id)
-        {
-            Some(track) => track,
-            None => return Ok(None),
+        let Some(track) = track else {
+            return Ok(None);
         };
+        let track_id = track.id;
 
         let mut decoder = symphonia::default::get_codecs()
             .make(&track.codec_params, &DecoderOptions::default())?;
-        let total_duration = stream
+        let total_duration = track
             .codec_params
             .time_base
-            .zip(stream.codec_params.n_frames)
+            .zip(track.codec_params.n_frames)
             .map(|(base, spans)| base.calc_time(spans));
 
         let mut decode_errors: usize = 0;
@@ -141,12 +156,79 @@ impl SymphoniaDecoder {
             decoder,
             current_span_offset: 0,
             format: probed.format,
+            track_id,
             total_duration,
             buffer,
             spec,
+            extra_cues: Vec::new(),
         }))
     }
 
+    /// Lists the tracks with a supported codec.
+    pub(crate) fn tracks(&self) -> Vec<TrackInfo> {
+        self.format
+            .tracks()
+            .iter()
+            .filter(|track| track.codec_params.codec != CODEC_TYPE_NULL)
+            .map(|track| {
+                let params = &track.codec_params;
+                TrackInfo {
+                    id: track.id,
+                    channels: params.channels.map(|c| c.count() as ChannelCount),
+                    sample_rate: params.sample_rate,
+                    total_duration: params
+                        .time_base
+                        .zip(params.n_frames)
+                        .map(|(base, frames)| time_to_duration(base.calc_time(frames))),
+                    language: track.language.clone(),
+                }
+            })
+            .collect()
+    }
+
+    /// Adds markers found outside of Symphonia, given as frames and labels.
+    #[cfg(feature = "symphonia-wav")]
+    pub(crate) fn add_cues(&mut self, cues: Vec<(u64, Option<String>)>) {
+        let sample_rate = self.sample_rate();
+        self.extra_cues
+            .extend(cues.into_iter().map(|(frame, label)| CuePoint {
+                timestamp: frames_to_duration(frame, sample_rate),
+                label,
+            }));
+    }
+
+    /// Returns the cues of the container, such as the cue sheet of a FLAC file, and those
+    /// added with [`add_cues`](Self::add_cues), ordered by position. The label of a cue is
+    /// its title tag.
+    pub(crate) fn cue_points(&self) -> Vec<CuePoint> {
+        let mut cues: Vec<CuePoint> = self
+            .format
+            .cues()
+            .iter()
+            .map(|cue| CuePoint {
+                timestamp: frames_to_duration(cue.start_ts, self.sample_rate()),
+                label: cue
+                    .tags
+                    .iter()
+                    .find(|tag| tag.std_key == Some(StandardTagKey::TrackTitle))
+                    .map(|tag| tag.value.to_string()),
+            })
+            .chain(self.extra_cues.iter().cloned())
+            .collect();
+        cues.sort_by_key(|cue| cue.timestamp);
+        cues
+    }
+
+    /// Returns the next packet of the decoded track, skipping those of the other tracks.
+    fn next_packet(&mut self) -> symphonia::core::errors::Result<Packet> {
+        loop {
+            let packet = self.format.next_packet()?;
+            if packet.track_id() == self.track_id {
+                return Ok(packet);
+            }
+        }
+    }
+
     #[inline]
     fn get_buffer(decoded: AudioBufferRef, spec: &SignalSpec) -> SampleBuffer<DecoderSample> {
         let duration = units::Duration::from(decoded.capacity() as u64);
@@ -200,7 +282,7 @@ impl Source for SymphoniaDecoder {
                 SeekMode::Accurate,
                 SeekTo::Time {
                     time,
-                    track_id: None,
+                    track_id: Some(self.track_id),
                 },
             )
             .map_err(SeekError::BaseSeek)?;
@@ -270,7 +352,7 @@ impl SymphoniaDecoder {
     fn refine_position(&mut self, seek_res: SeekedTo) -> Result<(), source::SeekError> {
         let mut samples_to_pass = seek_res.required_ts - seek_res.actual_ts;
         let packet = loop {
-            let candidate = self.format.next_packet().map_err(SeekError::Refining)?;
+            let candidate = self.next_packet().map_err(SeekError::Refining)?;
             if candidate.dur() > samples_to_pass {
                 break candidate;
             } else {
@@ -281,7 +363,7 @@ impl SymphoniaDecoder {
         let mut decoded = self.decoder.decode(&packet);
         for _ in 0..MAX_DECODE_RETRIES {
             if decoded.is_err() {
-                let packet = self.format.next_packet().map_err(SeekError::Retrying)?;
+                let packet = self.next_packet().map_err(SeekError::Retrying)?;
                 decoded = self.decoder.decode(&packet);
             }
         }
@@ -325,11 +407,11 @@ impl Iterator for SymphoniaDecoder {
     #[inline]
     fn next(&mut self) -> Option<Self::Item> {
         if self.current_span_offset >= self.buffer.len() {
-            let packet = self.format.next_packet().ok()?;
+            let packet = self.next_packet().ok()?;
             let mut decoded = self.decoder.decode(&packet);
             for _ in 0..MAX_DECODE_RETRIES {
                 if decoded.is_err() {
-                    let packet = self.format.next_packet().ok()?;
+                    let packet = self.next_packet().ok()?;
                     decoded = self.decoder.decode(&packet);
                 }
             }
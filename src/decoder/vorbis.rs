@@ -11,11 +11,17 @@ use lewton::samples::InterleavedSamples;
 use super::DecoderSample;
 
 /// Decoder for an OGG file that contains Vorbis sound format.
+///
+/// Chained files, in which one logical stream follows another, for example recordings of
+/// internet radio, are played to the end. The channel count and sample rate can change at the
+/// start of each stream, a new span starts there.
 pub struct VorbisDecoder<R>
 where
     R: Read + Seek,
 {
     stream_reader: OggStreamReader<R>,
+    // Samples of the packet being played, in the format of the current stream, and the
+    // position of the next one.
     current_data: Vec<DecoderSample>,
     next: usize,
 }
@@ -48,17 +54,38 @@ where
             data.append(&mut d.samples);
         }
 
-        VorbisDecoder {
+        let mut decoder = VorbisDecoder {
             stream_reader,
             current_data: data,
             next: 0,
+        };
+        if decoder.current_data.is_empty() {
+            decoder.read_packet();
         }
+        decoder
     }
 
     #[inline]
     pub fn into_inner(self) -> OggStreamReader<R> {
         self.stream_reader
     }
+
+    // Replaces the played samples with the next packet that has any, or with nothing at the
+    // end of the data. Reading the first packet of a chained stream switches the headers,
+    // and with them the format, to that stream.
+    fn read_packet(&mut self) {
+        self.current_data.clear();
+        self.next = 0;
+        while let Ok(Some(data)) = self
+            .stream_reader
+            .read_dec_packet_generic::<InterleavedSamples<DecoderSample>>()
+        {
+            if !data.samples.is_empty() {
+                self.current_data = data.samples;
+                return;
+            }
+        }
+    }
 }
 
 impl<R> Source for VorbisDecoder<R>
@@ -67,7 +94,7 @@ where
 {
     #[inline]
     fn current_span_len(&self) -> Option<usize> {
-        Some(self.current_data.len())
+        Some(self.current_data.len() - self.next)
     }
 
     #[inline]
@@ -110,35 +137,18 @@ where
 
     #[inline]
     fn next(&mut self) -> Option<Self::Item> {
-        if let Some(sample) = self.current_data.get(self.next).copied() {
-            self.next += 1;
-            if self.current_data.is_empty() {
-                if let Ok(Some(data)) = self
-                    .stream_reader
-                    .read_dec_packet_generic::<InterleavedSamples<DecoderSample>>()
-                {
-                    self.current_data = data.samples;
-                    self.next = 0;
-                }
-            }
-            Some(sample)
-        } else {
-            if let Ok(Some(data)) = self
-                .stream_reader
-                .read_dec_packet_generic::<InterleavedSamples<DecoderSample>>()
-            {
-                self.current_data = data.samples;
-                self.next = 0;
-            }
-            let sample = self.current_data.get(self.next).copied();
-            self.next += 1;
-            sample
+        let sample = *self.current_data.get(self.next)?;
+        self.next += 1;
+        // Read ahead so that the format of the next span is known before it starts.
+        if self.next >= self.current_data.len() {
+            self.read_packet();
         }
+        Some(sample)
     }
 
     #[inline]
     fn size_hint(&self) -> (usize, Option<usize>) {
-        (self.current_data.len(), None)
+        (self.current_data.len() - self.next, None)
     }
 }
 
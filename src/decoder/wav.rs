@@ -4,22 +4,47 @@ use std::time::Duration;
 use crate::source::SeekError;
 use crate::Source;
 
-use crate::common::{ChannelCount, SampleRate};
+use crate::common::{frames_to_duration, ChannelCount, SampleRate};
 
 use dasp_sample::{Sample, I24};
 use hound::{SampleFormat, WavReader};
 
-use super::DecoderSample;
+use super::cues::read_cues;
+use super::pcm::{self, PcmDecoder};
+use super::{CuePoint, DecoderSample};
 
 /// Decoder for the WAV format.
+///
+/// Reads integer PCM, 32 and 64 bit IEEE float and G.711 A-law and mu-law samples, also from
+/// files in the `WAVE_FORMAT_EXTENSIBLE` format written by many DAWs.
+///
+/// The lengths in the header of a WAV file that is still being written, such as a recording
+/// in progress, are usually zero, a placeholder or out of date. In that case the data is
+/// read until the end of the reader instead.
+///
+/// Markers in the `cue ` chunk are read as cue points, with the names of the `labl` chunks of
+/// the `adtl` list as their labels.
 pub struct WavDecoder<R>
 where
     R: Read + Seek,
 {
-    reader: SamplesIterator<R>,
-    total_duration: Duration,
+    samples: Samples<R>,
+    total_duration: Option<Duration>,
     sample_rate: SampleRate,
     channels: ChannelCount,
+    cue_points: Vec<CuePoint>,
+}
+
+enum Samples<R>
+where
+    R: Read + Seek,
+{
+    // The lengths in the header are final.
+    Header(SamplesIterator<R>),
+    // The lengths in the header are not final, the data goes on until the end of the reader.
+    Growing(PcmDecoder<R>),
+    // A format hound does not read correctly, decoded up to the end of the data chunk.
+    Raw(PcmDecoder<R>),
 }
 
 impl<R> WavDecoder<R>
@@ -28,6 +53,36 @@ where
 {
     /// Attempts to decode the data as WAV.
     pub fn new(mut data: R) -> Result<WavDecoder<R>, R> {
+        let cues = read_cues(data.by_ref());
+        let cue_points = |sample_rate: SampleRate| {
+            cues.into_iter()
+                .map(|(frame, label)| CuePoint {
+                    timestamp: frames_to_duration(frame, sample_rate),
+                    label,
+                })
+                .collect()
+        };
+
+        if let Some(header) = own_header(data.by_ref()) {
+            let decoder =
+                PcmDecoder::new(data, header.sample_rate, header.channels, header.format)?;
+            let (samples, total_duration) = match header.data_len {
+                Some(len) => {
+                    let decoder = decoder.with_data_len(len);
+                    let total_duration = decoder.total_duration();
+                    (Samples::Raw(decoder), total_duration)
+                }
+                None => (Samples::Growing(decoder), None),
+            };
+            return Ok(WavDecoder {
+                samples,
+                total_duration,
+                sample_rate: header.sample_rate,
+                channels: header.channels,
+                cue_points: cue_points(header.sample_rate),
+            });
+        }
+
         if !is_wave(data.by_ref()) {
             return Err(data);
         }
@@ -51,16 +106,26 @@ where
         };
 
         Ok(WavDecoder {
-            reader,
-            total_duration,
+            samples: Samples::Header(reader),
+            total_duration: Some(total_duration),
             sample_rate: sample_rate as SampleRate,
             channels: channels as ChannelCount,
+            cue_points: cue_points(sample_rate),
         })
     }
 
+    /// Returns the markers of the file, ordered by their position.
+    #[inline]
+    pub(crate) fn cue_points(&self) -> &[CuePoint] {
+        &self.cue_points
+    }
+
     #[inline]
     pub fn into_inner(self) -> R {
-        self.reader.reader.into_inner()
+        match self.samples {
+            Samples::Header(samples) => samples.reader.into_inner(),
+            Samples::Growing(decoder) | Samples::Raw(decoder) => decoder.into_inner(),
+        }
     }
 }
 
@@ -174,25 +239,51 @@ where
 
     #[inline]
     fn total_duration(&self) -> Option<Duration> {
-        Some(self.total_duration)
+        self.total_duration
+    }
+
+    #[inline]
+    fn total_samples(&self) -> Option<u64> {
+        match &self.samples {
+            Samples::Header(samples) => {
+                Some(samples.reader.duration() as u64 * self.channels as u64)
+            }
+            Samples::Raw(decoder) => decoder.total_samples(),
+            Samples::Growing(_) => None,
+        }
     }
 
     #[inline]
     fn try_seek(&mut self, pos: Duration) -> Result<(), SeekError> {
-        let file_len = self.reader.reader.duration();
+        let frame = pos.as_nanos() * self.sample_rate() as u128 / 1_000_000_000;
+        self.try_seek_frames(frame as u64)
+    }
 
-        let new_pos = pos.as_secs_f32() * self.sample_rate() as f32;
-        let new_pos = new_pos as u32;
-        let new_pos = new_pos.min(file_len); // saturate pos at the end of the source
+    /// Seeks to `frame`, saturating at the end of the file. The next sample is for the same
+    /// channel as before the seek.
+    ///
+    /// If the lengths in the header were not final, seeking past the end leaves nothing to
+    /// play and the next sample is for the first channel.
+    fn try_seek_frames(&mut self, frame: u64) -> Result<(), SeekError> {
+        let channels = self.channels() as u32;
+        let samples = match &mut self.samples {
+            Samples::Header(samples) => samples,
+            Samples::Growing(decoder) | Samples::Raw(decoder) => {
+                return decoder.try_seek_frames(frame)
+            }
+        };
+        let file_len = samples.reader.duration();
+        // saturate pos at the end of the source
+        let new_pos = frame.min(file_len as u64) as u32;
 
         // make sure the next sample is for the right channel
-        let to_skip = self.reader.samples_read % self.channels() as u32;
+        let to_skip = samples.samples_read % channels;
 
-        self.reader
+        samples
             .reader
             .seek(new_pos)
             .map_err(SeekError::HoundDecoder)?;
-        self.reader.samples_read = new_pos * self.channels() as u32;
+        samples.samples_read = new_pos * channels;
 
         for _ in 0..to_skip {
             self.next();
@@ -210,17 +301,21 @@ where
 
     #[inline]
     fn next(&mut self) -> Option<Self::Item> {
-        self.reader.next()
+        match &mut self.samples {
+            Samples::Header(samples) => samples.next(),
+            Samples::Growing(decoder) | Samples::Raw(decoder) => decoder.next(),
+        }
     }
 
     #[inline]
     fn size_hint(&self) -> (usize, Option<usize>) {
-        self.reader.size_hint()
+        match &self.samples {
+            Samples::Header(samples) => samples.size_hint(),
+            Samples::Growing(decoder) | Samples::Raw(decoder) => decoder.size_hint(),
+        }
     }
 }
 
-impl<R> ExactSizeIterator for WavDecoder<R> where R: Read + Seek {}
-
 /// Returns true if the stream contains WAV data, then resets it to where it was.
 fn is_wave<R>(mut data: R) -> bool
 where
@@ -231,3 +326,166 @@ where
     let _ = data.seek(SeekFrom::Start(stream_pos));
     result
 }
+
+/// Format of a WAV file that is decoded without hound.
+struct OwnHeader {
+    format: pcm::SampleFormat,
+    sample_rate: SampleRate,
+    channels: ChannelCount,
+    // Length of the data chunk, `None` if the lengths in the header are not final.
+    data_len: Option<u64>,
+}
+
+/// The sub format GUIDs of `WAVE_FORMAT_EXTENSIBLE` after their first two bytes, which hold
+/// the format tag.
+const SUB_FORMAT_GUID_END: [u8; 14] = [
+    0x00, 0x00, 0x00, 0x00, 0x10, 0x00, 0x80, 0x00, 0x00, 0xaa, 0x00, 0x38, 0x9b, 0x71,
+];
+
+/// Returns the format of the WAV in `data` if it is decoded without hound and leaves `data`
+/// at the first sample. Otherwise resets `data` to where it was.
+///
+/// That is the case if the lengths in its header are not final, or if hound does not read its
+/// format correctly: 64 bit floats, G.711 A-law and mu-law, and `WAVE_FORMAT_EXTENSIBLE`
+/// integers with fewer valid bits than their container, which hold the sample in the upper
+/// bits.
+///
+/// The lengths are not final if the data length is zero, if the data goes past the end of
+/// the stream or if the stream grew past the end of the file given in the header while the
+/// data was the last chunk.
+fn own_header<R>(mut data: R) -> Option<OwnHeader>
+where
+    R: Read + Seek,
+{
+    let stream_pos = data.stream_position().ok()?;
+    let header = read_own_header(data.by_ref(), stream_pos);
+    if header.is_none() {
+        let _ = data.seek(SeekFrom::Start(stream_pos));
+    }
+    header
+}
+
+// Whether a chunk that fits in the RIFF chunk starts at `pos`.
+fn chunk_at<R>(mut data: R, pos: u64, riff_end: u64) -> bool
+where
+    R: Read + Seek,
+{
+    let mut chunk = [0; 8];
+    if data.seek(SeekFrom::Start(pos)).is_err() || data.read_exact(&mut chunk).is_err() {
+        return false;
+    }
+    let len = u32::from_le_bytes([chunk[4], chunk[5], chunk[6], chunk[7]]) as u64;
+    chunk[..4]
+        .iter()
+        .all(|&b| b.is_ascii_alphanumeric() || b == b' ')
+        && pos + 8 + len <= riff_end
+}
+
+fn read_own_header<R>(mut data: R, start: u64) -> Option<OwnHeader>
+where
+    R: Read + Seek,
+{
+    fn read_array<const N: usize>(mut data: impl Read) -> Option<[u8; N]> {
+        let mut bytes = [0; N];
+        data.read_exact(&mut bytes).ok()?;
+        Some(bytes)
+    }
+    let read_u16 = |bytes: &[u8], at: usize| u16::from_le_bytes([bytes[at], bytes[at + 1]]);
+    let read_u32 = |bytes: &[u8], at: usize| {
+        u32::from_le_bytes([bytes[at], bytes[at + 1], bytes[at + 2], bytes[at + 3]])
+    };
+
+    let riff: [u8; 12] = read_array(data.by_ref())?;
+    if &riff[..4] != b"RIFF" || &riff[8..] != b"WAVE" {
+        return None;
+    }
+    let riff_len = read_u32(&riff, 4);
+    let riff_end = start + 8 + riff_len as u64;
+
+    let mut header = None;
+    let mut unsupported_by_hound = false;
+    let data_len = loop {
+        let chunk: [u8; 8] = read_array(data.by_ref())?;
+        let len = read_u32(&chunk, 4);
+        match &chunk[..4] {
+            b"fmt " if len >= 16 => {
+                let fmt: [u8; 16] = read_array(data.by_ref())?;
+                let mut tag = read_u16(&fmt, 0);
+                let mut skip = len as i64 - 16;
+                let channels = read_u16(&fmt, 2);
+                let sample_rate = read_u32(&fmt, 4);
+                let block_align = read_u16(&fmt, 12);
+                if channels == 0 || sample_rate == 0 || block_align % channels != 0 {
+                    return None;
+                }
+                let container = block_align / channels;
+                // WAVE_FORMAT_EXTENSIBLE, the actual format is in the sub format
+                if tag == 0xfffe && len >= 40 {
+                    let extension: [u8; 24] = read_array(data.by_ref())?;
+                    if extension[10..] != SUB_FORMAT_GUID_END {
+                        return None;
+                    }
+                    tag = read_u16(&extension, 8);
+                    let valid_bits = read_u16(&extension, 2);
+                    unsupported_by_hound |= tag == 1 && valid_bits < container * 8;
+                    skip -= 24;
+                }
+                let format = match (tag, container) {
+                    (1, 1) => pcm::SampleFormat::U8,
+                    (1, 2) => pcm::SampleFormat::I16Le,
+                    (1, 3) => pcm::SampleFormat::I24Le,
+                    (1, 4) => pcm::SampleFormat::I32Le,
+                    (3, 4) => pcm::SampleFormat::F32Le,
+                    (3, 8) => {
+                        unsupported_by_hound = true;
+                        pcm::SampleFormat::F64Le
+                    }
+                    (6, 1) => {
+                        unsupported_by_hound = true;
+                        pcm::SampleFormat::ALaw
+                    }
+                    (7, 1) => {
+                        unsupported_by_hound = true;
+                        pcm::SampleFormat::MuLaw
+                    }
+                    _ => return None,
+                };
+                header = Some(OwnHeader {
+                    format,
+                    sample_rate,
+                    channels,
+                    data_len: None,
+                });
+                data.seek(SeekFrom::Current(skip + (len & 1) as i64)).ok()?;
+            }
+            b"data" => break len,
+            _ => {
+                data.seek(SeekFrom::Current(len as i64 + (len & 1) as i64))
+                    .ok()?;
+            }
+        }
+    };
+
+    // Writers that could not finish the file leave the sizes at a placeholder, or only kept
+    // the size of the RIFF chunk up to date. Anything after the RIFF chunk, like an ID3 tag,
+    // is not part of the data.
+    let placeholder = |len: u32| len == 0 || len == u32::MAX;
+    let data_start = data.stream_position().ok()?;
+    let data_end = data_start + data_len as u64;
+    let stream_end = data.seek(SeekFrom::End(0)).ok()?;
+    let chunk_end = data_end + (data_len & 1) as u64;
+    let unfinished = placeholder(data_len)
+        || data_end > stream_end
+        || (!placeholder(riff_len)
+            && chunk_end < riff_end.min(stream_end)
+            && !chunk_at(data.by_ref(), chunk_end, riff_end));
+    if !unfinished && !unsupported_by_hound {
+        return None;
+    }
+    data.seek(SeekFrom::Start(data_start)).ok()?;
+    let mut header = header?;
+    if !unfinished {
+        header.data_len = Some(data_len as u64);
+    }
+    Some(header)
+}
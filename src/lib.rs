@@ -57,6 +57,11 @@
 //! std::thread::sleep(std::time::Duration::from_secs(5));
 //! ```
 //!
+//! To just play a file once, [rodio::play_once()] takes care of the stream and the sink
+//! ```no_run
+//! let sound = rodio::play_once("examples/music.ogg").unwrap();
+//! sound.wait();
+//! ```
 //!
 //! ## Sink
 //!
@@ -168,8 +173,11 @@ pub use cpal::{
 
 mod common;
 mod conversions;
+mod playlist;
+mod ring_buffer_output;
 mod sink;
 mod spatial_sink;
+mod spatial_voice_pool;
 #[cfg(feature = "playback")]
 mod stream;
 #[cfg(feature = "wav")]
@@ -183,12 +191,21 @@ pub mod source;
 pub mod static_buffer;
 
 pub use crate::common::{ChannelCount, SampleRate};
+#[cfg(feature = "playback")]
+pub use crate::conversions::DitherMode;
 pub use crate::conversions::Sample;
+pub use crate::conversions::{convert_samples_slice, remap_channels_slice, resample_slice};
 pub use crate::decoder::Decoder;
-pub use crate::sink::Sink;
+pub use crate::playlist::{Playlist, RepeatMode};
+pub use crate::ring_buffer_output::{RingBufferConsumer, RingBufferOutput};
+pub use crate::sink::{DecodeHandle, Sink, SinkBuilder, SinkState, SoundHandle};
 pub use crate::source::Source;
 pub use crate::spatial_sink::SpatialSink;
+pub use crate::spatial_voice_pool::{SpatialVoicePool, VoiceId};
 #[cfg(feature = "playback")]
-pub use crate::stream::{play, OutputStream, OutputStreamBuilder, PlayError, StreamError};
+pub use crate::stream::{
+    play, play_once, OutputStream, OutputStreamBuilder, PlayError, PlayHandle, Player, StreamError,
+    ThreadPriority,
+};
 #[cfg(feature = "wav")]
-pub use crate::wav_output::output_to_wav;
+pub use crate::wav_output::{export_region, export_region_with_fades, output_to_wav};
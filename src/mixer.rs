@@ -1,12 +1,18 @@
 //! Mixer that plays multiple sounds at the same time.
 
+use crate::buffer::SamplesBuffer;
 use crate::common::{ChannelCount, SampleRate};
 use crate::source::{SeekError, Source, UniformSourceIterator};
 use crate::Sample;
-use std::sync::atomic::{AtomicBool, Ordering};
+use dasp_sample::FromSample;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
 use std::sync::{Arc, Mutex};
 use std::time::Duration;
 
+/// Silence the output must produce before the mixer reports it is silent, so a sound that
+/// passes through zero is not mistaken for silence.
+const SILENCE_DURATION: Duration = Duration::from_millis(10);
+
 /// Builds a new mixer.
 ///
 /// You can choose the characteristics of the output thanks to this constructor. All the sounds
@@ -20,9 +26,13 @@ where
     let input = Arc::new(Mixer {
         has_pending: AtomicBool::new(false),
         pending_sources: Mutex::new(Vec::new()),
+        silent: AtomicBool::new(true),
+        resampling: Arc::new(AtomicUsize::new(0)),
         channels,
         sample_rate,
     });
+    let silence_threshold =
+        (SILENCE_DURATION.as_secs_f64() * sample_rate as f64) as usize * channels.max(1) as usize;
 
     let output = MixerSource {
         current_sources: Vec::with_capacity(16),
@@ -30,15 +40,28 @@ where
         sample_count: 0,
         still_pending: vec![],
         still_current: vec![],
+        silent_samples: 0,
+        silence_threshold: silence_threshold.max(1),
     };
 
     (input, output)
 }
 
 /// The input of the mixer.
+///
+/// The sources are summed in the order they were added, one after the other, and a source
+/// starts at the first frame that is produced after it was added. Floating point addition
+/// depends on its order, so this order is kept on purpose: when rendering offline, adding the
+/// same sources in the same order before pulling the output gives bit-identical results on
+/// every run. Sources added from another thread while the output plays start wherever the
+/// output happens to be at that moment.
 pub struct Mixer<S> {
     has_pending: AtomicBool,
     pending_sources: Mutex<Vec<Box<dyn Source<Item = S> + Send>>>,
+    // Set by the output, see `is_silent`.
+    silent: AtomicBool,
+    // Sources that are being resampled, see `is_resampling`.
+    resampling: Arc<AtomicUsize>,
     channels: ChannelCount,
     sample_rate: SampleRate,
 }
@@ -48,17 +71,204 @@ where
     S: Sample + Send + 'static,
 {
     /// Adds a new source to mix to the existing ones.
+    ///
+    /// The source is converted to the channel count and sample rate of the mixer. A mono
+    /// source is played on the first two channels, so it is centered on stereo output.
     #[inline]
     pub fn add<T>(&self, source: T)
     where
         T: Source<Item = S> + Send + 'static,
     {
+        let source = RateWatch {
+            input: source,
+            sample_rate: self.sample_rate,
+            state: RateState::Unknown(0),
+            resampling: self.resampling.clone(),
+            span_left: Some(0),
+        };
         let uniform_source = UniformSourceIterator::new(source, self.channels, self.sample_rate);
         self.pending_sources
             .lock()
             .unwrap()
             .push(Box::new(uniform_source) as Box<_>);
         self.has_pending.store(true, Ordering::SeqCst); // TODO: can we relax this ordering?
+        self.silent.store(false, Ordering::Relaxed);
+    }
+
+    /// Returns true while the output only produces silence, that is once every sample of the
+    /// last 10 milliseconds was zero. This is the case when no sources are playing, or when
+    /// all of them are silent, for example [`Sink`](crate::Sink)s that have played all their
+    /// sounds or are paused.
+    ///
+    /// An app can use this to save power by pausing the device, and resume it before adding
+    /// new sounds. Adding a source to the mixer makes it report sound right away, sounds
+    /// appended to a sink are only noticed once the output plays them.
+    #[inline]
+    pub fn is_silent(&self) -> bool {
+        self.silent.load(Ordering::Relaxed)
+    }
+
+    /// Returns true while any source plays sound at a sample rate other than the one of the
+    /// mixer, and is resampled on its way into the mix.
+    ///
+    /// Silence is not counted, so a [`Sink`](crate::Sink) that is idle does not count either.
+    /// A source counts from its first sample that is not zero until it moves on to a span at
+    /// the rate of the mixer or ends.
+    #[inline]
+    pub fn is_resampling(&self) -> bool {
+        self.resampling.load(Ordering::Relaxed) > 0
+    }
+
+    /// Returns the sample rate all sources are converted to.
+    #[cfg(feature = "playback")]
+    #[inline]
+    pub(crate) fn sample_rate(&self) -> SampleRate {
+        self.sample_rate
+    }
+}
+
+/// Whether a source added to the mixer is being resampled.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum RateState {
+    // Playing at this rate, not yet counted.
+    Unknown(SampleRate),
+    // Playing at the rate of the mixer, or only silence at another one.
+    Native(SampleRate),
+    // Counted in `Mixer::resampling`.
+    Resampling(SampleRate),
+}
+
+// Counts a source in `Mixer::resampling` while it plays sound at another rate than the mixer.
+// The rate is only looked at where a span starts.
+struct RateWatch<I> {
+    input: I,
+    sample_rate: SampleRate,
+    state: RateState,
+    resampling: Arc<AtomicUsize>,
+    // Samples left in the current span after the last one returned, `None` if it lasts until
+    // the end of the source.
+    span_left: Option<usize>,
+}
+
+impl<I> RateWatch<I> {
+    fn set_state(&mut self, state: RateState) {
+        let was = matches!(self.state, RateState::Resampling(_));
+        let is = matches!(state, RateState::Resampling(_));
+        if is && !was {
+            self.resampling.fetch_add(1, Ordering::Relaxed);
+        } else if was && !is {
+            self.resampling.fetch_sub(1, Ordering::Relaxed);
+        }
+        self.state = state;
+    }
+}
+
+impl<I> RateWatch<I>
+where
+    I: Source,
+    I::Item: Sample,
+{
+    fn start_span(&mut self) {
+        let rate = self.input.sample_rate();
+        self.span_left = self.input.current_span_len();
+        let state = match self.state {
+            RateState::Unknown(current)
+            | RateState::Native(current)
+            | RateState::Resampling(current)
+                if current == rate =>
+            {
+                self.state
+            }
+            _ if rate == self.sample_rate => RateState::Native(rate),
+            _ => RateState::Unknown(rate),
+        };
+        if state != self.state {
+            self.set_state(state);
+        }
+    }
+}
+
+impl<I> Drop for RateWatch<I> {
+    fn drop(&mut self) {
+        self.set_state(RateState::Unknown(0));
+    }
+}
+
+impl<I> Iterator for RateWatch<I>
+where
+    I: Source,
+    I::Item: Sample,
+{
+    type Item = I::Item;
+
+    #[inline]
+    fn next(&mut self) -> Option<I::Item> {
+        let sample = self.input.next();
+        // Read once the first sample of a span is out, sources switching between sounds only
+        // report the format of the next one then.
+        match &mut self.span_left {
+            Some(0) => self.start_span(),
+            Some(left) => *left -= 1,
+            None => {}
+        }
+        match (self.state, sample) {
+            (RateState::Unknown(rate), Some(sample)) if sample.to_f32() != 0.0 => {
+                self.set_state(RateState::Resampling(rate));
+            }
+            (_, None) => {
+                self.set_state(RateState::Unknown(0));
+                // the source may start again with another span
+                self.span_left = Some(0);
+            }
+            _ => {}
+        }
+        sample
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.input.size_hint()
+    }
+}
+
+impl<I> Source for RateWatch<I>
+where
+    I: Source,
+    I::Item: Sample,
+{
+    #[inline]
+    fn current_span_len(&self) -> Option<usize> {
+        self.input.current_span_len()
+    }
+
+    #[inline]
+    fn channels(&self) -> ChannelCount {
+        self.input.channels()
+    }
+
+    #[inline]
+    fn sample_rate(&self) -> SampleRate {
+        self.input.sample_rate()
+    }
+
+    #[inline]
+    fn total_duration(&self) -> Option<Duration> {
+        self.input.total_duration()
+    }
+
+    #[inline]
+    fn tail_duration(&self) -> Duration {
+        self.input.tail_duration()
+    }
+
+    #[inline]
+    fn latency(&self) -> Duration {
+        self.input.latency()
+    }
+
+    #[inline]
+    fn try_seek(&mut self, pos: Duration) -> Result<(), SeekError> {
+        self.input.try_seek(pos)
     }
 }
 
@@ -78,6 +288,10 @@ pub struct MixerSource<S> {
 
     // A temporary vec used in sum_current_sources.
     still_current: Vec<Box<dyn Source<Item = S> + Send>>,
+
+    // Zeros produced in a row, and how many make the mixer silent.
+    silent_samples: usize,
+    silence_threshold: usize,
 }
 
 impl<S> Source for MixerSource<S>
@@ -104,6 +318,15 @@ where
         None
     }
 
+    #[inline]
+    fn latency(&self) -> Duration {
+        self.current_sources
+            .iter()
+            .map(|source| source.latency())
+            .max()
+            .unwrap_or_default()
+    }
+
     #[inline]
     fn try_seek(&mut self, _: Duration) -> Result<(), SeekError> {
         Err(SeekError::NotSupported {
@@ -156,6 +379,7 @@ where
         self.sample_count += 1;
 
         let sum = self.sum_current_sources();
+        self.track_silence(sum.to_f32() == 0.0);
 
         if self.current_sources.is_empty() {
             None
@@ -191,11 +415,28 @@ where
             }
         }
         std::mem::swap(&mut self.still_pending, &mut pending);
+        // `Mixer::add` cleared the silent flag, a new source must be silent for a while too.
+        self.silent_samples = 0;
 
         let has_pending = !pending.is_empty();
         self.input.has_pending.store(has_pending, Ordering::SeqCst); // TODO: relax ordering?
     }
 
+    #[inline]
+    fn track_silence(&mut self, zero: bool) {
+        if !zero {
+            if self.silent_samples >= self.silence_threshold {
+                self.input.silent.store(false, Ordering::Relaxed);
+            }
+            self.silent_samples = 0;
+        } else if self.silent_samples < self.silence_threshold {
+            self.silent_samples += 1;
+            if self.silent_samples == self.silence_threshold {
+                self.input.silent.store(true, Ordering::Relaxed);
+            }
+        }
+    }
+
     fn sum_current_sources(&mut self) -> S {
         let mut sum = S::ZERO_VALUE;
 
@@ -211,11 +452,535 @@ where
     }
 }
 
+/// Renders the output of a mixer into buffers provided by the caller, for engines that run
+/// their own audio callback, for example on an existing cpal stream or a console SDK, instead
+/// of letting an [`OutputStream`](crate::OutputStream) own the device.
+///
+/// # Example
+///
+/// ```
+/// use rodio::mixer::{self, MixerRenderer};
+/// use rodio::source::{SineWave, Source};
+///
+/// let (mixer, source) = mixer::mixer::<f32>(2, 44100);
+/// let mut renderer = MixerRenderer::new(source);
+/// mixer.add(SineWave::new(440.0).take_duration(std::time::Duration::from_secs(1)));
+///
+/// // in the audio callback of the engine
+/// let mut buffer = [0.0f32; 512];
+/// renderer.fill(&mut buffer, 48000, 2);
+/// ```
+pub struct MixerRenderer<S>
+where
+    S: Sample + Send + 'static,
+{
+    // Empty only while it is being converted to another format.
+    output: Option<UniformSourceIterator<MixerSource<S>, f32>>,
+}
+
+impl<S> MixerRenderer<S>
+where
+    S: Sample + Send + 'static,
+    f32: FromSample<S>,
+{
+    /// Renders the output of `source`, which is returned by [`mixer`].
+    pub fn new(source: MixerSource<S>) -> MixerRenderer<S> {
+        let channels = source.channels();
+        let sample_rate = source.sample_rate();
+        MixerRenderer {
+            output: Some(UniformSourceIterator::new(source, channels, sample_rate)),
+        }
+    }
+
+    /// Fills `buffer` with the next samples of the mix, interleaved for `channels` channels at
+    /// `sample_rate`. The mix is converted if that differs from the format of the mixer, the
+    /// format can change between calls. Silence is written while no sources are playing.
+    pub fn fill(&mut self, buffer: &mut [f32], sample_rate: SampleRate, channels: ChannelCount) {
+        let mut output = self.output.take().unwrap();
+        if output.channels() != channels || output.sample_rate() != sample_rate {
+            output = UniformSourceIterator::new(output.into_inner(), channels, sample_rate);
+        }
+        for sample in buffer.iter_mut() {
+            *sample = output.next().unwrap_or(0.0);
+        }
+        self.output = Some(output);
+    }
+
+    /// Returns the output of the mixer. Samples that were read ahead for the sample rate
+    /// conversion are lost.
+    #[inline]
+    pub fn into_inner(self) -> MixerSource<S> {
+        self.output.unwrap().into_inner()
+    }
+}
+
+/// How a [`MixBuilder`] lowers the gain of its sources to leave headroom for their sum.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Headroom {
+    /// Scales every source by `1 / N` for `N` sources. The sum of full scale sources stays
+    /// within range, but the mix gets quieter with every source added.
+    #[default]
+    Linear,
+    /// Scales every source by `1 / sqrt(N)`. This keeps the loudness of unrelated sounds, but
+    /// the peaks of several loud sources can still add up beyond full scale.
+    EqualPower,
+    /// Leaves the gain of the sources untouched.
+    None,
+}
+
+impl Headroom {
+    fn gain(self, sources: usize) -> f32 {
+        let sources = sources.max(1) as f32;
+        match self {
+            Headroom::Linear => 1.0 / sources,
+            Headroom::EqualPower => 1.0 / sources.sqrt(),
+            Headroom::None => 1.0,
+        }
+    }
+}
+
+/// Sums a fixed set of sources into a single one, lowering their gain so that the mix does
+/// not clip.
+///
+/// Unlike a [`Mixer`] the sources can not be changed once the mix is built, but the gain of
+/// every source can. The headroom is [`Headroom::Linear`] unless changed with
+/// [`with_headroom`](MixBuilder::with_headroom).
+///
+/// Like in a [`Mixer`] the sources are summed in the order they were added, so the same
+/// sources always mix to bit-identical samples.
+///
+/// # Example
+///
+/// ```
+/// use rodio::mixer::{Headroom, MixBuilder};
+/// use rodio::source::{SineWave, Source};
+///
+/// let chord = MixBuilder::new(2, 44100)
+///     .with_headroom(Headroom::EqualPower)
+///     .add_source(SineWave::new(261.63))
+///     .add_source(SineWave::new(329.63))
+///     .add_source_with_gain(SineWave::new(392.0), 0.5)
+///     .build();
+/// ```
+pub struct MixBuilder<S> {
+    channels: ChannelCount,
+    sample_rate: SampleRate,
+    headroom: Headroom,
+    sources: Vec<(Box<dyn Source<Item = S> + Send>, f32)>,
+}
+
+impl<S> MixBuilder<S>
+where
+    S: Sample + Send + 'static,
+{
+    /// Creates an empty mix. All the sources are converted to these values.
+    #[inline]
+    pub fn new(channels: ChannelCount, sample_rate: SampleRate) -> MixBuilder<S> {
+        MixBuilder {
+            channels,
+            sample_rate,
+            headroom: Headroom::default(),
+            sources: Vec::new(),
+        }
+    }
+
+    /// Sets how the gain of the sources is lowered.
+    #[inline]
+    pub fn with_headroom(mut self, headroom: Headroom) -> MixBuilder<S> {
+        self.headroom = headroom;
+        self
+    }
+
+    /// Adds a source to the mix.
+    #[inline]
+    pub fn add_source<T>(self, source: T) -> MixBuilder<S>
+    where
+        T: Source<Item = S> + Send + 'static,
+    {
+        self.add_source_with_gain(source, 1.0)
+    }
+
+    /// Adds a source to the mix with its own gain, which is applied on top of the headroom.
+    pub fn add_source_with_gain<T>(mut self, source: T, gain: f32) -> MixBuilder<S>
+    where
+        T: Source<Item = S> + Send + 'static,
+    {
+        let source = UniformSourceIterator::new(source, self.channels, self.sample_rate);
+        self.sources.push((Box::new(source), gain));
+        self
+    }
+
+    /// Builds the source that plays the mix.
+    #[inline]
+    pub fn build(self) -> MixedSources<S> {
+        MixedSources {
+            headroom_gain: self.headroom.gain(self.sources.len()),
+            gains: self.sources.iter().map(|(_, gain)| *gain).collect(),
+            sources: self
+                .sources
+                .into_iter()
+                .enumerate()
+                .map(|(index, (source, _))| (index, source, false))
+                .collect(),
+            channels: self.channels,
+            sample_rate: self.sample_rate,
+            channel: 0,
+            ended: false,
+        }
+    }
+}
+
+impl MixBuilder<f32> {
+    /// Mixes the sources into a buffer and measures its peak, to find the gain that brings
+    /// the mix to full scale. See [`RenderedMix`].
+    ///
+    /// The samples are not clipped, a peak above `1.0` is kept as is. Only use this with
+    /// sources that end, it keeps rendering as long as any source plays.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::time::Duration;
+    /// use rodio::mixer::{Headroom, MixBuilder};
+    /// use rodio::source::{SineWave, Source};
+    ///
+    /// let second = Duration::from_secs(1);
+    /// let mix = MixBuilder::new(1, 44100)
+    ///     .with_headroom(Headroom::None)
+    ///     .add_source(SineWave::new(440.0).take_duration(second))
+    ///     .add_source(SineWave::new(440.0).take_duration(second))
+    ///     .render();
+    /// assert!(mix.overshoot_db() > 5.9);
+    /// let gain = mix.normalize_gain();
+    /// let normalized = mix.into_samples().amplify(gain);
+    /// ```
+    pub fn render(self) -> RenderedMix {
+        let (channels, sample_rate) = (self.channels, self.sample_rate);
+        let samples: Vec<f32> = self.build().collect();
+        let peak = samples
+            .iter()
+            .fold(0.0f32, |peak, sample| peak.max(sample.abs()));
+        RenderedMix {
+            samples: SamplesBuffer::new(channels, sample_rate, samples),
+            peak,
+        }
+    }
+}
+
+/// A mix rendered by [`MixBuilder::render`], along with its peak.
+#[derive(Clone, Debug)]
+pub struct RenderedMix {
+    samples: SamplesBuffer<f32>,
+    peak: f32,
+}
+
+impl RenderedMix {
+    /// Returns the largest absolute value of any sample in the mix.
+    #[inline]
+    pub fn peak(&self) -> f32 {
+        self.peak
+    }
+
+    /// Returns by how many decibels the peak exceeds full scale, `0.0` if it does not.
+    #[inline]
+    pub fn overshoot_db(&self) -> f32 {
+        (20.0 * self.peak.log10()).max(0.0)
+    }
+
+    /// Returns the gain that scales the mix so its peak is exactly at full scale, above one
+    /// for a quiet mix. A silent mix gets a gain of one.
+    #[inline]
+    pub fn normalize_gain(&self) -> f32 {
+        if self.peak > 0.0 {
+            1.0 / self.peak
+        } else {
+            1.0
+        }
+    }
+
+    /// Returns the mixed samples.
+    #[inline]
+    pub fn samples(&self) -> &SamplesBuffer<f32> {
+        &self.samples
+    }
+
+    /// Returns the mixed samples, to play or save them.
+    #[inline]
+    pub fn into_samples(self) -> SamplesBuffer<f32> {
+        self.samples
+    }
+}
+
+/// The sum of the sources of a [`MixBuilder`]. Ends once all of them have ended.
+pub struct MixedSources<S> {
+    // The sources that are still playing, with their index in `gains` and whether they ended
+    // during the current frame.
+    sources: Vec<(usize, Box<dyn Source<Item = S> + Send>, bool)>,
+    gains: Vec<f32>,
+    headroom_gain: f32,
+    channels: ChannelCount,
+    sample_rate: SampleRate,
+    // Channel of the next sample, and whether a source ended during the current frame.
+    channel: ChannelCount,
+    ended: bool,
+}
+
+impl<S> MixedSources<S> {
+    /// Changes the gain of the source that was added at position `index`, counting from zero.
+    /// The headroom is applied on top of it.
+    ///
+    /// # Panics
+    ///
+    /// If there are not that many sources.
+    #[inline]
+    pub fn set_gain(&mut self, index: usize, gain: f32) {
+        self.gains[index] = gain;
+    }
+
+    /// Returns the gain of the source that was added at position `index`, counting from zero.
+    ///
+    /// # Panics
+    ///
+    /// If there are not that many sources.
+    #[inline]
+    pub fn gain(&self, index: usize) -> f32 {
+        self.gains[index]
+    }
+}
+
+impl<S> Iterator for MixedSources<S>
+where
+    S: Sample + Send + 'static,
+{
+    type Item = S;
+
+    #[inline]
+    fn next(&mut self) -> Option<S> {
+        let mut sum = S::ZERO_VALUE;
+        let mut playing = false;
+        for (index, source, ended) in &mut self.sources {
+            if *ended {
+                continue;
+            }
+            match source.next() {
+                Some(value) => {
+                    let gain = self.gains[*index] * self.headroom_gain;
+                    sum = sum.saturating_add(value.amplify(gain));
+                    playing = true;
+                }
+                None => {
+                    *ended = true;
+                    self.ended = true;
+                }
+            }
+        }
+        if !playing && self.channel == 0 {
+            self.sources.clear();
+            return None;
+        }
+
+        // Sources that ended are only dropped between frames, an incomplete last frame is
+        // filled up with silence.
+        self.channel += 1;
+        if self.channel >= self.channels.max(1) {
+            self.channel = 0;
+            if self.ended {
+                self.sources.retain(|(_, _, ended)| !ended);
+                self.ended = false;
+            }
+        }
+        Some(sum)
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.sources
+            .iter()
+            .map(|(_, source, _)| source.size_hint())
+            .fold(
+                (0, Some(0)),
+                |(lower, upper), (source_lower, source_upper)| {
+                    let upper = match (upper, source_upper) {
+                        (Some(a), Some(b)) => Some(a.max(b)),
+                        _ => None,
+                    };
+                    (lower.max(source_lower), upper)
+                },
+            )
+    }
+}
+
+impl<S> Source for MixedSources<S>
+where
+    S: Sample + Send + 'static,
+{
+    #[inline]
+    fn current_span_len(&self) -> Option<usize> {
+        None
+    }
+
+    #[inline]
+    fn channels(&self) -> ChannelCount {
+        self.channels
+    }
+
+    #[inline]
+    fn sample_rate(&self) -> SampleRate {
+        self.sample_rate
+    }
+
+    /// The duration of the longest source, if all of them are known.
+    #[inline]
+    fn total_duration(&self) -> Option<Duration> {
+        self.sources
+            .iter()
+            .try_fold(Duration::ZERO, |longest, (_, source, _)| {
+                Some(longest.max(source.total_duration()?))
+            })
+    }
+
+    /// Everything that plays on after the last source without its tail ends, if all the
+    /// durations are known. Otherwise the longest tail.
+    fn tail_duration(&self) -> Duration {
+        let longest_tail = self
+            .sources
+            .iter()
+            .map(|(_, source, _)| source.tail_duration())
+            .max()
+            .unwrap_or_default();
+        let Some(end) = self.total_duration() else {
+            return longest_tail;
+        };
+        let last_dry_end = self
+            .sources
+            .iter()
+            .filter_map(|(_, source, _)| {
+                let total = source.total_duration()?;
+                Some(total.saturating_sub(source.tail_duration()))
+            })
+            .max()
+            .unwrap_or_default();
+        end.saturating_sub(last_dry_end)
+    }
+
+    fn latency(&self) -> Duration {
+        self.sources
+            .iter()
+            .map(|(_, source, _)| source.latency())
+            .max()
+            .unwrap_or_default()
+    }
+
+    /// Seeks all the sources that are still playing. This is not all or nothing: if a source
+    /// fails to seek the error is returned right away, the sources before it have already
+    /// moved to `pos` and those after it stay where they were.
+    #[inline]
+    fn try_seek(&mut self, pos: Duration) -> Result<(), SeekError> {
+        self.sources.retain(|(_, _, ended)| !ended);
+        self.ended = false;
+        self.channel = 0;
+        for (_, source, _) in &mut self.sources {
+            source.try_seek(pos)?;
+        }
+        Ok(())
+    }
+}
+
 #[cfg(test)]
 mod tests {
+    use std::time::Duration;
+
     use crate::buffer::SamplesBuffer;
-    use crate::mixer;
-    use crate::source::Source;
+    use crate::common::{ChannelCount, SampleRate};
+    use crate::mixer::{self, Headroom, MixBuilder, MixerRenderer};
+    use crate::source::{from_iter, SineWave, Source, SquareWave};
+
+    /// Source with broken metadata, as decoded from some malformed files.
+    struct Malformed {
+        samples: std::vec::IntoIter<i16>,
+        channels: ChannelCount,
+        sample_rate: SampleRate,
+    }
+
+    impl Iterator for Malformed {
+        type Item = i16;
+
+        fn next(&mut self) -> Option<i16> {
+            self.samples.next()
+        }
+    }
+
+    impl Source for Malformed {
+        fn current_span_len(&self) -> Option<usize> {
+            Some(self.samples.len())
+        }
+
+        fn channels(&self) -> ChannelCount {
+            self.channels
+        }
+
+        fn sample_rate(&self) -> SampleRate {
+            self.sample_rate
+        }
+
+        fn total_duration(&self) -> Option<Duration> {
+            None
+        }
+    }
+
+    #[test]
+    fn renderer_fills_buffers_in_the_requested_format() {
+        let (tx, rx) = mixer::mixer::<f32>(1, 48000);
+        let mut renderer = MixerRenderer::new(rx);
+        let mut buffer = [1.0f32; 4];
+        renderer.fill(&mut buffer, 48000, 1);
+        assert_eq!(buffer, [0.0; 4]);
+
+        tx.add(SamplesBuffer::new(1, 48000, vec![0.25f32; 1000]));
+        tx.add(SamplesBuffer::new(1, 48000, vec![-0.125f32; 1000]));
+        renderer.fill(&mut buffer, 48000, 1);
+        assert_eq!(buffer, [0.125; 4]);
+
+        let mut buffer = [0.0f32; 200];
+        renderer.fill(&mut buffer, 24000, 2);
+        assert_eq!(buffer, [0.125; 200]);
+
+        let mut buffer = [1.0f32; 1000];
+        renderer.fill(&mut buffer, 24000, 2);
+        assert!(buffer[buffer.len() - 20..].iter().all(|&s| s == 0.0));
+    }
+
+    #[test]
+    fn reports_silence_once_sources_finish() {
+        use crate::Sink;
+
+        // 10 milliseconds are 10 samples
+        let (tx, mut rx) = mixer::mixer::<f32>(1, 1000);
+        assert!(tx.is_silent());
+        tx.add(SamplesBuffer::new(1, 1000, vec![0.5f32; 20]));
+        assert!(!tx.is_silent());
+        // the output ends while nothing is playing, the device then plays silence
+        let mut pull = |samples: usize| (0..samples).filter_map(|_| rx.next()).count();
+        assert_eq!(pull(25), 20);
+        assert!(!tx.is_silent());
+        pull(5);
+        assert!(tx.is_silent());
+
+        // a sink stays in the mixer and plays silence once its sounds are done
+        let sink = Sink::connect_new(&tx);
+        sink.append(SamplesBuffer::new(1, 1000, vec![0.5f32; 20]));
+        let mut output = rx.by_ref().take(40);
+        assert!(output.by_ref().any(|s| s != 0.0));
+        assert!(!tx.is_silent());
+        output.for_each(drop);
+        assert!(rx.by_ref().take(600).all(|s| s == 0.0));
+        assert!(sink.empty());
+        assert!(tx.is_silent());
+
+        sink.append(SamplesBuffer::new(1, 1000, vec![0.5f32; 20]));
+        assert!(rx.by_ref().take(600).any(|s| s != 0.0));
+        assert!(!tx.is_silent());
+    }
 
     #[test]
     fn basic() {
@@ -262,6 +1027,8 @@ mod tests {
 
         assert_eq!(rx.channels(), 1);
         assert_eq!(rx.sample_rate(), 96000);
+        // Four frames at 48 kHz last as long as eight at 96 kHz. The last input frame is held
+        // rather than interpolated towards silence, so the last output frame is -10 + 5.
         assert_eq!(rx.next(), Some(15));
         assert_eq!(rx.next(), Some(5));
         assert_eq!(rx.next(), Some(-5));
@@ -269,9 +1036,49 @@ mod tests {
         assert_eq!(rx.next(), Some(15));
         assert_eq!(rx.next(), Some(5));
         assert_eq!(rx.next(), Some(-5));
+        assert_eq!(rx.next(), Some(-5));
         assert_eq!(rx.next(), None);
     }
 
+    #[test]
+    fn reports_resampling_while_sound_plays_at_another_rate() {
+        let (tx, mut rx) = mixer::mixer::<f32>(1, 48000);
+        tx.add(SamplesBuffer::new(1, 48000, vec![1.0f32; 10]));
+        rx.by_ref().take(5).for_each(drop);
+        assert!(!tx.is_resampling());
+
+        // silence at another rate does not count
+        tx.add(SamplesBuffer::new(1, 44100, vec![0.0f32; 100]));
+        tx.add(SamplesBuffer::new(
+            1,
+            24000,
+            [vec![0.0f32; 10], vec![0.5; 10]].concat(),
+        ));
+        rx.by_ref().take(10).for_each(drop);
+        assert!(!tx.is_resampling());
+        rx.by_ref().take(20).for_each(drop);
+        assert!(tx.is_resampling());
+
+        rx.by_ref().for_each(drop);
+        assert!(!tx.is_resampling());
+    }
+
+    #[test]
+    fn notices_rate_changes_between_spans() {
+        let (tx, mut rx) = mixer::mixer::<f32>(1, 48000);
+        tx.add(from_iter([
+            SamplesBuffer::new(1, 48000, vec![1.0f32; 10]),
+            SamplesBuffer::new(1, 24000, vec![1.0f32; 10]),
+            SamplesBuffer::new(1, 48000, vec![1.0f32; 10]),
+        ]));
+        rx.by_ref().take(5).for_each(drop);
+        assert!(!tx.is_resampling());
+        rx.by_ref().take(10).for_each(drop);
+        assert!(tx.is_resampling());
+        rx.by_ref().take(25).for_each(drop);
+        assert!(!tx.is_resampling());
+    }
+
     #[test]
     fn start_afterwards() {
         let (tx, mut rx) = mixer::mixer(1, 48000);
@@ -297,4 +1104,114 @@ mod tests {
 
         assert_eq!(rx.next(), None);
     }
+
+    #[test]
+    fn mix_builder_leaves_headroom() {
+        let tone = || SineWave::new(440.0).take_duration(Duration::from_millis(100));
+        let mix: Vec<f32> = MixBuilder::new(1, 48000)
+            .add_source(tone())
+            .add_source(tone())
+            .add_source(tone())
+            .add_source(SquareWave::new(110.0).take_duration(Duration::from_millis(200)))
+            .build()
+            .collect();
+
+        assert_eq!(mix.len(), 9600);
+        assert!(mix.iter().all(|s| s.abs() <= 1.0));
+        // the tones are in phase so their peaks add up to full scale
+        assert!(mix[..4800].iter().any(|s| s.abs() > 0.99));
+        // the square wave plays on alone at a quarter of its gain
+        assert!(mix[4800..].iter().all(|s| (s.abs() - 0.25).abs() < 1e-6));
+    }
+
+    #[test]
+    fn mix_builder_gains() {
+        let mut mix = MixBuilder::new(1, 1000)
+            .with_headroom(Headroom::EqualPower)
+            .add_source(SamplesBuffer::new(1, 1000, vec![0.5f32; 4]))
+            .add_source_with_gain(SamplesBuffer::new(1, 1000, vec![0.5f32; 4]), 0.0)
+            .add_source(SamplesBuffer::new(1, 1000, vec![0.5f32; 2]))
+            .add_source(SamplesBuffer::new(1, 1000, vec![0.5f32; 2]))
+            .build();
+        assert_eq!(mix.next(), Some(0.75));
+        mix.set_gain(1, 1.0);
+        assert_eq!(mix.next(), Some(1.0));
+        assert_eq!(mix.collect::<Vec<_>>(), [0.5, 0.5]);
+    }
+
+    #[test]
+    fn offline_renders_are_bit_identical() {
+        // large and small values, whose sum depends on the order they are added in
+        let render = || {
+            let (controller, output) = mixer::mixer::<f32>(2, 48_000);
+            controller.add(SineWave::new(440.0).take_duration(Duration::from_millis(50)));
+            controller.add(SamplesBuffer::new(1, 44_100, vec![1e8f32, -1e8, 0.1, 0.3]));
+            controller.add(
+                SquareWave::new(97.0)
+                    .amplify(0.3)
+                    .take_duration(Duration::from_millis(20)),
+            );
+            let mixed: Vec<u32> = output.map(f32::to_bits).collect();
+
+            let built = MixBuilder::new(2, 48_000)
+                .add_source(SineWave::new(440.0).take_duration(Duration::from_millis(50)))
+                .add_source_with_gain(SamplesBuffer::new(2, 48_000, vec![1e8f32, 3e-8, 1.0]), 0.7)
+                .build();
+            (mixed, built.map(f32::to_bits).collect::<Vec<u32>>())
+        };
+        let first = render();
+        assert!(first.0.len() >= 4800 && first.1.len() >= 4800);
+        assert!(first == render());
+    }
+
+    #[test]
+    fn render_reports_overshoot() {
+        // two tones whose peaks add up to 1.5 in some places
+        let tone = |freq: f32, amplitude: f32| {
+            let samples: Vec<f32> = (0..8000)
+                .map(|i| amplitude * (std::f32::consts::TAU * freq * i as f32 / 8000.0).sin())
+                .collect();
+            SamplesBuffer::new(1, 8000, samples)
+        };
+        let mix = MixBuilder::new(1, 8000)
+            .with_headroom(Headroom::None)
+            .add_source(tone(200.0, 0.8))
+            .add_source(tone(400.0, 0.7))
+            .render();
+
+        let samples: Vec<f32> = mix.samples().clone().collect();
+        assert_eq!(samples.len(), 8000);
+        let true_peak = samples.iter().fold(0.0f32, |peak, s| peak.max(s.abs()));
+        assert!(true_peak > 1.2);
+        assert_eq!(mix.peak(), true_peak);
+        assert!((mix.overshoot_db() - 20.0 * true_peak.log10()).abs() < 1e-6);
+
+        let normalized: Vec<f32> = mix.into_samples().amplify(1.0 / true_peak).collect();
+        let peak = normalized.iter().fold(0.0f32, |peak, s| peak.max(s.abs()));
+        assert!((peak - 1.0).abs() < 1e-6);
+
+        let quiet = MixBuilder::new(1, 8000)
+            .add_source(tone(200.0, 0.25))
+            .render();
+        assert_eq!(quiet.overshoot_db(), 0.0);
+        assert!((quiet.normalize_gain() - 4.0).abs() < 1e-3);
+    }
+
+    #[test]
+    fn malformed_sources_are_skipped() {
+        let (tx, mut rx) = mixer::mixer(2, 48000);
+
+        for (channels, sample_rate) in [(0, 48000), (2, 0), (0, 0)] {
+            tx.add(Malformed {
+                samples: vec![100i16; 8].into_iter(),
+                channels,
+                sample_rate,
+            });
+        }
+        tx.add(SamplesBuffer::new(2, 48000, vec![1i16, 2, 3, 4]));
+
+        let output: Vec<i16> = rx.by_ref().take(4).collect();
+        assert_eq!(output, [1, 2, 3, 4]);
+        assert_eq!(rx.next(), None);
+    }
 }
@@ -0,0 +1,544 @@
+use std::collections::hash_map::RandomState;
+use std::fs::File;
+use std::hash::{BuildHasher, Hasher};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, Weak};
+use std::time::Duration;
+
+use dasp_sample::FromSample;
+
+use crate::common::{ChannelCount, SampleRate};
+use crate::decoder::background;
+use crate::mixer::Mixer;
+use crate::source::{BoxedSource, SeekError};
+use crate::{Sample, Sink, Source};
+
+/// Longest span reported for a track that does not know the length of its own spans, see
+/// [`SourcesQueueOutput`](crate::queue::SourcesQueueOutput).
+const THRESHOLD: usize = 512;
+
+/// What happens when a track of a [`Playlist`] ends.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum RepeatMode {
+    /// Plays the next track and stops after the last one.
+    #[default]
+    Off,
+    /// Plays the current track again and again, [`Playlist::next`] still moves on.
+    One,
+    /// Plays the next track and starts over after the last one.
+    All,
+}
+
+/// A list of tracks played one after the other on a [`Sink`], with controls to move
+/// between them like a music player.
+///
+/// Tracks are added as a path to a file or as a closure that creates the source. They are
+/// only opened once their turn comes, so a long playlist costs next to nothing, and are
+/// opened again every time they are played. Opening happens on the audio thread, so the
+/// closures must be quick. Files are decoded on a thread of their own, like with
+/// [`Sink::append_async`], and can not seek. Opening a file waits for its first samples. Tracks that can not be opened are skipped.
+///
+/// The first track added starts playing right away, unless the sink is paused. Once a track
+/// ends the next one follows immediately, as set by [`set_repeat`](Playlist::set_repeat).
+///
+/// # Example
+///
+/// ```no_run
+/// use rodio::{OutputStreamBuilder, Playlist, RepeatMode};
+///
+/// let stream_handle = OutputStreamBuilder::open_default_stream()?;
+/// let playlist = Playlist::connect_new(&stream_handle.mixer());
+/// playlist.push_path("first.mp3");
+/// playlist.push_path("second.flac");
+/// playlist.set_repeat(RepeatMode::All);
+/// playlist.shuffle();
+/// playlist.next();
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+pub struct Playlist {
+    sink: Sink,
+    shared: Arc<Shared>,
+}
+
+struct Shared {
+    state: Mutex<State>,
+    // Copy of `State::generation`, so the source only locks the state when it changed.
+    generation: AtomicU64,
+}
+
+struct State {
+    tracks: Vec<Track>,
+    // Indices into `tracks` in the order they are played.
+    order: Vec<usize>,
+    // Position in `order` of the track that is playing, `None` once the playlist ended.
+    pos: Option<usize>,
+    repeat: RepeatMode,
+    // Bumped whenever the track that should play changes.
+    generation: u64,
+    // Held by the source playing the playlist, which sets it free once it ends.
+    source: Weak<()>,
+}
+
+enum Track {
+    Path(PathBuf),
+    Factory(Box<dyn FnMut() -> BoxedSource + Send>),
+}
+
+impl Track {
+    fn open(&mut self) -> Option<BoxedSource> {
+        match self {
+            Track::Path(path) => {
+                let file = File::open(path).ok()?;
+                let (source, _) = background::decode_in_background(file);
+                Some(Box::new(source))
+            }
+            Track::Factory(factory) => Some(factory()),
+        }
+    }
+}
+
+impl State {
+    // Position of the track after the current one, `None` if the playlist ends.
+    fn after(&self, pos: Option<usize>) -> Option<usize> {
+        let next = pos.map_or(0, |pos| pos + 1);
+        if next < self.order.len() {
+            Some(next)
+        } else if self.repeat == RepeatMode::All && !self.order.is_empty() {
+            Some(0)
+        } else {
+            None
+        }
+    }
+
+    fn set_pos(&mut self, pos: Option<usize>) {
+        self.pos = pos;
+        self.generation += 1;
+    }
+}
+
+impl Playlist {
+    /// Plays the playlist on `sink`. Sounds appended to the sink directly play before or
+    /// after the playlist, not in between its tracks.
+    pub fn new(sink: Sink) -> Playlist {
+        Playlist {
+            sink,
+            shared: Arc::new(Shared {
+                state: Mutex::new(State {
+                    tracks: Vec::new(),
+                    order: Vec::new(),
+                    pos: None,
+                    repeat: RepeatMode::Off,
+                    generation: 0,
+                    source: Weak::new(),
+                }),
+                generation: AtomicU64::new(0),
+            }),
+        }
+    }
+
+    /// Builds a new `Playlist` on a new sink playing on `mixer`.
+    pub fn connect_new(mixer: &Mixer<f32>) -> Playlist {
+        Playlist::new(Sink::connect_new(mixer))
+    }
+
+    /// Adds the file at `path` at the end of the playlist.
+    pub fn push_path(&self, path: impl Into<PathBuf>) {
+        self.push_track(Track::Path(path.into()));
+    }
+
+    /// Adds a track at the end of the playlist, `factory` creates its source every time the
+    /// track is played.
+    pub fn push<F, S>(&self, mut factory: F)
+    where
+        F: FnMut() -> S + Send + 'static,
+        S: Source + Send + 'static,
+        f32: FromSample<S::Item>,
+        S::Item: Sample + Send,
+    {
+        self.push_track(Track::Factory(Box::new(move || {
+            factory().convert_samples().boxed()
+        })));
+    }
+
+    fn push_track(&self, track: Track) {
+        self.update(|state| {
+            state.tracks.push(track);
+            state.order.push(state.tracks.len() - 1);
+            // The playlist was idle, play the new track.
+            if state.pos.is_none() {
+                state.set_pos(Some(state.order.len() - 1));
+            }
+        });
+    }
+
+    /// Returns the number of tracks.
+    pub fn len(&self) -> usize {
+        self.shared.state.lock().unwrap().tracks.len()
+    }
+
+    /// Returns true if no tracks were added.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns the index of the track that is playing, counting in the order the tracks
+    /// were added, or `None` once the playlist has ended.
+    pub fn current(&self) -> Option<usize> {
+        let state = self.shared.state.lock().unwrap();
+        state.pos.map(|pos| state.order[pos])
+    }
+
+    /// Skips to the next track. After the last track the playlist ends, or starts over with
+    /// [`RepeatMode::All`].
+    pub fn next(&self) {
+        self.update(|state| {
+            let pos = state.after(state.pos);
+            state.set_pos(pos);
+        });
+    }
+
+    /// Goes back to the previous track. The first track starts over, or after the last one
+    /// with [`RepeatMode::All`]. Once the playlist has ended this plays the last track.
+    pub fn prev(&self) {
+        self.update(|state| {
+            let last = state.order.len().checked_sub(1);
+            let pos = match state.pos {
+                None => last,
+                Some(0) if state.repeat == RepeatMode::All => last,
+                Some(pos) => Some(pos.saturating_sub(1)),
+            };
+            state.set_pos(pos);
+        });
+    }
+
+    /// Plays the track at `index`, counting in the order the tracks were added.
+    ///
+    /// # Panics
+    ///
+    /// If there is no track at `index`.
+    pub fn jump_to(&self, index: usize) {
+        self.update(|state| {
+            assert!(index < state.tracks.len(), "no track at index {index}");
+            let pos = state.order.iter().position(|&track| track == index);
+            state.set_pos(pos);
+        });
+    }
+
+    /// Puts the tracks in a random order. The current track keeps playing and is followed by
+    /// all the others.
+    pub fn shuffle(&self) {
+        let mut rng = Rng::new();
+        let mut state = self.shared.state.lock().unwrap();
+        let state = &mut *state;
+        let current = state.pos.map(|pos| state.order[pos]);
+        // Fisher-Yates
+        for i in (1..state.order.len()).rev() {
+            let j = (rng.next() % (i as u64 + 1)) as usize;
+            state.order.swap(i, j);
+        }
+        if let Some(current) = current {
+            let pos = state.order.iter().position(|&track| track == current);
+            state
+                .order
+                .swap(0, pos.expect("every track is in the order"));
+            state.pos = Some(0);
+        }
+    }
+
+    /// Puts the tracks back in the order they were added. The current track keeps playing.
+    pub fn unshuffle(&self) {
+        let mut state = self.shared.state.lock().unwrap();
+        let current = state.pos.map(|pos| state.order[pos]);
+        state.order = (0..state.tracks.len()).collect();
+        state.pos = current;
+    }
+
+    /// Changes what happens when a track ends, see [`RepeatMode`].
+    pub fn set_repeat(&self, repeat: RepeatMode) {
+        self.shared.state.lock().unwrap().repeat = repeat;
+    }
+
+    /// Returns what happens when a track ends.
+    pub fn repeat(&self) -> RepeatMode {
+        self.shared.state.lock().unwrap().repeat
+    }
+
+    /// Returns the sink the playlist plays on, for example to pause it or change the volume.
+    pub fn sink(&self) -> &Sink {
+        &self.sink
+    }
+
+    // Changes the state and starts playing again if the playlist had ended.
+    fn update(&self, change: impl FnOnce(&mut State)) {
+        let token = {
+            let mut state = self.shared.state.lock().unwrap();
+            change(&mut state);
+            self.shared
+                .generation
+                .store(state.generation, Ordering::Release);
+            if state.pos.is_none() || state.source.strong_count() > 0 {
+                return;
+            }
+            let token = Arc::new(());
+            state.source = Arc::downgrade(&token);
+            token
+        };
+        let mut source = PlaylistSource {
+            shared: self.shared.clone(),
+            token: Some(token),
+            current: None,
+            generation: 0,
+            channel: 0,
+            played: false,
+        };
+        // Outside of the lock, appending can wait for the audio thread.
+        source.open(false);
+        self.sink.append(source);
+    }
+}
+
+impl Shared {
+    // Opens the track that should play now, moving on first if `ended` and nothing changed
+    // in the meantime. Once the playlist ended `token` is dropped and `None` returned.
+    fn open(
+        &self,
+        generation: &mut u64,
+        ended: bool,
+        token: &mut Option<Arc<()>>,
+    ) -> Option<BoxedSource> {
+        let mut state = self.state.lock().unwrap();
+        if ended && *generation == state.generation {
+            let pos = match state.repeat {
+                RepeatMode::One => state.pos,
+                _ => state.after(state.pos),
+            };
+            state.set_pos(pos);
+        }
+        // Gives up after every track failed to open once, by then it is not going to work.
+        let mut source = None;
+        for _ in 0..state.tracks.len() {
+            let Some(pos) = state.pos else {
+                break;
+            };
+            let index = state.order[pos];
+            source = state.tracks[index].open();
+            if source.is_some() {
+                break;
+            }
+            let pos = state.after(Some(pos));
+            state.set_pos(pos);
+        }
+        if source.is_none() {
+            state.set_pos(None);
+            // Under the lock, so the next change sees that the playlist ended.
+            *token = None;
+        }
+        *generation = state.generation;
+        self.generation.store(state.generation, Ordering::Release);
+        source
+    }
+
+    // Tracks that are opened again and again without playing a sample, for example empty
+    // ones with `RepeatMode::One`.
+    fn tracks_before_giving_up(&self) -> usize {
+        self.state.lock().unwrap().tracks.len()
+    }
+}
+
+// Plays the tracks of a playlist, ends with the playlist.
+struct PlaylistSource {
+    shared: Arc<Shared>,
+    // Dropped once the playlist ended, so the next change appends a new source.
+    token: Option<Arc<()>>,
+    current: Option<BoxedSource>,
+    // Generation of the state `current` was opened for.
+    generation: u64,
+    // The channel of the next sample of `current`, changes only apply between frames.
+    channel: ChannelCount,
+    // Whether `current` has played a sample.
+    played: bool,
+}
+
+impl PlaylistSource {
+    fn open(&mut self, ended: bool) {
+        self.current = self
+            .shared
+            .open(&mut self.generation, ended, &mut self.token);
+        self.channel = 0;
+        self.played = false;
+    }
+}
+
+impl Iterator for PlaylistSource {
+    type Item = f32;
+
+    #[inline]
+    fn next(&mut self) -> Option<f32> {
+        let mut silent_tracks = 0;
+        loop {
+            let changed = self.shared.generation.load(Ordering::Acquire) != self.generation;
+            if self.channel == 0 && changed && self.token.is_some() {
+                self.open(false);
+            }
+            let current = self.current.as_mut()?;
+            if let Some(sample) = current.next() {
+                self.channel += 1;
+                if self.channel >= current.channels().max(1) {
+                    self.channel = 0;
+                }
+                self.played = true;
+                return Some(sample);
+            }
+            if !self.played {
+                silent_tracks += 1;
+                if silent_tracks > self.shared.tracks_before_giving_up() {
+                    let mut state = self.shared.state.lock().unwrap();
+                    state.set_pos(None);
+                    self.shared
+                        .generation
+                        .store(state.generation, Ordering::Release);
+                    self.token = None;
+                    self.current = None;
+                    return None;
+                }
+            }
+            self.open(true);
+        }
+    }
+}
+
+impl Source for PlaylistSource {
+    fn current_span_len(&self) -> Option<usize> {
+        // Like the queue, a track boundary must be a span boundary.
+        let Some(current) = &self.current else {
+            return Some(0);
+        };
+        match current.current_span_len() {
+            Some(len) if len > 0 => Some(len),
+            _ => match current.size_hint() {
+                (lower, _) if lower > 0 => Some(lower),
+                _ => Some(THRESHOLD),
+            },
+        }
+    }
+
+    fn channels(&self) -> ChannelCount {
+        self.current
+            .as_ref()
+            .map_or(1, |current| current.channels())
+    }
+
+    fn sample_rate(&self) -> SampleRate {
+        self.current
+            .as_ref()
+            .map_or(44100, |current| current.sample_rate())
+    }
+
+    fn total_duration(&self) -> Option<Duration> {
+        None
+    }
+
+    /// Seeks in the current track.
+    fn try_seek(&mut self, pos: Duration) -> Result<(), SeekError> {
+        match &mut self.current {
+            Some(current) => {
+                current.try_seek(pos)?;
+                self.channel = 0;
+                Ok(())
+            }
+            None => Err(SeekError::NotSupported {
+                underlying_source: std::any::type_name::<Self>(),
+            }),
+        }
+    }
+}
+
+// Small xorshift generator for shuffling, seeded from the randomness the standard library
+// uses for hash maps.
+struct Rng(u64);
+
+impl Rng {
+    fn new() -> Rng {
+        Rng(RandomState::new().build_hasher().finish() | 1)
+    }
+
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Playlist, RepeatMode};
+    use crate::buffer::SamplesBuffer;
+    use crate::Sink;
+
+    // A playlist of three tracks, each three samples of its number.
+    fn playlist() -> (Playlist, impl Iterator<Item = f32>) {
+        let (sink, output) = Sink::new();
+        let playlist = Playlist::new(sink);
+        for track in 1..=3 {
+            playlist.push(move || SamplesBuffer::new(1, 1, vec![track as f32; 3]));
+        }
+        (playlist, output)
+    }
+
+    fn take(output: &mut impl Iterator<Item = f32>, n: usize) -> Vec<f32> {
+        output.take(n).collect()
+    }
+
+    #[test]
+    fn tracks_advance_when_they_end() {
+        let (playlist, mut output) = playlist();
+        assert_eq!(playlist.current(), Some(0));
+        assert_eq!(
+            take(&mut output, 9),
+            [1.0, 1.0, 1.0, 2.0, 2.0, 2.0, 3.0, 3.0, 3.0]
+        );
+        assert_eq!(take(&mut output, 2), [0.0, 0.0]);
+        assert_eq!(playlist.current(), None);
+
+        // starts over with the last track, after the silence the sink played in the meantime
+        playlist.prev();
+        let mut output = output.skip_while(|s| *s == 0.0);
+        assert_eq!(take(&mut output, 4), [3.0, 3.0, 3.0, 0.0]);
+    }
+
+    #[test]
+    fn next_and_prev_move_between_tracks() {
+        let (playlist, mut output) = playlist();
+        assert_eq!(take(&mut output, 1), [1.0]);
+        playlist.next();
+        assert_eq!(take(&mut output, 2), [2.0, 2.0]);
+        assert_eq!(playlist.current(), Some(1));
+        playlist.prev();
+        assert_eq!(take(&mut output, 4), [1.0, 1.0, 1.0, 2.0]);
+        playlist.jump_to(2);
+        assert_eq!(take(&mut output, 1), [3.0]);
+        playlist.prev();
+        playlist.prev();
+        playlist.prev();
+        assert_eq!(playlist.current(), Some(0));
+        assert_eq!(take(&mut output, 1), [1.0]);
+    }
+
+    #[test]
+    fn repeat_modes_and_shuffle() {
+        let (playlist, mut output) = playlist();
+        playlist.set_repeat(RepeatMode::One);
+        assert_eq!(take(&mut output, 7), [1.0; 7]);
+        playlist.set_repeat(RepeatMode::All);
+        playlist.jump_to(2);
+        assert_eq!(take(&mut output, 4), [3.0, 3.0, 3.0, 1.0]);
+
+        playlist.shuffle();
+        assert_eq!(playlist.current(), Some(0));
+        let mut played: Vec<f32> = take(&mut output, 8).into_iter().step_by(3).collect();
+        played.sort_by(f32::total_cmp);
+        assert_eq!(played, [1.0, 2.0, 3.0]);
+    }
+}
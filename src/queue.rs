@@ -1,10 +1,10 @@
 //! Queue that plays sounds one after the other.
 
-use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
 use std::sync::{Arc, Mutex};
 use std::time::Duration;
 
-use crate::source::{Empty, SeekError, Source, Zero};
+use crate::source::{Empty, SeekError, Source};
 use crate::Sample;
 
 use crate::common::{ChannelCount, SampleRate};
@@ -31,11 +31,17 @@ where
     let input = Arc::new(SourcesQueueInput {
         next_sounds: Mutex::new(Vec::new()),
         keep_alive_if_empty: AtomicBool::new(keep_alive_if_empty),
+        frames_played: Arc::new(AtomicU64::new(0)),
+        time_played: Arc::new(AtomicU64::new(0)),
     });
 
     let output = SourcesQueueOutput {
         current: Box::new(Empty::<S>::new()) as Box<_>,
+        silence: 0,
         signal_after_end: None,
+        channel: 0,
+        frame_channels: 1,
+        frame_rate: SILENCE_SAMPLE_RATE,
         input: input.clone(),
     };
 
@@ -53,8 +59,19 @@ pub struct SourcesQueueInput<S> {
 
     // See constructor.
     keep_alive_if_empty: AtomicBool,
+
+    // Frames the output has played, including silence. Shared with the sources of a sink.
+    frames_played: Arc<AtomicU64>,
+
+    // Time the output has played in ticks of `CLOCK_RATE`, unlike `frames_played` it does not
+    // depend on the sample rates of the sounds.
+    time_played: Arc<AtomicU64>,
 }
 
+// Ticks per second of `time_played`, a multiple of all common sample rates so a frame is a
+// whole number of ticks.
+pub(crate) const CLOCK_RATE: u64 = 705_600_000;
+
 impl<S> SourcesQueueInput<S>
 where
     S: Sample + Send + 'static,
@@ -97,6 +114,22 @@ where
             .store(keep_alive_if_empty, Ordering::Release);
     }
 
+    /// Returns the number of frames the output has played so far, including the silence
+    /// played while the queue is empty. A frame holds one sample for every channel.
+    #[inline]
+    pub fn frames_played(&self) -> u64 {
+        self.frames_played.load(Ordering::Relaxed)
+    }
+
+    pub(crate) fn frame_clock(&self) -> Arc<AtomicU64> {
+        self.frames_played.clone()
+    }
+
+    // See `time_played`.
+    pub(crate) fn time_clock(&self) -> Arc<AtomicU64> {
+        self.time_played.clone()
+    }
+
     /// Removes all the sounds from the queue. Returns the number of sounds cleared.
     pub fn clear(&self) -> usize {
         let mut sounds = self.next_sounds.lock().unwrap();
@@ -110,14 +143,25 @@ pub struct SourcesQueueOutput<S> {
     // The current iterator that produces samples.
     current: Box<dyn Source<Item = S> + Send>,
 
+    // Samples of filler silence left to play once `current` has ended. Played without a
+    // source of its own so that an idle queue does not allocate.
+    silence: usize,
+
     // Signal this sender before picking from `next`.
     signal_after_end: Option<Sender<()>>,
 
+    // Channel of the next sample and the format of the frame it belongs to.
+    channel: ChannelCount,
+    frame_channels: ChannelCount,
+    frame_rate: SampleRate,
+
     // The next sounds.
     input: Arc<SourcesQueueInput<S>>,
 }
 
 const THRESHOLD: usize = 512;
+const SILENCE_CHANNELS: ChannelCount = 1;
+const SILENCE_SAMPLE_RATE: SampleRate = 44100;
 impl<S> Source for SourcesQueueOutput<S>
 where
     S: Sample + Send + 'static,
@@ -135,6 +179,10 @@ where
         // situation we force a span to have a maximum number of samples indicate by this
         // constant.
 
+        if self.silence > 0 {
+            return Some(self.silence);
+        }
+
         // Try the current `current_span_len`.
         if let Some(val) = self.current.current_span_len() {
             if val != 0 {
@@ -161,12 +209,20 @@ where
 
     #[inline]
     fn channels(&self) -> ChannelCount {
-        self.current.channels()
+        if self.silence > 0 {
+            SILENCE_CHANNELS
+        } else {
+            self.current.channels()
+        }
     }
 
     #[inline]
     fn sample_rate(&self) -> SampleRate {
-        self.current.sample_rate()
+        if self.silence > 0 {
+            SILENCE_SAMPLE_RATE
+        } else {
+            self.current.sample_rate()
+        }
     }
 
     #[inline]
@@ -174,6 +230,11 @@ where
         None
     }
 
+    #[inline]
+    fn latency(&self) -> Duration {
+        self.current.latency()
+    }
+
     /// Only seeks within the current source.
     // We can not go back to previous sources. We could implement seek such
     // that it advances the queue if the position is beyond the current song.
@@ -185,6 +246,9 @@ where
     // next few songs.
     #[inline]
     fn try_seek(&mut self, pos: Duration) -> Result<(), SeekError> {
+        if self.silence > 0 {
+            return Ok(());
+        }
         self.current.try_seek(pos)
     }
 }
@@ -197,11 +261,45 @@ where
 
     #[inline]
     fn next(&mut self) -> Option<S> {
+        let sample = self.next_sample()?;
+        // Sources switch to a new format when its first sample is taken.
+        if self.channel == 0 {
+            self.frame_channels = self.channels().max(1);
+            self.frame_rate = self.sample_rate().max(1);
+        }
+        self.channel += 1;
+        if self.channel >= self.frame_channels {
+            self.channel = 0;
+            self.input.frames_played.fetch_add(1, Ordering::Relaxed);
+            self.input
+                .time_played
+                .fetch_add(CLOCK_RATE / self.frame_rate as u64, Ordering::Relaxed);
+        }
+        Some(sample)
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.current.size_hint().0 + self.silence, None)
+    }
+}
+
+impl<S> SourcesQueueOutput<S>
+where
+    S: Sample + Send + 'static,
+{
+    // Returns the next sample of the current sound or of the filler silence.
+    #[inline]
+    fn next_sample(&mut self) -> Option<S> {
         loop {
             // Basic situation that will happen most of the time.
             if let Some(sample) = self.current.next() {
                 return Some(sample);
             }
+            if self.silence > 0 {
+                self.silence -= 1;
+                return Some(S::ZERO_VALUE);
+            }
 
             // Since `self.current` has finished, we need to pick the next sound.
             // In order to avoid inlining this expensive operation, the code is in another function.
@@ -211,21 +309,14 @@ where
         }
     }
 
-    #[inline]
-    fn size_hint(&self) -> (usize, Option<usize>) {
-        (self.current.size_hint().0, None)
-    }
-}
-
-impl<S> SourcesQueueOutput<S>
-where
-    S: Sample + Send + 'static,
-{
     // Called when `current` is empty and we must jump to the next element.
     // Returns `Ok` if the sound should continue playing, or an error if it should stop.
     //
     // This method is separate so that it is not inlined.
     fn go_next(&mut self) -> Result<(), ()> {
+        // Drop the finished sound before signalling, `Empty` has no size so this does not
+        // allocate.
+        self.current = Box::new(Empty::<S>::new());
         if let Some(signal_after_end) = self.signal_after_end.take() {
             let _ = signal_after_end.send(());
         }
@@ -234,10 +325,10 @@ where
             let mut next = self.input.next_sounds.lock().unwrap();
 
             if next.len() == 0 {
-                let silence = Box::new(Zero::<S>::new_samples(1, 44100, THRESHOLD)) as Box<_>;
                 if self.input.keep_alive_if_empty.load(Ordering::Acquire) {
                     // Play a short silence in order to avoid spinlocking.
-                    (silence, None)
+                    self.silence = THRESHOLD;
+                    (Box::new(Empty::<S>::new()) as Box<_>, None)
                 } else {
                     return Err(());
                 }
@@ -0,0 +1,285 @@
+//! Renders the mixer into a ring buffer that the rest of an application reads from, instead
+//! of playing it on an audio device.
+
+use std::sync::atomic::{AtomicBool, AtomicU32, AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+use crate::common::{ChannelCount, SampleRate};
+use crate::mixer::{mixer, Mixer, MixerSource};
+
+/// Shortest and longest time the render thread waits for the ring to make room.
+const MIN_WAIT: Duration = Duration::from_millis(1);
+const MAX_WAIT: Duration = Duration::from_millis(10);
+
+/// An output that renders the mixer into a ring buffer instead of an audio device, to send
+/// the audio elsewhere, for example over the network or to a visualizer.
+///
+/// Works like an [`OutputStream`](crate::OutputStream): play sounds through
+/// [`mixer`](RingBufferOutput::mixer), for example by connecting a
+/// [`Sink`](crate::Sink) to it. A background thread renders the mixer into the ring and the
+/// samples are read with the [`RingBufferConsumer`] that is returned alongside. Dropping the
+/// output stops the thread.
+///
+/// The thread renders ahead until the ring is full and then waits for the consumer to make
+/// room, so the consumer sets the pace and no audio is overwritten or lost. If the consumer
+/// reads in real time the audio is delayed by up to the capacity of the ring, pick it as small
+/// as the application allows. While the ring is full the mixer is not advanced, so if the
+/// consumer stops reading, playback pauses. If the consumer reads faster than the thread
+/// renders it gets fewer samples than it asked for, and decides itself how to fill the gap.
+///
+/// # Example
+///
+/// ```
+/// use std::time::Duration;
+/// use rodio::source::{SineWave, Source};
+/// use rodio::RingBufferOutput;
+///
+/// let (output, mut consumer) = RingBufferOutput::new(2, 48_000, 4800);
+/// output.mixer().add(SineWave::new(440.0).take_duration(Duration::from_secs(1)));
+///
+/// let mut buffer = [0.0f32; 960];
+/// let read = consumer.pop_slice(&mut buffer);
+/// assert!(read <= buffer.len());
+/// ```
+pub struct RingBufferOutput {
+    mixer: Arc<Mixer<f32>>,
+    ring: Arc<Ring>,
+    thread: Option<JoinHandle<()>>,
+}
+
+/// Reads the samples a [`RingBufferOutput`] renders. Can be sent to another thread.
+pub struct RingBufferConsumer {
+    ring: Arc<Ring>,
+    channels: ChannelCount,
+    sample_rate: SampleRate,
+}
+
+// Single producer single consumer ring of samples. The samples are stored as the bits of
+// an `f32` so that the ring can be shared without locks.
+struct Ring {
+    slots: Box<[AtomicU32]>,
+    // Positions of the next sample to write and to read. They wrap at twice the capacity so
+    // that a full ring can be told apart from an empty one.
+    written: AtomicUsize,
+    read: AtomicUsize,
+    stopped: AtomicBool,
+}
+
+impl RingBufferOutput {
+    /// Starts rendering a mixer with `channels` channels at `sample_rate` into a ring that
+    /// holds `capacity` samples, counted over all channels.
+    ///
+    /// # Panics
+    ///
+    /// If `channels`, `sample_rate` or `capacity` is zero.
+    pub fn new(
+        channels: ChannelCount,
+        sample_rate: SampleRate,
+        capacity: usize,
+    ) -> (RingBufferOutput, RingBufferConsumer) {
+        assert!(channels >= 1);
+        assert!(sample_rate >= 1);
+        assert!(capacity >= 1);
+
+        let ring = Arc::new(Ring {
+            slots: (0..capacity).map(|_| AtomicU32::new(0)).collect(),
+            written: AtomicUsize::new(0),
+            read: AtomicUsize::new(0),
+            stopped: AtomicBool::new(false),
+        });
+        let (controller, source) = mixer(channels, sample_rate);
+
+        // Waiting for a quarter of the ring to be played keeps it mostly full.
+        let ring_duration =
+            Duration::from_secs_f64(capacity as f64 / channels as f64 / sample_rate as f64);
+        let wait = (ring_duration / 4).clamp(MIN_WAIT, MAX_WAIT);
+        let thread = {
+            let ring = ring.clone();
+            thread::Builder::new()
+                .name("rodio-ring-buffer".into())
+                .spawn(move || render(source, &ring, wait))
+                .expect("failed to spawn the ring buffer render thread")
+        };
+
+        let output = RingBufferOutput {
+            mixer: controller,
+            ring: ring.clone(),
+            thread: Some(thread),
+        };
+        let consumer = RingBufferConsumer {
+            ring,
+            channels,
+            sample_rate,
+        };
+        (output, consumer)
+    }
+
+    /// Access the output's mixer.
+    pub fn mixer(&self) -> Arc<Mixer<f32>> {
+        self.mixer.clone()
+    }
+}
+
+impl Drop for RingBufferOutput {
+    fn drop(&mut self) {
+        self.ring.stopped.store(true, Ordering::Relaxed);
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+impl RingBufferConsumer {
+    /// Moves as many samples as are available, up to the length of `out`, into `out` and
+    /// returns how many that were. The samples are interleaved like those of a
+    /// [`Source`](crate::Source). Never blocks.
+    pub fn pop_slice(&mut self, out: &mut [f32]) -> usize {
+        let ring = &self.ring;
+        let mut read = ring.read.load(Ordering::Relaxed);
+        let available = ring.len(ring.written.load(Ordering::Acquire), read);
+        let count = available.min(out.len());
+        for sample in &mut out[..count] {
+            *sample = f32::from_bits(ring.slot(read).load(Ordering::Relaxed));
+            read = ring.advance(read);
+        }
+        ring.read.store(read, Ordering::Release);
+        count
+    }
+
+    /// Number of samples that can be read right now.
+    pub fn available(&self) -> usize {
+        let ring = &self.ring;
+        ring.len(
+            ring.written.load(Ordering::Acquire),
+            ring.read.load(Ordering::Relaxed),
+        )
+    }
+
+    /// Number of samples the ring holds.
+    pub fn capacity(&self) -> usize {
+        self.ring.slots.len()
+    }
+
+    /// Number of interleaved channels of the samples.
+    pub fn channels(&self) -> ChannelCount {
+        self.channels
+    }
+
+    /// Sample rate of the samples.
+    pub fn sample_rate(&self) -> SampleRate {
+        self.sample_rate
+    }
+}
+
+impl Ring {
+    // Number of samples between the read and the write position.
+    #[inline]
+    fn len(&self, written: usize, read: usize) -> usize {
+        let capacity = self.slots.len();
+        if written >= read {
+            written - read
+        } else {
+            written + 2 * capacity - read
+        }
+    }
+
+    #[inline]
+    fn slot(&self, index: usize) -> &AtomicU32 {
+        let capacity = self.slots.len();
+        &self.slots[if index >= capacity {
+            index - capacity
+        } else {
+            index
+        }]
+    }
+
+    #[inline]
+    fn advance(&self, index: usize) -> usize {
+        if index + 1 == 2 * self.slots.len() {
+            0
+        } else {
+            index + 1
+        }
+    }
+}
+
+// Fills the ring with the output of the mixer until the output is dropped.
+fn render(mut source: MixerSource<f32>, ring: &Ring, wait: Duration) {
+    let capacity = ring.slots.len();
+    while !ring.stopped.load(Ordering::Relaxed) {
+        let mut written = ring.written.load(Ordering::Relaxed);
+        let free = capacity - ring.len(written, ring.read.load(Ordering::Acquire));
+        if free == 0 {
+            thread::sleep(wait);
+            continue;
+        }
+        for _ in 0..free {
+            let sample = source.next().unwrap_or(0.0);
+            ring.slot(written)
+                .store(sample.to_bits(), Ordering::Relaxed);
+            written = ring.advance(written);
+        }
+        ring.written.store(written, Ordering::Release);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use std::sync::atomic::{AtomicBool, AtomicU32, AtomicUsize};
+
+    use super::{Ring, RingBufferOutput};
+    use crate::buffer::SamplesBuffer;
+    use crate::Sink;
+
+    #[test]
+    fn appended_sources_come_out_in_order() {
+        // Matches the format the queue of a sink reports before its first sound starts, so
+        // nothing is converted.
+        let (output, mut consumer) = RingBufferOutput::new(1, 48_000, 64);
+        assert_eq!(consumer.capacity(), 64);
+        assert_eq!((consumer.channels(), consumer.sample_rate()), (1, 48_000));
+
+        let sink = Sink::connect_new(&output.mixer());
+        let first: Vec<f32> = (1..=300).map(|i| i as f32 / 1000.0).collect();
+        let second: Vec<f32> = (1..=300).map(|i| -(i as f32) / 1000.0).collect();
+        sink.append(SamplesBuffer::new(1, 48_000, first.clone()));
+        sink.append(SamplesBuffer::new(1, 48_000, second.clone()));
+
+        // the ring is only 64 samples long, so this also checks the render thread waits
+        let expected: Vec<f32> = first.into_iter().chain(second).collect();
+        let mut received: Vec<f32> = Vec::new();
+        let mut buffer = [0.0f32; 50];
+        let deadline = Instant::now() + Duration::from_secs(10);
+        while received.len() < expected.len() && Instant::now() < deadline {
+            let read = consumer.pop_slice(&mut buffer);
+            // silence plays until the sink starts and once it is done
+            received.extend(buffer[..read].iter().filter(|&&s| s != 0.0));
+        }
+        assert_eq!(received, expected);
+    }
+
+    #[test]
+    fn positions_wrap_around() {
+        let ring = Ring {
+            slots: (0..3).map(|_| AtomicU32::new(0)).collect(),
+            written: AtomicUsize::new(0),
+            read: AtomicUsize::new(0),
+            stopped: AtomicBool::new(false),
+        };
+        let (mut written, mut read) = (0, 0);
+        for _ in 0..10 {
+            written = ring.advance(ring.advance(ring.advance(written)));
+            assert_eq!(ring.len(written, read), 3);
+            read = ring.advance(ring.advance(read));
+            assert_eq!(ring.len(written, read), 1);
+            read = ring.advance(read);
+            assert_eq!(ring.len(written, read), 0);
+        }
+        assert!(written < 6);
+        assert!(std::ptr::eq(ring.slot(written), &ring.slots[written % 3]));
+    }
+}
@@ -1,4 +1,6 @@
-use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::any::Any;
+use std::io::{Read, Seek};
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
 use std::sync::{Arc, Mutex};
 use std::time::Duration;
 
@@ -8,10 +10,16 @@ use dasp_sample::FromSample;
 #[cfg(not(feature = "crossbeam-channel"))]
 use std::sync::mpsc::{Receiver, Sender};
 
+use crate::common::{frames_to_duration, ChannelCount, SampleRate};
+use crate::decoder::{background, DecodePool, DecoderError};
 use crate::mixer::Mixer;
-use crate::source::SeekError;
+use crate::source::{Automation, AutomationCurve, BoxedSource, SeekError};
 use crate::{queue, source::Done, Sample, Source};
 
+/// How often the controls of a playing source are synchronised with the `Sink`.
+// if you change the duration update the docs for try_seek!
+const CONTROL_PERIOD: Duration = Duration::from_millis(5);
+
 /// Handle to a device that outputs sounds.
 ///
 /// Dropping the `Sink` stops all its sounds. You can use `detach` if you want the sounds to continue
@@ -22,17 +30,270 @@ pub struct Sink {
 
     controls: Arc<Controls>,
     sound_count: Arc<AtomicUsize>,
+    // Applied in order to every appended source, see `SinkBuilder`.
+    effects: Vec<Effect>,
+    // Decodes the sounds of `append_async`, see `SinkBuilder::decode_pool`.
+    decode_pool: Option<DecodePool>,
+    // Keeps whatever plays the sink's output alive, see `Player::sink`.
+    _keep_alive: Option<Box<dyn Any + Send + Sync>>,
+    // Set by `finish`, no more sources are accepted.
+    finishing: AtomicBool,
 
     detached: bool,
 }
 
+type Effect = Box<dyn Fn(BoxedSource) -> BoxedSource + Send + Sync>;
+
+/// Builds a [`Sink`] that runs every appended source through the same chain of effects.
+///
+/// The chain is created anew for each source, so filters and other effects with an internal
+/// state start fresh with every sound.
+///
+/// # Example
+///
+/// ```no_run
+/// use rodio::source::{SineWave, Source};
+/// use rodio::{OutputStreamBuilder, SinkBuilder};
+/// use std::time::Duration;
+///
+/// let stream_handle = OutputStreamBuilder::open_default_stream().unwrap();
+/// let sink = SinkBuilder::new()
+///     .low_pass(8000)
+///     .amplify(0.5)
+///     .build(&stream_handle.mixer());
+///
+/// sink.append(SineWave::new(440.0).take_duration(Duration::from_secs(1)));
+/// sink.append(SineWave::new(880.0).take_duration(Duration::from_secs(1)));
+/// ```
+#[derive(Default)]
+pub struct SinkBuilder {
+    effects: Vec<Effect>,
+    decode_pool: Option<DecodePool>,
+}
+
+impl SinkBuilder {
+    /// Creates a builder without any effects.
+    #[inline]
+    pub fn new() -> SinkBuilder {
+        SinkBuilder::default()
+    }
+
+    /// Adds an arbitrary effect to the end of the chain.
+    pub fn effect<F, S>(mut self, effect: F) -> SinkBuilder
+    where
+        F: Fn(BoxedSource) -> S + Send + Sync + 'static,
+        S: Source<Item = f32> + Send + 'static,
+    {
+        self.effects
+            .push(Box::new(move |source| effect(source).boxed()));
+        self
+    }
+
+    /// Adds a low-pass filter to the chain, see [`Source::low_pass`].
+    #[inline]
+    pub fn low_pass(self, freq: u32) -> SinkBuilder {
+        self.effect(move |source| source.low_pass(freq))
+    }
+
+    /// Adds a high-pass filter to the chain, see [`Source::high_pass`].
+    #[inline]
+    pub fn high_pass(self, freq: u32) -> SinkBuilder {
+        self.effect(move |source| source.high_pass(freq))
+    }
+
+    /// Adds a fixed gain to the chain, see [`Source::amplify`]. This is independent from the
+    /// volume of the sink.
+    #[inline]
+    pub fn amplify(self, value: f32) -> SinkBuilder {
+        self.effect(move |source| source.amplify(value))
+    }
+
+    /// Decodes the sounds appended with [`Sink::append_async`] on the threads of `pool`
+    /// instead of a new thread for each sound. See [`DecodePool`].
+    #[inline]
+    pub fn decode_pool(mut self, pool: DecodePool) -> SinkBuilder {
+        self.decode_pool = Some(pool);
+        self
+    }
+
+    /// Builds the sink and begins playback on a stream, like [`Sink::connect_new`].
+    #[inline]
+    pub fn build(self, mixer: &Mixer<f32>) -> Sink {
+        let (sink, source) = self.build_unconnected();
+        mixer.add(source);
+        sink
+    }
+
+    /// Builds the sink without connecting it to anything, like [`Sink::new`].
+    #[inline]
+    pub fn build_unconnected(self) -> (Sink, queue::SourcesQueueOutput<f32>) {
+        let (mut sink, source) = Sink::new();
+        sink.effects = self.effects;
+        sink.decode_pool = self.decode_pool;
+        (sink, source)
+    }
+}
+
+/// Controls a single sound of a [`Sink`], returned by [`Sink::append`].
+///
+/// The handle can be cloned and dropped freely, dropping it does not affect the sound.
+#[derive(Clone)]
+pub struct SoundHandle {
+    controls: Arc<SoundControls>,
+}
+
+struct SoundControls {
+    stopped: AtomicBool,
+    finished: AtomicBool,
+    fade: Mutex<Option<Automation>>,
+    // Samples of the source played so far and the position they add up to, as the bits of
+    // an `f64` in seconds.
+    samples_played: AtomicU64,
+    position: AtomicU64,
+}
+
+impl SoundHandle {
+    /// Returns true once the sound has played to its end, was stopped or was removed from the
+    /// sink. A sound that is still waiting in the queue is not finished.
+    #[inline]
+    pub fn is_finished(&self) -> bool {
+        self.controls.finished.load(Ordering::SeqCst)
+    }
+
+    /// Stops this sound, other sounds in the sink are not affected. If it is waiting in the
+    /// queue it is skipped once its turn comes.
+    #[inline]
+    pub fn stop(&self) {
+        self.controls.stopped.store(true, Ordering::SeqCst);
+    }
+
+    /// Fades this sound out over `duration` and then stops it. The fade only progresses while
+    /// the sound is playing, on top of the volume of the sink.
+    pub fn fade_out(&self, duration: Duration) {
+        let mut fade = self.controls.fade.lock().unwrap();
+        let start = fade.as_ref().map_or(1.0, Automation::value);
+        *fade = Some(Automation::new(
+            start,
+            0.0,
+            duration,
+            AutomationCurve::Linear,
+        ));
+    }
+
+    /// Returns how many samples of this sound have been played, counted over all channels.
+    ///
+    /// Unlike the position of the sink this is counted in the sound itself, before the speed
+    /// of the sink is applied, as the queue pulls the samples. Seeking sets it to the sample
+    /// that is sought to, looping back with [`set_ab_loop`](Sink::set_ab_loop) counts as a
+    /// seek.
+    #[inline]
+    pub fn samples_played(&self) -> u64 {
+        self.controls.samples_played.load(Ordering::Relaxed)
+    }
+
+    /// Returns the position in this sound of what is being played, for example to keep
+    /// lyrics or an animation in sync with it. Zero until it starts playing.
+    ///
+    /// The position is measured in the time of the sound: after a second of playback at a
+    /// speed of 1.5 it is 1.5 seconds. It follows seeks like
+    /// [`samples_played`](SoundHandle::samples_played).
+    #[inline]
+    pub fn position(&self) -> Duration {
+        Duration::from_secs_f64(f64::from_bits(
+            self.controls.position.load(Ordering::Relaxed),
+        ))
+    }
+}
+
+/// Controls a sound appended with [`Sink::append_async`] and reports how decoding it went.
+pub struct DecodeHandle {
+    sound: SoundHandle,
+    result: Mutex<DecodeResult>,
+}
+
+enum DecodeResult {
+    Pending(std::sync::mpsc::Receiver<Result<(), DecoderError>>),
+    Done(Result<(), DecoderError>),
+}
+
+impl DecodeHandle {
+    /// Returns the handle that controls the sound.
+    #[inline]
+    pub fn sound(&self) -> &SoundHandle {
+        &self.sound
+    }
+
+    /// Returns the result of decoding once it is done, without waiting. Decoding fails if
+    /// the format of the data is not recognized, the sound is then skipped. It also counts as
+    /// done if the sound was stopped first.
+    pub fn try_result(&self) -> Option<Result<(), DecoderError>> {
+        let mut result = self.result.lock().unwrap();
+        if let DecodeResult::Pending(receiver) = &*result {
+            match receiver.try_recv() {
+                Ok(done) => *result = DecodeResult::Done(done),
+                Err(std::sync::mpsc::TryRecvError::Empty) => return None,
+                // The decoding thread panicked.
+                Err(std::sync::mpsc::TryRecvError::Disconnected) => {
+                    *result = DecodeResult::Done(Err(DecoderError::UnrecognizedFormat))
+                }
+            }
+        }
+        match &*result {
+            DecodeResult::Done(done) => Some(done.clone()),
+            DecodeResult::Pending(_) => None,
+        }
+    }
+
+    /// Blocks until decoding is done and returns its result, see
+    /// [`try_result`](DecodeHandle::try_result). Decoding runs ahead of playback by a few
+    /// chunks only, so this returns shortly before the sound has finished playing.
+    pub fn wait(&self) -> Result<(), DecoderError> {
+        let mut result = self.result.lock().unwrap();
+        if let DecodeResult::Pending(receiver) = &*result {
+            let done = receiver
+                .recv()
+                .unwrap_or(Err(DecoderError::UnrecognizedFormat));
+            *result = DecodeResult::Done(done);
+        }
+        match &*result {
+            DecodeResult::Done(done) => done.clone(),
+            DecodeResult::Pending(_) => unreachable!("the result was just received"),
+        }
+    }
+}
+
+// Marks the sound as finished once the source that plays it is dropped.
+struct FinishOnDrop(Arc<SoundControls>);
+
+impl Drop for FinishOnDrop {
+    fn drop(&mut self) {
+        self.0.finished.store(true, Ordering::SeqCst);
+    }
+}
+
+/// What a [`Sink`] is doing, see [`Sink::state`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SinkState {
+    /// A sound is playing.
+    Playing,
+    /// Sounds are queued but the sink is paused.
+    Paused,
+    /// Nothing is queued, regardless of whether the sink is paused.
+    Idle,
+}
+
+enum SeekTarget {
+    Time(Duration),
+    Frame(u64),
+}
+
 struct SeekOrder {
-    pos: Duration,
-    feedback: Sender<Result<(), SeekError>>,
+    target: SeekTarget,
+    feedback: Sender<Result<Duration, SeekError>>,
 }
 
 impl SeekOrder {
-    fn new(pos: Duration) -> (Self, Receiver<Result<(), SeekError>>) {
+    fn new(target: SeekTarget) -> (Self, Receiver<Result<Duration, SeekError>>) {
         #[cfg(not(feature = "crossbeam-channel"))]
         let (tx, rx) = {
             use std::sync::mpsc;
@@ -44,7 +305,13 @@ impl SeekOrder {
             use crossbeam_channel::bounded;
             bounded(1)
         };
-        (Self { pos, feedback: tx }, rx)
+        (
+            Self {
+                target,
+                feedback: tx,
+            },
+            rx,
+        )
     }
 
     fn attempt<S>(self, maybe_seekable: &mut S)
@@ -52,17 +319,294 @@ impl SeekOrder {
         S: Source,
         S::Item: Sample + Send,
     {
-        let res = maybe_seekable.try_seek(self.pos);
+        let res = match self.target {
+            SeekTarget::Time(pos) => maybe_seekable.try_seek(pos).map(|()| pos),
+            SeekTarget::Frame(frame) => {
+                let pos = frames_to_duration(frame, maybe_seekable.sample_rate());
+                maybe_seekable.try_seek_frames(frame).map(|()| pos)
+            }
+        };
         let _ignore_receiver_dropped = self.feedback.send(res);
     }
 }
 
+/// A fade scheduled with [`Sink::schedule_fade`], positions are frames of the sink clock.
+#[derive(Clone, Copy, Debug)]
+struct ScheduledFade {
+    start: u64,
+    len: u64,
+    from: f32,
+    to: f32,
+}
+
+impl ScheduledFade {
+    fn gain_at(&self, frame: u64) -> f32 {
+        if frame < self.start {
+            self.from
+        } else if frame - self.start >= self.len {
+            self.to
+        } else {
+            let t = (frame - self.start) as f64 / self.len as f64;
+            self.from + (self.to - self.from) * t as f32
+        }
+    }
+}
+
+/// A limit set with [`Sink::set_max_play_duration`], counted from tick `start` of the time
+/// the queue has played, so sounds at different sample rates count alike.
+#[derive(Clone, Copy, Debug)]
+struct PlayLimit {
+    start: u64,
+    max: Duration,
+    fade: Duration,
+}
+
+impl PlayLimit {
+    // The gain at tick `now`, `None` once the limit is reached.
+    fn gain_at(&self, now: u64) -> Option<f32> {
+        let to_ticks =
+            |duration: Duration| (duration.as_secs_f64() * queue::CLOCK_RATE as f64).round() as u64;
+        let end = self.start.saturating_add(to_ticks(self.max));
+        let left = end.checked_sub(now).filter(|&left| left > 0)?;
+        let fade = to_ticks(self.fade);
+        if left >= fade {
+            Some(1.0)
+        } else {
+            Some((left as f64 / fade as f64) as f32)
+        }
+    }
+}
+
+// Applies the fade scheduled on the sink and the play limit to every sample, looking up the
+// frame that is being played on the sink clock and the time played, so they do not depend on
+// the control period.
+struct ClockedFade<I> {
+    input: I,
+    controls: Arc<Controls>,
+    clock: Arc<AtomicU64>,
+    time: Arc<AtomicU64>,
+    // Copies of the scheduled fade and the play limit and the generation they were taken from.
+    fade: Option<ScheduledFade>,
+    limit: Option<PlayLimit>,
+    generation: u64,
+}
+
+impl<I> Iterator for ClockedFade<I>
+where
+    I: Source<Item = f32>,
+{
+    type Item = f32;
+
+    #[inline]
+    fn next(&mut self) -> Option<f32> {
+        let generation = self.controls.fade_generation.load(Ordering::Acquire);
+        if generation != self.generation {
+            self.fade = *self.controls.scheduled_fade.lock().unwrap();
+            self.limit = *self.controls.play_limit.lock().unwrap();
+            self.generation = generation;
+        }
+        let frame = self.clock.load(Ordering::Relaxed);
+        let mut gain = 1.0;
+        if let Some(limit) = self.limit {
+            match limit.gain_at(self.time.load(Ordering::Relaxed)) {
+                Some(limit_gain) => gain = limit_gain,
+                None => {
+                    // Ends the sounds that are still queued as well.
+                    self.controls.stopped.store(true, Ordering::SeqCst);
+                    return None;
+                }
+            }
+        }
+        let sample = self.input.next()?;
+        match self.fade {
+            Some(fade) => Some(sample * gain * fade.gain_at(frame)),
+            None => Some(sample * gain),
+        }
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.input.size_hint()
+    }
+}
+
+impl<I> Source for ClockedFade<I>
+where
+    I: Source<Item = f32>,
+{
+    #[inline]
+    fn current_span_len(&self) -> Option<usize> {
+        self.input.current_span_len()
+    }
+
+    #[inline]
+    fn channels(&self) -> ChannelCount {
+        self.input.channels()
+    }
+
+    #[inline]
+    fn sample_rate(&self) -> SampleRate {
+        self.input.sample_rate()
+    }
+
+    #[inline]
+    fn total_duration(&self) -> Option<Duration> {
+        self.input.total_duration()
+    }
+
+    #[inline]
+    fn tail_duration(&self) -> Duration {
+        self.input.tail_duration()
+    }
+
+    #[inline]
+    fn latency(&self) -> Duration {
+        self.input.latency()
+    }
+
+    #[inline]
+    fn try_seek(&mut self, pos: Duration) -> Result<(), SeekError> {
+        self.input.try_seek(pos)
+    }
+}
+
+// Counts the samples the queue pulls from a sound for the position of its handle.
+struct CountPlayed<I> {
+    input: I,
+    controls: Arc<SoundControls>,
+    // Position at the start of the samples counted in the current format.
+    offset: f64,
+    counted: u64,
+    channels: ChannelCount,
+    sample_rate: SampleRate,
+}
+
+impl<I> CountPlayed<I> {
+    fn seconds(&self) -> f64 {
+        if self.counted == 0 {
+            self.offset
+        } else {
+            self.offset + self.counted as f64 / self.channels as f64 / self.sample_rate as f64
+        }
+    }
+}
+
+impl<I> Iterator for CountPlayed<I>
+where
+    I: Source,
+    I::Item: Sample,
+{
+    type Item = I::Item;
+
+    #[inline]
+    fn next(&mut self) -> Option<I::Item> {
+        let (channels, sample_rate) = (self.input.channels(), self.input.sample_rate());
+        if (channels, sample_rate) != (self.channels, self.sample_rate) {
+            self.offset = self.seconds();
+            self.counted = 0;
+            self.channels = channels.max(1);
+            self.sample_rate = sample_rate.max(1);
+        }
+        let sample = self.input.next()?;
+        self.counted += 1;
+        let controls = &self.controls;
+        controls.samples_played.fetch_add(1, Ordering::Relaxed);
+        controls
+            .position
+            .store(self.seconds().to_bits(), Ordering::Relaxed);
+        Some(sample)
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.input.size_hint()
+    }
+}
+
+impl<I> Source for CountPlayed<I>
+where
+    I: Source,
+    I::Item: Sample,
+{
+    #[inline]
+    fn current_span_len(&self) -> Option<usize> {
+        self.input.current_span_len()
+    }
+
+    #[inline]
+    fn channels(&self) -> ChannelCount {
+        self.input.channels()
+    }
+
+    #[inline]
+    fn sample_rate(&self) -> SampleRate {
+        self.input.sample_rate()
+    }
+
+    #[inline]
+    fn total_duration(&self) -> Option<Duration> {
+        self.input.total_duration()
+    }
+
+    #[inline]
+    fn tail_duration(&self) -> Duration {
+        self.input.tail_duration()
+    }
+
+    #[inline]
+    fn latency(&self) -> Duration {
+        self.input.latency()
+    }
+
+    #[inline]
+    fn try_seek(&mut self, pos: Duration) -> Result<(), SeekError> {
+        self.input.try_seek(pos)?;
+        let frame = (pos.as_secs_f64() * self.input.sample_rate() as f64).round() as u64;
+        self.seeked(pos.as_secs_f64(), frame);
+        Ok(())
+    }
+
+    #[inline]
+    fn try_seek_frames(&mut self, frame: u64) -> Result<(), SeekError> {
+        self.input.try_seek_frames(frame)?;
+        self.seeked(frame as f64 / self.input.sample_rate().max(1) as f64, frame);
+        Ok(())
+    }
+}
+
+impl<I> CountPlayed<I>
+where
+    I: Source,
+    I::Item: Sample,
+{
+    // Starts counting over from `frame`, which is `offset` seconds into the source.
+    fn seeked(&mut self, offset: f64, frame: u64) {
+        self.offset = offset;
+        self.counted = 0;
+        let controls = &self.controls;
+        controls
+            .samples_played
+            .store(frame * self.input.channels() as u64, Ordering::Relaxed);
+        controls
+            .position
+            .store(self.offset.to_bits(), Ordering::Relaxed);
+    }
+}
+
 struct Controls {
     pause: AtomicBool,
     volume: Mutex<f32>,
+    volume_automation: Mutex<Option<Automation>>,
+    scheduled_fade: Mutex<Option<ScheduledFade>>,
+    play_limit: Mutex<Option<PlayLimit>>,
+    // Bumped whenever `scheduled_fade` or `play_limit` changes, so sources only lock them
+    // when needed.
+    fade_generation: AtomicU64,
     stopped: AtomicBool,
     speed: Mutex<f32>,
     to_clear: Mutex<u32>,
+    zero_crossing_window: Mutex<Duration>,
+    ab_loop: Mutex<Option<(Duration, Duration)>>,
     seek: Mutex<Option<SeekOrder>>,
     position: Mutex<Duration>,
 }
@@ -87,26 +631,58 @@ impl Sink {
             controls: Arc::new(Controls {
                 pause: AtomicBool::new(false),
                 volume: Mutex::new(1.0),
+                volume_automation: Mutex::new(None),
+                scheduled_fade: Mutex::new(None),
+                play_limit: Mutex::new(None),
+                fade_generation: AtomicU64::new(0),
                 stopped: AtomicBool::new(false),
                 speed: Mutex::new(1.0),
                 to_clear: Mutex::new(0),
+                zero_crossing_window: Mutex::new(Duration::ZERO),
+                ab_loop: Mutex::new(None),
                 seek: Mutex::new(None),
                 position: Mutex::new(Duration::ZERO),
             }),
             sound_count: Arc::new(AtomicUsize::new(0)),
+            effects: Vec::new(),
+            decode_pool: None,
+            _keep_alive: None,
+            finishing: AtomicBool::new(false),
             detached: false,
         };
         (sink, queue_rx)
     }
 
+    /// Keeps `value` alive for as long as this sink exists.
+    #[cfg(feature = "playback")]
+    pub(crate) fn keep_alive(&mut self, value: impl Any + Send + Sync) {
+        self._keep_alive = Some(Box::new(value));
+    }
+
     /// Appends a sound to the queue of sounds to play.
+    ///
+    /// The returned handle controls just this sound, for example to stop it without affecting
+    /// the others. After [`finish`](Sink::finish) the sound is dropped instead and the handle
+    /// reports it as finished right away.
     #[inline]
-    pub fn append<S>(&self, source: S)
+    pub fn append<S>(&self, source: S) -> SoundHandle
     where
         S: Source + Send + 'static,
         f32: FromSample<S::Item>,
         S::Item: Sample + Send,
     {
+        if self.finishing.load(Ordering::SeqCst) {
+            return SoundHandle {
+                controls: Arc::new(SoundControls {
+                    stopped: AtomicBool::new(true),
+                    finished: AtomicBool::new(true),
+                    fade: Mutex::new(None),
+                    samples_played: AtomicU64::new(0),
+                    position: AtomicU64::new(0),
+                }),
+            };
+        }
+
         // Wait for the queue to flush then resume stopped playback
         if self.controls.stopped.load(Ordering::SeqCst) {
             if self.sound_count.load(Ordering::SeqCst) > 0 {
@@ -115,11 +691,90 @@ impl Sink {
             self.controls.stopped.store(false, Ordering::SeqCst);
         }
 
+        if self.effects.is_empty() {
+            self.append_controlled(source)
+        } else {
+            let source = self
+                .effects
+                .iter()
+                .fold(source.convert_samples::<f32>().boxed(), |source, effect| {
+                    effect(source)
+                });
+            self.append_controlled::<BoxedSource>(source)
+        }
+    }
+
+    /// Appends an encoded sound, for example a file, that is decoded on a thread of its own,
+    /// or on the [`DecodePool`] of the sink if it was built with one.
+    ///
+    /// Waits until the data is probed and the first samples are decoded, so the sound starts
+    /// in its own format, then appends it and returns. The rest is decoded while it plays. If
+    /// decoding falls behind, silence is played until it catches up. Errors, such as an
+    /// unrecognized format, are reported through the returned handle and the sound is
+    /// skipped.
+    ///
+    /// The sound can not seek.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use std::fs::File;
+    /// use rodio::OutputStreamBuilder;
+    ///
+    /// let stream_handle = OutputStreamBuilder::open_default_stream().unwrap();
+    /// let sink = rodio::Sink::connect_new(&stream_handle.mixer());
+    /// let handle = sink.append_async(File::open("music.flac").unwrap());
+    /// if let Err(err) = handle.wait() {
+    ///     eprintln!("could not decode music.flac: {err}");
+    /// }
+    /// sink.sleep_until_end();
+    /// ```
+    pub fn append_async<R>(&self, data: R) -> DecodeHandle
+    where
+        R: Read + Seek + Send + Sync + 'static,
+    {
+        let (source, result) = match &self.decode_pool {
+            Some(pool) => pool.decode(data),
+            None => background::decode_in_background(data),
+        };
+        DecodeHandle {
+            sound: self.append(source),
+            result: Mutex::new(DecodeResult::Pending(result)),
+        }
+    }
+
+    /// Wraps the source with the controls of this sink and adds it to the queue.
+    fn append_controlled<S>(&self, source: S) -> SoundHandle
+    where
+        S: Source + Send + 'static,
+        f32: FromSample<S::Item>,
+        S::Item: Sample + Send,
+    {
         let controls = self.controls.clone();
+        let fade_controls = self.controls.clone();
+        let handle = SoundHandle {
+            controls: Arc::new(SoundControls {
+                stopped: AtomicBool::new(false),
+                finished: AtomicBool::new(false),
+                fade: Mutex::new(None),
+                samples_played: AtomicU64::new(0),
+                position: AtomicU64::new(0),
+            }),
+        };
+        let sound = FinishOnDrop(handle.controls.clone());
+        let source = CountPlayed {
+            input: source,
+            controls: handle.controls.clone(),
+            offset: 0.0,
+            counted: 0,
+            channels: 0,
+            sample_rate: 0,
+        };
 
         let start_played = AtomicBool::new(false);
 
         let source = source
+            .loop_region(Duration::ZERO, Duration::ZERO)
             .speed(1.0)
             // must be placed before pausable but after speed & delay
             .track_position()
@@ -127,39 +782,89 @@ impl Sink {
             .amplify(1.0)
             .skippable()
             .stoppable()
-            // if you change the duration update the docs for try_seek!
-            .periodic_access(Duration::from_millis(5), move |src| {
+            .periodic_access(CONTROL_PERIOD, move |src| {
+                let sound = &sound.0;
                 if controls.stopped.load(Ordering::SeqCst) {
                     src.stop();
                     *controls.position.lock().unwrap() = Duration::ZERO;
                 }
+                if sound.stopped.load(Ordering::SeqCst) {
+                    src.stop();
+                }
                 {
                     let mut to_clear = controls.to_clear.lock().unwrap();
                     if *to_clear > 0 {
-                        src.inner_mut().skip();
+                        let skippable = src.inner_mut();
+                        let window = *controls.zero_crossing_window.lock().unwrap();
+                        let frames = window.as_secs_f64() * skippable.sample_rate() as f64;
+                        skippable.skip_at_zero_crossing(frames as usize);
                         *to_clear -= 1;
                         *controls.position.lock().unwrap() = Duration::ZERO;
                     } else {
                         *controls.position.lock().unwrap() = src.inner().inner().inner().inner().get_pos();
                     }
                 }
+                let mut sound_volume = 1.0;
+                if !controls.pause.load(Ordering::SeqCst) {
+                    let mut automation = controls.volume_automation.lock().unwrap();
+                    if let Some(ramp) = automation.as_mut() {
+                        *controls.volume.lock().unwrap() = ramp.value();
+                        if ramp.is_finished() {
+                            *automation = None;
+                        } else {
+                            ramp.advance(CONTROL_PERIOD);
+                        }
+                    }
+                    if let Some(fade) = sound.fade.lock().unwrap().as_mut() {
+                        sound_volume = fade.value();
+                        if fade.is_finished() {
+                            src.stop();
+                        } else {
+                            fade.advance(CONTROL_PERIOD);
+                        }
+                    }
+                } else if let Some(fade) = sound.fade.lock().unwrap().as_ref() {
+                    sound_volume = fade.value();
+                }
                 let amp = src.inner_mut().inner_mut();
-                amp.set_factor(*controls.volume.lock().unwrap());
+                amp.set_factor(*controls.volume.lock().unwrap() * sound_volume);
                 amp.inner_mut()
                     .set_paused(controls.pause.load(Ordering::SeqCst));
-                amp.inner_mut()
-                    .inner_mut()
-                    .inner_mut()
-                    .set_factor(*controls.speed.lock().unwrap());
+                let speed = *controls.speed.lock().unwrap();
+                let track_position = amp.inner_mut().inner_mut();
+                track_position.inner_mut().set_factor(speed);
+                // The loop works in the time of the source, independent of the speed.
+                let looped = track_position.inner_mut().inner_mut();
+                match *controls.ab_loop.lock().unwrap() {
+                    Some((a, b)) => looped.set_region(a, b),
+                    None => looped.clear_region(),
+                }
+                // Jumps back are not seen by the position tracking, correct it.
+                let looped_pos = (looped.loop_count() > 0).then(|| looped.get_pos());
+                if let Some(pos) = looped_pos.filter(|_| speed > 0.0) {
+                    let pos = pos.div_f32(speed);
+                    track_position.set_pos(pos);
+                    *controls.position.lock().unwrap() = pos;
+                }
                 if let Some(seek) = controls.seek.lock().unwrap().take() {
                     seek.attempt(amp)
                 }
                 start_played.store(true, Ordering::SeqCst);
             })
             .convert_samples();
+        let source = ClockedFade {
+            input: source,
+            controls: fade_controls,
+            clock: self.queue_tx.frame_clock(),
+            time: self.queue_tx.time_clock(),
+            fade: None,
+            limit: None,
+            generation: 0,
+        };
         self.sound_count.fetch_add(1, Ordering::Relaxed);
         let source = Done::new(source, self.sound_count.clone());
         *self.sleep_until_end.lock().unwrap() = Some(self.queue_tx.append_with_signal(source));
+        handle
     }
 
     /// Gets the volume of the sound.
@@ -175,11 +880,139 @@ impl Sink {
     ///
     /// The value `1.0` is the "normal" volume (unfiltered input). Any value other than `1.0` will
     /// multiply each sample by this value.
+    ///
+    /// Cancels any volume change scheduled with [`automate_volume`](Sink::automate_volume).
     #[inline]
     pub fn set_volume(&self, value: f32) {
+        *self.controls.volume_automation.lock().unwrap() = None;
         *self.controls.volume.lock().unwrap() = value;
     }
 
+    /// Gets the volume on the scale of [`set_volume_perceptual`](Sink::set_volume_perceptual).
+    #[inline]
+    pub fn volume_perceptual(&self) -> f32 {
+        self.volume().max(0.0).cbrt()
+    }
+
+    /// Changes the volume on a scale that follows how loud it sounds, like the volume slider
+    /// of a media player: `0.0` is silent, `1.0` the "normal" volume and the steps in between
+    /// sound even.
+    ///
+    /// The ear hears the gain logarithmically, so a linear slider seems to do little at the
+    /// top and a lot at the bottom. The value is cubed into a gain instead, `0.5` plays at a
+    /// gain of `0.125`, about -18 dB. Negative values are silent, values above `1.0` amplify.
+    ///
+    /// Cancels any volume change scheduled with [`automate_volume`](Sink::automate_volume).
+    #[inline]
+    pub fn set_volume_perceptual(&self, value: f32) {
+        self.set_volume(value.max(0.0).powi(3));
+    }
+
+    /// Smoothly changes the volume from its current value to `target` over `duration`,
+    /// following `curve`.
+    ///
+    /// The change is driven by the playing source, so it advances with the audio rather than
+    /// with wall clock time and is paused together with the sink. Calling this again replaces
+    /// the ongoing automation, starting from wherever the volume currently is.
+    pub fn automate_volume(&self, target: f32, duration: Duration, curve: AutomationCurve) {
+        let start = self.volume();
+        *self.controls.volume_automation.lock().unwrap() =
+            Some(Automation::new(start, target, duration, curve));
+    }
+
+    /// Returns the sink clock, the number of frames the sink has played since it was created.
+    ///
+    /// A frame holds one sample for every channel. The frames are counted at the sample rate
+    /// of the sound that is playing, before the mixer converts it, so the clock only keeps pace
+    /// with the mixer while the sounds share its rate. Paused sounds keep counting, as does the
+    /// silence the sink plays at 44.1 kHz while it is idle.
+    #[inline]
+    pub fn sample_clock(&self) -> u64 {
+        self.queue_tx.frames_played()
+    }
+
+    /// Schedules a linear fade of the volume from `from` to `to`, starting at frame
+    /// `start_sample` of the [sink clock](Sink::sample_clock) and lasting `len_samples`
+    /// frames.
+    ///
+    /// The gain is computed for every sample from the frame being played, so the fade starts
+    /// and ends exactly at the given frames, unlike with
+    /// [`automate_volume`](Sink::automate_volume) which follows the control period of about 5
+    /// milliseconds. Until the start the gain is `from` and after the end it stays at `to`.
+    /// The gain applies on top of [`volume`](Sink::volume). Calling this again replaces the
+    /// scheduled fade.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rodio::Sink;
+    ///
+    /// let (sink, _output) = Sink::new();
+    /// // fade out over one second at 48 kHz, starting half a second from now
+    /// let start = sink.sample_clock() + 24_000;
+    /// sink.schedule_fade(start, 48_000, 1.0, 0.0);
+    /// ```
+    pub fn schedule_fade(&self, start_sample: u64, len_samples: u64, from: f32, to: f32) {
+        *self.controls.scheduled_fade.lock().unwrap() = Some(ScheduledFade {
+            start: start_sample,
+            len: len_samples,
+            from,
+            to,
+        });
+        self.controls
+            .fade_generation
+            .fetch_add(1, Ordering::Release);
+    }
+
+    /// Stops the sink once `max` of audio has played from now on. Every sound counts at its
+    /// own sample rate, unlike on the [sink clock](Sink::sample_clock).
+    ///
+    /// The sink stops exactly at the frame the limit is reached, like with
+    /// [`stop`](Sink::stop) the sounds that are still queued are dropped. The clock keeps
+    /// running while the sink is paused or idle, that time counts towards the limit too. Sounds
+    /// appended after the limit is reached are stopped right away, until it is raised or
+    /// [cleared](Sink::clear_max_play_duration). Calling this again replaces the limit and
+    /// starts counting anew.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rodio::source::{SineWave, Source};
+    /// use rodio::Sink;
+    /// use std::time::Duration;
+    ///
+    /// let (sink, _output) = Sink::new();
+    /// // a preview of the first thirty seconds
+    /// sink.set_max_play_duration(Duration::from_secs(30));
+    /// sink.append(SineWave::new(440.0).take_duration(Duration::from_secs(90)));
+    /// ```
+    #[inline]
+    pub fn set_max_play_duration(&self, max: Duration) {
+        self.set_max_play_duration_with_fade(max, Duration::ZERO);
+    }
+
+    /// Like [`set_max_play_duration`](Sink::set_max_play_duration), but fades the volume out
+    /// linearly over the last `fade` before the limit so playback does not end with a click.
+    /// A fade longer than `max` starts right away.
+    pub fn set_max_play_duration_with_fade(&self, max: Duration, fade: Duration) {
+        *self.controls.play_limit.lock().unwrap() = Some(PlayLimit {
+            start: self.queue_tx.time_clock().load(Ordering::Relaxed),
+            max,
+            fade,
+        });
+        self.controls
+            .fade_generation
+            .fetch_add(1, Ordering::Release);
+    }
+
+    /// Removes the limit set with [`set_max_play_duration`](Sink::set_max_play_duration).
+    pub fn clear_max_play_duration(&self) {
+        *self.controls.play_limit.lock().unwrap() = None;
+        self.controls
+            .fade_generation
+            .fetch_add(1, Ordering::Release);
+    }
+
     /// Changes the play speed of the sound. Does not adjust the samples, only the playback speed.
     ///
     /// # Note:
@@ -247,7 +1080,24 @@ impl Sink {
     /// When seeking beyond the end of a source this
     /// function might return an error if the duration of the source is not known.
     pub fn try_seek(&self, pos: Duration) -> Result<(), SeekError> {
-        let (order, feedback) = SeekOrder::new(pos);
+        self.seek_to(SeekTarget::Time(pos))
+    }
+
+    /// Attempts to seek to the frame at index `frame` in the current source.
+    ///
+    /// A frame holds one sample for every channel, so `frame` is the number of samples per
+    /// channel from the start of the source, at its own sample rate. With a [`Decoder`] for
+    /// WAV or raw PCM data this lands exactly on that frame as long as the speed is 1.0.
+    ///
+    /// Behaves like [`try_seek`](Sink::try_seek) otherwise.
+    ///
+    /// [`Decoder`]: crate::Decoder
+    pub fn try_seek_samples(&self, frame: u64) -> Result<(), SeekError> {
+        self.seek_to(SeekTarget::Frame(frame))
+    }
+
+    fn seek_to(&self, target: SeekTarget) -> Result<(), SeekError> {
+        let (order, feedback) = SeekOrder::new(target);
         *self.controls.seek.lock().unwrap() = Some(order);
 
         if self.sound_count.load(Ordering::Acquire) == 0 {
@@ -256,10 +1106,9 @@ impl Sink {
         }
 
         match feedback.recv() {
-            Ok(seek_res) => {
+            Ok(seek_res) => seek_res.map(|pos| {
                 *self.controls.position.lock().unwrap() = pos;
-                seek_res
-            }
+            }),
             // The feedback channel closed. Probably another SeekOrder was set
             // invalidating this one and closing the feedback channel
             // ... or the audio thread panicked.
@@ -284,6 +1133,20 @@ impl Sink {
         self.controls.pause.load(Ordering::SeqCst)
     }
 
+    /// Returns whether the sink is playing, paused or has nothing to play.
+    ///
+    /// A sound counts as queued until the audio thread has played it to the end, so this
+    /// follows the actual playback rather than the calls made on the sink.
+    pub fn state(&self) -> SinkState {
+        if self.sound_count.load(Ordering::SeqCst) == 0 {
+            SinkState::Idle
+        } else if self.controls.pause.load(Ordering::SeqCst) {
+            SinkState::Paused
+        } else {
+            SinkState::Playing
+        }
+    }
+
     /// Removes all currently loaded `Source`s from the `Sink`, and pauses it.
     ///
     /// See `pause()` for information about pausing a `Sink`.
@@ -299,6 +1162,9 @@ impl Sink {
     /// If there are more `Source`s appended to the `Sink` at the time,
     /// it will play the next one. Otherwise, the `Sink` will finish as if
     /// it had finished playing a `Source` all the way through.
+    ///
+    /// See [`set_zero_crossing_window`](Sink::set_zero_crossing_window) to avoid a click at
+    /// the cut.
     pub fn skip_one(&self) {
         let len = self.sound_count.load(Ordering::SeqCst) as u32;
         let mut to_clear = self.controls.to_clear.lock().unwrap();
@@ -307,12 +1173,64 @@ impl Sink {
         }
     }
 
+    /// Makes [`skip_one`](Sink::skip_one) wait for the current sound to reach a zero crossing
+    /// before switching to the next one, which avoids a click at the cut.
+    ///
+    /// The skip is delayed by at most `window` (on top of the usual control period of about
+    /// 5 milliseconds). A window of zero, the default, cuts immediately.
+    #[inline]
+    pub fn set_zero_crossing_window(&self, window: Duration) {
+        *self.controls.zero_crossing_window.lock().unwrap() = window;
+    }
+
+    /// Repeats the part of the current sound between `a` and `b` until
+    /// [`clear_ab_loop`](Sink::clear_ab_loop) is called.
+    ///
+    /// Once playback reaches `b` it jumps back to `a`, exactly at the sample. The positions are
+    /// relative to the start of the sound as recorded, so the loop stays in place when the
+    /// speed is changed with [`set_speed`](Sink::set_speed). The sound must support seeking,
+    /// otherwise it plays on normally. The loop applies to whichever sound is playing, also
+    /// after skipping to the next one.
+    pub fn set_ab_loop(&self, a: Duration, b: Duration) {
+        *self.controls.ab_loop.lock().unwrap() = Some((a, b));
+    }
+
+    /// Stops looping, see [`set_ab_loop`](Sink::set_ab_loop). Playback continues from where it
+    /// is.
+    pub fn clear_ab_loop(&self) {
+        *self.controls.ab_loop.lock().unwrap() = None;
+    }
+
     /// Stops the sink by emptying the queue.
     #[inline]
     pub fn stop(&self) {
         self.controls.stopped.store(true, Ordering::SeqCst);
     }
 
+    /// Plays out the sounds that are queued and blocks until they are done, then the sink
+    /// goes idle for good. From now on [`append`](Sink::append) ignores new sounds.
+    ///
+    /// If `timeout` is given and the sounds take longer than that, the remaining ones are
+    /// stopped. Returns true if everything played to the end. Without a timeout this blocks
+    /// forever while the sink is paused.
+    pub fn finish(&self, timeout: Option<Duration>) -> bool {
+        self.finishing.store(true, Ordering::SeqCst);
+        // Lets the output end once the last sound is done.
+        self.queue_tx.set_keep_alive_if_empty(false);
+
+        let Some(until_end) = self.sleep_until_end.lock().unwrap().take() else {
+            return true;
+        };
+        let drained = match timeout {
+            Some(timeout) => until_end.recv_timeout(timeout).is_ok(),
+            None => until_end.recv().is_ok(),
+        };
+        if !drained {
+            self.stop();
+        }
+        drained
+    }
+
     /// Destroys the sink without stopping the sounds that are still playing.
     #[inline]
     pub fn detach(mut self) {
@@ -368,8 +1286,78 @@ impl Drop for Sink {
 mod tests {
     use std::sync::atomic::Ordering;
 
+    use std::time::Duration;
+
     use crate::buffer::SamplesBuffer;
-    use crate::{Sink, Source};
+    use crate::common::{ChannelCount, SampleRate};
+    use crate::source::{AutomationCurve, SeekError};
+    use crate::{Sink, SinkBuilder, SinkState, Source};
+
+    // Counts up from zero and can only seek to a frame.
+    struct FrameSeekable {
+        channels: ChannelCount,
+        next: u64,
+    }
+
+    impl Iterator for FrameSeekable {
+        type Item = f32;
+
+        fn next(&mut self) -> Option<f32> {
+            self.next += 1;
+            Some((self.next - 1) as f32)
+        }
+    }
+
+    impl Source for FrameSeekable {
+        fn current_span_len(&self) -> Option<usize> {
+            None
+        }
+
+        fn channels(&self) -> ChannelCount {
+            self.channels
+        }
+
+        fn sample_rate(&self) -> SampleRate {
+            1
+        }
+
+        fn total_duration(&self) -> Option<Duration> {
+            None
+        }
+
+        fn try_seek_frames(&mut self, frame: u64) -> Result<(), SeekError> {
+            self.next = frame * self.channels as u64;
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn seeking_samples_lands_on_the_frame() {
+        let (sink, mut queue_rx) = Sink::new();
+        sink.append(FrameSeekable {
+            channels: 2,
+            next: 0,
+        });
+
+        let output = std::thread::scope(|scope| {
+            let seek = scope.spawn(|| sink.try_seek_samples(1_000_000));
+            let mut output = Vec::new();
+            while !seek.is_finished() {
+                output.extend(queue_rx.next());
+            }
+            seek.join().unwrap().unwrap();
+            output.extend(queue_rx.by_ref().take(4));
+            output
+        });
+        // the source does not support seeking to a `Duration`, so the frame reached it
+        let jump = (1..output.len())
+            .find(|&i| output[i] != output[i - 1] + 1.0)
+            .unwrap();
+        assert_eq!(
+            output[jump..jump + 4],
+            [2_000_000.0, 2_000_001.0, 2_000_002.0, 2_000_003.0]
+        );
+    }
 
     #[test]
     fn test_pause_and_stop() {
@@ -430,6 +1418,20 @@ mod tests {
         assert_eq!(queue_rx.next(), src.next());
     }
 
+    #[test]
+    fn perceptual_volume_is_cubed() {
+        let (sink, _) = Sink::new();
+        sink.set_volume_perceptual(0.5);
+        let db = 20.0 * sink.volume().log10();
+        assert!((db + 18.06).abs() < 0.01, "{db}");
+        assert!((sink.volume_perceptual() - 0.5).abs() < 1e-6);
+
+        sink.set_volume_perceptual(1.0);
+        assert_eq!(sink.volume(), 1.0);
+        sink.set_volume_perceptual(-1.0);
+        assert_eq!(sink.volume(), 0.0);
+    }
+
     #[test]
     fn test_volume() {
         let (sink, mut queue_rx) = Sink::new();
@@ -447,4 +1449,334 @@ mod tests {
             assert_eq!(queue_rx.next(), src.next());
         }
     }
+
+    #[test]
+    fn test_automate_volume() {
+        let (sink, mut queue_rx) = Sink::new();
+
+        // 1 kHz mono, every control period of 5ms spans exactly 5 samples.
+        sink.append(SamplesBuffer::new(1, 1000, vec![1.0f32; 1500]));
+        sink.automate_volume(0.0, Duration::from_secs(1), AutomationCurve::Linear);
+
+        for n in 0..1500 {
+            let expected = (1.0 - n as f32 / 1000.0).max(0.0);
+            let sample = queue_rx.next().unwrap();
+            assert!(
+                (sample - expected).abs() < 0.01,
+                "sample {n}: got {sample}, expected {expected}"
+            );
+        }
+        assert_eq!(sink.volume(), 0.0);
+    }
+
+    #[test]
+    fn scheduled_fade_is_sample_exact() {
+        let (sink, mut queue_rx) = Sink::new();
+
+        // starts and ends between two control periods of 5 samples
+        sink.schedule_fade(103, 200, 0.2, 0.8);
+        sink.append(SamplesBuffer::new(1, 1000, vec![1.0f32; 400]));
+
+        let played: Vec<f32> = queue_rx.by_ref().take(400).collect();
+        assert!(played[..=103].iter().all(|&s| s == 0.2));
+        assert!((played[104] - 0.203).abs() < 1e-6);
+        assert!((played[203] - 0.5).abs() < 1e-6);
+        assert!((played[302] - 0.797).abs() < 1e-6);
+        assert!(played[303..].iter().all(|&s| s == 0.8));
+        assert_eq!(sink.sample_clock(), 400);
+    }
+
+    #[test]
+    fn max_play_duration_stops_the_queue() {
+        let (sink, mut queue_rx) = Sink::new();
+        sink.set_max_play_duration(Duration::from_millis(250));
+        for _ in 0..3 {
+            sink.append(SamplesBuffer::new(1, 1000, vec![1.0f32; 100]));
+        }
+
+        // the limit falls in the middle of the third sound and between two control periods
+        let played: Vec<f32> = queue_rx.by_ref().take(300).collect();
+        assert!(played[..250].iter().all(|&s| s == 1.0));
+        assert!(played[250..].iter().all(|&s| s == 0.0));
+        assert!(sink.empty());
+
+        // until the limit is cleared new sounds stop right away, the queue plays filler
+        // silence in between
+        let mut sounding = |sink: &Sink| {
+            sink.append(SamplesBuffer::new(1, 1000, vec![1.0f32; 10]));
+            queue_rx.by_ref().take(1000).filter(|&s| s == 1.0).count()
+        };
+        assert_eq!(sounding(&sink), 0);
+        sink.clear_max_play_duration();
+        assert_eq!(sounding(&sink), 10);
+    }
+
+    #[test]
+    fn max_play_duration_counts_every_sound_at_its_rate() {
+        let (sink, mut queue_rx) = Sink::new();
+        sink.set_max_play_duration(Duration::from_millis(150));
+        sink.append(SamplesBuffer::new(1, 22050, vec![1.0f32; 2205]));
+        sink.append(SamplesBuffer::new(1, 48000, vec![0.5f32; 4800]));
+
+        // 100 ms of the first sound, then 50 ms of the second
+        let played: Vec<f32> = queue_rx.by_ref().take(2205 + 4800).collect();
+        assert!(played[..2205].iter().all(|&s| s == 1.0));
+        assert!(played[2205..2205 + 2400].iter().all(|&s| s == 0.5));
+        assert!(played[2205 + 2400..].iter().all(|&s| s == 0.0));
+    }
+
+    #[test]
+    fn max_play_duration_fades_out() {
+        let (sink, mut queue_rx) = Sink::new();
+        sink.set_max_play_duration_with_fade(Duration::from_millis(100), Duration::from_millis(20));
+        sink.append(SamplesBuffer::new(1, 1000, vec![1.0f32; 200]));
+
+        let played: Vec<f32> = queue_rx.by_ref().take(120).collect();
+        assert!(played[..80].iter().all(|&s| s == 1.0));
+        assert!((played[90] - 0.5).abs() < 1e-6);
+        assert!((played[99] - 0.05).abs() < 1e-6);
+        assert!(played[100..].iter().all(|&s| s == 0.0));
+    }
+
+    #[cfg(feature = "wav")]
+    #[test]
+    fn async_append_plays_every_sample() {
+        use std::io::Cursor;
+        use std::time::Instant;
+
+        use crate::{Decoder, Sample};
+
+        let data = include_bytes!("../assets/beep.wav");
+        let expected: Vec<f32> = Decoder::new(Cursor::new(&data[..]))
+            .unwrap()
+            .map(|s| s.to_f32())
+            .filter(|&s| s != 0.0)
+            .collect();
+
+        let (sink, mut queue_rx) = Sink::new();
+        let handle = sink.append_async(Cursor::new(&data[..]));
+        let failed = sink.append_async(Cursor::new(vec![0u8; 100]));
+
+        // silence is played if the decoder falls behind
+        let mut played = Vec::new();
+        let deadline = Instant::now() + Duration::from_secs(10);
+        while !failed.sound().is_finished() && Instant::now() < deadline {
+            played.extend(queue_rx.by_ref().take(1000).filter(|&s| s != 0.0));
+        }
+        assert_eq!(played, expected);
+        assert!(handle.wait().is_ok());
+        assert!(handle.sound().is_finished());
+        assert!(matches!(failed.try_result(), Some(Err(_))));
+    }
+
+    #[cfg(feature = "wav")]
+    #[test]
+    fn pool_decodes_more_sounds_than_it_has_threads() {
+        use std::io::Cursor;
+        use std::time::Instant;
+
+        use crate::decoder::DecodePool;
+        use crate::{Decoder, Sample};
+
+        let data = include_bytes!("../assets/beep.wav");
+        let expected: Vec<f32> = Decoder::new(Cursor::new(&data[..]))
+            .unwrap()
+            .map(|s| s.to_f32())
+            .filter(|&s| s != 0.0)
+            .collect();
+
+        let pool = DecodePool::new(2);
+        let mut sounds: Vec<_> = (0..4)
+            .map(|_| {
+                let (sink, queue_rx) = SinkBuilder::new()
+                    .decode_pool(pool.clone())
+                    .build_unconnected();
+                let handle = sink.append_async(Cursor::new(&data[..]));
+                (sink, queue_rx, handle, Vec::new())
+            })
+            .collect();
+        drop(pool);
+
+        // every sound needs more chunks than are decoded ahead, so the threads take turns
+        let deadline = Instant::now() + Duration::from_secs(30);
+        while sounds
+            .iter()
+            .any(|(_, _, handle, _)| !handle.sound().is_finished())
+            && Instant::now() < deadline
+        {
+            for (_, queue_rx, _, played) in &mut sounds {
+                played.extend(queue_rx.by_ref().take(1000).filter(|&s| s != 0.0));
+            }
+        }
+        for (_, _, handle, played) in &sounds {
+            assert!(handle.wait().is_ok());
+            assert!(*played == expected);
+        }
+    }
+
+    #[test]
+    fn sinks_on_separate_mixers_play_independently() {
+        use crate::mixer;
+
+        // the mixers stand in for the streams of two devices, with their own formats
+        let (device_a, mut output_a) = mixer::mixer::<f32>(1, 44100);
+        let (device_b, mut output_b) = mixer::mixer::<f32>(2, 48000);
+        let sink_a = Sink::connect_new(&device_a);
+        let sink_b = Sink::connect_new(&device_b);
+        sink_a.append(SamplesBuffer::new(1, 44100, vec![0.5f32; 100]));
+        sink_b.append(SamplesBuffer::new(2, 48000, vec![-0.25f32; 400]));
+
+        // each device only plays its own sink, in its own format
+        let played_a: Vec<f32> = output_a.by_ref().take(2000).collect();
+        let played_b: Vec<f32> = output_b.by_ref().take(2000).collect();
+        assert_eq!(output_b.channels(), 2);
+        assert!(played_a.iter().all(|s| (0.0..=0.5).contains(s)));
+        assert!(played_a.iter().filter(|s| **s == 0.5).count() >= 90);
+        assert!(played_b.iter().all(|s| (-0.25..=0.0).contains(s)));
+        assert!(played_b.iter().filter(|s| **s == -0.25).count() >= 390);
+        assert!(sink_a.empty() && sink_b.empty());
+    }
+
+    #[test]
+    fn builder_effects_apply_to_every_source() {
+        let (sink, mut queue_rx) = SinkBuilder::new().amplify(0.5).build_unconnected();
+
+        sink.append(SamplesBuffer::new(1, 1000, vec![0.5f32, -1.0]));
+        sink.append(SamplesBuffer::new(2, 48000, vec![i16::MAX, i16::MIN]));
+
+        assert_eq!(queue_rx.next(), Some(0.25));
+        assert_eq!(queue_rx.next(), Some(-0.5));
+        let mut second = SamplesBuffer::new(2, 48000, vec![i16::MAX, i16::MIN])
+            .convert_samples::<f32>()
+            .amplify(0.5);
+        assert_eq!(queue_rx.next(), second.next());
+        assert_eq!(queue_rx.next(), second.next());
+    }
+
+    #[test]
+    fn ab_loop_repeats_region() {
+        for speed in [1.0, 2.0] {
+            let (sink, mut queue_rx) = Sink::new();
+            let data: Vec<f32> = (0..100).map(|i| i as f32).collect();
+
+            sink.set_speed(speed);
+            sink.set_ab_loop(Duration::from_millis(200), Duration::from_millis(400));
+            sink.append(SamplesBuffer::new(1, 100, data));
+
+            let played: Vec<f32> = queue_rx.by_ref().take(60).collect();
+            let mut expected: Vec<f32> = (0..40).map(|i| i as f32).collect();
+            expected.extend((20..40).map(|i| i as f32));
+            assert_eq!(played, expected, "speed {speed}");
+
+            sink.clear_ab_loop();
+            let rest: Vec<f32> = queue_rx.by_ref().take(60).collect();
+            assert_eq!(rest, (40..100).map(|i| i as f32).collect::<Vec<_>>());
+        }
+    }
+
+    #[test]
+    fn state_follows_playback() {
+        let (sink, mut queue_rx) = Sink::new();
+        assert_eq!(sink.state(), SinkState::Idle);
+
+        sink.append(SamplesBuffer::new(1, 1, vec![1.0f32, 2.0, 3.0]));
+        assert_eq!(sink.state(), SinkState::Playing);
+        assert_eq!(queue_rx.next(), Some(1.0));
+
+        sink.pause();
+        assert_eq!(sink.state(), SinkState::Paused);
+        assert_eq!(queue_rx.next(), Some(0.0));
+
+        sink.play();
+        assert_eq!(sink.state(), SinkState::Playing);
+        assert_eq!(queue_rx.next(), Some(2.0));
+        assert_eq!(queue_rx.next(), Some(3.0));
+        assert_eq!(sink.state(), SinkState::Playing);
+
+        // the end of the sound is only noticed when the next sample is requested
+        assert_eq!(queue_rx.next(), Some(0.0));
+        assert_eq!(sink.state(), SinkState::Idle);
+    }
+
+    #[test]
+    fn handle_stops_single_sound() {
+        let (sink, mut queue_rx) = Sink::new();
+
+        let first = sink.append(SamplesBuffer::new(1, 1, vec![1.0f32, 1.0]));
+        let second = sink.append(SamplesBuffer::new(1, 1, vec![2.0f32, 2.0]));
+        let third = sink.append(SamplesBuffer::new(1, 1, vec![3.0f32, 3.0]));
+        assert_eq!(sink.len(), 3);
+
+        second.stop();
+        let played: Vec<f32> = queue_rx.by_ref().take(4).collect();
+        assert_eq!(played, [1.0, 1.0, 3.0, 3.0]);
+        assert!(first.is_finished());
+        assert!(second.is_finished());
+        assert!(!third.is_finished());
+
+        assert_eq!(queue_rx.next(), Some(0.0));
+        assert!(third.is_finished());
+        assert!(sink.empty());
+    }
+
+    #[test]
+    fn handle_fades_out_single_sound() {
+        let (sink, mut queue_rx) = Sink::new();
+
+        // 1 kHz mono, every control period of 5ms spans exactly 5 samples.
+        let sound = sink.append(SamplesBuffer::new(1, 1000, vec![1.0f32; 100]));
+        sound.fade_out(Duration::from_millis(50));
+        let played: Vec<f32> = queue_rx.by_ref().take(100).collect();
+        assert_eq!(played[0], 1.0);
+        assert!(played[25] > 0.4 && played[25] < 0.6);
+        assert!(played[50..].iter().all(|&s| s == 0.0));
+        assert!(sound.is_finished());
+    }
+
+    #[test]
+    fn handle_reports_position_at_speed() {
+        let (controller, mut output) = crate::mixer::mixer::<f32>(1, 48_000);
+        let sink = Sink::connect_new(&controller);
+        sink.set_speed(1.5);
+        let sound = sink.append(SamplesBuffer::new(1, 48_000, vec![0.5f32; 96_000]));
+        assert_eq!(sound.position(), Duration::ZERO);
+
+        // Give the mixer time to pick up the sink and settle on the sample rate of the sped up
+        // sound.
+        output.by_ref().take(4800).for_each(drop);
+        let start = sound.position();
+        let start_samples = sound.samples_played();
+        assert!(start_samples > 0);
+
+        // Half a second of output at 1.5 times the speed covers 0.75 seconds of the sound.
+        output.by_ref().take(24_000).for_each(drop);
+        let elapsed = (sound.position() - start).as_secs_f64();
+        assert!((elapsed - 0.75).abs() < 0.001, "{elapsed}");
+        let samples = sound.samples_played() - start_samples;
+        assert!(samples.abs_diff(36_000) <= 1, "{samples}");
+    }
+
+    #[test]
+    fn finish_plays_out_queue() {
+        let (sink, queue_rx) = Sink::new();
+
+        let first = sink.append(SamplesBuffer::new(1, 1000, vec![1.0f32; 10]));
+        let second = sink.append(SamplesBuffer::new(1, 1000, vec![2.0f32; 10]));
+        let output = std::thread::spawn(move || queue_rx.collect::<Vec<f32>>());
+
+        assert!(sink.finish(None));
+        assert!(first.is_finished());
+        assert!(second.is_finished());
+
+        let rejected = sink.append(SamplesBuffer::new(1, 1000, vec![3.0f32; 10]));
+        assert!(rejected.is_finished());
+        assert!(sink.empty());
+
+        let mut expected = vec![1.0; 10];
+        expected.extend([2.0; 10]);
+        // the queue plays silence if it runs empty before `finish` is called
+        let output: Vec<f32> = output.join().unwrap();
+        let output: Vec<f32> = output.into_iter().filter(|&s| s != 0.0).collect();
+        assert_eq!(output, expected);
+    }
 }
@@ -420,15 +420,20 @@ where
         sample.amplify(self.current_gain)
     }
 
-    /// Returns a mutable reference to the inner source.
+    /// Returns a reference to the inner source.
     pub fn inner(&self) -> &I {
         &self.input
     }
 
-    /// Returns the inner source.
+    /// Returns a mutable reference to the inner source.
     pub fn inner_mut(&mut self) -> &mut I {
         &mut self.input
     }
+
+    /// Returns the inner source.
+    pub fn into_inner(self) -> I {
+        self.input
+    }
 }
 
 impl<I> Iterator for AutomaticGainControl<I>
@@ -487,6 +492,16 @@ where
         self.input.total_duration()
     }
 
+    #[inline]
+    fn tail_duration(&self) -> Duration {
+        self.input.tail_duration()
+    }
+
+    #[inline]
+    fn latency(&self) -> Duration {
+        self.input.latency()
+    }
+
     #[inline]
     fn try_seek(&mut self, pos: Duration) -> Result<(), SeekError> {
         self.input.try_seek(pos)
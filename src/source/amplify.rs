@@ -1,6 +1,6 @@
 use std::time::Duration;
 
-use super::SeekError;
+use super::{SampleProcessor, SeekError};
 use crate::common::{ChannelCount, SampleRate};
 use crate::{Sample, Source};
 
@@ -46,6 +46,36 @@ impl<I> Amplify<I> {
     }
 }
 
+/// Multiplies every sample by a given value, like [`Amplify`], as a [`SampleProcessor`] for
+/// an [`EffectRack`](super::EffectRack).
+#[derive(Clone, Debug)]
+pub struct GainProcessor {
+    factor: f32,
+}
+
+impl GainProcessor {
+    /// Builds a processor that multiplies every sample by `factor`.
+    #[inline]
+    pub fn new(factor: f32) -> GainProcessor {
+        GainProcessor { factor }
+    }
+
+    /// Modifies the amplification factor.
+    #[inline]
+    pub fn set_factor(&mut self, factor: f32) {
+        self.factor = factor;
+    }
+}
+
+impl SampleProcessor for GainProcessor {
+    #[inline]
+    fn process(&mut self, frame: &mut [f32], _: ChannelCount, _: SampleRate) {
+        for sample in frame {
+            *sample *= self.factor;
+        }
+    }
+}
+
 impl<I> Iterator for Amplify<I>
 where
     I: Source,
@@ -96,8 +126,23 @@ where
         self.input.total_duration()
     }
 
+    #[inline]
+    fn latency(&self) -> Duration {
+        self.input.latency()
+    }
+
+    #[inline]
+    fn tail_duration(&self) -> Duration {
+        self.input.tail_duration()
+    }
+
     #[inline]
     fn try_seek(&mut self, pos: Duration) -> Result<(), SeekError> {
         self.input.try_seek(pos)
     }
+
+    #[inline]
+    fn try_seek_frames(&mut self, frame: u64) -> Result<(), SeekError> {
+        self.input.try_seek_frames(frame)
+    }
 }
@@ -0,0 +1,177 @@
+use std::time::Duration;
+
+/// Shape of the transition described by an [`Automation`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AutomationCurve {
+    /// Changes at a constant rate.
+    Linear,
+    /// Starts slowly and accelerates towards the end.
+    EaseIn,
+    /// Starts quickly and slows down towards the end.
+    EaseOut,
+    /// Starts and ends slowly, fastest in the middle (smoothstep).
+    SCurve,
+    /// Changes by a constant ratio per unit of time. This sounds even when used for gain.
+    ///
+    /// Falls back to [`AutomationCurve::Linear`] if either end is not strictly positive.
+    Exponential,
+}
+
+impl AutomationCurve {
+    /// Maps the linear progress `t` in `[0.0, 1.0]` onto this curve.
+    #[inline]
+    fn shape(self, t: f32) -> f32 {
+        match self {
+            AutomationCurve::Linear | AutomationCurve::Exponential => t,
+            AutomationCurve::EaseIn => t * t,
+            AutomationCurve::EaseOut => t * (2.0 - t),
+            AutomationCurve::SCurve => t * t * (3.0 - 2.0 * t),
+        }
+    }
+}
+
+/// Moves a parameter from one value to another over a given duration.
+///
+/// An `Automation` does not keep track of time by itself, it has to be driven by calling
+/// [`advance`](Automation::advance). This is usually done from within
+/// [`Source::periodic_access`](crate::Source::periodic_access) so the parameter follows
+/// the sample clock of the audio thread rather than the wall clock of the caller.
+///
+/// # Example
+///
+/// ```
+/// use std::time::Duration;
+/// use rodio::source::{Automation, AutomationCurve, SineWave, Source};
+///
+/// let mut fade = Automation::new(1.0, 0.0, Duration::from_secs(2), AutomationCurve::SCurve);
+/// let source = SineWave::new(440.0)
+///     .amplify(1.0)
+///     .periodic_access(Duration::from_millis(10), move |src| {
+///         src.set_factor(fade.value());
+///         fade.advance(Duration::from_millis(10));
+///     });
+/// ```
+#[derive(Clone, Debug)]
+pub struct Automation {
+    start: f32,
+    end: f32,
+    duration: Duration,
+    elapsed: Duration,
+    curve: AutomationCurve,
+}
+
+impl Automation {
+    /// Creates an automation going from `start` to `end` in `duration` along `curve`.
+    #[inline]
+    pub fn new(start: f32, end: f32, duration: Duration, curve: AutomationCurve) -> Automation {
+        Automation {
+            start,
+            end,
+            duration,
+            elapsed: Duration::ZERO,
+            curve,
+        }
+    }
+
+    /// Returns the value of the parameter `elapsed` time after the start of the automation.
+    ///
+    /// Saturates at the end value once `elapsed` exceeds the duration.
+    pub fn value_at(&self, elapsed: Duration) -> f32 {
+        if elapsed >= self.duration {
+            return self.end;
+        }
+
+        let t = elapsed.as_secs_f32() / self.duration.as_secs_f32();
+        if self.curve == AutomationCurve::Exponential && self.start > 0.0 && self.end > 0.0 {
+            return self.start * (self.end / self.start).powf(t);
+        }
+
+        let t = self.curve.shape(t);
+        self.start + (self.end - self.start) * t
+    }
+
+    /// Returns the current value of the parameter.
+    #[inline]
+    pub fn value(&self) -> f32 {
+        self.value_at(self.elapsed)
+    }
+
+    /// Moves the automation forward by `delta` and returns the new value.
+    #[inline]
+    pub fn advance(&mut self, delta: Duration) -> f32 {
+        self.elapsed = self.elapsed.saturating_add(delta).min(self.duration);
+        self.value()
+    }
+
+    /// Returns true once the end value has been reached.
+    #[inline]
+    pub fn is_finished(&self) -> bool {
+        self.elapsed >= self.duration
+    }
+
+    /// Returns the value the automation ends at.
+    #[inline]
+    pub fn target(&self) -> f32 {
+        self.end
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use approx::assert_abs_diff_eq;
+
+    const CURVES: [AutomationCurve; 5] = [
+        AutomationCurve::Linear,
+        AutomationCurve::EaseIn,
+        AutomationCurve::EaseOut,
+        AutomationCurve::SCurve,
+        AutomationCurve::Exponential,
+    ];
+
+    #[test]
+    fn curves_hit_their_end_points() {
+        for curve in CURVES {
+            let mut automation = Automation::new(0.25, 2.0, Duration::from_secs(1), curve);
+            assert_abs_diff_eq!(automation.value(), 0.25);
+            assert!(!automation.is_finished());
+
+            automation.advance(Duration::from_millis(500));
+            let halfway = automation.value();
+            assert!(halfway > 0.25 && halfway < 2.0, "{curve:?}: {halfway}");
+
+            automation.advance(Duration::from_secs(5));
+            assert_abs_diff_eq!(automation.value(), 2.0);
+            assert!(automation.is_finished());
+        }
+    }
+
+    #[test]
+    fn curve_shapes() {
+        let second = Duration::from_secs(1);
+        let half = Duration::from_millis(500);
+
+        let linear = Automation::new(0.0, 1.0, second, AutomationCurve::Linear);
+        assert_abs_diff_eq!(linear.value_at(half), 0.5);
+
+        let ease_in = Automation::new(0.0, 1.0, second, AutomationCurve::EaseIn);
+        assert_abs_diff_eq!(ease_in.value_at(half), 0.25);
+
+        let ease_out = Automation::new(0.0, 1.0, second, AutomationCurve::EaseOut);
+        assert_abs_diff_eq!(ease_out.value_at(half), 0.75);
+
+        let s_curve = Automation::new(0.0, 1.0, second, AutomationCurve::SCurve);
+        assert_abs_diff_eq!(s_curve.value_at(half), 0.5);
+
+        // geometric mean halfway
+        let exponential = Automation::new(0.25, 1.0, second, AutomationCurve::Exponential);
+        assert_abs_diff_eq!(exponential.value_at(half), 0.5, epsilon = 1e-6);
+    }
+
+    #[test]
+    fn zero_duration_jumps_to_end() {
+        let automation = Automation::new(1.0, 0.0, Duration::ZERO, AutomationCurve::Linear);
+        assert!(automation.is_finished());
+        assert_eq!(automation.value(), 0.0);
+    }
+}
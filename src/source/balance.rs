@@ -0,0 +1,178 @@
+use std::time::Duration;
+
+use super::SeekError;
+use crate::common::{ChannelCount, SampleRate};
+use crate::{Sample, Source};
+
+/// Internal function that builds a `Balance` object.
+pub fn balance<I>(input: I, value: f32) -> Balance<I>
+where
+    I: Source,
+    I::Item: Sample,
+{
+    let mut balance = Balance {
+        input,
+        value: 0.0,
+        gains: [1.0; 2],
+        channel: 0,
+    };
+    balance.set_balance(value);
+    balance
+}
+
+/// Turns down one channel of a stereo source, like the balance knob of an amplifier.
+///
+/// Unlike [`Pan`](super::Pan), which mixes a source down and places it in the stereo field,
+/// the channels stay separate. At `0.0` both play unchanged, towards `-1.0` the right channel
+/// is turned down until it is silent and towards `1.0` the left one. The other channel keeps
+/// its level.
+///
+/// Sources that do not have two channels are passed on unchanged.
+#[derive(Clone, Debug)]
+pub struct Balance<I> {
+    input: I,
+    value: f32,
+    // Gains of the left and right channel.
+    gains: [f32; 2],
+    // The channel the next sample of a stereo source belongs to.
+    channel: usize,
+}
+
+impl<I> Balance<I>
+where
+    I: Source,
+    I::Item: Sample,
+{
+    /// Changes the balance while playing, values out of `-1.0..=1.0` are clamped.
+    #[inline]
+    pub fn set_balance(&mut self, value: f32) {
+        self.value = value.clamp(-1.0, 1.0);
+        self.gains = [(1.0 - self.value).min(1.0), (1.0 + self.value).min(1.0)];
+    }
+
+    /// Returns the current balance.
+    #[inline]
+    pub fn balance(&self) -> f32 {
+        self.value
+    }
+
+    /// Returns a reference to the inner source.
+    #[inline]
+    pub fn inner(&self) -> &I {
+        &self.input
+    }
+
+    /// Returns a mutable reference to the inner source.
+    #[inline]
+    pub fn inner_mut(&mut self) -> &mut I {
+        &mut self.input
+    }
+
+    /// Returns the inner source.
+    #[inline]
+    pub fn into_inner(self) -> I {
+        self.input
+    }
+}
+
+impl<I> Iterator for Balance<I>
+where
+    I: Source,
+    I::Item: Sample,
+{
+    type Item = I::Item;
+
+    #[inline]
+    fn next(&mut self) -> Option<I::Item> {
+        if self.channel == 0 && self.input.channels() != 2 {
+            return self.input.next();
+        }
+        let sample = self.input.next()?;
+        let gain = self.gains[self.channel];
+        self.channel = 1 - self.channel;
+        Some(sample.amplify(gain))
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.input.size_hint()
+    }
+}
+
+impl<I> ExactSizeIterator for Balance<I>
+where
+    I: Source + ExactSizeIterator,
+    I::Item: Sample,
+{
+}
+
+impl<I> Source for Balance<I>
+where
+    I: Source,
+    I::Item: Sample,
+{
+    #[inline]
+    fn current_span_len(&self) -> Option<usize> {
+        self.input.current_span_len()
+    }
+
+    #[inline]
+    fn channels(&self) -> ChannelCount {
+        self.input.channels()
+    }
+
+    #[inline]
+    fn sample_rate(&self) -> SampleRate {
+        self.input.sample_rate()
+    }
+
+    #[inline]
+    fn total_duration(&self) -> Option<Duration> {
+        self.input.total_duration()
+    }
+
+    #[inline]
+    fn tail_duration(&self) -> Duration {
+        self.input.tail_duration()
+    }
+
+    #[inline]
+    fn latency(&self) -> Duration {
+        self.input.latency()
+    }
+
+    #[inline]
+    fn try_seek(&mut self, pos: Duration) -> Result<(), SeekError> {
+        self.input.try_seek(pos)?;
+        self.channel = 0;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::buffer::SamplesBuffer;
+    use crate::source::Source;
+
+    fn balanced(value: f32) -> Vec<f32> {
+        SamplesBuffer::new(2, 1000, vec![1.0f32, 1.0, 0.5, -0.5])
+            .balance(value)
+            .collect()
+    }
+
+    #[test]
+    fn turns_down_one_channel() {
+        assert_eq!(balanced(0.0), [1.0, 1.0, 0.5, -0.5]);
+        assert_eq!(balanced(-1.0), [1.0, 0.0, 0.5, 0.0]);
+        assert_eq!(balanced(1.0), [0.0, 1.0, 0.0, -0.5]);
+        assert_eq!(balanced(-0.25), [1.0, 0.75, 0.5, -0.375]);
+        assert_eq!(balanced(0.5), [0.5, 1.0, 0.25, -0.5]);
+        assert_eq!(balanced(3.0), balanced(1.0));
+    }
+
+    #[test]
+    fn other_channel_counts_pass_through() {
+        let mono = SamplesBuffer::new(1, 1000, vec![0.25f32; 3]).balance(-1.0);
+        assert_eq!(mono.collect::<Vec<_>>(), [0.25; 3]);
+    }
+}
@@ -3,10 +3,26 @@ use crate::Source;
 use std::f32::consts::PI;
 use std::time::Duration;
 
-use super::SeekError;
+use super::{SampleProcessor, SeekError};
 
 // Implemented following http://www.musicdsp.org/files/Audio-EQ-Cookbook.txt
 
+/// Values in the feedback path closer to zero than this are flushed to zero. This is about
+/// -300 dB, far below anything audible, but well above the range of denormal floats, which
+/// are slow to compute with on many CPUs.
+const DENORMAL_THRESHOLD: f32 = 1e-15;
+
+/// Flushes values that are about to decay into denormals to zero, used by the filters that
+/// feed their output back so they do not slow down while playing silence.
+#[inline]
+pub(crate) fn flush_denormal(value: f32) -> f32 {
+    if value.abs() < DENORMAL_THRESHOLD {
+        0.0
+    } else {
+        value
+    }
+}
+
 /// Internal function that builds a `BltFilter` object.
 pub fn low_pass<I>(input: I, freq: u32) -> BltFilter<I>
 where
@@ -31,10 +47,9 @@ where
         input,
         formula: BltFormula::LowPass { freq, q },
         applier: None,
-        x_n1: 0.0,
-        x_n2: 0.0,
-        y_n1: 0.0,
-        y_n2: 0.0,
+        states: Vec::new(),
+        channel: 0,
+        denormal_protection: true,
     }
 }
 
@@ -47,23 +62,72 @@ where
         input,
         formula: BltFormula::HighPass { freq, q },
         applier: None,
-        x_n1: 0.0,
-        x_n2: 0.0,
-        y_n1: 0.0,
-        y_n2: 0.0,
+        states: Vec::new(),
+        channel: 0,
+        denormal_protection: true,
+    }
+}
+
+/// Internal function that builds an all-pass `BltFilter` object.
+pub fn all_pass<I>(input: I, freq: u32, q: f32) -> BltFilter<I>
+where
+    I: Source<Item = f32>,
+{
+    BltFilter {
+        input,
+        formula: BltFormula::AllPass { freq, q },
+        applier: None,
+        states: Vec::new(),
+        channel: 0,
+        denormal_protection: true,
+    }
+}
+
+/// Internal function that builds a low-shelf `BltFilter` object.
+pub fn low_shelf<I>(input: I, freq: u32, gain_db: f32, q: f32) -> BltFilter<I>
+where
+    I: Source<Item = f32>,
+{
+    BltFilter {
+        input,
+        formula: BltFormula::LowShelf { freq, gain_db, q },
+        applier: None,
+        states: Vec::new(),
+        channel: 0,
+        denormal_protection: true,
     }
 }
 
-/// This applies an audio filter, it can be a high or low pass filter.
+/// Internal function that builds a high-shelf `BltFilter` object.
+pub fn high_shelf<I>(input: I, freq: u32, gain_db: f32, q: f32) -> BltFilter<I>
+where
+    I: Source<Item = f32>,
+{
+    BltFilter {
+        input,
+        formula: BltFormula::HighShelf { freq, gain_db, q },
+        applier: None,
+        states: Vec::new(),
+        channel: 0,
+        denormal_protection: true,
+    }
+}
+
+/// This applies an audio filter, it can be a high pass, low pass, all-pass or shelf filter.
+///
+/// The history of the filter is flushed to zero once it decays to about -300 dB, so that a
+/// filter fed with silence does not end up computing with denormal floats, which makes the
+/// CPU usage spike on many platforms. See [`set_denormal_protection`](Self::set_denormal_protection).
 #[derive(Clone, Debug)]
 pub struct BltFilter<I> {
     input: I,
     formula: BltFormula,
     applier: Option<BltApplier>,
-    x_n1: f32,
-    x_n2: f32,
-    y_n1: f32,
-    y_n2: f32,
+    // The filter history of each channel, channels must not influence each other.
+    states: Vec<BltState>,
+    // The channel the next sample belongs to.
+    channel: usize,
+    denormal_protection: bool,
 }
 
 impl<I> BltFilter<I> {
@@ -89,6 +153,42 @@ impl<I> BltFilter<I> {
         self.applier = None;
     }
 
+    /// Modifies this filter so that it becomes an all-pass filter, see
+    /// [`Source::all_pass`].
+    pub fn to_all_pass(&mut self, freq: u32, q: f32) {
+        self.formula = BltFormula::AllPass { freq, q };
+        self.applier = None;
+    }
+
+    /// Modifies this filter so that it becomes a low-shelf filter, see
+    /// [`Source::low_shelf`].
+    pub fn to_low_shelf(&mut self, freq: u32, gain_db: f32, q: f32) {
+        self.formula = BltFormula::LowShelf { freq, gain_db, q };
+        self.applier = None;
+    }
+
+    /// Modifies this filter so that it becomes a high-shelf filter, see
+    /// [`Source::high_shelf`].
+    pub fn to_high_shelf(&mut self, freq: u32, gain_db: f32, q: f32) {
+        self.formula = BltFormula::HighShelf { freq, gain_db, q };
+        self.applier = None;
+    }
+
+    /// Turns flushing the history of the filter to zero before it becomes denormal on or off.
+    /// On by default, turning it off only makes sense to get output that is bit exact with
+    /// other implementations.
+    #[inline]
+    pub fn set_denormal_protection(&mut self, enabled: bool) {
+        self.denormal_protection = enabled;
+    }
+
+    /// Returns whether the history of the filter is flushed to zero before it becomes
+    /// denormal.
+    #[inline]
+    pub fn denormal_protection(&self) -> bool {
+        self.denormal_protection
+    }
+
     /// Returns a reference to the inner source.
     #[inline]
     pub fn inner(&self) -> &I {
@@ -120,22 +220,25 @@ where
 
         if self.applier.is_none() {
             self.applier = Some(self.formula.to_applier(self.input.sample_rate()));
+
+            let channels = self.input.channels().max(1) as usize;
+            if self.states.len() != channels {
+                self.states.resize(channels, BltState::default());
+                self.channel = 0;
+            }
         }
 
         let sample = self.input.next()?;
-        let result = self
-            .applier
-            .as_ref()
-            .unwrap()
-            .apply(sample, self.x_n1, self.x_n2, self.y_n1, self.y_n2);
-
-        self.y_n2 = self.y_n1;
-        self.x_n2 = self.x_n1;
-        self.y_n1 = result;
-        self.x_n1 = sample;
+        let result = self.states[self.channel].filter(
+            self.applier.as_ref().unwrap(),
+            sample,
+            self.denormal_protection,
+        );
 
+        self.channel = (self.channel + 1) % self.states.len();
         if last_in_span {
             self.applier = None;
+            self.channel = 0;
         }
 
         Some(result)
@@ -173,16 +276,105 @@ where
         self.input.total_duration()
     }
 
+    #[inline]
+    fn tail_duration(&self) -> Duration {
+        self.input.tail_duration()
+    }
+
+    #[inline]
+    fn latency(&self) -> Duration {
+        self.input.latency()
+    }
+
     #[inline]
     fn try_seek(&mut self, pos: Duration) -> Result<(), SeekError> {
         self.input.try_seek(pos)
     }
 }
 
+/// The filters of [`BltFilter`] as a [`SampleProcessor`] for an
+/// [`EffectRack`](super::EffectRack).
+#[derive(Clone, Debug)]
+pub struct FilterProcessor {
+    formula: BltFormula,
+    // The coefficients and the sample rate they were computed for.
+    applier: Option<(SampleRate, BltApplier)>,
+    states: Vec<BltState>,
+    denormal_protection: bool,
+}
+
+impl FilterProcessor {
+    /// A low-pass filter, see [`Source::low_pass_with_q`].
+    pub fn low_pass(freq: u32, q: f32) -> FilterProcessor {
+        FilterProcessor::new(BltFormula::LowPass { freq, q })
+    }
+
+    /// A high-pass filter, see [`Source::high_pass_with_q`].
+    pub fn high_pass(freq: u32, q: f32) -> FilterProcessor {
+        FilterProcessor::new(BltFormula::HighPass { freq, q })
+    }
+
+    /// An all-pass filter, see [`Source::all_pass`].
+    pub fn all_pass(freq: u32, q: f32) -> FilterProcessor {
+        FilterProcessor::new(BltFormula::AllPass { freq, q })
+    }
+
+    /// A low-shelf filter, see [`Source::low_shelf`].
+    pub fn low_shelf(freq: u32, gain_db: f32, q: f32) -> FilterProcessor {
+        FilterProcessor::new(BltFormula::LowShelf { freq, gain_db, q })
+    }
+
+    /// A high-shelf filter, see [`Source::high_shelf`].
+    pub fn high_shelf(freq: u32, gain_db: f32, q: f32) -> FilterProcessor {
+        FilterProcessor::new(BltFormula::HighShelf { freq, gain_db, q })
+    }
+
+    /// Turns flushing the history of the filter to zero before it becomes denormal on or off,
+    /// see [`BltFilter::set_denormal_protection`]. On by default.
+    #[inline]
+    pub fn set_denormal_protection(&mut self, enabled: bool) {
+        self.denormal_protection = enabled;
+    }
+
+    fn new(formula: BltFormula) -> FilterProcessor {
+        FilterProcessor {
+            formula,
+            applier: None,
+            states: Vec::new(),
+            denormal_protection: true,
+        }
+    }
+}
+
+impl SampleProcessor for FilterProcessor {
+    fn process(&mut self, frame: &mut [f32], _: ChannelCount, sample_rate: SampleRate) {
+        if !matches!(self.applier, Some((rate, _)) if rate == sample_rate) {
+            self.applier = Some((sample_rate, self.formula.to_applier(sample_rate)));
+        }
+        if self.states.len() != frame.len() {
+            self.states.clear();
+            self.states.resize(frame.len(), BltState::default());
+        }
+        let (_, applier) = self.applier.as_ref().unwrap();
+        for (sample, state) in frame.iter_mut().zip(&mut self.states) {
+            *sample = state.filter(applier, *sample, self.denormal_protection);
+        }
+    }
+
+    fn reset(&mut self) {
+        self.states.clear();
+    }
+}
+
+// Named after the filters they describe.
+#[allow(clippy::enum_variant_names)]
 #[derive(Clone, Debug)]
 enum BltFormula {
     LowPass { freq: u32, q: f32 },
     HighPass { freq: u32, q: f32 },
+    AllPass { freq: u32, q: f32 },
+    LowShelf { freq: u32, gain_db: f32, q: f32 },
+    HighShelf { freq: u32, gain_db: f32, q: f32 },
 }
 
 impl BltFormula {
@@ -227,7 +419,96 @@ impl BltFormula {
                     a2: a2 / a0,
                 }
             }
+            BltFormula::AllPass { freq, q } => {
+                let w0 = 2.0 * PI * freq as f32 / sampling_frequency as f32;
+                let cos_w0 = w0.cos();
+                let alpha = w0.sin() / (2.0 * q);
+
+                let b0 = 1.0 - alpha;
+                let b1 = -2.0 * cos_w0;
+                let b2 = 1.0 + alpha;
+                let a0 = 1.0 + alpha;
+                let a1 = -2.0 * cos_w0;
+                let a2 = 1.0 - alpha;
+
+                BltApplier {
+                    b0: b0 / a0,
+                    b1: b1 / a0,
+                    b2: b2 / a0,
+                    a1: a1 / a0,
+                    a2: a2 / a0,
+                }
+            }
+            BltFormula::LowShelf { freq, gain_db, q } => {
+                let a = 10f32.powf(gain_db / 40.0);
+                let w0 = 2.0 * PI * freq as f32 / sampling_frequency as f32;
+                let cos_w0 = w0.cos();
+                let alpha = w0.sin() / (2.0 * q);
+                let sqrt_a_alpha = 2.0 * a.sqrt() * alpha;
+
+                let b0 = a * ((a + 1.0) - (a - 1.0) * cos_w0 + sqrt_a_alpha);
+                let b1 = 2.0 * a * ((a - 1.0) - (a + 1.0) * cos_w0);
+                let b2 = a * ((a + 1.0) - (a - 1.0) * cos_w0 - sqrt_a_alpha);
+                let a0 = (a + 1.0) + (a - 1.0) * cos_w0 + sqrt_a_alpha;
+                let a1 = -2.0 * ((a - 1.0) + (a + 1.0) * cos_w0);
+                let a2 = (a + 1.0) + (a - 1.0) * cos_w0 - sqrt_a_alpha;
+
+                BltApplier {
+                    b0: b0 / a0,
+                    b1: b1 / a0,
+                    b2: b2 / a0,
+                    a1: a1 / a0,
+                    a2: a2 / a0,
+                }
+            }
+            BltFormula::HighShelf { freq, gain_db, q } => {
+                let a = 10f32.powf(gain_db / 40.0);
+                let w0 = 2.0 * PI * freq as f32 / sampling_frequency as f32;
+                let cos_w0 = w0.cos();
+                let alpha = w0.sin() / (2.0 * q);
+                let sqrt_a_alpha = 2.0 * a.sqrt() * alpha;
+
+                let b0 = a * ((a + 1.0) + (a - 1.0) * cos_w0 + sqrt_a_alpha);
+                let b1 = -2.0 * a * ((a - 1.0) + (a + 1.0) * cos_w0);
+                let b2 = a * ((a + 1.0) + (a - 1.0) * cos_w0 - sqrt_a_alpha);
+                let a0 = (a + 1.0) - (a - 1.0) * cos_w0 + sqrt_a_alpha;
+                let a1 = 2.0 * ((a - 1.0) - (a + 1.0) * cos_w0);
+                let a2 = (a + 1.0) - (a - 1.0) * cos_w0 - sqrt_a_alpha;
+
+                BltApplier {
+                    b0: b0 / a0,
+                    b1: b1 / a0,
+                    b2: b2 / a0,
+                    a1: a1 / a0,
+                    a2: a2 / a0,
+                }
+            }
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+struct BltState {
+    x_n1: f32,
+    x_n2: f32,
+    y_n1: f32,
+    y_n2: f32,
+}
+
+impl BltState {
+    // Filters the next sample of the channel, optionally flushing the output kept for the
+    // feedback to zero before it becomes denormal.
+    #[inline]
+    fn filter(&mut self, applier: &BltApplier, x_n: f32, flush: bool) -> f32 {
+        let mut result = applier.apply(x_n, self.x_n1, self.x_n2, self.y_n1, self.y_n2);
+        if flush {
+            result = flush_denormal(result);
         }
+        self.y_n2 = self.y_n1;
+        self.x_n2 = self.x_n1;
+        self.y_n1 = result;
+        self.x_n1 = x_n;
+        result
     }
 }
 
@@ -246,3 +527,120 @@ impl BltApplier {
         self.b0 * x_n + self.b1 * x_n1 + self.b2 * x_n2 - self.a1 * y_n1 - self.a2 * y_n2
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::f32::consts::PI;
+
+    use approx::assert_abs_diff_eq;
+
+    use crate::buffer::SamplesBuffer;
+    use crate::source::Source;
+
+    #[test]
+    fn channels_are_filtered_independently() {
+        let left: Vec<f32> = (0..200)
+            .map(|i| if i % 8 < 4 { 1.0 } else { -1.0 })
+            .collect();
+        let right: Vec<f32> = (0..200).map(|i| (i as f32 * 0.05).sin() * 0.3).collect();
+        let stereo: Vec<f32> = left
+            .iter()
+            .zip(right.iter())
+            .flat_map(|(l, r)| [*l, *r])
+            .collect();
+
+        let filtered: Vec<f32> = SamplesBuffer::new(2, 8000, stereo).low_pass(500).collect();
+        let expected_left: Vec<f32> = SamplesBuffer::new(1, 8000, left).low_pass(500).collect();
+        let expected_right: Vec<f32> = SamplesBuffer::new(1, 8000, right).low_pass(500).collect();
+
+        assert_eq!(filtered.len(), 400);
+        for (frame, (l, r)) in filtered
+            .chunks_exact(2)
+            .zip(expected_left.iter().zip(expected_right.iter()))
+        {
+            assert_abs_diff_eq!(frame[0], *l);
+            assert_abs_diff_eq!(frame[1], *r);
+        }
+    }
+
+    #[test]
+    fn all_pass_keeps_magnitude_and_shifts_phase() {
+        let sine = |freq: f32| -> Vec<f32> {
+            (0..8000)
+                .map(|i| (2.0 * PI * freq * i as f32 / 8000.0).sin())
+                .collect()
+        };
+        let rms = |samples: &[f32]| {
+            (samples.iter().map(|s| s * s).sum::<f32>() / samples.len() as f32).sqrt()
+        };
+
+        for freq in [50.0, 200.0, 1000.0, 3000.0] {
+            let input = sine(freq);
+            let output: Vec<f32> = SamplesBuffer::new(1, 8000, input.clone())
+                .all_pass(1000, 0.7)
+                .collect();
+            // skip the first half second while the filter settles
+            let gain_db = 20.0 * (rms(&output[4000..]) / rms(&input[4000..])).log10();
+            assert_abs_diff_eq!(gain_db, 0.0, epsilon = 0.05);
+        }
+
+        // half a period of phase shift at the center frequency, none far below it
+        let input = sine(1000.0);
+        let output: Vec<f32> = SamplesBuffer::new(1, 8000, input.clone())
+            .all_pass(1000, 0.7)
+            .collect();
+        for (x, y) in input.iter().zip(&output).skip(4000) {
+            assert_abs_diff_eq!(*y, -x, epsilon = 1e-3);
+        }
+        let input = sine(10.0);
+        let output: Vec<f32> = SamplesBuffer::new(1, 8000, input.clone())
+            .all_pass(1000, 0.7)
+            .collect();
+        for (x, y) in input.iter().zip(&output).skip(4000) {
+            assert_abs_diff_eq!(*y, *x, epsilon = 0.05);
+        }
+    }
+
+    #[test]
+    fn shelves_change_one_side_of_the_spectrum() {
+        let gain_db = |freq: f32, shelf: &dyn Fn(SamplesBuffer<f32>) -> Vec<f32>| {
+            let input: Vec<f32> = (0..44100)
+                .map(|i| (2.0 * PI * freq * i as f32 / 44100.0).sin())
+                .collect();
+            let output = shelf(SamplesBuffer::new(1, 44100, input.clone()));
+            let energy = |samples: &[f32]| samples.iter().map(|s| s * s).sum::<f32>();
+            10.0 * (energy(&output[22050..]) / energy(&input[22050..])).log10()
+        };
+        let low = |s: SamplesBuffer<f32>| s.low_shelf(200, 6.0, 0.707).collect();
+        let high = |s: SamplesBuffer<f32>| s.high_shelf(2000, -6.0, 0.707).collect();
+
+        assert_abs_diff_eq!(gain_db(30.0, &low), 6.0, epsilon = 0.2);
+        assert_abs_diff_eq!(gain_db(200.0, &low), 3.0, epsilon = 0.2);
+        assert_abs_diff_eq!(gain_db(5000.0, &low), 0.0, epsilon = 0.1);
+
+        assert_abs_diff_eq!(gain_db(100.0, &high), 0.0, epsilon = 0.1);
+        assert_abs_diff_eq!(gain_db(15000.0, &high), -6.0, epsilon = 0.2);
+    }
+
+    #[test]
+    fn silence_does_not_leave_denormals_in_the_history() {
+        // A resonant filter rings for a long time after an impulse.
+        let mut input = vec![0.0f32; 200_000];
+        input[0] = 1.0;
+        let denormals = |protection: bool| {
+            let mut filter = SamplesBuffer::new(1, 44_100, input.clone()).low_pass_with_q(100, 5.0);
+            filter.set_denormal_protection(protection);
+            let mut count = 0;
+            while filter.next().is_some() {
+                let state = &filter.states[0];
+                count += [state.y_n1, state.y_n2]
+                    .iter()
+                    .filter(|y| **y != 0.0 && !y.is_normal())
+                    .count();
+            }
+            count
+        };
+        assert!(denormals(false) > 0);
+        assert_eq!(denormals(true), 0);
+    }
+}
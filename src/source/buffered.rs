@@ -1,5 +1,6 @@
 use std::cmp;
 use std::mem;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
 use std::sync::{Arc, Mutex};
 use std::time::Duration;
 
@@ -10,17 +11,52 @@ use crate::{Sample, Source};
 /// Internal function that builds a `Buffered` object.
 #[inline]
 pub fn buffered<I>(input: I) -> Buffered<I>
+where
+    I: Source,
+    I::Item: Sample,
+{
+    new_buffered(input, None)
+}
+
+/// Internal function that builds a `Buffered` object which stores at most `max_bytes` at
+/// once and plays the rest unbuffered. Returns the input back if it is known to be larger.
+#[inline]
+pub fn buffered_with_limit<I>(input: I, max_bytes: usize) -> Result<Buffered<I>, I>
+where
+    I: Source,
+    I::Item: Sample,
+{
+    if let Some(duration) = input.total_duration() {
+        let samples = duration.as_secs_f64() * input.sample_rate() as f64 * input.channels() as f64;
+        if samples * mem::size_of::<I::Item>() as f64 > max_bytes as f64 {
+            return Err(input);
+        }
+    }
+    let memory = Arc::new(Memory {
+        used: AtomicUsize::new(0),
+        limit: max_bytes,
+        limit_reached: AtomicBool::new(false),
+    });
+    Ok(new_buffered(input, Some(memory)))
+}
+
+fn new_buffered<I>(input: I, memory: Option<Arc<Memory>>) -> Buffered<I>
 where
     I: Source,
     I::Item: Sample,
 {
     let total_duration = input.total_duration();
-    let first_span = extract(input);
+    let tail_duration = input.tail_duration();
+    let latency = input.latency();
+    let first_span = extract(input, memory.as_ref());
 
     Buffered {
         current_span: first_span,
         position_in_span: 0,
         total_duration,
+        tail_duration,
+        latency,
+        memory,
     }
 }
 
@@ -38,6 +74,34 @@ where
 
     /// Obtained once at creation and never modified again.
     total_duration: Option<Duration>,
+
+    /// Obtained once at creation and never modified again.
+    tail_duration: Duration,
+
+    /// Obtained once at creation and never modified again.
+    latency: Duration,
+
+    /// Shared with all clones, only kept for a limited buffer.
+    memory: Option<Arc<Memory>>,
+}
+
+/// Keeps track of the memory used by the spans that are still referenced.
+struct Memory {
+    used: AtomicUsize,
+    limit: usize,
+    limit_reached: AtomicBool,
+}
+
+/// Gives back the memory of a span to its `Memory` when dropped.
+struct MemoryGuard {
+    memory: Arc<Memory>,
+    bytes: usize,
+}
+
+impl Drop for MemoryGuard {
+    fn drop(&mut self) {
+        self.memory.used.fetch_sub(self.bytes, Ordering::Relaxed);
+    }
 }
 
 enum Span<I>
@@ -55,6 +119,10 @@ where
     /// Unextracted data. The `Option` should never be `None` and is only here for easier data
     /// processing.
     Input(Mutex<Option<I>>),
+
+    /// The rest of the data did not fit in the memory limit, it is read straight from the
+    /// iterator by whichever clone gets to a sample first.
+    Unbuffered(Mutex<I>),
 }
 
 struct SpanData<I>
@@ -66,6 +134,7 @@ where
     channels: ChannelCount,
     rate: SampleRate,
     next: Mutex<Arc<Span<I>>>,
+    _memory: Option<MemoryGuard>,
 }
 
 impl<I> Drop for SpanData<I>
@@ -98,8 +167,9 @@ where
     }
 }
 
-/// Builds a span from the input iterator.
-fn extract<I>(mut input: I) -> Arc<Span<I>>
+/// Builds a span from the input iterator. Once the memory limit is reached the rest of the
+/// input is not buffered anymore.
+fn extract<I>(mut input: I, memory: Option<&Arc<Memory>>) -> Arc<Span<I>>
 where
     I: Source,
     I::Item: Sample,
@@ -112,20 +182,41 @@ where
 
     let channels = input.channels();
     let rate = input.sample_rate();
-    let data: Vec<I::Item> = input
-        .by_ref()
-        .take(cmp::min(span_len.unwrap_or(32768), 32768))
-        .collect();
+    let mut len = cmp::min(span_len.unwrap_or(32768), 32768);
+    if let Some(memory) = memory {
+        let free = memory
+            .limit
+            .saturating_sub(memory.used.load(Ordering::Relaxed))
+            / mem::size_of::<I::Item>().max(1);
+        // Whole frames only, so the unbuffered rest starts at the first channel.
+        let free = free - free % channels.max(1) as usize;
+        if free == 0 {
+            memory.limit_reached.store(true, Ordering::Relaxed);
+            return Arc::new(Span::Unbuffered(Mutex::new(input)));
+        }
+        len = len.min(free);
+    }
+    let data: Vec<I::Item> = input.by_ref().take(len).collect();
 
     if data.is_empty() {
         return Arc::new(Span::End);
     }
 
+    let guard = memory.map(|memory| {
+        let bytes = data.len() * mem::size_of::<I::Item>();
+        memory.used.fetch_add(bytes, Ordering::Relaxed);
+        MemoryGuard {
+            memory: memory.clone(),
+            bytes,
+        }
+    });
+
     Arc::new(Span::Data(SpanData {
         data,
         channels,
         rate,
         next: Mutex::new(Arc::new(Span::Input(Mutex::new(Some(input))))),
+        _memory: guard,
     }))
 }
 
@@ -134,6 +225,28 @@ where
     I: Source,
     I::Item: Sample,
 {
+    /// Returns the number of bytes of samples currently kept in memory by this source and
+    /// its clones. Only counted for sources built with [`Source::buffered_with_limit`], `None`
+    /// otherwise.
+    ///
+    /// Samples are freed once no clone refers to them anymore, so this only grows steadily
+    /// while a clone is kept at an earlier position, for example to repeat the source.
+    #[inline]
+    pub fn memory_usage(&self) -> Option<usize> {
+        self.memory
+            .as_ref()
+            .map(|memory| memory.used.load(Ordering::Relaxed))
+    }
+
+    /// Returns true once buffering more of the source would have exceeded the limit set with
+    /// [`Source::buffered_with_limit`], from then on the rest is played without buffering.
+    #[inline]
+    pub fn limit_reached(&self) -> bool {
+        self.memory
+            .as_ref()
+            .is_some_and(|memory| memory.limit_reached.load(Ordering::Relaxed))
+    }
+
     /// Advances to the next span.
     fn next_span(&mut self) {
         let next_span = {
@@ -143,11 +256,10 @@ where
             };
 
             let next_span = match &**next_span_ptr {
-                Span::Data(_) => next_span_ptr.clone(),
-                Span::End => next_span_ptr.clone(),
+                Span::Data(_) | Span::End | Span::Unbuffered(_) => next_span_ptr.clone(),
                 Span::Input(input) => {
                     let input = input.lock().unwrap().take().unwrap();
-                    extract(input)
+                    extract(input, self.memory.as_ref())
                 }
             };
 
@@ -184,6 +296,8 @@ where
                 advance_span = false;
             }
 
+            Span::Unbuffered(input) => return input.lock().unwrap().next(),
+
             Span::Input(_) => unreachable!(),
         };
 
@@ -215,6 +329,7 @@ where
         match &*self.current_span {
             Span::Data(SpanData { data, .. }) => Some(data.len() - self.position_in_span),
             Span::End => Some(0),
+            Span::Unbuffered(ref input) => input.lock().unwrap().current_span_len(),
             Span::Input(_) => unreachable!(),
         }
     }
@@ -224,6 +339,7 @@ where
         match *self.current_span {
             Span::Data(SpanData { channels, .. }) => channels,
             Span::End => 1,
+            Span::Unbuffered(ref input) => input.lock().unwrap().channels(),
             Span::Input(_) => unreachable!(),
         }
     }
@@ -233,6 +349,7 @@ where
         match *self.current_span {
             Span::Data(SpanData { rate, .. }) => rate,
             Span::End => 44100,
+            Span::Unbuffered(ref input) => input.lock().unwrap().sample_rate(),
             Span::Input(_) => unreachable!(),
         }
     }
@@ -242,6 +359,16 @@ where
         self.total_duration
     }
 
+    #[inline]
+    fn tail_duration(&self) -> Duration {
+        self.tail_duration
+    }
+
+    #[inline]
+    fn latency(&self) -> Duration {
+        self.latency
+    }
+
     /// Can not support seek, in the end state we lose the underlying source
     /// which makes seeking back impossible.
     #[inline]
@@ -263,6 +390,64 @@ where
             current_span: self.current_span.clone(),
             position_in_span: self.position_in_span,
             total_duration: self.total_duration,
+            tail_duration: self.tail_duration,
+            latency: self.latency,
+            memory: self.memory.clone(),
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::mem;
+
+    use crate::buffer::SamplesBuffer;
+    use crate::source::{SineWave, Source};
+
+    #[test]
+    fn oversized_source_is_returned() {
+        // one second of 48 kHz stereo is 384 kB of f32 samples
+        let source = SamplesBuffer::new(2, 48000, vec![0.0f32; 96000]);
+        let source = source.buffered_with_limit(100_000).err().unwrap();
+        assert!(source.buffered_with_limit(400_000).is_ok());
+    }
+
+    #[test]
+    fn plays_past_the_limit_unbuffered() {
+        let limit = 300_000;
+        let source = SineWave::new(440.0)
+            .buffered_with_limit(limit)
+            .ok()
+            .unwrap();
+        // keep a clone at the start so that nothing can be freed
+        let start = source.clone();
+
+        let buffered = limit / mem::size_of::<f32>();
+        let played: Vec<f32> = source.take(2 * buffered).collect();
+        let expected: Vec<f32> = SineWave::new(440.0).take(2 * buffered).collect();
+        assert_eq!(played, expected);
+        assert!(start.limit_reached());
+        assert_eq!(start.memory_usage(), Some(limit));
+
+        // the clone replays what was buffered
+        let replayed: Vec<f32> = start.take(buffered).collect();
+        assert_eq!(replayed, expected[..buffered]);
+    }
+
+    #[test]
+    fn memory_is_freed() {
+        let mut source = SamplesBuffer::new(1, 1000, vec![0i16; 100_000])
+            .buffered_with_limit(usize::MAX)
+            .ok()
+            .unwrap();
+        let _ = source.next();
+        assert!(source.memory_usage() > Some(0));
+
+        let _ = source.by_ref().count();
+        assert_eq!(source.memory_usage(), Some(0));
+        assert!(!source.limit_reached());
+
+        // plain buffering does not count
+        assert_eq!(SineWave::new(440.0).buffered().memory_usage(), None);
+    }
+}
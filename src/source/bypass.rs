@@ -0,0 +1,324 @@
+use std::sync::atomic::{AtomicBool, AtomicU16, AtomicU32, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use super::SeekError;
+use crate::common::{ChannelCount, SampleRate};
+use crate::Source;
+
+/// Time over which the effect fades in and out at the edges of the window.
+const RAMP: Duration = Duration::from_millis(5);
+
+/// Internal function that builds a `BypassOutside` object.
+pub fn bypass_outside<I, F, E>(
+    input: I,
+    start: Duration,
+    end: Duration,
+    effect: F,
+) -> BypassOutside<I, E>
+where
+    I: Source<Item = f32>,
+    F: FnOnce(BypassInput) -> E,
+    E: Source<Item = f32>,
+{
+    let shared = Arc::new(Shared {
+        sample: AtomicU32::new(0),
+        pending: AtomicBool::new(false),
+        channels: AtomicU16::new(input.channels()),
+        sample_rate: AtomicU32::new(input.sample_rate()),
+    });
+    let effect = effect(BypassInput {
+        shared: shared.clone(),
+    });
+    let sample_rate = input.sample_rate();
+    BypassOutside {
+        input,
+        effect,
+        shared,
+        start: start.as_secs_f64(),
+        end: end.as_secs_f64(),
+        base: 0.0,
+        frames: 0,
+        sample_rate,
+        channel: 0,
+        wet: 0.0,
+    }
+}
+
+/// Applies an effect only between two positions of a source and plays the source dry
+/// elsewhere, like an automation clip. See [`Source::bypass_outside`].
+///
+/// Every sample is run through the effect, also outside the window, so the effect is settled
+/// when it is faded in. The effect fades in over 5 milliseconds from the start of the window
+/// and fades out over the same time from its end.
+///
+/// The effect must produce one sample for every sample of its input, like filters, gains and
+/// distortion do. Adapters that change the length or the format, such as
+/// [`speed`](Source::speed) or [`take_duration`](Source::take_duration), do not work.
+pub struct BypassOutside<I, E> {
+    input: I,
+    effect: E,
+    shared: Arc<Shared>,
+    // The window in seconds.
+    start: f64,
+    end: f64,
+    // The position of the next frame is `frames` at `sample_rate` after `base` seconds,
+    // counted in frames so the edges of the window fall on exact frames.
+    base: f64,
+    frames: u64,
+    sample_rate: SampleRate,
+    channel: ChannelCount,
+    // Share of the effect in the output.
+    wet: f32,
+}
+
+// The sample passed from the source to the effect.
+struct Shared {
+    sample: AtomicU32,
+    pending: AtomicBool,
+    channels: AtomicU16,
+    sample_rate: AtomicU32,
+}
+
+/// The source an effect passed to [`Source::bypass_outside`] is built on, it plays the
+/// samples of the source one at a time.
+pub struct BypassInput {
+    shared: Arc<Shared>,
+}
+
+impl Iterator for BypassInput {
+    type Item = f32;
+
+    #[inline]
+    fn next(&mut self) -> Option<f32> {
+        if self.shared.pending.swap(false, Ordering::Relaxed) {
+            Some(f32::from_bits(self.shared.sample.load(Ordering::Relaxed)))
+        } else {
+            None
+        }
+    }
+}
+
+impl Source for BypassInput {
+    #[inline]
+    fn current_span_len(&self) -> Option<usize> {
+        None
+    }
+
+    #[inline]
+    fn channels(&self) -> ChannelCount {
+        self.shared.channels.load(Ordering::Relaxed)
+    }
+
+    #[inline]
+    fn sample_rate(&self) -> SampleRate {
+        self.shared.sample_rate.load(Ordering::Relaxed)
+    }
+
+    #[inline]
+    fn total_duration(&self) -> Option<Duration> {
+        None
+    }
+
+    /// Does nothing, the position is that of the source of the [`BypassOutside`].
+    #[inline]
+    fn try_seek(&mut self, _: Duration) -> Result<(), SeekError> {
+        Ok(())
+    }
+}
+
+impl<I, E> BypassOutside<I, E>
+where
+    I: Source<Item = f32>,
+    E: Source<Item = f32>,
+{
+    /// Moves the window the effect is applied in.
+    #[inline]
+    pub fn set_window(&mut self, start: Duration, end: Duration) {
+        self.start = start.as_secs_f64();
+        self.end = end.as_secs_f64();
+    }
+
+    /// Returns a reference to the effect.
+    #[inline]
+    pub fn effect(&self) -> &E {
+        &self.effect
+    }
+
+    /// Returns a mutable reference to the effect, for example to change its settings.
+    #[inline]
+    pub fn effect_mut(&mut self) -> &mut E {
+        &mut self.effect
+    }
+
+    /// Returns a reference to the inner source.
+    #[inline]
+    pub fn inner(&self) -> &I {
+        &self.input
+    }
+
+    /// Returns a mutable reference to the inner source.
+    #[inline]
+    pub fn inner_mut(&mut self) -> &mut I {
+        &mut self.input
+    }
+
+    /// Returns the inner source.
+    #[inline]
+    pub fn into_inner(self) -> I {
+        self.input
+    }
+
+    #[inline]
+    fn pos(&self) -> f64 {
+        self.base + self.frames as f64 / self.sample_rate.max(1) as f64
+    }
+}
+
+impl<I, E> Iterator for BypassOutside<I, E>
+where
+    I: Source<Item = f32>,
+    E: Source<Item = f32>,
+{
+    type Item = f32;
+
+    #[inline]
+    fn next(&mut self) -> Option<f32> {
+        let channels = self.input.channels().max(1);
+        let sample_rate = self.input.sample_rate().max(1);
+        if self.channel == 0 {
+            if sample_rate != self.sample_rate {
+                self.base = self.pos();
+                self.frames = 0;
+                self.sample_rate = sample_rate;
+            }
+            self.shared.channels.store(channels, Ordering::Relaxed);
+            self.shared
+                .sample_rate
+                .store(sample_rate, Ordering::Relaxed);
+            let target = if (self.start..self.end).contains(&self.pos()) {
+                1.0
+            } else {
+                0.0
+            };
+            let step = 1.0 / (RAMP.as_secs_f32() * sample_rate as f32).max(1.0);
+            if self.wet < target {
+                self.wet = (self.wet + step).min(target);
+            } else if self.wet > target {
+                self.wet = (self.wet - step).max(target);
+            }
+        }
+
+        let dry = self.input.next()?;
+        self.shared.sample.store(dry.to_bits(), Ordering::Relaxed);
+        self.shared.pending.store(true, Ordering::Relaxed);
+        let wet = self.effect.next().unwrap_or(dry);
+        // Drops the sample if the effect did not take it.
+        self.shared.pending.store(false, Ordering::Relaxed);
+
+        self.channel += 1;
+        if self.channel >= channels {
+            self.channel = 0;
+            self.frames += 1;
+        }
+        Some(dry + (wet - dry) * self.wet)
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.input.size_hint()
+    }
+}
+
+impl<I, E> Source for BypassOutside<I, E>
+where
+    I: Source<Item = f32>,
+    E: Source<Item = f32>,
+{
+    #[inline]
+    fn current_span_len(&self) -> Option<usize> {
+        self.input.current_span_len()
+    }
+
+    #[inline]
+    fn channels(&self) -> ChannelCount {
+        self.input.channels()
+    }
+
+    #[inline]
+    fn sample_rate(&self) -> SampleRate {
+        self.input.sample_rate()
+    }
+
+    #[inline]
+    fn total_duration(&self) -> Option<Duration> {
+        self.input.total_duration()
+    }
+
+    #[inline]
+    fn tail_duration(&self) -> Duration {
+        self.input.tail_duration()
+    }
+
+    #[inline]
+    fn latency(&self) -> Duration {
+        self.input.latency()
+    }
+
+    #[inline]
+    fn try_seek(&mut self, pos: Duration) -> Result<(), SeekError> {
+        self.input.try_seek(pos)?;
+        self.effect.try_seek(pos)?;
+        self.base = pos.as_secs_f64();
+        self.frames = 0;
+        self.channel = 0;
+        // Jumps to the level at the new position instead of fading.
+        self.wet = if (self.start..self.end).contains(&self.base) {
+            1.0
+        } else {
+            0.0
+        };
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use crate::buffer::SamplesBuffer;
+    use crate::source::Source;
+
+    #[test]
+    fn effect_is_only_applied_in_the_window() {
+        // at 1 kHz the window is frames 100 to 200 and the ramps take 5 frames
+        let source = SamplesBuffer::new(2, 1000, vec![1.0f32; 600]).bypass_outside(
+            Duration::from_millis(100),
+            Duration::from_millis(200),
+            |input| input.amplify(0.0),
+        );
+        let output: Vec<f32> = source.collect();
+
+        assert!(output[..200].iter().all(|&s| s == 1.0));
+        let fading_in = &output[200..210];
+        assert!(fading_in.windows(2).all(|w| w[1] <= w[0]));
+        assert_eq!(fading_in[..2], [0.8, 0.8]);
+        assert!(output[210..400].iter().all(|&s| s == 0.0));
+        let fading_out = &output[400..410];
+        assert!(fading_out.windows(2).all(|w| w[1] >= w[0]));
+        assert!(output[410..].iter().all(|&s| s == 1.0));
+    }
+
+    #[test]
+    fn seeking_jumps_into_the_window() {
+        let mut source = SamplesBuffer::new(1, 1000, vec![1.0f32; 300]).bypass_outside(
+            Duration::from_millis(100),
+            Duration::from_millis(200),
+            |input| input.amplify(0.5),
+        );
+        source.try_seek(Duration::from_millis(150)).unwrap();
+        assert_eq!(source.next(), Some(0.5));
+        source.try_seek(Duration::from_millis(250)).unwrap();
+        assert_eq!(source.next(), Some(1.0));
+    }
+}
@@ -0,0 +1,254 @@
+use std::collections::VecDeque;
+use std::time::Duration;
+
+use super::SeekError;
+use crate::common::{ChannelCount, SampleRate};
+use crate::{Sample, Source};
+
+/// Internal function that builds a `ChannelDelays` object.
+pub fn channel_delays<I>(input: I, delays: Vec<Duration>) -> ChannelDelays<I>
+where
+    I: Source,
+    I::Item: Sample,
+{
+    assert_eq!(
+        delays.len(),
+        input.channels() as usize,
+        "there must be one delay per channel"
+    );
+    let sample_rate = input.sample_rate();
+    let delays: Vec<usize> = delays
+        .iter()
+        .map(|delay| (delay.as_nanos() * sample_rate as u128 / 1_000_000_000) as usize)
+        .collect();
+    let mut source = ChannelDelays {
+        input,
+        lines: delays
+            .iter()
+            .map(|&delay| VecDeque::with_capacity(delay + 1))
+            .collect(),
+        longest: delays.iter().copied().max().unwrap_or(0),
+        delays,
+        sample_rate,
+        channel: 0,
+        tail_frames: None,
+    };
+    source.reset();
+    source
+}
+
+/// Delays every channel of a source by its own amount, for example to widen a stereo image
+/// with the Haas effect or to time align recordings made with several microphones.
+///
+/// The delays are rounded down to whole samples. The channels that are delayed less are
+/// padded with silence at the end, so that the source plays until the channel delayed the
+/// most has ended. The channel count and sample rate of the source at the time this was
+/// created are used throughout.
+#[derive(Clone, Debug)]
+pub struct ChannelDelays<I>
+where
+    I: Source,
+    I::Item: Sample,
+{
+    input: I,
+    // Delay of every channel in samples.
+    delays: Vec<usize>,
+    longest: usize,
+    // One delay line per channel.
+    lines: Vec<VecDeque<I::Item>>,
+    sample_rate: SampleRate,
+    channel: usize,
+    // Frames still to be played once the input ended.
+    tail_frames: Option<usize>,
+}
+
+impl<I> ChannelDelays<I>
+where
+    I: Source,
+    I::Item: Sample,
+{
+    /// Returns the delay of every channel, rounded down to whole samples.
+    #[inline]
+    pub fn delays(&self) -> Vec<Duration> {
+        self.delays
+            .iter()
+            .map(|&delay| Duration::from_secs_f64(delay as f64 / self.sample_rate as f64))
+            .collect()
+    }
+
+    /// Returns a reference to the inner source.
+    #[inline]
+    pub fn inner(&self) -> &I {
+        &self.input
+    }
+
+    /// Returns a mutable reference to the inner source.
+    #[inline]
+    pub fn inner_mut(&mut self) -> &mut I {
+        &mut self.input
+    }
+
+    /// Returns the inner source.
+    #[inline]
+    pub fn into_inner(self) -> I {
+        self.input
+    }
+
+    fn reset(&mut self) {
+        for (line, &delay) in self.lines.iter_mut().zip(&self.delays) {
+            line.clear();
+            line.resize(delay, I::Item::ZERO_VALUE);
+        }
+        self.channel = 0;
+        self.tail_frames = None;
+    }
+}
+
+impl<I> Iterator for ChannelDelays<I>
+where
+    I: Source,
+    I::Item: Sample,
+{
+    type Item = I::Item;
+
+    #[inline]
+    fn next(&mut self) -> Option<I::Item> {
+        if self.lines.is_empty() {
+            return None;
+        }
+        if self.channel == 0 {
+            if let Some(frames) = self.tail_frames.as_mut() {
+                if *frames == 0 {
+                    return None;
+                }
+                *frames -= 1;
+            }
+        }
+
+        let sample = match self.tail_frames {
+            Some(_) => I::Item::ZERO_VALUE,
+            None => match self.input.next() {
+                Some(sample) => sample,
+                None if self.channel == 0 => {
+                    if self.longest == 0 {
+                        return None;
+                    }
+                    self.tail_frames = Some(self.longest - 1);
+                    I::Item::ZERO_VALUE
+                }
+                // The last frame is incomplete, finish it with silence.
+                None => {
+                    self.tail_frames = Some(self.longest);
+                    I::Item::ZERO_VALUE
+                }
+            },
+        };
+
+        let line = &mut self.lines[self.channel];
+        line.push_back(sample);
+        let delayed = line.pop_front();
+        self.channel += 1;
+        if self.channel >= self.lines.len() {
+            self.channel = 0;
+        }
+        delayed
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let tail = self.longest * self.lines.len();
+        let (lower, upper) = self.input.size_hint();
+        (
+            lower.saturating_add(tail),
+            upper.and_then(|upper| upper.checked_add(tail)),
+        )
+    }
+}
+
+impl<I> Source for ChannelDelays<I>
+where
+    I: Source,
+    I::Item: Sample,
+{
+    #[inline]
+    fn current_span_len(&self) -> Option<usize> {
+        None
+    }
+
+    #[inline]
+    fn channels(&self) -> ChannelCount {
+        self.lines.len() as ChannelCount
+    }
+
+    #[inline]
+    fn sample_rate(&self) -> SampleRate {
+        self.sample_rate
+    }
+
+    #[inline]
+    fn total_duration(&self) -> Option<Duration> {
+        let tail = Duration::from_secs_f64(self.longest as f64 / self.sample_rate as f64);
+        self.input.total_duration().map(|total| total + tail)
+    }
+
+    /// The tail of the input, plus the delay of the most delayed channel.
+    #[inline]
+    fn tail_duration(&self) -> Duration {
+        let tail = Duration::from_secs_f64(self.longest as f64 / self.sample_rate as f64);
+        self.input.tail_duration() + tail
+    }
+
+    #[inline]
+    fn latency(&self) -> Duration {
+        self.input.latency()
+    }
+
+    /// Clears the delay lines, the delayed channels are silent for their delay after the
+    /// seek.
+    #[inline]
+    fn try_seek(&mut self, pos: Duration) -> Result<(), SeekError> {
+        self.input.try_seek(pos)?;
+        self.reset();
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use crate::buffer::SamplesBuffer;
+    use crate::source::Source;
+
+    #[test]
+    fn delays_only_the_given_channel() {
+        let data: Vec<f32> = (1..=8).map(|i| i as f32).collect();
+        let delayed = SamplesBuffer::new(2, 1000, data)
+            .channel_delays(vec![Duration::ZERO, Duration::from_millis(2)]);
+        assert_eq!(delayed.total_duration(), Some(Duration::from_millis(6)));
+
+        let output: Vec<f32> = delayed.collect();
+        assert_eq!(output, [1., 0., 3., 0., 5., 2., 7., 4., 0., 6., 0., 8.]);
+    }
+
+    #[test]
+    fn mono_becomes_wide_stereo() {
+        let data: Vec<f32> = (1..=6).map(|i| i as f32).collect();
+        let wide = SamplesBuffer::new(1, 1000, data).mono_to_wide_stereo(Duration::from_millis(2));
+        assert_eq!(wide.channels(), 2);
+
+        let output: Vec<f32> = wide.collect();
+        assert_eq!(
+            output,
+            [1., 0., 2., 0., 3., 1., 4., 2., 5., 3., 6., 4., 0., 5., 0., 6.]
+        );
+    }
+
+    #[test]
+    fn incomplete_frame_is_padded() {
+        let delayed = SamplesBuffer::new(2, 1000, vec![1.0f32, 2.0, 3.0])
+            .channel_delays(vec![Duration::from_millis(1), Duration::ZERO]);
+        let output: Vec<f32> = delayed.collect();
+        assert_eq!(output, [0., 2., 1., 0., 3., 0.]);
+    }
+}
@@ -48,6 +48,9 @@ impl Iterator for Chirp {
 
     fn next(&mut self) -> Option<Self::Item> {
         let i = self.elapsed_samples;
+        if i >= self.total_samples {
+            return None;
+        }
         let ratio = self.elapsed_samples as f32 / self.total_samples as f32;
         self.elapsed_samples += 1;
         let freq = self.start_frequency * (1.0 - ratio) + self.end_frequency * ratio;
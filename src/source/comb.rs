@@ -0,0 +1,235 @@
+use std::time::Duration;
+
+use super::blt::flush_denormal;
+use super::SeekError;
+use crate::common::{ChannelCount, SampleRate};
+use crate::Source;
+
+/// Internal function that builds a `CombFilter` object.
+pub fn comb_filter<I>(input: I, delay: Duration, feedback: f32, feedforward: f32) -> CombFilter<I>
+where
+    I: Source<Item = f32>,
+{
+    let mut filter = CombFilter {
+        input,
+        delay,
+        feedback,
+        feedforward,
+        inputs: Vec::new(),
+        outputs: Vec::new(),
+        pos: 0,
+        channel: 0,
+        channels: 0,
+        sample_rate: 0,
+    };
+    filter.reset();
+    filter
+}
+
+/// A comb filter, which adds delayed copies of its input and output to the signal. See
+/// [`Source::comb_filter`].
+///
+/// Every output sample is `x[n] + feedforward * x[n - d] + feedback * y[n - d]`, where `x` is
+/// the input, `y` the output and `d` the delay in frames, rounded to the nearest frame and at
+/// least one. The channels have separate delay lines.
+///
+/// The feedback part makes the filter ring, an impulse comes back every `d` frames scaled by
+/// `feedback` each time. It is only stable while `feedback` is between `-1.0` and `1.0`
+/// exclusive, at `1.0` or beyond the echoes never fade or grow without bound. The feedforward
+/// part adds a single echo and is always stable.
+///
+/// The filter ends with its input, append silence to let the echoes ring out.
+#[derive(Clone, Debug)]
+pub struct CombFilter<I> {
+    input: I,
+    delay: Duration,
+    feedback: f32,
+    feedforward: f32,
+    // The last `d` frames of the input and of the output, interleaved.
+    inputs: Vec<f32>,
+    outputs: Vec<f32>,
+    // Index of the oldest frame in the delay lines.
+    pos: usize,
+    channel: ChannelCount,
+    // Format the delay lines are laid out for.
+    channels: ChannelCount,
+    sample_rate: SampleRate,
+}
+
+impl<I> CombFilter<I>
+where
+    I: Source<Item = f32>,
+{
+    /// Changes the gain of the delayed output, see [`CombFilter`] for the range that is
+    /// stable.
+    #[inline]
+    pub fn set_feedback(&mut self, feedback: f32) {
+        self.feedback = feedback;
+    }
+
+    /// Changes the gain of the delayed input.
+    #[inline]
+    pub fn set_feedforward(&mut self, feedforward: f32) {
+        self.feedforward = feedforward;
+    }
+
+    /// Returns the delay in frames the filter works with.
+    #[inline]
+    pub fn delay_frames(&self) -> usize {
+        self.inputs.len() / self.channels.max(1) as usize
+    }
+
+    /// Returns a reference to the inner source.
+    #[inline]
+    pub fn inner(&self) -> &I {
+        &self.input
+    }
+
+    /// Returns a mutable reference to the inner source.
+    #[inline]
+    pub fn inner_mut(&mut self) -> &mut I {
+        &mut self.input
+    }
+
+    /// Returns the inner source.
+    #[inline]
+    pub fn into_inner(self) -> I {
+        self.input
+    }
+
+    // Empties the delay lines and sizes them for the current format of the input.
+    fn reset(&mut self) {
+        self.channels = self.input.channels().max(1);
+        self.sample_rate = self.input.sample_rate();
+        let frames = (self.delay.as_secs_f64() * self.sample_rate as f64).round() as usize;
+        let len = frames.max(1) * self.channels as usize;
+        self.inputs.clear();
+        self.inputs.resize(len, 0.0);
+        self.outputs.clear();
+        self.outputs.resize(len, 0.0);
+        self.pos = 0;
+        self.channel = 0;
+    }
+}
+
+impl<I> Iterator for CombFilter<I>
+where
+    I: Source<Item = f32>,
+{
+    type Item = f32;
+
+    #[inline]
+    fn next(&mut self) -> Option<f32> {
+        if self.channel == 0
+            && (self.input.channels().max(1) != self.channels
+                || self.input.sample_rate() != self.sample_rate)
+        {
+            self.reset();
+        }
+        let sample = self.input.next()?;
+        let index = self.pos + self.channel as usize;
+        let output = flush_denormal(
+            sample + self.feedforward * self.inputs[index] + self.feedback * self.outputs[index],
+        );
+        self.inputs[index] = sample;
+        self.outputs[index] = output;
+
+        self.channel += 1;
+        if self.channel >= self.channels {
+            self.channel = 0;
+            self.pos += self.channels as usize;
+            if self.pos >= self.inputs.len() {
+                self.pos = 0;
+            }
+        }
+        Some(output)
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.input.size_hint()
+    }
+}
+
+impl<I> ExactSizeIterator for CombFilter<I> where I: Source<Item = f32> + ExactSizeIterator {}
+
+impl<I> Source for CombFilter<I>
+where
+    I: Source<Item = f32>,
+{
+    #[inline]
+    fn current_span_len(&self) -> Option<usize> {
+        self.input.current_span_len()
+    }
+
+    #[inline]
+    fn channels(&self) -> ChannelCount {
+        self.input.channels()
+    }
+
+    #[inline]
+    fn sample_rate(&self) -> SampleRate {
+        self.input.sample_rate()
+    }
+
+    #[inline]
+    fn total_duration(&self) -> Option<Duration> {
+        self.input.total_duration()
+    }
+
+    #[inline]
+    fn tail_duration(&self) -> Duration {
+        self.input.tail_duration()
+    }
+
+    #[inline]
+    fn latency(&self) -> Duration {
+        self.input.latency()
+    }
+
+    /// The delay lines are emptied, so the echoes of the old position are not heard.
+    #[inline]
+    fn try_seek(&mut self, pos: Duration) -> Result<(), SeekError> {
+        self.input.try_seek(pos)?;
+        self.reset();
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use crate::buffer::SamplesBuffer;
+    use crate::source::Source;
+
+    fn impulse(channels: u16, frames: usize) -> SamplesBuffer<f32> {
+        let mut samples = vec![0.0f32; frames * channels as usize];
+        samples[..channels as usize].fill(1.0);
+        SamplesBuffer::new(channels, 1000, samples)
+    }
+
+    #[test]
+    fn feedback_makes_a_decaying_pulse_train() {
+        // 4 milliseconds at 1 kHz are 4 frames
+        let output: Vec<f32> = impulse(2, 16)
+            .comb_filter(Duration::from_millis(4), 0.5, 0.0)
+            .collect();
+        for (frame, samples) in output.chunks(2).enumerate() {
+            let expected = if frame % 4 == 0 {
+                0.5f32.powi(frame as i32 / 4)
+            } else {
+                0.0
+            };
+            assert_eq!(samples, [expected, expected], "frame {frame}");
+        }
+    }
+
+    #[test]
+    fn feedforward_adds_a_single_echo() {
+        let filter = impulse(1, 8).comb_filter(Duration::from_millis(3), 0.0, -0.5);
+        assert_eq!(filter.delay_frames(), 3);
+        let output: Vec<f32> = filter.collect();
+        assert_eq!(output, [1.0, 0.0, 0.0, -0.5, 0.0, 0.0, 0.0, 0.0]);
+    }
+}
@@ -0,0 +1,252 @@
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use super::SeekError;
+use crate::common::{ChannelCount, SampleRate};
+use crate::{Sample, Source};
+
+/// Internal function that builds a `Correlation` object.
+pub fn correlation<I>(input: I, window: Duration) -> Correlation<I>
+where
+    I: Source,
+    I::Item: Sample,
+{
+    let mut correlation = Correlation {
+        input,
+        window,
+        coeff: 0.0,
+        sample_rate: 0,
+        left_right: 0.0,
+        left_square: 0.0,
+        right_square: 0.0,
+        left: 0.0,
+        channel: 0,
+        value: Arc::new(AtomicU32::new(0f32.to_bits())),
+    };
+    correlation.update_coeff();
+    correlation
+}
+
+/// Passes a source through unchanged while measuring the correlation between its first two
+/// channels, to check how a stereo mix holds up when it is played in mono. See
+/// [`Source::correlation`].
+///
+/// The correlation coefficient goes from `1.0`, when both channels carry the same signal, over
+/// `0.0` for unrelated signals, to `-1.0` when one channel is the inverse of the other. Values
+/// below zero mean that parts of the mix cancel out when the channels are summed to mono. It is
+/// measured over a moving window and `0.0` while the source is silent. Mono sources measure
+/// `1.0`, channels after the second one are ignored.
+///
+/// Read the measurement with a [`CorrelationHandle`] obtained from [`Correlation::handle`].
+#[derive(Debug)]
+pub struct Correlation<I> {
+    input: I,
+    window: Duration,
+    // Weight of a new frame in the moving averages.
+    coeff: f64,
+    // Sample rate `coeff` was computed for.
+    sample_rate: SampleRate,
+    // Moving averages of the product and of the squares of the two channels.
+    left_right: f64,
+    left_square: f64,
+    right_square: f64,
+    // The first channel of the current frame.
+    left: f32,
+    channel: ChannelCount,
+    value: Arc<AtomicU32>,
+}
+
+/// Reads the measurement of a [`Correlation`] source from any thread.
+#[derive(Clone, Debug)]
+pub struct CorrelationHandle {
+    value: Arc<AtomicU32>,
+}
+
+impl CorrelationHandle {
+    /// Returns the correlation coefficient of the most recent window, from `-1.0` to `1.0`.
+    #[inline]
+    pub fn correlation(&self) -> f32 {
+        f32::from_bits(self.value.load(Ordering::Relaxed))
+    }
+}
+
+impl<I> Correlation<I>
+where
+    I: Source,
+    I::Item: Sample,
+{
+    /// Returns a handle to read the correlation with. Get it before handing the source to a
+    /// sink or mixer.
+    #[inline]
+    pub fn handle(&self) -> CorrelationHandle {
+        CorrelationHandle {
+            value: self.value.clone(),
+        }
+    }
+
+    /// Returns a reference to the inner source.
+    #[inline]
+    pub fn inner(&self) -> &I {
+        &self.input
+    }
+
+    /// Returns a mutable reference to the inner source.
+    #[inline]
+    pub fn inner_mut(&mut self) -> &mut I {
+        &mut self.input
+    }
+
+    /// Returns the inner source.
+    #[inline]
+    pub fn into_inner(self) -> I {
+        self.input
+    }
+
+    fn update_coeff(&mut self) {
+        self.sample_rate = self.input.sample_rate();
+        let frames = self.window.as_secs_f64() * self.sample_rate as f64;
+        self.coeff = 1.0 - (-1.0 / frames.max(1.0)).exp();
+    }
+
+    fn push_frame(&mut self, left: f32, right: f32) {
+        let (left, right) = (left as f64, right as f64);
+        self.left_right += (left * right - self.left_right) * self.coeff;
+        self.left_square += (left * left - self.left_square) * self.coeff;
+        self.right_square += (right * right - self.right_square) * self.coeff;
+
+        let energy = (self.left_square * self.right_square).sqrt();
+        let correlation = if energy > f64::EPSILON {
+            (self.left_right / energy).clamp(-1.0, 1.0)
+        } else {
+            0.0
+        };
+        self.value
+            .store((correlation as f32).to_bits(), Ordering::Relaxed);
+    }
+}
+
+impl<I> Iterator for Correlation<I>
+where
+    I: Source,
+    I::Item: Sample,
+{
+    type Item = I::Item;
+
+    #[inline]
+    fn next(&mut self) -> Option<I::Item> {
+        if self.channel == 0 && self.input.sample_rate() != self.sample_rate {
+            self.update_coeff();
+        }
+        let sample = self.input.next()?;
+
+        let channels = self.input.channels().max(1);
+        let value = sample.to_f32();
+        match self.channel {
+            0 if channels == 1 => self.push_frame(value, value),
+            0 => self.left = value,
+            1 => self.push_frame(self.left, value),
+            _ => (),
+        }
+        self.channel += 1;
+        if self.channel >= channels {
+            self.channel = 0;
+        }
+
+        Some(sample)
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.input.size_hint()
+    }
+}
+
+impl<I> ExactSizeIterator for Correlation<I>
+where
+    I: Source + ExactSizeIterator,
+    I::Item: Sample,
+{
+}
+
+impl<I> Source for Correlation<I>
+where
+    I: Source,
+    I::Item: Sample,
+{
+    #[inline]
+    fn current_span_len(&self) -> Option<usize> {
+        self.input.current_span_len()
+    }
+
+    #[inline]
+    fn channels(&self) -> ChannelCount {
+        self.input.channels()
+    }
+
+    #[inline]
+    fn sample_rate(&self) -> SampleRate {
+        self.input.sample_rate()
+    }
+
+    #[inline]
+    fn total_duration(&self) -> Option<Duration> {
+        self.input.total_duration()
+    }
+
+    #[inline]
+    fn tail_duration(&self) -> Duration {
+        self.input.tail_duration()
+    }
+
+    #[inline]
+    fn latency(&self) -> Duration {
+        self.input.latency()
+    }
+
+    /// The measurement is kept, it settles on the new position within the window.
+    #[inline]
+    fn try_seek(&mut self, pos: Duration) -> Result<(), SeekError> {
+        self.input.try_seek(pos)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use crate::buffer::SamplesBuffer;
+    use crate::source::{SineWave, Source};
+
+    // One second of stereo at 8 kHz, made of `frame` applied to every frame index.
+    fn measure(frame: impl Fn(usize) -> [f32; 2]) -> f32 {
+        let samples: Vec<f32> = (0..8000).flat_map(frame).collect();
+        let mut source =
+            SamplesBuffer::new(2, 8000, samples).correlation(Duration::from_millis(100));
+        let handle = source.handle();
+        source.by_ref().for_each(drop);
+        handle.correlation()
+    }
+
+    #[test]
+    fn identical_and_inverted_channels() {
+        let sine: Vec<f32> = SineWave::new(440.0).take(8000).collect();
+        assert!((measure(|i| [sine[i], sine[i]]) - 1.0).abs() < 1e-4);
+        assert!((measure(|i| [sine[i], -0.5 * sine[i]]) + 1.0).abs() < 1e-4);
+    }
+
+    #[test]
+    fn unrelated_noise_is_close_to_zero() {
+        let mut state = 0x2545_f491_4f6c_dd1du64;
+        let noise: Vec<f32> = (0..16000)
+            .map(|_| {
+                state ^= state << 13;
+                state ^= state >> 7;
+                state ^= state << 17;
+                (state >> 40) as f32 / (1u64 << 23) as f32 - 1.0
+            })
+            .collect();
+        let correlation = measure(|i| [noise[2 * i], noise[2 * i + 1]]);
+        assert!(correlation.abs() < 0.2, "{correlation}");
+    }
+}
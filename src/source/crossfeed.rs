@@ -0,0 +1,234 @@
+use std::collections::VecDeque;
+use std::f32::consts::TAU;
+use std::time::Duration;
+
+use super::blt::flush_denormal;
+use super::SeekError;
+use crate::common::{ChannelCount, SampleRate};
+use crate::Source;
+
+/// Cutoff of the low-pass filter applied to the fed across signal, roughly where the head
+/// starts to shadow the ear on the far side.
+const CUTOFF_HZ: f32 = 700.0;
+
+/// How much later the fed across signal arrives, about the time sound takes to travel around
+/// the head.
+const DELAY: Duration = Duration::from_micros(300);
+
+/// Level of the fed across signal at an amount of 1, about -6 dB.
+const MAX_LEVEL: f32 = 0.5;
+
+/// Internal function that builds a `Crossfeed` object.
+pub fn crossfeed<I>(input: I, amount: f32) -> Crossfeed<I>
+where
+    I: Source<Item = f32>,
+{
+    let mut crossfeed = Crossfeed {
+        input,
+        level: amount.clamp(0.0, 1.0) * MAX_LEVEL,
+        sample_rate: 0,
+        coefficient: 0.0,
+        filtered: [0.0; 2],
+        delayed: [VecDeque::new(), VecDeque::new()],
+        pending: None,
+    };
+    crossfeed.reset();
+    crossfeed
+}
+
+/// Makes stereo sources easier to listen to on headphones by feeding a bit of each channel
+/// into the other, like when listening to speakers.
+///
+/// Following the crossfeed of Benjamin Bauer, the fed across signal is low-passed at 700 Hz
+/// and delayed by 0.3 milliseconds, as sound reaching the far ear is shadowed and delayed by
+/// the head. The output is lowered by the same amount that is fed across so that low
+/// frequencies, which are mostly the same on both channels, keep their level.
+///
+/// Sources that do not have two channels are passed on unchanged.
+#[derive(Clone, Debug)]
+pub struct Crossfeed<I> {
+    input: I,
+    // Gain of the fed across signal.
+    level: f32,
+    // Sample rate the filter and delay are set up for.
+    sample_rate: SampleRate,
+    coefficient: f32,
+    // State of the low-pass filter of each channel.
+    filtered: [f32; 2],
+    // Low-passed samples of each channel waiting to be fed into the other one.
+    delayed: [VecDeque<f32>; 2],
+    // Right sample of the current frame, returned after the left one.
+    pending: Option<f32>,
+}
+
+impl<I> Crossfeed<I>
+where
+    I: Source<Item = f32>,
+{
+    /// Returns a reference to the inner source.
+    #[inline]
+    pub fn inner(&self) -> &I {
+        &self.input
+    }
+
+    /// Returns a mutable reference to the inner source.
+    #[inline]
+    pub fn inner_mut(&mut self) -> &mut I {
+        &mut self.input
+    }
+
+    /// Returns the inner source.
+    #[inline]
+    pub fn into_inner(self) -> I {
+        self.input
+    }
+
+    fn reset(&mut self) {
+        self.sample_rate = self.input.sample_rate().max(1);
+        let sample_rate = self.sample_rate as f32;
+        self.coefficient = 1.0 - (-TAU * CUTOFF_HZ / sample_rate).exp();
+        let delay = (DELAY.as_secs_f32() * sample_rate).round() as usize;
+        self.filtered = [0.0; 2];
+        for line in &mut self.delayed {
+            line.clear();
+            line.resize(delay, 0.0);
+        }
+        self.pending = None;
+    }
+}
+
+impl<I> Iterator for Crossfeed<I>
+where
+    I: Source<Item = f32>,
+{
+    type Item = f32;
+
+    #[inline]
+    fn next(&mut self) -> Option<f32> {
+        if let Some(right) = self.pending.take() {
+            return Some(right);
+        }
+        if self.input.channels() != 2 {
+            return self.input.next();
+        }
+        if self.input.sample_rate() != self.sample_rate {
+            self.reset();
+        }
+
+        let left = self.input.next()?;
+        // An incomplete frame at the end is treated as if the right channel was silent.
+        let right = self.input.next().unwrap_or(0.0);
+        let mut crossed = [0.0; 2];
+        for (channel, sample) in [left, right].into_iter().enumerate() {
+            let filtered = &mut self.filtered[channel];
+            *filtered = flush_denormal(*filtered + self.coefficient * (sample - *filtered));
+            let line = &mut self.delayed[channel];
+            line.push_back(*filtered);
+            crossed[1 - channel] = line.pop_front().unwrap_or(0.0);
+        }
+
+        let normalize = 1.0 / (1.0 + self.level);
+        self.pending = Some((right + self.level * crossed[1]) * normalize);
+        Some((left + self.level * crossed[0]) * normalize)
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let pending = self.pending.is_some() as usize;
+        let (lower, upper) = self.input.size_hint();
+        (
+            lower.saturating_add(pending),
+            upper.and_then(|upper| upper.checked_add(pending)),
+        )
+    }
+}
+
+impl<I> Source for Crossfeed<I>
+where
+    I: Source<Item = f32>,
+{
+    #[inline]
+    fn current_span_len(&self) -> Option<usize> {
+        self.input
+            .current_span_len()
+            .map(|len| len + self.pending.is_some() as usize)
+    }
+
+    #[inline]
+    fn channels(&self) -> ChannelCount {
+        if self.pending.is_some() {
+            2
+        } else {
+            self.input.channels()
+        }
+    }
+
+    #[inline]
+    fn sample_rate(&self) -> SampleRate {
+        if self.pending.is_some() {
+            self.sample_rate
+        } else {
+            self.input.sample_rate()
+        }
+    }
+
+    #[inline]
+    fn total_duration(&self) -> Option<Duration> {
+        self.input.total_duration()
+    }
+
+    #[inline]
+    fn tail_duration(&self) -> Duration {
+        self.input.tail_duration()
+    }
+
+    #[inline]
+    fn latency(&self) -> Duration {
+        self.input.latency()
+    }
+
+    /// Clears the filters and the delay.
+    #[inline]
+    fn try_seek(&mut self, pos: Duration) -> Result<(), SeekError> {
+        self.input.try_seek(pos)?;
+        self.reset();
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::buffer::SamplesBuffer;
+    use crate::source::Source;
+
+    #[test]
+    fn left_bleeds_into_right() {
+        // a click on the left channel only, at 44.1 kHz the delay is 13 frames
+        let mut data = vec![0.0f32; 200];
+        data[0] = 1.0;
+        let output: Vec<f32> = SamplesBuffer::new(2, 44_100, data).crossfeed(1.0).collect();
+        assert_eq!(output.len(), 200);
+
+        let left: Vec<f32> = output.iter().step_by(2).copied().collect();
+        let right: Vec<f32> = output.iter().skip(1).step_by(2).copied().collect();
+        assert!(left[0] > 0.6);
+        assert!(right[..13].iter().all(|&s| s == 0.0));
+        assert!(right[13] > 0.0);
+        // low-passed, so spread out and far quieter than the click itself
+        let peak = right.iter().copied().fold(0.0, f32::max);
+        assert!(peak < 0.1 * left[0]);
+        assert!(right[14..40].iter().all(|&s| s > 0.0));
+    }
+
+    #[test]
+    fn in_phase_bass_keeps_its_level() {
+        let output: Vec<f32> = SamplesBuffer::new(2, 44_100, vec![0.5f32; 2000])
+            .crossfeed(0.7)
+            .collect();
+        assert!(output[1000..].iter().all(|&s| (s - 0.5).abs() < 1e-4));
+
+        // other channel counts pass through
+        let mono = SamplesBuffer::new(1, 44_100, vec![0.25f32; 10]).crossfeed(1.0);
+        assert_eq!(mono.collect::<Vec<_>>(), [0.25; 10]);
+    }
+}
@@ -115,6 +115,16 @@ where
             .map(|val| val + self.requested_duration)
     }
 
+    #[inline]
+    fn tail_duration(&self) -> Duration {
+        self.input.tail_duration()
+    }
+
+    #[inline]
+    fn latency(&self) -> Duration {
+        self.input.latency()
+    }
+
     /// Pos is seen from the perspective of the api user.
     ///
     /// # Example
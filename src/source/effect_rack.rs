@@ -0,0 +1,373 @@
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use super::SeekError;
+use crate::common::{ChannelCount, SampleRate};
+use crate::Source;
+
+/// An effect that processes one frame at a time, so that it can be put in an [`EffectRack`]
+/// and swapped out while playing.
+///
+/// Unlike the effects of [`Source`], which wrap the source they process, a processor only
+/// sees the samples, so processors can be added, removed and reordered without rebuilding
+/// the chain. [`GainProcessor`](super::GainProcessor) and
+/// [`FilterProcessor`](super::FilterProcessor) are provided, others can be implemented by
+/// hand.
+pub trait SampleProcessor {
+    /// Processes one frame in place, it holds a sample for each of the `channels` channels.
+    /// The channel count and sample rate can change between calls.
+    fn process(&mut self, frame: &mut [f32], channels: ChannelCount, sample_rate: SampleRate);
+
+    /// Clears the state kept from previous frames, called when the source seeks. Does
+    /// nothing by default.
+    fn reset(&mut self) {}
+}
+
+/// Identifies an effect in an [`EffectRack`], returned when adding it.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct EffectId(u64);
+
+type Effect = Box<dyn SampleProcessor + Send>;
+
+// A change sent from a handle to the rack. Indices are already clamped to the chain.
+enum Change {
+    Insert(usize, EffectId, Effect),
+    Remove(EffectId),
+    Move(EffectId, usize),
+    Clear,
+}
+
+// The handles' view of the chain. The rack owns the effects themselves and applies the
+// changes in the order they are sent, so both sides agree on the order.
+struct Effects {
+    next_id: u64,
+    ids: Vec<EffectId>,
+    changes: Sender<Change>,
+}
+
+/// Internal function that builds an `EffectRack` object.
+pub fn effect_rack<I>(input: I) -> EffectRack<I>
+where
+    I: Source<Item = f32>,
+{
+    let (changes, receiver) = mpsc::channel();
+    EffectRack {
+        input,
+        chain: Vec::new(),
+        changes: receiver,
+        effects: Arc::new(Mutex::new(Effects {
+            next_id: 0,
+            ids: Vec::new(),
+            changes,
+        })),
+        frame: Vec::new(),
+        pos: 0,
+        channels: 1,
+        sample_rate: 1,
+    }
+}
+
+/// Runs the source through a chain of [`SampleProcessor`]s that can be changed while it
+/// plays, like a rack of effects.
+///
+/// The rack starts out empty and passes the source on unchanged. Change it with an
+/// [`EffectRackHandle`] from [`EffectRack::handle`]. Changes take effect at the next frame.
+/// The rack never waits on a handle, so changing the effects from another thread can not
+/// hold up playback.
+pub struct EffectRack<I> {
+    input: I,
+    chain: Vec<(EffectId, Effect)>,
+    changes: Receiver<Change>,
+    effects: Arc<Mutex<Effects>>,
+    // The frame being played and the position of the next sample in it.
+    frame: Vec<f32>,
+    pos: usize,
+    // Format of `frame`.
+    channels: ChannelCount,
+    sample_rate: SampleRate,
+}
+
+/// Adds, removes and reorders the effects of an [`EffectRack`], for example from a UI thread.
+///
+/// Can be cloned and sent to another thread, get one with [`EffectRack::handle`].
+#[derive(Clone)]
+pub struct EffectRackHandle {
+    effects: Arc<Mutex<Effects>>,
+}
+
+impl EffectRackHandle {
+    /// Adds an effect at the end of the chain, so it is applied last.
+    pub fn push<P>(&self, effect: P) -> EffectId
+    where
+        P: SampleProcessor + Send + 'static,
+    {
+        self.insert(usize::MAX, effect)
+    }
+
+    /// Adds an effect at `index` in the chain, or at the end if `index` is past it.
+    pub fn insert<P>(&self, index: usize, effect: P) -> EffectId
+    where
+        P: SampleProcessor + Send + 'static,
+    {
+        let mut effects = self.effects.lock().unwrap();
+        let id = EffectId(effects.next_id);
+        effects.next_id += 1;
+        let index = index.min(effects.ids.len());
+        effects.ids.insert(index, id);
+        effects.send(Change::Insert(index, id, Box::new(effect)));
+        id
+    }
+
+    /// Removes an effect from the chain. Returns false if it was already removed.
+    pub fn remove(&self, id: EffectId) -> bool {
+        let mut effects = self.effects.lock().unwrap();
+        let Some(index) = effects.index_of(id) else {
+            return false;
+        };
+        effects.ids.remove(index);
+        effects.send(Change::Remove(id));
+        true
+    }
+
+    /// Moves an effect to `index` in the chain, or to the end if `index` is past it. Returns
+    /// false if the effect was removed.
+    pub fn move_to(&self, id: EffectId, index: usize) -> bool {
+        let mut effects = self.effects.lock().unwrap();
+        let Some(from) = effects.index_of(id) else {
+            return false;
+        };
+        effects.ids.remove(from);
+        let index = index.min(effects.ids.len());
+        effects.ids.insert(index, id);
+        effects.send(Change::Move(id, index));
+        true
+    }
+
+    /// Removes all effects.
+    pub fn clear(&self) {
+        let mut effects = self.effects.lock().unwrap();
+        effects.ids.clear();
+        effects.send(Change::Clear);
+    }
+
+    /// Returns the effects in the order they are applied.
+    pub fn effects(&self) -> Vec<EffectId> {
+        self.effects.lock().unwrap().ids.clone()
+    }
+}
+
+impl Effects {
+    fn index_of(&self, id: EffectId) -> Option<usize> {
+        self.ids.iter().position(|effect| *effect == id)
+    }
+
+    // The rack has been dropped if this fails, then there is nothing left to change.
+    fn send(&self, change: Change) {
+        let _ = self.changes.send(change);
+    }
+}
+
+impl<I> EffectRack<I>
+where
+    I: Source<Item = f32>,
+{
+    /// Returns a handle to change the effects from another thread.
+    #[inline]
+    pub fn handle(&self) -> EffectRackHandle {
+        EffectRackHandle {
+            effects: self.effects.clone(),
+        }
+    }
+
+    /// Returns a reference to the inner source.
+    #[inline]
+    pub fn inner(&self) -> &I {
+        &self.input
+    }
+
+    /// Returns a mutable reference to the inner source.
+    #[inline]
+    pub fn inner_mut(&mut self) -> &mut I {
+        &mut self.input
+    }
+
+    /// Returns the inner source.
+    #[inline]
+    pub fn into_inner(self) -> I {
+        self.input
+    }
+
+    // Reads and processes the next frame, returns false if the input has ended.
+    fn next_frame(&mut self) -> bool {
+        self.channels = self.input.channels().max(1);
+        self.sample_rate = self.input.sample_rate();
+        self.frame.clear();
+        self.frame
+            .extend(self.input.by_ref().take(self.channels as usize));
+        self.pos = 0;
+        if self.frame.is_empty() {
+            return false;
+        }
+
+        // An incomplete frame at the end is processed as if the missing channels were silent.
+        let len = self.frame.len();
+        self.frame.resize(self.channels as usize, 0.0);
+        self.apply_changes();
+        for (_, effect) in &mut self.chain {
+            effect.process(&mut self.frame, self.channels, self.sample_rate);
+        }
+        self.frame.truncate(len);
+        true
+    }
+
+    // Applies the changes sent by the handles since the last frame, without waiting.
+    fn apply_changes(&mut self) {
+        while let Ok(change) = self.changes.try_recv() {
+            match change {
+                Change::Insert(index, id, effect) => self.chain.insert(index, (id, effect)),
+                Change::Remove(id) => self.chain.retain(|(effect, _)| *effect != id),
+                Change::Move(id, index) => {
+                    if let Some(from) = self.chain.iter().position(|(effect, _)| *effect == id) {
+                        let effect = self.chain.remove(from);
+                        self.chain.insert(index, effect);
+                    }
+                }
+                Change::Clear => self.chain.clear(),
+            }
+        }
+    }
+
+    fn pending(&self) -> usize {
+        self.frame.len() - self.pos
+    }
+}
+
+impl<I> Iterator for EffectRack<I>
+where
+    I: Source<Item = f32>,
+{
+    type Item = f32;
+
+    #[inline]
+    fn next(&mut self) -> Option<f32> {
+        if self.pos >= self.frame.len() && !self.next_frame() {
+            return None;
+        }
+        let sample = self.frame[self.pos];
+        self.pos += 1;
+        Some(sample)
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let pending = self.pending();
+        let (lower, upper) = self.input.size_hint();
+        (
+            lower.saturating_add(pending),
+            upper.and_then(|upper| upper.checked_add(pending)),
+        )
+    }
+}
+
+impl<I> Source for EffectRack<I>
+where
+    I: Source<Item = f32>,
+{
+    #[inline]
+    fn current_span_len(&self) -> Option<usize> {
+        self.input
+            .current_span_len()
+            .map(|len| len + self.pending())
+    }
+
+    #[inline]
+    fn channels(&self) -> ChannelCount {
+        if self.pending() > 0 {
+            self.channels
+        } else {
+            self.input.channels()
+        }
+    }
+
+    #[inline]
+    fn sample_rate(&self) -> SampleRate {
+        if self.pending() > 0 {
+            self.sample_rate
+        } else {
+            self.input.sample_rate()
+        }
+    }
+
+    #[inline]
+    fn total_duration(&self) -> Option<Duration> {
+        self.input.total_duration()
+    }
+
+    #[inline]
+    fn tail_duration(&self) -> Duration {
+        self.input.tail_duration()
+    }
+
+    #[inline]
+    fn latency(&self) -> Duration {
+        self.input.latency()
+    }
+
+    /// Resets all effects.
+    #[inline]
+    fn try_seek(&mut self, pos: Duration) -> Result<(), SeekError> {
+        self.input.try_seek(pos)?;
+        self.frame.clear();
+        self.pos = 0;
+        self.apply_changes();
+        for (_, effect) in &mut self.chain {
+            effect.reset();
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::buffer::SamplesBuffer;
+    use crate::source::{FilterProcessor, GainProcessor, Source};
+
+    #[test]
+    fn effects_can_be_added_and_removed_while_playing() {
+        let data: Vec<f32> = (1..=12).map(|i| i as f32).collect();
+        let mut rack = SamplesBuffer::new(2, 1000, data).effect_rack();
+        let handle = rack.handle();
+
+        assert_eq!(
+            rack.by_ref().take(4).collect::<Vec<_>>(),
+            [1.0, 2.0, 3.0, 4.0]
+        );
+        let half = handle.push(GainProcessor::new(0.5));
+        assert_eq!(
+            rack.by_ref().take(4).collect::<Vec<_>>(),
+            [2.5, 3.0, 3.5, 4.0]
+        );
+        assert!(handle.remove(half));
+        assert!(!handle.remove(half));
+        assert_eq!(rack.collect::<Vec<_>>(), [9.0, 10.0, 11.0, 12.0]);
+    }
+
+    #[test]
+    fn effects_are_applied_in_order() {
+        let data: Vec<f32> = (0..400).map(|i| (i as f32 * 0.7).sin()).collect();
+        let expected: Vec<f32> = SamplesBuffer::new(1, 8000, data.clone())
+            .low_pass(500)
+            .amplify(2.0)
+            .collect();
+
+        let rack = SamplesBuffer::new(1, 8000, data).effect_rack();
+        let handle = rack.handle();
+        let gain = handle.push(GainProcessor::new(2.0));
+        let filter = handle.insert(0, FilterProcessor::low_pass(500, 0.5));
+        assert_eq!(handle.effects(), [filter, gain]);
+        for (output, expected) in rack.zip(expected) {
+            assert!((output - expected).abs() < 1e-5);
+        }
+    }
+}
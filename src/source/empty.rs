@@ -6,6 +6,8 @@ use crate::common::{ChannelCount, SampleRate};
 use crate::{Sample, Source};
 
 /// An empty source.
+///
+/// Useful as a placeholder, for example as the default of a slot that can hold a source.
 #[derive(Debug, Copy, Clone)]
 pub struct Empty<S>(PhantomData<S>);
 
@@ -32,8 +34,15 @@ impl<S> Iterator for Empty<S> {
     fn next(&mut self) -> Option<S> {
         None
     }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (0, Some(0))
+    }
 }
 
+impl<S> ExactSizeIterator for Empty<S> {}
+
 impl<S> Source for Empty<S>
 where
     S: Sample,
@@ -65,3 +74,17 @@ where
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::Empty;
+    use crate::Source;
+
+    #[test]
+    fn ends_immediately() {
+        let mut source = Empty::<f32>::new();
+        assert_eq!(source.size_hint(), (0, Some(0)));
+        assert_eq!(source.total_duration(), Some(std::time::Duration::ZERO));
+        assert_eq!(source.next(), None);
+    }
+}
@@ -95,6 +95,16 @@ where
         self.inner().total_duration()
     }
 
+    #[inline]
+    fn tail_duration(&self) -> Duration {
+        self.inner().tail_duration()
+    }
+
+    #[inline]
+    fn latency(&self) -> Duration {
+        self.inner().latency()
+    }
+
     #[inline]
     fn try_seek(&mut self, pos: Duration) -> Result<(), SeekError> {
         self.inner_mut().try_seek(pos)
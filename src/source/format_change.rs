@@ -0,0 +1,170 @@
+use std::time::Duration;
+
+use super::SeekError;
+use crate::common::{ChannelCount, SampleRate};
+use crate::{Sample, Source};
+
+/// Internal function that builds an `OnFormatChange` object.
+pub fn on_format_change<I, F>(input: I, callback: F) -> OnFormatChange<I, F>
+where
+    I: Source,
+    I::Item: Sample,
+    F: FnMut(ChannelCount, SampleRate),
+{
+    OnFormatChange {
+        channel: 0,
+        format: None,
+        input,
+        callback,
+    }
+}
+
+/// Calls a closure whenever the channel count or sample rate of a source changes and passes
+/// the samples on unchanged.
+///
+/// The format is checked at the start of every frame. The closure is called with the new
+/// channel count and sample rate right before the first sample in the new format is
+/// returned. It is not called for the format of the first frame.
+#[derive(Clone, Debug)]
+pub struct OnFormatChange<I, F> {
+    input: I,
+    callback: F,
+    channel: ChannelCount,
+    // Format of the current frame, unknown until the first one.
+    format: Option<(ChannelCount, SampleRate)>,
+}
+
+impl<I, F> OnFormatChange<I, F>
+where
+    I: Source,
+    I::Item: Sample,
+    F: FnMut(ChannelCount, SampleRate),
+{
+    /// Returns a reference to the inner source.
+    #[inline]
+    pub fn inner(&self) -> &I {
+        &self.input
+    }
+
+    /// Returns a mutable reference to the inner source.
+    #[inline]
+    pub fn inner_mut(&mut self) -> &mut I {
+        &mut self.input
+    }
+
+    /// Returns the inner source.
+    #[inline]
+    pub fn into_inner(self) -> I {
+        self.input
+    }
+
+    fn check_format(&mut self) -> ChannelCount {
+        let format = (self.input.channels(), self.input.sample_rate());
+        if self.format.is_some_and(|current| current != format) {
+            (self.callback)(format.0, format.1);
+        }
+        self.format = Some(format);
+        format.0
+    }
+}
+
+impl<I, F> Iterator for OnFormatChange<I, F>
+where
+    I: Source,
+    I::Item: Sample,
+    F: FnMut(ChannelCount, SampleRate),
+{
+    type Item = I::Item;
+
+    #[inline]
+    fn next(&mut self) -> Option<I::Item> {
+        let sample = self.input.next()?;
+        // Sources switch to a new format when its first sample is taken.
+        let channels = match self.format {
+            Some((channels, _)) if self.channel != 0 => channels,
+            _ => self.check_format(),
+        };
+        self.channel += 1;
+        if self.channel >= channels {
+            self.channel = 0;
+        }
+        Some(sample)
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.input.size_hint()
+    }
+}
+
+impl<I, F> Source for OnFormatChange<I, F>
+where
+    I: Source,
+    I::Item: Sample,
+    F: FnMut(ChannelCount, SampleRate),
+{
+    #[inline]
+    fn current_span_len(&self) -> Option<usize> {
+        self.input.current_span_len()
+    }
+
+    #[inline]
+    fn channels(&self) -> ChannelCount {
+        self.input.channels()
+    }
+
+    #[inline]
+    fn sample_rate(&self) -> SampleRate {
+        self.input.sample_rate()
+    }
+
+    #[inline]
+    fn total_duration(&self) -> Option<Duration> {
+        self.input.total_duration()
+    }
+
+    #[inline]
+    fn tail_duration(&self) -> Duration {
+        self.input.tail_duration()
+    }
+
+    #[inline]
+    fn latency(&self) -> Duration {
+        self.input.latency()
+    }
+
+    #[inline]
+    fn try_seek(&mut self, pos: Duration) -> Result<(), SeekError> {
+        self.input.try_seek(pos)?;
+        self.channel = 0;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::Cell;
+
+    use crate::buffer::SamplesBuffer;
+    use crate::queue;
+    use crate::source::Source;
+
+    #[test]
+    fn reports_rate_change() {
+        let (input, source) = queue::queue(false);
+        input.append(SamplesBuffer::new(1, 1000, vec![1.0f32; 5]));
+        input.append(SamplesBuffer::new(1, 1000, vec![2.0f32; 5]));
+        input.append(SamplesBuffer::new(2, 8000, vec![3.0f32; 4]));
+
+        let mut changes = Vec::new();
+        let played = Cell::new(0);
+        let mut source = source.on_format_change(|channels, sample_rate| {
+            changes.push((played.get(), channels, sample_rate))
+        });
+        while source.next().is_some() {
+            played.set(played.get() + 1);
+        }
+        drop(source);
+        assert_eq!(changes, [(10, 2, 8000)]);
+    }
+}
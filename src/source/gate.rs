@@ -0,0 +1,264 @@
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use super::SeekError;
+use crate::common::{ChannelCount, SampleRate};
+use crate::Source;
+
+/// Time over which the gate opens or closes, short enough to sound instant but long enough
+/// to not click.
+const RAMP: Duration = Duration::from_millis(2);
+
+/// Internal function that builds a `Gate` object.
+pub fn gate<I>(input: I, open: bool) -> Gate<I>
+where
+    I: Source<Item = f32>,
+{
+    let gain = if open { 1.0 } else { 0.0 };
+    Gate {
+        input,
+        shared: Arc::new(Shared::default()),
+        changes: Vec::new(),
+        frame: 0,
+        channel: 0,
+        gain,
+        target: gain,
+        step: 0.0,
+    }
+}
+
+/// Opens and closes a source at exact frames, for example to chop a sustained sound to the
+/// beat of a sequencer. See [`Source::gate`].
+///
+/// Changes are made with a [`GateHandle`] from [`Gate::handle`], either right away or at a
+/// frame of the clock of the gate, which counts the frames it has played. The gain ramps over
+/// 2 milliseconds starting at that frame.
+pub struct Gate<I> {
+    input: I,
+    shared: Arc<Shared>,
+    // Changes taken from `shared`, sorted by frame.
+    changes: Vec<Change>,
+    // Frames played so far and the channel of the next sample.
+    frame: u64,
+    channel: ChannelCount,
+    gain: f32,
+    target: f32,
+    // Change of the gain per frame while ramping to `target`.
+    step: f32,
+}
+
+#[derive(Clone, Copy, Debug)]
+struct Change {
+    frame: u64,
+    open: bool,
+}
+
+#[derive(Default)]
+struct Shared {
+    changes: Mutex<Vec<Change>>,
+    // Whether `changes` holds anything, so the gate only locks it when needed.
+    pending: AtomicBool,
+    frame: AtomicU64,
+}
+
+/// Opens and closes a [`Gate`]. Can be cloned and sent to another thread.
+#[derive(Clone)]
+pub struct GateHandle {
+    shared: Arc<Shared>,
+}
+
+impl GateHandle {
+    /// Opens or closes the gate at the next frame.
+    #[inline]
+    pub fn open(&self, open: bool) {
+        self.open_at(0, open);
+    }
+
+    /// Opens or closes the gate once it has played `frame` frames. Frames that have already
+    /// been played are applied at the next frame, changes at the same frame in the order
+    /// they were made.
+    pub fn open_at(&self, frame: u64, open: bool) {
+        self.shared
+            .changes
+            .lock()
+            .unwrap()
+            .push(Change { frame, open });
+        self.shared.pending.store(true, Ordering::Release);
+    }
+
+    /// Returns the number of frames the gate has played, the clock changes are scheduled on.
+    #[inline]
+    pub fn frames_played(&self) -> u64 {
+        self.shared.frame.load(Ordering::Relaxed)
+    }
+}
+
+impl<I> Gate<I>
+where
+    I: Source<Item = f32>,
+{
+    /// Returns a handle to open and close the gate from another thread.
+    #[inline]
+    pub fn handle(&self) -> GateHandle {
+        GateHandle {
+            shared: self.shared.clone(),
+        }
+    }
+
+    /// Returns a reference to the inner source.
+    #[inline]
+    pub fn inner(&self) -> &I {
+        &self.input
+    }
+
+    /// Returns a mutable reference to the inner source.
+    #[inline]
+    pub fn inner_mut(&mut self) -> &mut I {
+        &mut self.input
+    }
+
+    /// Returns the inner source.
+    #[inline]
+    pub fn into_inner(self) -> I {
+        self.input
+    }
+
+    // Applies the changes due at the frame that starts now.
+    fn start_frame(&mut self) {
+        if self.shared.pending.swap(false, Ordering::Acquire) {
+            let mut changes = self.shared.changes.lock().unwrap();
+            self.changes.append(&mut changes);
+            // Stable, so changes at the same frame keep their order.
+            self.changes.sort_by_key(|change| change.frame);
+        }
+        let due = self
+            .changes
+            .iter()
+            .take_while(|change| change.frame <= self.frame)
+            .count();
+        if let Some(change) = self.changes.drain(..due).next_back() {
+            self.target = if change.open { 1.0 } else { 0.0 };
+            let ramp = RAMP.as_secs_f32() * self.input.sample_rate() as f32;
+            self.step = (self.target - self.gain) / ramp.max(1.0);
+        }
+        if self.gain != self.target {
+            self.gain += self.step;
+            if (self.step > 0.0 && self.gain >= self.target)
+                || (self.step < 0.0 && self.gain <= self.target)
+            {
+                self.gain = self.target;
+            }
+        }
+    }
+}
+
+impl<I> Iterator for Gate<I>
+where
+    I: Source<Item = f32>,
+{
+    type Item = f32;
+
+    #[inline]
+    fn next(&mut self) -> Option<f32> {
+        if self.channel == 0 {
+            self.start_frame();
+        }
+        let sample = self.input.next()?;
+        self.channel += 1;
+        if self.channel >= self.input.channels().max(1) {
+            self.channel = 0;
+            self.frame += 1;
+            self.shared.frame.store(self.frame, Ordering::Relaxed);
+        }
+        Some(sample * self.gain)
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.input.size_hint()
+    }
+}
+
+impl<I> Source for Gate<I>
+where
+    I: Source<Item = f32>,
+{
+    #[inline]
+    fn current_span_len(&self) -> Option<usize> {
+        self.input.current_span_len()
+    }
+
+    #[inline]
+    fn channels(&self) -> ChannelCount {
+        self.input.channels()
+    }
+
+    #[inline]
+    fn sample_rate(&self) -> SampleRate {
+        self.input.sample_rate()
+    }
+
+    #[inline]
+    fn total_duration(&self) -> Option<Duration> {
+        self.input.total_duration()
+    }
+
+    #[inline]
+    fn tail_duration(&self) -> Duration {
+        self.input.tail_duration()
+    }
+
+    #[inline]
+    fn latency(&self) -> Duration {
+        self.input.latency()
+    }
+
+    /// The clock of the gate keeps running, it counts the frames played and not the position
+    /// in the source.
+    #[inline]
+    fn try_seek(&mut self, pos: Duration) -> Result<(), SeekError> {
+        self.input.try_seek(pos)?;
+        self.channel = 0;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::buffer::SamplesBuffer;
+    use crate::source::Source;
+
+    #[test]
+    fn closed_periods_are_silent_with_ramped_edges() {
+        // at 10 kHz the ramp takes 20 frames
+        let gate = SamplesBuffer::new(1, 10_000, vec![1.0f32; 2000]).gate(true);
+        let handle = gate.handle();
+        handle.open_at(1000, true);
+        handle.open_at(500, false);
+        let output: Vec<f32> = gate.collect();
+
+        assert!(output[..500].iter().all(|&s| s == 1.0));
+        let closing = &output[500..520];
+        assert!(closing.windows(2).all(|w| w[1] < w[0]));
+        assert!(closing[0] < 1.0 && closing[0] > 0.9);
+        assert!(output[520..1000].iter().all(|&s| s == 0.0));
+        let opening = &output[1000..1020];
+        assert!(opening.windows(2).all(|w| w[1] > w[0]));
+        assert!(opening[0] > 0.0 && opening[0] < 0.1);
+        assert!(output[1020..].iter().all(|&s| s == 1.0));
+    }
+
+    #[test]
+    fn stereo_frames_are_gated_together() {
+        let mut gate = SamplesBuffer::new(2, 1000, vec![1.0f32; 40]).gate(false);
+        let handle = gate.handle();
+        assert_eq!(gate.by_ref().take(4).collect::<Vec<_>>(), [0.0; 4]);
+        assert_eq!(handle.frames_played(), 2);
+
+        // at 1 kHz the ramp takes 2 frames
+        handle.open(true);
+        let output: Vec<f32> = gate.take(6).collect();
+        assert_eq!(output, [0.5, 0.5, 1.0, 1.0, 1.0, 1.0]);
+    }
+}
@@ -0,0 +1,134 @@
+use std::time::Duration;
+
+use super::SeekError;
+use crate::common::{ChannelCount, SampleRate};
+use crate::{Sample, Source};
+
+/// Internal function that builds an `InspectSamples` object.
+pub fn inspect_samples<I, F>(input: I, inspect: F) -> InspectSamples<I, F>
+where
+    I: Source,
+    I::Item: Sample,
+    F: FnMut(&I::Item),
+{
+    InspectSamples { input, inspect }
+}
+
+/// Calls a closure for every sample of a source and passes the samples on unchanged.
+#[derive(Clone, Debug)]
+pub struct InspectSamples<I, F> {
+    input: I,
+    inspect: F,
+}
+
+impl<I, F> InspectSamples<I, F>
+where
+    I: Source,
+    I::Item: Sample,
+    F: FnMut(&I::Item),
+{
+    /// Returns a reference to the inner source.
+    #[inline]
+    pub fn inner(&self) -> &I {
+        &self.input
+    }
+
+    /// Returns a mutable reference to the inner source.
+    #[inline]
+    pub fn inner_mut(&mut self) -> &mut I {
+        &mut self.input
+    }
+
+    /// Returns the inner source.
+    #[inline]
+    pub fn into_inner(self) -> I {
+        self.input
+    }
+}
+
+impl<I, F> Iterator for InspectSamples<I, F>
+where
+    I: Source,
+    I::Item: Sample,
+    F: FnMut(&I::Item),
+{
+    type Item = I::Item;
+
+    #[inline]
+    fn next(&mut self) -> Option<I::Item> {
+        let sample = self.input.next()?;
+        (self.inspect)(&sample);
+        Some(sample)
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.input.size_hint()
+    }
+}
+
+impl<I, F> Source for InspectSamples<I, F>
+where
+    I: Source,
+    I::Item: Sample,
+    F: FnMut(&I::Item),
+{
+    #[inline]
+    fn current_span_len(&self) -> Option<usize> {
+        self.input.current_span_len()
+    }
+
+    #[inline]
+    fn channels(&self) -> ChannelCount {
+        self.input.channels()
+    }
+
+    #[inline]
+    fn sample_rate(&self) -> SampleRate {
+        self.input.sample_rate()
+    }
+
+    #[inline]
+    fn total_duration(&self) -> Option<Duration> {
+        self.input.total_duration()
+    }
+
+    #[inline]
+    fn tail_duration(&self) -> Duration {
+        self.input.tail_duration()
+    }
+
+    #[inline]
+    fn latency(&self) -> Duration {
+        self.input.latency()
+    }
+
+    #[inline]
+    fn try_seek(&mut self, pos: Duration) -> Result<(), SeekError> {
+        self.input.try_seek(pos)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::buffer::SamplesBuffer;
+    use crate::source::Source;
+
+    #[test]
+    fn called_once_per_sample() {
+        let mut calls = 0;
+        let mut sum = 0;
+        let source = SamplesBuffer::new(2, 44100, vec![1i16, 2, 3, 4, 5, 6]);
+
+        let output: Vec<i16> = source
+            .inspect_samples(|sample| {
+                calls += 1;
+                sum += *sample;
+            })
+            .collect();
+
+        assert_eq!(output, [1, 2, 3, 4, 5, 6]);
+        assert_eq!(calls, 6);
+        assert_eq!(sum, 21);
+    }
+}
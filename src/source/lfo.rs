@@ -0,0 +1,258 @@
+use std::f64::consts::TAU;
+use std::time::Duration;
+
+use super::SeekError;
+use crate::common::{ChannelCount, SampleRate};
+use crate::Source;
+
+/// The waveform of an [`Lfo`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum LfoShape {
+    /// A sine wave, starting at zero and rising.
+    #[default]
+    Sine,
+    /// A triangle wave, starting at zero and rising like the sine.
+    Triangle,
+    /// The maximum for the first half of every cycle and the minimum for the second half.
+    Square,
+    /// Rises from the minimum to the maximum over every cycle.
+    SawUp,
+    /// Falls from the maximum to the minimum over every cycle.
+    SawDown,
+    /// Holds a random value for every cycle.
+    SampleAndHold,
+}
+
+/// An infinite low frequency oscillator, a control signal to route to effects such as
+/// [`modulate_amplitude`](Source::modulate_amplitude). Has one channel.
+///
+/// The output goes from `-1.0` to `1.0`, or from `0.0` to `1.0` after
+/// [`unipolar`](Lfo::unipolar). The rate is independent of the sample rate, which only sets
+/// how finely the waveform is sampled, so the same settings sound the same at any sample
+/// rate. The random values of [`LfoShape::SampleAndHold`] only depend on the cycle, seeking
+/// back plays the same values again.
+///
+/// # Example
+///
+/// ```
+/// use rodio::source::{Lfo, LfoShape, SineWave, Source};
+///
+/// // a choppy tremolo, four times a second
+/// let lfo = Lfo::new(LfoShape::Square, 4.0, 1000).unipolar();
+/// let tremolo = SineWave::new(440.0).modulate_amplitude(lfo);
+/// ```
+#[derive(Clone, Debug)]
+pub struct Lfo {
+    shape: LfoShape,
+    rate_hz: f32,
+    sample_rate: SampleRate,
+    unipolar: bool,
+    // Position within the current cycle, `0.0..1.0`, and the number of cycles before it.
+    phase: f64,
+    cycle: u64,
+}
+
+impl Lfo {
+    /// Builds an oscillator with the given shape that completes `rate_hz` cycles per second,
+    /// sampled at `sample_rate`.
+    ///
+    /// # Panics
+    ///
+    /// If `sample_rate` is zero.
+    pub fn new(shape: LfoShape, rate_hz: f32, sample_rate: SampleRate) -> Lfo {
+        assert!(sample_rate > 0, "sample rate must be greater than zero");
+        Lfo {
+            shape,
+            rate_hz,
+            sample_rate,
+            unipolar: false,
+            phase: 0.0,
+            cycle: 0,
+        }
+    }
+
+    /// Maps the output onto `0.0..=1.0` instead of `-1.0..=1.0`, the range of a gain.
+    #[inline]
+    pub fn unipolar(mut self) -> Lfo {
+        self.unipolar = true;
+        self
+    }
+
+    /// Changes the cycles per second, the oscillator continues from where it is.
+    #[inline]
+    pub fn set_rate(&mut self, rate_hz: f32) {
+        self.rate_hz = rate_hz;
+    }
+
+    /// Returns the cycles per second.
+    #[inline]
+    pub fn rate(&self) -> f32 {
+        self.rate_hz
+    }
+
+    /// Changes the waveform, the oscillator continues from where it is.
+    #[inline]
+    pub fn set_shape(&mut self, shape: LfoShape) {
+        self.shape = shape;
+    }
+
+    /// Returns the waveform.
+    #[inline]
+    pub fn shape(&self) -> LfoShape {
+        self.shape
+    }
+
+    fn value(&self) -> f64 {
+        let phase = self.phase;
+        match self.shape {
+            LfoShape::Sine => (TAU * phase).sin(),
+            LfoShape::Triangle => {
+                if phase < 0.25 {
+                    4.0 * phase
+                } else if phase < 0.75 {
+                    2.0 - 4.0 * phase
+                } else {
+                    4.0 * phase - 4.0
+                }
+            }
+            LfoShape::Square => {
+                if phase < 0.5 {
+                    1.0
+                } else {
+                    -1.0
+                }
+            }
+            LfoShape::SawUp => 2.0 * phase - 1.0,
+            LfoShape::SawDown => 1.0 - 2.0 * phase,
+            LfoShape::SampleAndHold => {
+                // splitmix64 of the cycle, the top 53 bits mapped to -1.0..1.0
+                let mut z = self.cycle.wrapping_add(0x9e37_79b9_7f4a_7c15);
+                z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+                z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+                z ^= z >> 31;
+                (z >> 11) as f64 / (1u64 << 52) as f64 - 1.0
+            }
+        }
+    }
+}
+
+impl Iterator for Lfo {
+    type Item = f32;
+
+    #[inline]
+    fn next(&mut self) -> Option<f32> {
+        let value = self.value();
+        self.phase += self.rate_hz as f64 / self.sample_rate as f64;
+        if !(0.0..1.0).contains(&self.phase) {
+            let cycles = self.phase.floor();
+            self.cycle = self.cycle.wrapping_add_signed(cycles as i64);
+            self.phase -= cycles;
+        }
+        let value = if self.unipolar {
+            (value + 1.0) / 2.0
+        } else {
+            value
+        };
+        Some(value as f32)
+    }
+}
+
+impl Source for Lfo {
+    #[inline]
+    fn current_span_len(&self) -> Option<usize> {
+        None
+    }
+
+    #[inline]
+    fn channels(&self) -> ChannelCount {
+        1
+    }
+
+    #[inline]
+    fn sample_rate(&self) -> SampleRate {
+        self.sample_rate
+    }
+
+    #[inline]
+    fn total_duration(&self) -> Option<Duration> {
+        None
+    }
+
+    /// Moves to the position of the oscillator `pos` after it started, as if the rate had
+    /// always been the current one.
+    #[inline]
+    fn try_seek(&mut self, pos: Duration) -> Result<(), SeekError> {
+        let cycles = pos.as_secs_f64() * self.rate_hz as f64;
+        self.cycle = cycles.floor() as u64;
+        self.phase = cycles - cycles.floor();
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::{Lfo, LfoShape};
+    use crate::Source;
+
+    // One cycle of `shape` at 1 Hz sampled 8 times.
+    fn cycle(shape: LfoShape) -> Vec<f32> {
+        Lfo::new(shape, 1.0, 8).take(8).collect()
+    }
+
+    fn assert_close(actual: &[f32], expected: &[f32]) {
+        assert_eq!(actual.len(), expected.len());
+        for (a, e) in actual.iter().zip(expected) {
+            assert!((a - e).abs() < 1e-6, "{actual:?} != {expected:?}");
+        }
+    }
+
+    #[test]
+    fn shapes_follow_their_waveform() {
+        let h = std::f32::consts::FRAC_1_SQRT_2;
+        assert_close(&cycle(LfoShape::Sine), &[0.0, h, 1.0, h, 0.0, -h, -1.0, -h]);
+        assert_close(
+            &cycle(LfoShape::Triangle),
+            &[0.0, 0.5, 1.0, 0.5, 0.0, -0.5, -1.0, -0.5],
+        );
+        assert_close(
+            &cycle(LfoShape::Square),
+            &[1.0, 1.0, 1.0, 1.0, -1.0, -1.0, -1.0, -1.0],
+        );
+        assert_close(
+            &cycle(LfoShape::SawUp),
+            &[-1.0, -0.75, -0.5, -0.25, 0.0, 0.25, 0.5, 0.75],
+        );
+        assert_close(
+            &cycle(LfoShape::SawDown),
+            &[1.0, 0.75, 0.5, 0.25, 0.0, -0.25, -0.5, -0.75],
+        );
+
+        // twice the rate at twice the sample rate gives the same samples
+        let fast: Vec<f32> = Lfo::new(LfoShape::SawUp, 2.0, 16).take(8).collect();
+        assert_close(&fast, &cycle(LfoShape::SawUp));
+
+        let unipolar: Vec<f32> = Lfo::new(LfoShape::Square, 1.0, 8)
+            .unipolar()
+            .take(8)
+            .collect();
+        assert_close(&unipolar, &[1.0, 1.0, 1.0, 1.0, 0.0, 0.0, 0.0, 0.0]);
+    }
+
+    #[test]
+    fn sample_and_hold_holds_for_a_cycle() {
+        let mut lfo = Lfo::new(LfoShape::SampleAndHold, 2.0, 1000);
+        let output: Vec<f32> = lfo.by_ref().take(2000).collect();
+        let held: Vec<f32> = output.chunks(500).map(|cycle| cycle[0]).collect();
+        for (cycle, value) in output.chunks(500).zip(&held) {
+            assert!(cycle.iter().all(|s| s == value));
+            assert!((-1.0..=1.0).contains(value));
+        }
+        assert!(held.windows(2).all(|w| w[0] != w[1]));
+
+        // seeking back plays the same values again
+        lfo.try_seek(Duration::from_millis(500)).unwrap();
+        assert_eq!(lfo.next(), Some(held[1]));
+    }
+}
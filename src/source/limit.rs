@@ -0,0 +1,369 @@
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use super::SeekError;
+use crate::common::{ChannelCount, SampleRate};
+use crate::{Sample, Source};
+
+/// Look-ahead used unless [`Limit::with_lookahead`] is called.
+const DEFAULT_LOOKAHEAD: Duration = Duration::from_millis(5);
+
+/// Time it takes the gain to recover most of the way after a peak.
+const RELEASE: Duration = Duration::from_millis(50);
+
+/// Internal function that builds a `Limit` object.
+pub fn limit<I>(input: I, threshold: f32) -> Limit<I>
+where
+    I: Source,
+    I::Item: Sample,
+{
+    let channels = input.channels().max(1);
+    let sample_rate = input.sample_rate().max(1);
+    let release_frames = RELEASE.as_secs_f32() * sample_rate as f32;
+    let mut limit = Limit {
+        input,
+        threshold,
+        channels,
+        sample_rate,
+        lookahead: 0,
+        release_coefficient: 1.0 - (-1.0 / release_frames).exp(),
+        delay: VecDeque::new(),
+        frames_left: 0,
+        minimum: VecDeque::new(),
+        history: VecDeque::new(),
+        history_sum: 0.0,
+        frame: 0,
+        gain: 1.0,
+        channel: 0,
+        meter: GainReductionMeter {
+            gain: Arc::new(AtomicU32::new(1.0f32.to_bits())),
+        },
+    };
+    limit.set_lookahead(DEFAULT_LOOKAHEAD);
+    limit
+}
+
+/// Peak limiter that keeps every sample within a threshold without clipping.
+///
+/// The limiter looks ahead to lower the gain smoothly before a peak arrives, so that
+/// transients shorter than the look-ahead are caught too. This delays the whole signal by
+/// the look-ahead, which is 5 milliseconds by default, see
+/// [`with_lookahead`](Limit::with_lookahead). A longer look-ahead gives smoother gain changes
+/// and catches transients more accurately at the cost of latency.
+///
+/// All channels share the same gain so that the stereo image is kept. The channel count and
+/// sample rate of the source at the time this was created are used throughout.
+#[derive(Clone, Debug)]
+pub struct Limit<I>
+where
+    I: Source,
+    I::Item: Sample,
+{
+    input: I,
+    threshold: f32,
+    channels: ChannelCount,
+    sample_rate: SampleRate,
+    // Look-ahead in frames, at least one.
+    lookahead: usize,
+    release_coefficient: f32,
+    // Samples that are delayed by the look-ahead.
+    delay: VecDeque<I::Item>,
+    // Frames of the input that are still to be played, counted once the input ended.
+    frames_left: usize,
+    // Sliding minimum of the gain each frame needs over the look-ahead, as index and gain.
+    minimum: VecDeque<(u64, f32)>,
+    // Last `lookahead` values of that minimum, averaged to smooth out the gain changes.
+    history: VecDeque<f32>,
+    history_sum: f64,
+    frame: u64,
+    // Gain applied to the current output frame.
+    gain: f32,
+    channel: ChannelCount,
+    meter: GainReductionMeter,
+}
+
+/// Reads the current gain reduction of a [`Limit`], for example to show it in a meter.
+///
+/// Can be cloned and sent to another thread, get one with [`Limit::meter`].
+#[derive(Clone, Debug)]
+pub struct GainReductionMeter {
+    gain: Arc<AtomicU32>,
+}
+
+impl GainReductionMeter {
+    /// Returns by how many decibels the signal is currently lowered, zero if it is not.
+    #[inline]
+    pub fn decibels(&self) -> f32 {
+        let gain = f32::from_bits(self.gain.load(Ordering::Relaxed));
+        (-20.0 * gain.log10()).max(0.0)
+    }
+}
+
+impl<I> Limit<I>
+where
+    I: Source,
+    I::Item: Sample,
+{
+    /// Sets how far ahead the limiter looks for peaks. The whole signal is delayed by this
+    /// amount.
+    ///
+    /// Meant to be called before playback starts, audio that was already delayed is lost.
+    #[inline]
+    pub fn with_lookahead(mut self, lookahead: Duration) -> Self {
+        self.set_lookahead(lookahead);
+        self
+    }
+
+    /// Returns a handle to read the gain reduction from another thread.
+    #[inline]
+    pub fn meter(&self) -> GainReductionMeter {
+        self.meter.clone()
+    }
+
+    /// Returns a reference to the inner source.
+    #[inline]
+    pub fn inner(&self) -> &I {
+        &self.input
+    }
+
+    /// Returns a mutable reference to the inner source.
+    #[inline]
+    pub fn inner_mut(&mut self) -> &mut I {
+        &mut self.input
+    }
+
+    /// Returns the inner source.
+    #[inline]
+    pub fn into_inner(self) -> I {
+        self.input
+    }
+
+    fn set_lookahead(&mut self, lookahead: Duration) {
+        let frames = (lookahead.as_secs_f64() * self.sample_rate as f64).round() as usize;
+        self.lookahead = frames.max(1);
+        self.reset();
+    }
+
+    fn reset(&mut self) {
+        // Silence fills the delay until the first frame of the input comes out of it.
+        let delayed = (self.lookahead - 1) * self.channels as usize;
+        self.delay.clear();
+        self.delay.resize(delayed, I::Item::ZERO_VALUE);
+        self.frames_left = usize::MAX;
+        self.minimum.clear();
+        self.history.clear();
+        self.history.resize(self.lookahead, 1.0);
+        self.history_sum = self.lookahead as f64;
+        self.gain = 1.0;
+        self.channel = 0;
+    }
+
+    // Reads the next frame of the input into the delay and works out the gain for the frame
+    // that comes out of it. Returns false once everything has been played.
+    fn next_frame(&mut self) -> bool {
+        if self.frames_left == usize::MAX {
+            let mut peak = Some(0.0f32);
+            for read in 0..self.channels {
+                match self.input.next() {
+                    Some(sample) => {
+                        peak = peak.map(|peak| peak.max(sample.to_f32().abs()));
+                        self.delay.push_back(sample);
+                    }
+                    None => {
+                        // An incomplete frame is dropped.
+                        self.delay.truncate(self.delay.len() - read as usize);
+                        self.frames_left = self.lookahead - 1;
+                        peak = None;
+                        break;
+                    }
+                }
+            }
+            match peak {
+                Some(peak) if peak > self.threshold => {
+                    self.push_required_gain(self.threshold / peak)
+                }
+                Some(_) => self.push_required_gain(1.0),
+                None => (),
+            }
+        }
+        if self.frames_left != usize::MAX {
+            if self.frames_left == 0 {
+                return false;
+            }
+            self.frames_left -= 1;
+            self.push_required_gain(1.0);
+        }
+
+        // Every value averaged here is the minimum over a window that contains the frame that
+        // is about to be played, so the gain never exceeds what that frame needs.
+        let smoothed = (self.history_sum / self.lookahead as f64) as f32;
+        let released = self.gain + (1.0 - self.gain) * self.release_coefficient;
+        self.gain = smoothed.min(released);
+        self.meter
+            .gain
+            .store(self.gain.to_bits(), Ordering::Relaxed);
+        true
+    }
+
+    fn push_required_gain(&mut self, required: f32) {
+        let frame = self.frame;
+        self.frame += 1;
+        while self
+            .minimum
+            .back()
+            .is_some_and(|&(_, gain)| gain >= required)
+        {
+            self.minimum.pop_back();
+        }
+        self.minimum.push_back((frame, required));
+        while self
+            .minimum
+            .front()
+            .is_some_and(|&(start, _)| start + (self.lookahead as u64) <= frame)
+        {
+            self.minimum.pop_front();
+        }
+        let minimum = self.minimum.front().map_or(1.0, |&(_, gain)| gain);
+
+        self.history_sum += minimum as f64 - self.history.pop_front().unwrap_or(1.0) as f64;
+        self.history.push_back(minimum);
+    }
+}
+
+impl<I> Iterator for Limit<I>
+where
+    I: Source,
+    I::Item: Sample,
+{
+    type Item = I::Item;
+
+    #[inline]
+    fn next(&mut self) -> Option<I::Item> {
+        if self.channel == 0 && !self.next_frame() {
+            return None;
+        }
+        self.channel += 1;
+        if self.channel >= self.channels {
+            self.channel = 0;
+        }
+        self.delay
+            .pop_front()
+            .map(|sample| sample.amplify(self.gain))
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let (lower, upper) = self.input.size_hint();
+        let delayed = self.delay.len();
+        (
+            lower.saturating_add(delayed),
+            upper.and_then(|upper| upper.checked_add(delayed)),
+        )
+    }
+}
+
+impl<I> Source for Limit<I>
+where
+    I: Source,
+    I::Item: Sample,
+{
+    #[inline]
+    fn current_span_len(&self) -> Option<usize> {
+        None
+    }
+
+    #[inline]
+    fn channels(&self) -> ChannelCount {
+        self.channels
+    }
+
+    #[inline]
+    fn sample_rate(&self) -> SampleRate {
+        self.sample_rate
+    }
+
+    #[inline]
+    fn total_duration(&self) -> Option<Duration> {
+        let delay = Duration::from_secs_f64((self.lookahead - 1) as f64 / self.sample_rate as f64);
+        self.input.total_duration().map(|total| total + delay)
+    }
+
+    #[inline]
+    fn tail_duration(&self) -> Duration {
+        self.input.tail_duration()
+    }
+
+    #[inline]
+    fn latency(&self) -> Duration {
+        let delay = Duration::from_secs_f64((self.lookahead - 1) as f64 / self.sample_rate as f64);
+        self.input.latency() + delay
+    }
+
+    /// Clears the look-ahead, playback resumes after the usual delay.
+    #[inline]
+    fn try_seek(&mut self, pos: Duration) -> Result<(), SeekError> {
+        self.input.try_seek(pos)?;
+        self.reset();
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use crate::buffer::SamplesBuffer;
+    use crate::source::Source;
+
+    #[test]
+    fn catches_transient_without_overshoot() {
+        // a quiet signal with a single loud click, 1 kHz stereo
+        let mut data = vec![0.2f32; 2000];
+        data[200] = 1.0;
+        data[201] = -0.9;
+        let limited = SamplesBuffer::new(2, 1000, data).limit(0.5);
+        let meter = limited.meter();
+        let limited = limited.with_lookahead(Duration::from_millis(10));
+
+        let output: Vec<f32> = limited.collect();
+        assert_eq!(output.len(), 2000 + 9 * 2);
+        assert!(output.iter().all(|s| s.abs() <= 0.5 + 1e-6));
+        // the click comes out 9 frames late, lowered just enough
+        assert!((output[218] - 0.5).abs() < 1e-6);
+        assert!((output[219] + 0.45).abs() < 1e-6);
+        // far from the click the signal is untouched
+        assert_eq!(output[18..40], [0.2; 22]);
+        // and recovers after it
+        assert!((output.last().unwrap() - 0.2).abs() < 0.01);
+        assert!(meter.decibels() < 0.5);
+    }
+
+    #[test]
+    fn meter_reports_gain_reduction() {
+        let limited = SamplesBuffer::new(1, 1000, vec![1.0f32; 100]).limit(0.5);
+        let meter = limited.meter();
+        let output: Vec<f32> = limited.take(80).collect();
+        assert!((output[79] - 0.5).abs() < 1e-6);
+        assert!((meter.decibels() - 6.02).abs() < 0.01);
+    }
+
+    #[test]
+    fn latency_passes_through_effects() {
+        let limited = SamplesBuffer::new(2, 1000, vec![0.2f32; 2000])
+            .limit(0.5)
+            .with_lookahead(Duration::from_millis(10));
+        let delay = Duration::from_millis(9);
+        assert_eq!(limited.latency(), delay);
+
+        let source = limited
+            .low_pass(200)
+            .fade_in(Duration::from_millis(5))
+            .skip_duration(Duration::from_millis(1))
+            .take_duration(Duration::from_millis(500))
+            .buffered();
+        assert_eq!(source.latency(), delay);
+        assert_eq!(source.clone().mix(source).latency(), delay);
+    }
+}
@@ -0,0 +1,481 @@
+use std::time::Duration;
+
+use super::zero_crossing::{is_crossing, splice_point};
+use super::SeekError;
+use crate::common::{frames_to_duration, ChannelCount, SampleRate};
+use crate::{Sample, Source};
+
+const NS_PER_SECOND: u128 = 1_000_000_000;
+
+/// Internal function that builds a `LoopRegion` object.
+pub fn loop_region<I>(input: I, start: Duration, end: Duration) -> LoopRegion<I>
+where
+    I: Source,
+    I::Item: Sample,
+{
+    LoopRegion {
+        input,
+        start,
+        end,
+        offset: Duration::ZERO,
+        frames: 0,
+        sample_rate: 0,
+        channel: 0,
+        loops: 0,
+        fork: None,
+        tails: Vec::new(),
+        zero_crossing: None,
+        lead: Vec::new(),
+        lead_pos: 0,
+        frame_sum: 0.0,
+        previous_frame: None,
+        crossed: false,
+    }
+}
+
+/// Plays a source normally until it reaches a given end position, then seeks back to a given
+/// start position and repeats the part in between indefinitely.
+///
+/// The end is checked at every frame so the loop is sample accurate, apart from the precision
+/// of the seek implementation of the input. If `end` is not after `start`, or if the input
+/// fails to seek, the source simply plays on. See
+/// [`with_tail_overlap`](LoopRegion::with_tail_overlap) to let an effect ring on after a jump
+/// and [`with_zero_crossing`](LoopRegion::with_zero_crossing) to jump without a click.
+#[derive(Clone, Debug)]
+pub struct LoopRegion<I>
+where
+    I: Source,
+    I::Item: Sample,
+{
+    input: I,
+    start: Duration,
+    end: Duration,
+    // Position of the input at the last seek or sample rate change.
+    offset: Duration,
+    // Frames played since `offset`.
+    frames: u64,
+    // Sample rate the frames were counted at.
+    sample_rate: SampleRate,
+    channel: ChannelCount,
+    loops: u64,
+    // Copies the input to play its tail after a jump, if the tail overlaps.
+    fork: Option<fn(&I) -> I>,
+    // Copies of the input that play their tail after a jump, with the samples left to play.
+    tails: Vec<(I, usize)>,
+    // Window to look for zero crossings in at both ends of the region, if any.
+    zero_crossing: Option<Duration>,
+    // Samples read ahead from the start of the region to find a zero crossing, played before
+    // reading from the input again.
+    lead: Vec<I::Item>,
+    lead_pos: usize,
+    // Sum of the samples of the current frame across channels, the sum of the previous frame
+    // and whether the signal crossed zero between the two frames before that.
+    frame_sum: f32,
+    previous_frame: Option<f32>,
+    crossed: bool,
+}
+
+impl<I> LoopRegion<I>
+where
+    I: Source + Clone,
+    I::Item: Sample,
+{
+    /// Plays the tail of the input, as reported by [`Source::tail_duration`], over the start
+    /// of the region after every jump back instead of cutting it off. The tail is added to
+    /// the samples.
+    ///
+    /// Meant for regions that end where the effect tail of the input starts, for example
+    /// where the dry signal of an echo ends. Whatever follows `end` in the input is played for
+    /// the length of the tail. Needs a copy of the input for every tail that is still ringing.
+    pub fn with_tail_overlap(mut self, overlap: bool) -> Self {
+        self.fork = if overlap { Some(I::clone) } else { None };
+        self
+    }
+}
+
+impl<I> LoopRegion<I>
+where
+    I: Source,
+    I::Item: Sample,
+{
+    /// Moves both ends of every jump to a zero crossing, which avoids a click where the end of
+    /// the region meets its start. `None`, the default, jumps at the exact positions.
+    ///
+    /// Playback continues past `end` until the signal crosses zero and drops the start of the
+    /// region up to its first zero crossing after `start`, both within `window`. So the end of
+    /// the region comes at most `window` late, and after every jump up to `window` of the
+    /// input is read ahead to find the crossing. Channels are mixed down to mono to find the
+    /// crossings.
+    pub fn with_zero_crossing(mut self, window: Option<Duration>) -> Self {
+        self.zero_crossing = window;
+        self
+    }
+
+    /// Changes the region that is looped. It takes effect immediately, so if the current
+    /// position is already past `end` playback jumps back to `start` right away.
+    #[inline]
+    pub fn set_region(&mut self, start: Duration, end: Duration) {
+        self.start = start;
+        self.end = end;
+    }
+
+    /// Stops looping, the source continues to play from where it is.
+    #[inline]
+    pub fn clear_region(&mut self) {
+        self.set_region(Duration::ZERO, Duration::ZERO);
+    }
+
+    /// Returns true if a region to loop is set.
+    #[inline]
+    pub fn is_looping(&self) -> bool {
+        self.end > self.start
+    }
+
+    /// Returns the position in the input, taking the jumps back to the start of the region
+    /// into account.
+    #[inline]
+    pub fn get_pos(&self) -> Duration {
+        if self.frames == 0 {
+            return self.offset;
+        }
+        let nanos = self.frames as u128 * NS_PER_SECOND / self.sample_rate.max(1) as u128;
+        self.offset + Duration::from_nanos(nanos as u64)
+    }
+
+    /// Returns how many times playback jumped back to the start of the region.
+    #[inline]
+    pub fn loop_count(&self) -> u64 {
+        self.loops
+    }
+
+    /// Returns a reference to the inner source.
+    #[inline]
+    pub fn inner(&self) -> &I {
+        &self.input
+    }
+
+    /// Returns a mutable reference to the inner source.
+    #[inline]
+    pub fn inner_mut(&mut self) -> &mut I {
+        &mut self.input
+    }
+
+    /// Returns the inner source.
+    #[inline]
+    pub fn into_inner(self) -> I {
+        self.input
+    }
+
+    // Whether the end of the region is reached, at a zero crossing if they are looked for.
+    fn at_end(&self) -> bool {
+        if !self.is_looping() {
+            return false;
+        }
+        let pos = self.get_pos();
+        match self.zero_crossing {
+            None => pos >= self.end,
+            Some(window) => pos >= self.end && (self.crossed || pos >= self.end + window),
+        }
+    }
+
+    fn jump_to_start(&mut self) {
+        let ringing = self.fork.and_then(|fork| {
+            let channels = self.input.channels() as f64;
+            let frames = self.input.tail_duration().as_secs_f64() * self.input.sample_rate() as f64;
+            let len = (frames.round() * channels) as usize;
+            (len > 0).then(|| (fork(&self.input), len))
+        });
+        if self.input.try_seek(self.start).is_ok() {
+            self.tails.extend(ringing);
+            self.loops += 1;
+            self.reset_position(self.start);
+            if let Some(window) = self.zero_crossing {
+                self.find_start_crossing(window);
+            }
+        } else {
+            self.clear_region();
+        }
+    }
+
+    // Reads up to `window` ahead and drops the samples before the first zero crossing.
+    fn find_start_crossing(&mut self, window: Duration) {
+        let channels = self.input.channels().max(1) as usize;
+        let max_frames = (window.as_secs_f64() * self.input.sample_rate() as f64) as usize;
+        // One frame more than the window so a crossing right at its end can be found.
+        self.lead
+            .extend(self.input.by_ref().take((max_frames + 1) * channels));
+        let start = splice_point(&self.lead, channels);
+        self.lead_pos = start * channels;
+        self.frames = start as u64;
+    }
+
+    #[inline]
+    fn reset_position(&mut self, pos: Duration) {
+        self.offset = pos;
+        self.frames = 0;
+        self.channel = 0;
+        self.lead.clear();
+        self.lead_pos = 0;
+        self.frame_sum = 0.0;
+        self.previous_frame = None;
+        self.crossed = false;
+    }
+
+    #[inline]
+    fn next_input(&mut self) -> Option<I::Item> {
+        if let Some(sample) = self.lead.get(self.lead_pos) {
+            self.lead_pos += 1;
+            return Some(*sample);
+        }
+        self.input.next()
+    }
+}
+
+impl<I> Iterator for LoopRegion<I>
+where
+    I: Source,
+    I::Item: Sample,
+{
+    type Item = I::Item;
+
+    #[inline]
+    fn next(&mut self) -> Option<I::Item> {
+        if self.channel == 0 {
+            let sample_rate = self.input.sample_rate();
+            if sample_rate != self.sample_rate {
+                let pos = self.get_pos();
+                self.reset_position(pos);
+                self.sample_rate = sample_rate;
+            }
+            if self.at_end() {
+                self.jump_to_start();
+            }
+        }
+
+        let mut sample = self.next_input()?;
+        if !self.tails.is_empty() {
+            self.tails.retain_mut(|(tail, left)| match tail.next() {
+                Some(value) => {
+                    sample = sample.saturating_add(value);
+                    *left -= 1;
+                    *left > 0
+                }
+                None => false,
+            });
+        }
+        if self.zero_crossing.is_some() {
+            self.frame_sum += sample.to_f32();
+        }
+        self.channel += 1;
+        if self.channel >= self.input.channels() {
+            self.channel = 0;
+            self.frames += 1;
+            if self.zero_crossing.is_some() {
+                let frame = std::mem::take(&mut self.frame_sum);
+                self.crossed = self
+                    .previous_frame
+                    .is_some_and(|previous| is_crossing(previous, frame));
+                self.previous_frame = Some(frame);
+            }
+        }
+        Some(sample)
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let lead = self.lead.len() - self.lead_pos.min(self.lead.len());
+        let (lower, upper) = self.input.size_hint();
+        if self.is_looping() {
+            (lower + lead, None)
+        } else {
+            (lower + lead, upper.map(|upper| upper + lead))
+        }
+    }
+}
+
+impl<I> Source for LoopRegion<I>
+where
+    I: Source,
+    I::Item: Sample,
+{
+    #[inline]
+    fn current_span_len(&self) -> Option<usize> {
+        // A jump can happen in the middle of a span of the input.
+        if self.is_looping() || self.lead_pos < self.lead.len() {
+            None
+        } else {
+            self.input.current_span_len()
+        }
+    }
+
+    #[inline]
+    fn channels(&self) -> ChannelCount {
+        self.input.channels()
+    }
+
+    #[inline]
+    fn sample_rate(&self) -> SampleRate {
+        self.input.sample_rate()
+    }
+
+    #[inline]
+    fn total_duration(&self) -> Option<Duration> {
+        if self.is_looping() {
+            None
+        } else {
+            self.input.total_duration()
+        }
+    }
+
+    #[inline]
+    fn tail_duration(&self) -> Duration {
+        if self.is_looping() {
+            Duration::ZERO
+        } else {
+            self.input.tail_duration()
+        }
+    }
+
+    #[inline]
+    fn latency(&self) -> Duration {
+        self.input.latency()
+    }
+
+    #[inline]
+    fn try_seek(&mut self, pos: Duration) -> Result<(), SeekError> {
+        self.input.try_seek(pos)?;
+        self.reset_position(pos);
+        self.tails.clear();
+        Ok(())
+    }
+
+    #[inline]
+    fn try_seek_frames(&mut self, frame: u64) -> Result<(), SeekError> {
+        self.input.try_seek_frames(frame)?;
+        self.reset_position(frames_to_duration(frame, self.input.sample_rate()));
+        self.tails.clear();
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use crate::buffer::SamplesBuffer;
+    use crate::common::{ChannelCount, SampleRate};
+    use crate::source::{SeekError, Source};
+
+    // A click with an echo that reports the echo as its tail.
+    #[derive(Clone)]
+    struct Echo(SamplesBuffer<f32>);
+
+    impl Iterator for Echo {
+        type Item = f32;
+
+        fn next(&mut self) -> Option<f32> {
+            self.0.next()
+        }
+    }
+
+    impl Source for Echo {
+        fn current_span_len(&self) -> Option<usize> {
+            self.0.current_span_len()
+        }
+
+        fn channels(&self) -> ChannelCount {
+            self.0.channels()
+        }
+
+        fn sample_rate(&self) -> SampleRate {
+            self.0.sample_rate()
+        }
+
+        fn total_duration(&self) -> Option<Duration> {
+            self.0.total_duration()
+        }
+
+        fn tail_duration(&self) -> Duration {
+            Duration::from_millis(15)
+        }
+
+        fn try_seek(&mut self, pos: Duration) -> Result<(), SeekError> {
+            self.0.try_seek(pos)
+        }
+    }
+
+    #[test]
+    fn tail_overlap_rings_over_the_start() {
+        // a click every 10 milliseconds with an echo 15 milliseconds later
+        let mut data = vec![0.0f32; 25];
+        data[0] = 1.0;
+        data[15] = 0.5;
+        let source = Echo(SamplesBuffer::new(1, 1000, data));
+
+        let region = (Duration::ZERO, Duration::from_millis(10));
+        let output: Vec<f32> = source
+            .clone()
+            .loop_region(region.0, region.1)
+            .with_tail_overlap(true)
+            .take(40)
+            .collect();
+        for (i, sample) in output.iter().enumerate() {
+            let click = if i % 10 == 0 { 1.0 } else { 0.0 };
+            let echo = if i >= 15 && i % 10 == 5 { 0.5 } else { 0.0 };
+            assert_eq!(*sample, click + echo, "sample {i}");
+        }
+
+        // otherwise the echo is cut off at every jump
+        let output: Vec<f32> = source.loop_region(region.0, region.1).take(40).collect();
+        assert!(output.iter().all(|&sample| sample != 0.5));
+    }
+
+    #[test]
+    fn jumps_at_zero_crossings() {
+        // at 1 kHz a 50 Hz sine moves by at most 0.31 per sample, the region starts at a peak
+        // and ends at a trough
+        let data: Vec<f32> = (0..200)
+            .map(|i| (std::f32::consts::TAU * 50.0 * i as f32 / 1000.0).sin())
+            .collect();
+        let region = (Duration::from_millis(5), Duration::from_millis(35));
+        let source = || SamplesBuffer::new(1, 1000, data.clone());
+
+        let exact: Vec<f32> = source().loop_region(region.0, region.1).take(36).collect();
+        assert!(exact[35] - exact[34] > 1.9);
+
+        let mut looped = source()
+            .loop_region(region.0, region.1)
+            .with_zero_crossing(Some(Duration::from_millis(10)));
+        let output: Vec<f32> = looped.by_ref().take(100).collect();
+        assert_eq!(looped.loop_count(), 2);
+        // plays on to the crossing after the end and starts at the one after the start
+        assert_eq!(output[..41], data[..41]);
+        assert_eq!(output[41..72], data[10..41]);
+        assert!(output[40].abs() < 1e-6 && output[41].abs() < 1e-6);
+        assert!(output
+            .windows(2)
+            .all(|pair| (pair[1] - pair[0]).abs() < 0.31));
+    }
+
+    #[test]
+    fn repeats_region() {
+        let data: Vec<f32> = (0..10).map(|i| i as f32).collect();
+        let source = SamplesBuffer::new(2, 1000, data)
+            .loop_region(Duration::from_millis(1), Duration::from_millis(3));
+        let output: Vec<f32> = source.take(12).collect();
+        assert_eq!(output, [0., 1., 2., 3., 4., 5., 2., 3., 4., 5., 2., 3.]);
+    }
+
+    #[test]
+    fn clearing_continues_playback() {
+        let data: Vec<f32> = (0..6).map(|i| i as f32).collect();
+        let mut source =
+            SamplesBuffer::new(1, 1000, data).loop_region(Duration::ZERO, Duration::from_millis(2));
+        let looped: Vec<f32> = source.by_ref().take(3).collect();
+        assert_eq!(looped, [0., 1., 0.]);
+        assert_eq!(source.loop_count(), 1);
+
+        source.clear_region();
+        assert_eq!(source.collect::<Vec<_>>(), [1., 2., 3., 4., 5.]);
+    }
+}
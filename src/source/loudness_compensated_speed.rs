@@ -0,0 +1,312 @@
+use std::time::Duration;
+
+use super::{SeekError, Speed, UniformSourceIterator};
+use crate::common::{ChannelCount, SampleRate};
+use crate::Source;
+
+/// Length of the blocks over which the energy of the input and output is compared.
+const BLOCK: Duration = Duration::from_millis(50);
+
+/// Largest correction applied, in either direction.
+const MAX_GAIN: f32 = 4.0;
+
+/// Internal function that builds a `LoudnessCompensatedSpeed` object.
+pub fn loudness_compensated_speed<I>(input: I, ratio: f32) -> LoudnessCompensatedSpeed<I>
+where
+    I: Source<Item = f32>,
+{
+    let channels = input.channels().max(1);
+    let sample_rate = input.sample_rate().max(1);
+    let tap = EnergyTap {
+        input,
+        energy: Energy::default(),
+    };
+    let block = (BLOCK.as_secs_f32() * sample_rate as f32) as u64 * channels as u64;
+    LoudnessCompensatedSpeed {
+        input: UniformSourceIterator::new(tap.speed(ratio), channels, sample_rate),
+        output_energy: 0.0,
+        output_count: 0,
+        block: block.max(1),
+        gain: 1.0,
+        target_gain: 1.0,
+        step: 0.0,
+    }
+}
+
+/// Changes the play speed like [`Speed`] and corrects the gain so that the result is about
+/// as loud as the original.
+///
+/// The sped up source is resampled back to the sample rate the source had when this was
+/// created. Speeding a sound up moves its energy to higher frequencies and that conversion
+/// loses part of what ends up close to or above half the sample rate, slowing it down has
+/// the opposite effect. Every 50 milliseconds the RMS level of the output is compared to that
+/// of the input that was played in the meantime, and the gain is ramped over the next block
+/// to make up for the difference, by at most a factor of 4.
+///
+/// This is an approximation. The level is matched after the fact, so sudden changes are
+/// corrected a block late, and matching the RMS does not account for the ear being more
+/// sensitive to some frequencies than others.
+pub struct LoudnessCompensatedSpeed<I>
+where
+    I: Source<Item = f32>,
+{
+    input: UniformSourceIterator<Speed<EnergyTap<I>>, f32>,
+    // Sum of the squares of the output samples of the current block, and their number.
+    output_energy: f64,
+    output_count: u64,
+    // Samples per block.
+    block: u64,
+    gain: f32,
+    target_gain: f32,
+    // Change of the gain per sample while ramping to `target_gain`.
+    step: f32,
+}
+
+// Energy of the input samples read since the end of the previous block.
+#[derive(Debug, Default)]
+struct Energy {
+    sum: f64,
+    count: u64,
+}
+
+impl Energy {
+    #[inline]
+    fn add(&mut self, sample: f32) {
+        self.sum += (sample * sample) as f64;
+        self.count += 1;
+    }
+
+    // Returns the mean square since the last call, if any samples were read.
+    fn take_mean_square(&mut self) -> Option<f64> {
+        let Energy { sum, count } = std::mem::take(self);
+        (count > 0).then(|| sum / count as f64)
+    }
+}
+
+// Measures the energy of the input before it is sped up and resampled.
+struct EnergyTap<I> {
+    input: I,
+    energy: Energy,
+}
+
+impl<I> Iterator for EnergyTap<I>
+where
+    I: Source<Item = f32>,
+{
+    type Item = f32;
+
+    #[inline]
+    fn next(&mut self) -> Option<f32> {
+        let sample = self.input.next()?;
+        self.energy.add(sample);
+        Some(sample)
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.input.size_hint()
+    }
+}
+
+impl<I> Source for EnergyTap<I>
+where
+    I: Source<Item = f32>,
+{
+    #[inline]
+    fn current_span_len(&self) -> Option<usize> {
+        self.input.current_span_len()
+    }
+
+    #[inline]
+    fn channels(&self) -> ChannelCount {
+        self.input.channels()
+    }
+
+    #[inline]
+    fn sample_rate(&self) -> SampleRate {
+        self.input.sample_rate()
+    }
+
+    #[inline]
+    fn total_duration(&self) -> Option<Duration> {
+        self.input.total_duration()
+    }
+
+    #[inline]
+    fn tail_duration(&self) -> Duration {
+        self.input.tail_duration()
+    }
+
+    #[inline]
+    fn latency(&self) -> Duration {
+        self.input.latency()
+    }
+
+    #[inline]
+    fn try_seek(&mut self, pos: Duration) -> Result<(), SeekError> {
+        self.input.try_seek(pos)
+    }
+}
+
+impl<I> LoudnessCompensatedSpeed<I>
+where
+    I: Source<Item = f32>,
+{
+    /// Returns the gain currently applied on top of the speed change.
+    #[inline]
+    pub fn gain(&self) -> f32 {
+        self.gain
+    }
+
+    /// Returns a reference to the inner source.
+    #[inline]
+    pub fn inner(&self) -> &I {
+        &self.input.inner().inner().input
+    }
+
+    /// Returns a mutable reference to the inner source.
+    #[inline]
+    pub fn inner_mut(&mut self) -> &mut I {
+        &mut self.input.inner_mut().inner_mut().input
+    }
+
+    /// Returns the inner source. Samples that were read ahead for the resampling are lost.
+    #[inline]
+    pub fn into_inner(self) -> I {
+        self.input.into_inner().into_inner().input
+    }
+
+    // The energy of the input, the tap sits under the speed change and the resampling.
+    fn energy(&mut self) -> &mut Energy {
+        &mut self.input.inner_mut().inner_mut().energy
+    }
+
+    // Compares the block that just ended with the input it was made from.
+    fn end_block(&mut self) {
+        let output = self.output_energy / self.output_count as f64;
+        self.output_energy = 0.0;
+        self.output_count = 0;
+        self.gain = self.target_gain;
+        self.step = 0.0;
+
+        let Some(input) = self.energy().take_mean_square() else {
+            return;
+        };
+        // Silence has nothing to correct.
+        if output < 1e-12 || input < 1e-12 {
+            return;
+        }
+        self.target_gain = ((input / output).sqrt() as f32).clamp(1.0 / MAX_GAIN, MAX_GAIN);
+        self.step = (self.target_gain - self.gain) / self.block as f32;
+    }
+}
+
+impl<I> Iterator for LoudnessCompensatedSpeed<I>
+where
+    I: Source<Item = f32>,
+{
+    type Item = f32;
+
+    #[inline]
+    fn next(&mut self) -> Option<f32> {
+        let sample = self.input.next()?;
+        self.output_energy += (sample * sample) as f64;
+        self.output_count += 1;
+        let output = sample * self.gain;
+        self.gain += self.step;
+        if self.output_count >= self.block {
+            self.end_block();
+        }
+        Some(output)
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.input.size_hint()
+    }
+}
+
+impl<I> Source for LoudnessCompensatedSpeed<I>
+where
+    I: Source<Item = f32>,
+{
+    #[inline]
+    fn current_span_len(&self) -> Option<usize> {
+        self.input.current_span_len()
+    }
+
+    #[inline]
+    fn channels(&self) -> ChannelCount {
+        self.input.channels()
+    }
+
+    #[inline]
+    fn sample_rate(&self) -> SampleRate {
+        self.input.sample_rate()
+    }
+
+    #[inline]
+    fn total_duration(&self) -> Option<Duration> {
+        self.input.total_duration()
+    }
+
+    #[inline]
+    fn tail_duration(&self) -> Duration {
+        self.input.tail_duration()
+    }
+
+    #[inline]
+    fn latency(&self) -> Duration {
+        self.input.latency()
+    }
+
+    /// Keeps the current gain, the energy measured so far is dropped.
+    #[inline]
+    fn try_seek(&mut self, pos: Duration) -> Result<(), SeekError> {
+        self.input.try_seek(pos)?;
+        self.energy().take_mean_square();
+        self.output_energy = 0.0;
+        self.output_count = 0;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f32::consts::TAU;
+
+    use crate::buffer::SamplesBuffer;
+    use crate::source::{Source, UniformSourceIterator};
+
+    fn rms(samples: &[f32]) -> f32 {
+        (samples.iter().map(|s| s * s).sum::<f32>() / samples.len() as f32).sqrt()
+    }
+
+    #[test]
+    fn sped_up_output_keeps_its_level() {
+        // Played 1.5 times faster the tone ends up above half the sample rate, resampling
+        // it back to 8 kHz loses a large part of it.
+        let input: Vec<f32> = (0..24_000)
+            .map(|i| 0.5 * (TAU * 3000.0 * i as f32 / 8000.0).sin())
+            .collect();
+        let original = rms(&input);
+
+        let plain: Vec<f32> = UniformSourceIterator::<_, f32>::new(
+            SamplesBuffer::new(1, 8000, input.clone()).speed(1.5),
+            1,
+            8000,
+        )
+        .collect();
+        assert!(rms(&plain[4000..]) < 0.8 * original);
+
+        let compensated: Vec<f32> = SamplesBuffer::new(1, 8000, input)
+            .speed_loudness_compensated(1.5)
+            .collect();
+        assert_eq!(compensated.len(), plain.len());
+        let level = rms(&compensated[4000..]);
+        assert!(
+            (level / original - 1.0).abs() < 0.05,
+            "{level} vs {original}"
+        );
+    }
+}
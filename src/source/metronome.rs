@@ -0,0 +1,197 @@
+//! Metronome source.
+
+use std::f32::consts::TAU;
+use std::time::Duration;
+
+use super::SeekError;
+use crate::common::{ChannelCount, SampleRate};
+use crate::Source;
+
+/// Length of a single click.
+const CLICK_DURATION: Duration = Duration::from_millis(30);
+
+/// Gain of the clicks that are not on the first beat of a bar.
+const BEAT_GAIN: f32 = 0.6;
+
+/// An infinite mono source that clicks at a given tempo.
+///
+/// The first beat of every bar is accented with a louder and, by default, higher click.
+/// Every click is a short sine burst that decays quickly.
+#[derive(Clone, Debug)]
+pub struct Metronome {
+    sample_rate: SampleRate,
+    samples_per_beat: f64,
+    // Samples since the start of the current beat.
+    beat_position: f64,
+    beat: u32,
+    beats_per_bar: u32,
+    accent_frequency: f32,
+    beat_frequency: f32,
+    click_len: f64,
+}
+
+impl Metronome {
+    /// Creates a metronome at `bpm` beats per minute in 4/4 time.
+    ///
+    /// # Panics
+    ///
+    /// If `bpm` is not positive.
+    pub fn new(bpm: f32, sample_rate: SampleRate) -> Metronome {
+        let mut metronome = Metronome {
+            sample_rate,
+            samples_per_beat: 0.0,
+            beat_position: 0.0,
+            beat: 0,
+            beats_per_bar: 4,
+            accent_frequency: 1760.0,
+            beat_frequency: 880.0,
+            click_len: CLICK_DURATION.as_secs_f64() * sample_rate as f64,
+        };
+        metronome.set_bpm(bpm);
+        metronome
+    }
+
+    /// Changes the tempo. The current beat is stretched or shortened to the new tempo, so
+    /// the next click follows the new tempo right away.
+    ///
+    /// # Panics
+    ///
+    /// If `bpm` is not positive.
+    #[inline]
+    pub fn set_bpm(&mut self, bpm: f32) {
+        assert!(bpm > 0.0, "bpm must be positive");
+        self.samples_per_beat = self.sample_rate as f64 * 60.0 / bpm as f64;
+    }
+
+    /// Returns the tempo in beats per minute.
+    #[inline]
+    pub fn bpm(&self) -> f32 {
+        (self.sample_rate as f64 * 60.0 / self.samples_per_beat) as f32
+    }
+
+    /// Sets the number of beats in a bar, the first of which is accented. Zero disables the
+    /// accent.
+    #[inline]
+    pub fn set_beats_per_bar(&mut self, beats_per_bar: u32) {
+        self.beats_per_bar = beats_per_bar;
+    }
+
+    /// Sets the pitch of the accented click and the other clicks in Hz.
+    #[inline]
+    pub fn set_click_frequencies(&mut self, accent: f32, beat: f32) {
+        self.accent_frequency = accent;
+        self.beat_frequency = beat;
+    }
+
+    #[inline]
+    fn is_accent(&self) -> bool {
+        self.beat.checked_rem(self.beats_per_bar) == Some(0)
+    }
+}
+
+impl Iterator for Metronome {
+    type Item = f32;
+
+    #[inline]
+    fn next(&mut self) -> Option<f32> {
+        if self.beat_position >= self.samples_per_beat {
+            self.beat_position -= self.samples_per_beat;
+            // after a tempo change the next beat may be overdue as well
+            if self.beat_position >= self.samples_per_beat {
+                self.beat_position = 0.0;
+            }
+            self.beat = self.beat.wrapping_add(1);
+        }
+
+        let position = self.beat_position;
+        self.beat_position += 1.0;
+        if position >= self.click_len {
+            return Some(0.0);
+        }
+
+        let (frequency, gain) = if self.is_accent() {
+            (self.accent_frequency, 1.0)
+        } else {
+            (self.beat_frequency, BEAT_GAIN)
+        };
+        let t = position as f32 / self.sample_rate as f32;
+        // decays to about 1% at the end of the click
+        let envelope = (-4.6 * position / self.click_len).exp() as f32;
+        Some((TAU * frequency * t).sin() * envelope * gain)
+    }
+}
+
+impl Source for Metronome {
+    #[inline]
+    fn current_span_len(&self) -> Option<usize> {
+        None
+    }
+
+    #[inline]
+    fn channels(&self) -> ChannelCount {
+        1
+    }
+
+    #[inline]
+    fn sample_rate(&self) -> SampleRate {
+        self.sample_rate
+    }
+
+    #[inline]
+    fn total_duration(&self) -> Option<Duration> {
+        None
+    }
+
+    /// Seeks assuming the current tempo was used from the start.
+    #[inline]
+    fn try_seek(&mut self, pos: Duration) -> Result<(), SeekError> {
+        let samples = (pos.as_secs_f64() * self.sample_rate as f64).floor();
+        let beats = (samples / self.samples_per_beat).floor();
+        self.beat = beats as u32;
+        self.beat_position = samples - beats * self.samples_per_beat;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Metronome;
+
+    fn peak(samples: &[f32]) -> f32 {
+        samples.iter().fold(0.0, |max, s| max.max(s.abs()))
+    }
+
+    #[test]
+    fn clicks_on_the_beat() {
+        // 90 bpm at 48 kHz is a beat every 32000 samples, a click is 1440 samples
+        let samples: Vec<f32> = Metronome::new(90.0, 48000).take(32000 * 8).collect();
+
+        for beat in 0..8 {
+            let start = beat * 32000;
+            assert!(peak(&samples[start..start + 100]) > 0.1, "beat {beat}");
+            assert_eq!(
+                peak(&samples[start + 1440..start + 32000]),
+                0.0,
+                "beat {beat}"
+            );
+        }
+
+        let accent = peak(&samples[..1440]);
+        assert!(accent > peak(&samples[32000..33440]));
+        assert_eq!(accent, peak(&samples[4 * 32000..4 * 32000 + 1440]));
+    }
+
+    #[test]
+    fn tempo_changes_apply_to_the_next_beat() {
+        let mut metronome = Metronome::new(60.0, 1000);
+        let _ = metronome.by_ref().take(10).count();
+        metronome.set_bpm(120.0);
+        assert_eq!(metronome.bpm(), 120.0);
+
+        let rest: Vec<f32> = metronome.take(1000).collect();
+        // the current beat is over after 500 samples, 10 of which were already played
+        assert_eq!(peak(&rest[460..490]), 0.0);
+        assert!(peak(&rest[490..500]) > 0.0);
+        assert!(peak(&rest[990..1000]) > 0.0);
+    }
+}
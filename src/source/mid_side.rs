@@ -0,0 +1,193 @@
+use std::time::Duration;
+
+use super::SeekError;
+use crate::common::{ChannelCount, SampleRate};
+use crate::Source;
+
+/// Internal function that builds a `MidSide` object converting stereo to mid/side.
+pub fn to_mid_side<I>(input: I) -> MidSide<I>
+where
+    I: Source<Item = f32>,
+{
+    mid_side(input, Direction::Encode)
+}
+
+/// Internal function that builds a `MidSide` object converting mid/side to stereo.
+pub fn from_mid_side<I>(input: I) -> MidSide<I>
+where
+    I: Source<Item = f32>,
+{
+    mid_side(input, Direction::Decode)
+}
+
+fn mid_side<I>(input: I, direction: Direction) -> MidSide<I>
+where
+    I: Source<Item = f32>,
+{
+    assert_eq!(
+        input.channels(),
+        2,
+        "mid/side conversion needs a stereo source"
+    );
+    MidSide {
+        input,
+        direction,
+        pending: None,
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Direction {
+    Encode,
+    Decode,
+}
+
+/// Converts between left/right and mid/side stereo, see [`Source::to_mid_side`] and
+/// [`Source::from_mid_side`].
+#[derive(Clone, Debug)]
+pub struct MidSide<I> {
+    input: I,
+    direction: Direction,
+    // Second channel of the current frame, already converted.
+    pending: Option<f32>,
+}
+
+impl<I> MidSide<I>
+where
+    I: Source<Item = f32>,
+{
+    /// Returns a reference to the inner source.
+    #[inline]
+    pub fn inner(&self) -> &I {
+        &self.input
+    }
+
+    /// Returns a mutable reference to the inner source.
+    #[inline]
+    pub fn inner_mut(&mut self) -> &mut I {
+        &mut self.input
+    }
+
+    /// Returns the inner source.
+    #[inline]
+    pub fn into_inner(self) -> I {
+        self.input
+    }
+}
+
+impl<I> Iterator for MidSide<I>
+where
+    I: Source<Item = f32>,
+{
+    type Item = f32;
+
+    #[inline]
+    fn next(&mut self) -> Option<f32> {
+        if let Some(sample) = self.pending.take() {
+            return Some(sample);
+        }
+
+        let first = self.input.next()?;
+        // Spans that are not stereo are passed through.
+        if self.input.channels() != 2 {
+            return Some(first);
+        }
+        let Some(second) = self.input.next() else {
+            return Some(first);
+        };
+
+        let (first, second) = match self.direction {
+            Direction::Encode => ((first + second) / 2.0, (first - second) / 2.0),
+            Direction::Decode => (first + second, first - second),
+        };
+        self.pending = Some(second);
+        Some(first)
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let pending = self.pending.is_some() as usize;
+        let (lower, upper) = self.input.size_hint();
+        (
+            lower.saturating_add(pending),
+            upper.and_then(|upper| upper.checked_add(pending)),
+        )
+    }
+}
+
+impl<I> Source for MidSide<I>
+where
+    I: Source<Item = f32>,
+{
+    #[inline]
+    fn current_span_len(&self) -> Option<usize> {
+        self.input
+            .current_span_len()
+            .map(|len| len + self.pending.is_some() as usize)
+    }
+
+    #[inline]
+    fn channels(&self) -> ChannelCount {
+        self.input.channels()
+    }
+
+    #[inline]
+    fn sample_rate(&self) -> SampleRate {
+        self.input.sample_rate()
+    }
+
+    #[inline]
+    fn total_duration(&self) -> Option<Duration> {
+        self.input.total_duration()
+    }
+
+    #[inline]
+    fn tail_duration(&self) -> Duration {
+        self.input.tail_duration()
+    }
+
+    #[inline]
+    fn latency(&self) -> Duration {
+        self.input.latency()
+    }
+
+    #[inline]
+    fn try_seek(&mut self, pos: Duration) -> Result<(), SeekError> {
+        self.input.try_seek(pos)?;
+        self.pending = None;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use approx::assert_abs_diff_eq;
+
+    use crate::buffer::SamplesBuffer;
+    use crate::source::Source;
+
+    #[test]
+    fn encodes_mid_and_side() {
+        let source = SamplesBuffer::new(2, 44100, vec![1.0f32, 1.0, 0.5, -0.5, 1.0, 0.0]);
+        let output: Vec<f32> = source.to_mid_side().collect();
+        assert_eq!(output, [1.0, 0.0, 0.0, 0.5, 0.5, 0.5]);
+    }
+
+    #[test]
+    fn round_trip() {
+        let data: Vec<f32> = (0..1000).map(|i| (i as f32 * 0.37).sin() * 0.9).collect();
+        let source = SamplesBuffer::new(2, 44100, data.clone());
+        let output: Vec<f32> = source.to_mid_side().from_mid_side().collect();
+
+        assert_eq!(output.len(), data.len());
+        for (output, input) in output.iter().zip(&data) {
+            assert_abs_diff_eq!(output, input, epsilon = 1e-6);
+        }
+    }
+
+    #[test]
+    #[should_panic]
+    fn requires_stereo() {
+        let _ = SamplesBuffer::new(1, 44100, vec![0.0f32; 4]).to_mid_side();
+    }
+}
@@ -37,6 +37,33 @@ where
     input2: UniformSourceIterator<I2, I2::Item>,
 }
 
+impl<I1, I2> Mix<I1, I2>
+where
+    I1: Source,
+    I1::Item: FromSample<I2::Item> + Sample,
+    I2: Source,
+    I2::Item: Sample,
+{
+    /// Returns references to the two mixed sources.
+    #[inline]
+    pub fn inner(&self) -> (&I1, &I2) {
+        (self.input1.inner(), self.input2.inner())
+    }
+
+    /// Returns mutable references to the two mixed sources.
+    #[inline]
+    pub fn inner_mut(&mut self) -> (&mut I1, &mut I2) {
+        (self.input1.inner_mut(), self.input2.inner_mut())
+    }
+
+    /// Returns the two mixed sources. Samples that were read ahead for converting them to the
+    /// same format are lost.
+    #[inline]
+    pub fn into_inner(self) -> (I1, I2) {
+        (self.input1.into_inner(), self.input2.into_inner())
+    }
+}
+
 impl<I1, I2> Iterator for Mix<I1, I2>
 where
     I1: Source,
@@ -122,6 +149,22 @@ where
         }
     }
 
+    /// Everything that plays on after the first source ends, or the longest tail of the two
+    /// if a duration is unknown.
+    #[inline]
+    fn tail_duration(&self) -> Duration {
+        let t1 = self.input1.tail_duration();
+        match (self.input1.total_duration(), self.input2.total_duration()) {
+            (Some(f1), Some(f2)) => f2.saturating_sub(f1.saturating_sub(t1)).max(t1),
+            _ => t1.max(self.input2.tail_duration()),
+        }
+    }
+
+    #[inline]
+    fn latency(&self) -> Duration {
+        self.input1.latency().max(self.input2.latency())
+    }
+
     /// Will only attempt a seek if both underlying sources support seek.
     #[inline]
     fn try_seek(&mut self, _: Duration) -> Result<(), SeekError> {
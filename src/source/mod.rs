@@ -3,81 +3,148 @@
 use core::fmt;
 use core::time::Duration;
 
-use crate::common::{ChannelCount, SampleRate};
+use crate::common::{frames_to_duration, ChannelCount, SampleRate};
 use crate::Sample;
 use dasp_sample::FromSample;
 
 pub use self::agc::AutomaticGainControl;
-pub use self::amplify::Amplify;
-pub use self::blt::BltFilter;
+pub use self::amplify::{Amplify, GainProcessor};
+pub use self::automation::{Automation, AutomationCurve};
+pub use self::balance::Balance;
+pub use self::blt::{BltFilter, FilterProcessor};
 pub use self::buffered::Buffered;
+pub use self::bypass::{BypassInput, BypassOutside};
+pub use self::channel_delay::ChannelDelays;
 pub use self::channel_volume::ChannelVolume;
 pub use self::chirp::{chirp, Chirp};
+pub use self::comb::CombFilter;
+pub use self::correlation::{Correlation, CorrelationHandle};
 pub use self::crossfade::Crossfade;
+pub use self::crossfeed::Crossfeed;
 pub use self::delay::Delay;
 pub use self::done::Done;
+pub use self::effect_rack::{EffectId, EffectRack, EffectRackHandle, SampleProcessor};
 pub use self::empty::Empty;
 pub use self::empty_callback::EmptyCallback;
 pub use self::fadein::FadeIn;
 pub use self::fadeout::FadeOut;
+pub use self::format_change::OnFormatChange;
 pub use self::from_factory::{from_factory, FromFactoryIter};
 pub use self::from_iter::{from_iter, FromIter};
+pub use self::gate::{Gate, GateHandle};
+pub use self::inspect::InspectSamples;
+pub use self::lfo::{Lfo, LfoShape};
+pub use self::limit::{GainReductionMeter, Limit};
 pub use self::linear_ramp::LinearGainRamp;
+pub use self::loop_region::LoopRegion;
+pub use self::loudness_compensated_speed::LoudnessCompensatedSpeed;
+pub use self::metronome::Metronome;
+pub use self::mid_side::MidSide;
 pub use self::mix::Mix;
+pub use self::modulate::ModulateAmplitude;
+pub use self::mute_channels::MuteChannels;
+pub use self::normalize_rms::NormalizeRmsStreaming;
+pub use self::pan::{Pan, PanLaw};
 pub use self::pausable::Pausable;
 pub use self::periodic::PeriodicAccess;
+pub use self::phaser::Phaser;
+pub use self::plucked_string::PluckedString;
 pub use self::position::TrackPosition;
-pub use self::repeat::Repeat;
+pub use self::prewarm::{Prewarm, PrewarmInput};
+pub use self::repeat::{Repeat, RepeatCrossfade};
+pub use self::route_channels::RouteChannels;
 pub use self::samples_converter::SamplesConverter;
 pub use self::sawtooth::SawtoothWave;
+pub use self::scrub::{Scrub, Scrubber};
 pub use self::signal_generator::{Function, SignalGenerator};
 pub use self::sine::SineWave;
 pub use self::skip::SkipDuration;
 pub use self::skippable::Skippable;
 pub use self::spatial::Spatial;
+pub use self::spectrum::{Spectrum, SpectrumHandle};
 pub use self::speed::Speed;
 pub use self::square::SquareWave;
 pub use self::stoppable::Stoppable;
 pub use self::take::TakeDuration;
 pub use self::triangle::TriangleWave;
+pub use self::trim_silence::TrimSilence;
 pub use self::uniform::UniformSourceIterator;
+pub use self::wavetable::Wavetable;
+pub use self::weighted_mix::WeightedMix;
 pub use self::zero::Zero;
+pub use self::zero_crossing::AlignToZeroCrossing;
+pub use crate::conversions::{Interpolation, Resampler};
+
+/// A [`Source`] of any type that can be sent to the audio thread, as returned by
+/// [`Source::boxed`].
+pub type BoxedSource<S = f32> = Box<dyn Source<Item = S> + Send>;
 
 mod agc;
 mod amplify;
+mod automation;
+mod balance;
 mod blt;
 mod buffered;
+mod bypass;
+mod channel_delay;
 mod channel_volume;
 mod chirp;
+mod comb;
+mod correlation;
 mod crossfade;
+mod crossfeed;
 mod delay;
 mod done;
+mod effect_rack;
 mod empty;
 mod empty_callback;
 mod fadein;
 mod fadeout;
+mod format_change;
 mod from_factory;
 mod from_iter;
+mod gate;
+mod inspect;
+mod lfo;
+mod limit;
 mod linear_ramp;
+mod loop_region;
+mod loudness_compensated_speed;
+mod metronome;
+mod mid_side;
 mod mix;
+mod modulate;
+mod mute_channels;
+mod normalize_rms;
+mod pan;
 mod pausable;
 mod periodic;
+mod phaser;
+mod plucked_string;
 mod position;
+mod prewarm;
 mod repeat;
+mod route_channels;
 mod samples_converter;
 mod sawtooth;
+mod scrub;
 mod signal_generator;
 mod sine;
 mod skip;
 mod skippable;
 mod spatial;
+mod spectrum;
 mod speed;
 mod square;
 mod stoppable;
 mod take;
 mod triangle;
+mod trim_silence;
 mod uniform;
+mod wavetable;
+mod weighted_mix;
 mod zero;
+mod zero_crossing;
 
 #[cfg(feature = "noise")]
 mod noise;
@@ -175,6 +242,48 @@ where
     /// `None` indicates at the same time "infinite" or "unknown".
     fn total_duration(&self) -> Option<Duration>;
 
+    /// Returns the total number of samples of this source, counted over all channels, if
+    /// known.
+    ///
+    /// This is consistent with [`total_duration`](Source::total_duration): by default it is
+    /// the total duration rounded to a whole number of frames, times the number of channels.
+    /// Sources that know their exact length, like [`SamplesBuffer`](crate::buffer::SamplesBuffer)
+    /// or a WAV [`Decoder`](crate::Decoder), report it directly. Useful to allocate a buffer
+    /// of the right size before rendering a source.
+    #[inline]
+    fn total_samples(&self) -> Option<u64> {
+        let nanos = self.total_duration()?.as_nanos();
+        let frames = (nanos * self.sample_rate() as u128 + 500_000_000) / 1_000_000_000;
+        Some(frames as u64 * self.channels() as u64)
+    }
+
+    /// Returns how long effects keep ringing at the end of this source, for example the
+    /// echo added by [`reverb`](Source::reverb). The tail is included in the samples and the
+    /// [`total_duration`](Source::total_duration) of the source.
+    ///
+    /// This is a hint for [`Repeat::with_tail_overlap`] and the other `with_tail_overlap`
+    /// methods, which play the tail over the start of the next repetition instead of before
+    /// it. Zero by default. [`mix`](Source::mix) reports everything that plays on after the
+    /// first source ends as tail, while effects that pass the samples on report the tail of
+    /// their input.
+    #[inline]
+    fn tail_duration(&self) -> Duration {
+        Duration::ZERO
+    }
+
+    /// Returns how much later this source plays what it reads from its input, for example
+    /// the look-ahead of a [`limit`](Source::limit).
+    ///
+    /// This is a hint for [`OutputStream::output_latency`](crate::OutputStream::output_latency).
+    /// Zero by default. Effects that pass the samples on report the latency of their input.
+    /// Effects that combine sources, such as [`mix`](Source::mix) and the output of a
+    /// [`Mixer`](crate::mixer::Mixer), report the largest latency of their sources, and the
+    /// queue of a [`Sink`](crate::Sink) that of its current sound.
+    #[inline]
+    fn latency(&self) -> Duration {
+        Duration::ZERO
+    }
+
     /// Stores the source in a buffer in addition to returning it. This iterator can be cloned.
     #[inline]
     fn buffered(self) -> Buffered<Self>
@@ -184,6 +293,22 @@ where
         buffered::buffered(self)
     }
 
+    /// Like [`buffered`](Source::buffered) but keeps at most `max_bytes` of samples in
+    /// memory, as reported by [`Buffered::memory_usage`].
+    ///
+    /// If the total duration of the source is known and it does not fit, the source is
+    /// returned back untouched so it can be played without buffering instead. Otherwise the
+    /// rest of the source is played without buffering once the limit is reached, see
+    /// [`Buffered::limit_reached`]. Clones that get there share the rest, each sample of it is
+    /// only played by the clone that reads it first.
+    #[inline]
+    fn buffered_with_limit(self, max_bytes: usize) -> Result<Buffered<Self>, Self>
+    where
+        Self: Sized,
+    {
+        buffered::buffered_with_limit(self, max_bytes)
+    }
+
     /// Mixes this source with another one.
     #[inline]
     fn mix<S>(self, other: S) -> Mix<Self, S>
@@ -208,6 +333,63 @@ where
         repeat::repeat(self)
     }
 
+    /// Repeats this source forever, crossfading the end of every repetition into the start
+    /// of the next one over `fade`. This hides the click at the seam of sounds that were not
+    /// made to loop.
+    ///
+    /// If the length of the source is known the fade is limited to half of it, so that every
+    /// repetition has an end to fade out and a start to fade in.
+    ///
+    /// Like [`repeat_infinite`](Source::repeat_infinite) this stores the data in a buffer.
+    #[inline]
+    fn repeat_infinite_crossfade(self, fade: Duration) -> RepeatCrossfade<Self>
+    where
+        Self: Sized,
+        Self::Item: Sample,
+    {
+        repeat::repeat_crossfade(self, fade)
+    }
+
+    /// Repeats the part of this source between `start` and `end` forever, once playback
+    /// reaches `end`. Unlike [`repeat_infinite`](Source::repeat_infinite) this does not buffer
+    /// anything but requires the source to support seeking.
+    ///
+    /// The region can be changed or cleared while playing, and the jumps can be moved to zero
+    /// crossings, see [`LoopRegion`].
+    #[inline]
+    fn loop_region(self, start: Duration, end: Duration) -> LoopRegion<Self>
+    where
+        Self: Sized,
+        Self::Item: Sample,
+    {
+        loop_region::loop_region(self, start, end)
+    }
+
+    /// Turns this source into the sound of its playhead being dragged around, for a waveform
+    /// scrubber. See [`Scrub`] for how it works, the source has to support seeking.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rodio::buffer::SamplesBuffer;
+    /// use rodio::source::Source;
+    /// use std::time::Duration;
+    ///
+    /// let scrub = SamplesBuffer::new(1, 44100, vec![0.0f32; 44100]).scrub();
+    /// let scrubber = scrub.scrubber();
+    /// // sink.append(scrub);
+    /// // In the UI thread, while the playhead is dragged:
+    /// scrubber.set_position(Duration::from_millis(250));
+    /// ```
+    #[inline]
+    fn scrub(self) -> Scrub<Self>
+    where
+        Self: Sized,
+        Self::Item: Sample,
+    {
+        scrub::scrub(self)
+    }
+
     /// Takes a certain duration of this source and then stops.
     #[inline]
     fn take_duration(self, duration: Duration) -> TakeDuration<Self>
@@ -229,6 +411,115 @@ where
         delay::delay(self, duration)
     }
 
+    /// Delays every channel by its own duration, rounded down to whole samples. See
+    /// [`ChannelDelays`].
+    ///
+    /// # Panics
+    ///
+    /// If the number of delays is not the number of channels.
+    ///
+    /// # Example
+    ///
+    /// Widens a mono sound played on both channels with the Haas effect.
+    ///
+    /// ```
+    /// use rodio::source::{ChannelVolume, SineWave, Source};
+    /// use std::time::Duration;
+    ///
+    /// let stereo = ChannelVolume::new(SineWave::new(440.0), vec![1.0, 1.0]);
+    /// let wide = stereo.channel_delays(vec![Duration::ZERO, Duration::from_millis(15)]);
+    /// ```
+    #[inline]
+    fn channel_delays(self, delays: Vec<Duration>) -> ChannelDelays<Self>
+    where
+        Self: Sized,
+        Self::Item: Sample,
+    {
+        channel_delay::channel_delays(self, delays)
+    }
+
+    /// Turns a mono source into a wide pseudo stereo one with the Haas effect: the sound is
+    /// played on both channels, delayed by `delay` on the right one. Delays between 5 and 30
+    /// milliseconds widen the image without being heard as an echo.
+    ///
+    /// Sources with more than one channel are mixed down to mono first.
+    #[inline]
+    fn mono_to_wide_stereo(self, delay: Duration) -> ChannelDelays<ChannelVolume<Self>>
+    where
+        Self: Sized,
+        Self::Item: Sample,
+    {
+        ChannelVolume::new(self, vec![1.0, 1.0]).channel_delays(vec![Duration::ZERO, delay])
+    }
+
+    /// Silences the channels for which `mask` is `true` and leaves the others untouched. See
+    /// [`MuteChannels`].
+    ///
+    /// # Panics
+    ///
+    /// If the length of `mask` is not the number of channels.
+    ///
+    /// # Example
+    ///
+    /// Only plays the left channel of a stereo source.
+    ///
+    /// ```
+    /// use rodio::source::{ChannelVolume, SineWave, Source};
+    ///
+    /// let stereo = ChannelVolume::new(SineWave::new(440.0), vec![1.0, 1.0]);
+    /// let left = stereo.mute_channels(&[false, true]);
+    /// ```
+    #[inline]
+    fn mute_channels(self, mask: &[bool]) -> MuteChannels<Self>
+    where
+        Self: Sized,
+        Self::Item: Sample,
+    {
+        mute_channels::mute_channels(self, mask)
+    }
+
+    /// Silences all channels but the one at `index`, counting from zero.
+    ///
+    /// # Panics
+    ///
+    /// If there is no channel `index`.
+    #[inline]
+    fn solo_channel(self, index: usize) -> MuteChannels<Self>
+    where
+        Self: Sized,
+        Self::Item: Sample,
+    {
+        mute_channels::solo_channel(self, index)
+    }
+
+    /// Plays channel `n` of the source on output channel `indices[n]`, counting from zero,
+    /// and silence on the other channels. See [`RouteChannels`].
+    ///
+    /// # Panics
+    ///
+    /// If the length of `indices` is not the number of channels.
+    ///
+    /// # Example
+    ///
+    /// Plays a stereo source on the third and fourth output of a four channel mixer.
+    ///
+    /// ```
+    /// use rodio::mixer;
+    /// use rodio::source::{ChannelVolume, SineWave, Source};
+    ///
+    /// let (mixer, output) = mixer::mixer::<f32>(4, 44100);
+    /// let stereo = ChannelVolume::new(SineWave::new(440.0), vec![1.0, 1.0]);
+    /// mixer.add(stereo.route_to_channels(&[2, 3]));
+    /// ```
+    #[inline]
+    fn route_to_channels(self, indices: &[usize]) -> RouteChannels<Self>
+    where
+        Self: Sized,
+        Self::Item: Sample,
+    {
+        route_channels::route_to_channels(self, indices)
+    }
+
     /// Immediately skips a certain duration of this source.
     ///
     /// If the specified duration is longer than the source itself, `skip_duration` will skip to the end of the source.
@@ -240,6 +531,33 @@ where
         skip::skip_duration(self, duration)
     }
 
+    /// Removes the silence at the start and the end of a finite source.
+    ///
+    /// Everything up to the first frame that exceeds `threshold_db` (in dBFS, so `-60.0` is
+    /// a reasonable value for recordings) is skipped, as are up to ten seconds after the last
+    /// such frame. Quiet passages in between are kept, see [`TrimSilence`] for the details.
+    #[inline]
+    fn trim_silence(self, threshold_db: f32) -> TrimSilence<Self>
+    where
+        Self: Sized,
+    {
+        trim_silence::trim_silence(self, threshold_db)
+    }
+
+    /// Drops the start of the sound up to its first zero crossing, searching at most
+    /// `max_offset` ahead. Use this on a sound that is spliced onto another one to avoid a
+    /// click at the splice point.
+    ///
+    /// See [`AlignToZeroCrossing`] for details.
+    #[inline]
+    fn align_to_zero_crossing(self, max_offset: Duration) -> AlignToZeroCrossing<Self>
+    where
+        Self: Sized,
+        Self::Item: Sample,
+    {
+        zero_crossing::align_to_zero_crossing(self, max_offset)
+    }
+
     /// Amplifies the sound by the given value.
     #[inline]
     fn amplify(self, value: f32) -> Amplify<Self>
@@ -249,6 +567,33 @@ where
         amplify::amplify(self, value)
     }
 
+    /// Multiplies the sound frame by frame with the samples of a control source, such as a
+    /// low frequency oscillator or an envelope.
+    ///
+    /// The control source is resampled to match this sound, see [`ModulateAmplitude`] for
+    /// how control samples are mapped to gains.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rodio::source::{SineWave, Source};
+    ///
+    /// // a tremolo at 5 Hz between full and half volume
+    /// let mut tremolo = SineWave::new(440.0).modulate_amplitude(SineWave::new(5.0));
+    /// tremolo.set_range(0.5, 1.0);
+    /// ```
+    #[inline]
+    fn modulate_amplitude<C>(self, control: C) -> ModulateAmplitude<Self, C>
+    where
+        Self: Sized,
+        Self::Item: Sample,
+        C: Source,
+        C::Item: Sample,
+        f32: FromSample<C::Item>,
+    {
+        modulate::modulate_amplitude(self, control)
+    }
+
     /// Applies automatic gain control to the sound.
     ///
     /// Automatic Gain Control (AGC) adjusts the amplitude of the audio signal
@@ -346,9 +691,69 @@ where
         )
     }
 
+    /// Brings the source to `target_db` dBFS RMS while it plays, measuring its level over a
+    /// moving `window`. At most `max_gain_db` dB of gain is applied, so silence and noise are
+    /// not blown up. See [`NormalizeRmsStreaming`].
+    ///
+    /// Works on live streams and other infinite sources, the gain follows the level with a lag
+    /// of about one `window`. A window of a few seconds only follows the overall level.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::time::Duration;
+    /// use rodio::source::{SineWave, Source};
+    ///
+    /// let source = SineWave::new(440.0)
+    ///     .amplify(0.1)
+    ///     .normalize_rms_streaming(-16.0, Duration::from_secs(3), 20.0);
+    /// ```
+    #[inline]
+    fn normalize_rms_streaming(
+        self,
+        target_db: f32,
+        window: Duration,
+        max_gain_db: f32,
+    ) -> NormalizeRmsStreaming<Self>
+    where
+        Self: Sized,
+        Self::Item: Sample,
+    {
+        normalize_rms::normalize_rms_streaming(self, target_db, window, max_gain_db)
+    }
+
+    /// Keeps the peaks of the source below `threshold` without clipping, see [`Limit`].
+    ///
+    /// The signal is delayed by the look-ahead of the limiter, 5 milliseconds unless changed
+    /// with [`Limit::with_lookahead`].
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rodio::source::{SineWave, Source};
+    /// use std::time::Duration;
+    ///
+    /// let limited = SineWave::new(440.0)
+    ///     .amplify(2.0)
+    ///     .limit(0.9)
+    ///     .with_lookahead(Duration::from_millis(2));
+    /// let meter = limited.meter();
+    /// ```
+    #[inline]
+    fn limit(self, threshold: f32) -> Limit<Self>
+    where
+        Self: Sized,
+        Self::Item: Sample,
+    {
+        limit::limit(self, threshold)
+    }
+
     /// Mixes this sound fading out with another sound fading in for the given duration.
     ///
     /// Only the crossfaded portion (beginning of self, beginning of other) is returned.
+    ///
+    /// See [`take_crossfade_at_zero_crossing`](Source::take_crossfade_at_zero_crossing) to
+    /// start `other` at a zero crossing instead of at an arbitrary sample.
     #[inline]
     fn take_crossfade_with<S: Source>(self, other: S, duration: Duration) -> Crossfade<Self, S>
     where
@@ -359,6 +764,25 @@ where
         crossfade::crossfade(self, other, duration)
     }
 
+    /// Like [`take_crossfade_with`](Source::take_crossfade_with), but starts `other` at its
+    /// first zero crossing within `max_offset`, like
+    /// [`align_to_zero_crossing`](Source::align_to_zero_crossing). Up to `max_offset` of
+    /// `other` is read ahead when the crossfade starts and at most that much of it is dropped.
+    #[inline]
+    fn take_crossfade_at_zero_crossing<S: Source>(
+        self,
+        other: S,
+        duration: Duration,
+        max_offset: Duration,
+    ) -> Crossfade<Self, AlignToZeroCrossing<S>>
+    where
+        Self: Sized,
+        Self::Item: FromSample<S::Item>,
+        <S as Iterator>::Item: Sample,
+    {
+        crossfade::crossfade(self, other.align_to_zero_crossing(max_offset), duration)
+    }
+
     /// Fades in the sound.
     #[inline]
     fn fade_in(self, duration: Duration) -> FadeIn<Self>
@@ -415,6 +839,121 @@ where
         periodic::periodic(self, period, access)
     }
 
+    /// Calls the `inspect` closure with every sample and passes the samples on unchanged,
+    /// like [`Iterator::inspect`] but keeping the result a [`Source`].
+    ///
+    /// Useful to observe the samples flowing through a chain, for example to copy them into
+    /// a ring buffer that a visualizer reads from. The closure runs on the audio thread for
+    /// every single sample, so it must be cheap: avoid locking, allocating or blocking in it.
+    /// It also has to be `Send` for the source to be played.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::sync::atomic::{AtomicU32, Ordering};
+    /// use std::sync::Arc;
+    /// use rodio::source::{SineWave, Source};
+    ///
+    /// // keep track of the loudest sample
+    /// let peak = Arc::new(AtomicU32::new(0));
+    /// let peak_writer = peak.clone();
+    /// let source = SineWave::new(440.0).inspect_samples(move |sample| {
+    ///     peak_writer.fetch_max(sample.abs().to_bits(), Ordering::Relaxed);
+    /// });
+    /// # let _ = source.take(100).count();
+    /// # assert!(f32::from_bits(peak.load(Ordering::Relaxed)) > 0.0);
+    /// ```
+    #[inline]
+    fn inspect_samples<F>(self, inspect: F) -> InspectSamples<Self, F>
+    where
+        Self: Sized,
+        F: FnMut(&Self::Item),
+    {
+        inspect::inspect_samples(self, inspect)
+    }
+
+    /// Calls `callback` with the new channel count and sample rate whenever they change, for
+    /// example when a [`Decoder`](crate::Decoder) plays a stream that switches its format.
+    /// See [`OnFormatChange`].
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rodio::source::{SineWave, Source};
+    ///
+    /// let source = SineWave::new(440.0).on_format_change(|channels, sample_rate| {
+    ///     println!("now playing {channels} channels at {sample_rate} Hz");
+    /// });
+    /// ```
+    #[inline]
+    fn on_format_change<F>(self, callback: F) -> OnFormatChange<Self, F>
+    where
+        Self: Sized,
+        F: FnMut(ChannelCount, SampleRate),
+    {
+        format_change::on_format_change(self, callback)
+    }
+
+    /// Computes the magnitude spectrum of the source on a background thread while passing the
+    /// samples through unchanged.
+    ///
+    /// The channels are mixed down to mono and analyzed in overlapping, Hann windowed frames
+    /// of `fft_size` samples. Use [`Spectrum::handle`] to read the latest spectrum from any
+    /// thread, for example to draw a visualizer. Frames are skipped rather than delaying the
+    /// audio if the analysis can not keep up.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `fft_size` is not a power of two or is smaller than 2.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rodio::source::{SineWave, Source};
+    ///
+    /// let source = SineWave::new(440.0).spectrum(2048);
+    /// let spectrum = source.handle();
+    /// // sink.append(source);
+    /// // In the UI thread:
+    /// for (bin, magnitude) in spectrum.magnitudes().iter().enumerate() {
+    ///     println!("{} Hz: {magnitude}", spectrum.bin_frequency(bin));
+    /// }
+    /// ```
+    #[inline]
+    fn spectrum(self, fft_size: usize) -> Spectrum<Self>
+    where
+        Self: Sized,
+    {
+        spectrum::spectrum(self, fft_size)
+    }
+
+    /// Measures the correlation between the first two channels over a moving `window`, while
+    /// passing the samples through unchanged. Use [`Correlation::handle`] to read it from any
+    /// thread, a value below zero warns that the mix partly cancels out in mono.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::time::Duration;
+    /// use rodio::source::{SineWave, Source};
+    ///
+    /// let source = SineWave::new(440.0).correlation(Duration::from_millis(300));
+    /// let meter = source.handle();
+    /// // sink.append(source);
+    /// // In the UI thread:
+    /// if meter.correlation() < 0.0 {
+    ///     println!("the mix is not mono compatible");
+    /// }
+    /// ```
+    #[inline]
+    fn correlation(self, window: Duration) -> Correlation<Self>
+    where
+        Self: Sized,
+        Self::Item: Sample,
+    {
+        correlation::correlation(self, window)
+    }
+
     /// Changes the play speed of the sound. Does not adjust the samples, only the playback speed.
     ///
     /// # Note:
@@ -428,7 +967,8 @@ where
     /// - If you set the speed to 2 the total duration will be halve of what it
     ///   was.
     ///
-    /// See [`Speed`] for details
+    /// See [`Speed`] for details, and [`Speed::with_interpolation`] to resample with a better
+    /// interpolation than the linear one of the output.
     #[inline]
     fn speed(self, ratio: f32) -> Speed<Self>
     where
@@ -437,6 +977,57 @@ where
         speed::speed(self, ratio)
     }
 
+    /// Changes the play speed like [`speed`](Source::speed), resamples the result back to the
+    /// current sample rate and adjusts the gain so that it stays about as loud as the
+    /// original. Meant for comparing a sound at different speeds. The correction is
+    /// approximate, see [`LoudnessCompensatedSpeed`].
+    #[inline]
+    fn speed_loudness_compensated(self, ratio: f32) -> LoudnessCompensatedSpeed<Self>
+    where
+        Self: Sized,
+        Self: Source<Item = f32>,
+    {
+        loudness_compensated_speed::loudness_compensated_speed(self, ratio)
+    }
+
+    /// Shifts the pitch of the sound by a musical interval in `cents`, 100 cents being a
+    /// semitone and 1200 cents an octave.
+    ///
+    /// This is the same as [`speed`](Source::speed) with a ratio of `2^(cents / 1200)`, so it
+    /// changes the duration as well: one octave up plays twice as fast.
+    #[inline]
+    fn detune(self, cents: f32) -> Speed<Self>
+    where
+        Self: Sized,
+    {
+        speed::detune(self, cents)
+    }
+
+    /// Adds delayed copies of the input and of the output to the signal, a comb filter.
+    /// Every sample becomes `x[n] + feedforward * x[n - d] + feedback * y[n - d]` for a delay
+    /// of `d` frames, see [`CombFilter`].
+    ///
+    /// A building block for plucked strings, flangers and reverbs. The filter is only stable
+    /// while `feedback` is strictly between `-1.0` and `1.0`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::time::Duration;
+    /// use rodio::source::{SineWave, Source};
+    ///
+    /// // a metallic ring at 200 Hz
+    /// let ringing = SineWave::new(440.0).comb_filter(Duration::from_millis(5), 0.8, 0.0);
+    /// ```
+    #[inline]
+    fn comb_filter(self, delay: Duration, feedback: f32, feedforward: f32) -> CombFilter<Self>
+    where
+        Self: Sized,
+        Self: Source<Item = f32>,
+    {
+        comb::comb_filter(self, delay, feedback, feedforward)
+    }
+
     /// Adds a basic reverb effect.
     ///
     /// This function requires the source to implement `Clone`. This can be done by using
@@ -468,6 +1059,80 @@ where
         SamplesConverter::new(self)
     }
 
+    /// Plays the whole source into a [`SamplesBuffer`](crate::buffer::SamplesBuffer) of
+    /// samples of type `D`, keeping its channel count and sample rate. Useful for tests and
+    /// offline processing.
+    ///
+    /// If the channel count or sample rate of the source changes, the rest of it is converted
+    /// to the format it started with. A source that does not know its total duration might
+    /// never end, so it is returned back untouched instead.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::time::Duration;
+    /// use rodio::source::{chirp, SineWave, Source};
+    ///
+    /// let sweep = chirp(44100, 200.0, 2000.0, Duration::from_millis(10));
+    /// let buffer = sweep.collect_samples::<i16>().unwrap();
+    /// assert_eq!(buffer.count(), 441);
+    ///
+    /// assert!(SineWave::new(440.0).collect_samples::<f32>().is_err());
+    /// ```
+    fn collect_samples<D>(self) -> Result<crate::buffer::SamplesBuffer<D>, Self>
+    where
+        Self: Sized,
+        D: FromSample<Self::Item> + Sample,
+    {
+        if self.total_duration().is_none() {
+            return Err(self);
+        }
+        let channels = self.channels();
+        let sample_rate = self.sample_rate();
+        let data: Vec<D> = UniformSourceIterator::new(self, channels, sample_rate).collect();
+        Ok(crate::buffer::SamplesBuffer::new(
+            channels,
+            sample_rate,
+            data,
+        ))
+    }
+
+    /// Boxes the source so that sources of different types can be handled alike, for example
+    /// to store them in a `Vec` or to build a chain of effects at runtime.
+    ///
+    /// All the filters of this trait can be applied to the result.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::time::Duration;
+    /// use rodio::source::{BoxedSource, SineWave, Source};
+    ///
+    /// let effects = ["low_pass", "speed", "fade_in"];
+    ///
+    /// let mut source = SineWave::new(440.0)
+    ///     .take_duration(Duration::from_secs(1))
+    ///     .boxed();
+    /// for effect in effects {
+    ///     source = match effect {
+    ///         "low_pass" => source.low_pass(1000).boxed(),
+    ///         "speed" => source.speed(2.0).boxed(),
+    ///         "fade_in" => source.fade_in(Duration::from_millis(100)).boxed(),
+    ///         _ => source,
+    ///     };
+    /// }
+    ///
+    /// let playlist: Vec<BoxedSource> = vec![source, SineWave::new(220.0).amplify(0.5).boxed()];
+    /// assert_eq!(playlist[0].sample_rate(), 2 * playlist[1].sample_rate());
+    /// ```
+    #[inline]
+    fn boxed(self) -> BoxedSource<Self::Item>
+    where
+        Self: Sized + Send + 'static,
+    {
+        Box::new(self)
+    }
+
     /// Makes the sound pausable.
     // TODO: add example
     #[inline]
@@ -516,7 +1181,6 @@ where
     }
 
     /// Applies a low-pass filter to the source.
-    /// **Warning**: Probably buggy.
     #[inline]
     fn low_pass(self, freq: u32) -> BltFilter<Self>
     where
@@ -556,6 +1220,291 @@ where
         blt::high_pass_with_q(self, freq, q)
     }
 
+    /// Applies an all-pass filter to the source. All frequencies pass at the same volume but
+    /// their phase is shifted, by half a period at `freq`. A higher `q` makes the shift
+    /// happen over a narrower range of frequencies around `freq`.
+    ///
+    /// This is a building block for phasers, reverbs and other effects that mix the filtered
+    /// signal with the original one.
+    #[inline]
+    fn all_pass(self, freq: u32, q: f32) -> BltFilter<Self>
+    where
+        Self: Sized,
+        Self: Source<Item = f32>,
+    {
+        blt::all_pass(self, freq, q)
+    }
+
+    /// Applies a low-shelf filter to the source, like the bass knob of an amplifier.
+    /// Frequencies below `freq` are boosted by `gain_db` decibels, or cut if it is negative,
+    /// the ones above it pass unchanged. A higher `q` makes the transition steeper, `0.707`
+    /// gives a smooth one without a bump.
+    #[inline]
+    fn low_shelf(self, freq: u32, gain_db: f32, q: f32) -> BltFilter<Self>
+    where
+        Self: Sized,
+        Self: Source<Item = f32>,
+    {
+        blt::low_shelf(self, freq, gain_db, q)
+    }
+
+    /// Applies a high-shelf filter to the source, like the treble knob of an amplifier. The
+    /// counterpart of [`low_shelf`](Source::low_shelf) for the frequencies above `freq`.
+    #[inline]
+    fn high_shelf(self, freq: u32, gain_db: f32, q: f32) -> BltFilter<Self>
+    where
+        Self: Sized,
+        Self: Source<Item = f32>,
+    {
+        blt::high_shelf(self, freq, gain_db, q)
+    }
+
+    /// Applies a phaser effect, notches that sweep up and down the spectrum. See [`Phaser`].
+    ///
+    /// `stages` all-pass filters are swept `rate_hz` times per second, every two of them add
+    /// a notch. `depth` and `mix` range from 0 to 1, `feedback` from -0.95 to 0.95.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rodio::source::{SineWave, Source};
+    ///
+    /// let swirl = SineWave::new(440.0).phaser(4, 0.5, 1.0, 0.3, 0.5);
+    /// ```
+    #[inline]
+    fn phaser(
+        self,
+        stages: usize,
+        rate_hz: f32,
+        depth: f32,
+        feedback: f32,
+        mix: f32,
+    ) -> Phaser<Self>
+    where
+        Self: Sized,
+        Self: Source<Item = f32>,
+    {
+        phaser::phaser(self, stages, rate_hz, depth, feedback, mix)
+    }
+
+    /// Feeds a low-passed and slightly delayed copy of each channel of a stereo source into the
+    /// other, so that hard panned recordings sound more like speakers when listening on
+    /// headphones. See [`Crossfeed`].
+    ///
+    /// `amount` ranges from 0, which leaves the channels separate, to 1, which feeds the other
+    /// channel in at about -6 dB.
+    #[inline]
+    fn crossfeed(self, amount: f32) -> Crossfeed<Self>
+    where
+        Self: Sized,
+        Self: Source<Item = f32>,
+    {
+        crossfeed::crossfeed(self, amount)
+    }
+
+    /// Runs the source through a rack of effects that can be added, removed and reordered
+    /// while it plays, without rebuilding the chain. See [`EffectRack`].
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rodio::source::{FilterProcessor, GainProcessor, SineWave, Source};
+    ///
+    /// let rack = SineWave::new(440.0).effect_rack();
+    /// let handle = rack.handle();
+    /// let gain = handle.push(GainProcessor::new(0.5));
+    /// handle.insert(0, FilterProcessor::low_pass(1000, 0.7));
+    /// // later, for example from another thread
+    /// handle.remove(gain);
+    /// ```
+    #[inline]
+    fn effect_rack(self) -> EffectRack<Self>
+    where
+        Self: Sized,
+        Self: Source<Item = f32>,
+    {
+        effect_rack::effect_rack(self)
+    }
+
+    /// Applies an effect only between `start` and `end` and plays the source unchanged
+    /// elsewhere, with a short crossfade at the edges. `effect` builds the effect on the
+    /// [`BypassInput`] it is given. See [`BypassOutside`].
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::time::Duration;
+    /// use rodio::source::{SineWave, Source};
+    ///
+    /// // muffle the sound from the second to the fourth second
+    /// let source = SineWave::new(440.0).bypass_outside(
+    ///     Duration::from_secs(2),
+    ///     Duration::from_secs(4),
+    ///     |input| input.low_pass(500),
+    /// );
+    /// ```
+    #[inline]
+    fn bypass_outside<F, E>(
+        self,
+        start: Duration,
+        end: Duration,
+        effect: F,
+    ) -> BypassOutside<Self, E>
+    where
+        Self: Sized,
+        Self: Source<Item = f32>,
+        F: FnOnce(BypassInput) -> E,
+        E: Source<Item = f32>,
+    {
+        bypass::bypass_outside(self, start, end, effect)
+    }
+
+    /// Runs an effect on `duration` of silence before the source starts, so that it has
+    /// settled when the first sample is heard. `effect` builds the effect on the
+    /// [`PrewarmInput`] it is given. See [`Prewarm`].
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::time::Duration;
+    /// use rodio::source::{SineWave, Source};
+    ///
+    /// // the gain control has turned up quiet passages by the time the source starts
+    /// let source = SineWave::new(440.0).amplify(0.1).prewarm(Duration::from_secs(1), |input| {
+    ///     input.normalize_rms_streaming(-16.0, Duration::from_millis(200), 12.0)
+    /// });
+    /// ```
+    #[inline]
+    fn prewarm<F, E>(self, duration: Duration, effect: F) -> Prewarm<E>
+    where
+        Self: Sized,
+        Self::Item: Sample,
+        F: FnOnce(PrewarmInput<Self>) -> E,
+        E: Source<Item = Self::Item>,
+    {
+        prewarm::prewarm(self, duration, effect)
+    }
+
+    /// Opens and closes the source at exact frames with a short ramp against clicks, for
+    /// example to chop a sustained sound to a beat. `open` is the initial state. See [`Gate`].
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rodio::source::{SineWave, Source};
+    ///
+    /// // eighth notes at 120 bpm, 48 kHz
+    /// let frames_per_eighth = 12_000;
+    /// let gate = SineWave::new(220.0).gate(true);
+    /// let handle = gate.handle();
+    /// for eighth in 0..8 {
+    ///     handle.open_at(eighth * frames_per_eighth, eighth % 2 == 0);
+    /// }
+    /// ```
+    #[inline]
+    fn gate(self, open: bool) -> Gate<Self>
+    where
+        Self: Sized,
+        Self: Source<Item = f32>,
+    {
+        gate::gate(self, open)
+    }
+
+    /// Places the source in the stereo field. `position` ranges from `-1.0` (left) over
+    /// `0.0` (center) to `1.0` (right).
+    ///
+    /// The source is mixed down to mono and played on two channels. The gains follow the
+    /// equal power [`PanLaw`] unless changed with [`Pan::set_pan_law`].
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rodio::source::{PanLaw, SineWave, Source};
+    ///
+    /// let mut source = SineWave::new(440.0).pan(-0.5);
+    /// source.set_pan_law(PanLaw::Linear);
+    /// ```
+    #[inline]
+    fn pan(self, position: f32) -> Pan<Self>
+    where
+        Self: Sized,
+    {
+        pan::pan(self, position)
+    }
+
+    /// Turns down one channel of a stereo source. `value` ranges from `-1.0`, which silences
+    /// the right channel, over `0.0`, which changes nothing, to `1.0`, which silences the left
+    /// channel.
+    ///
+    /// Unlike [`pan`](Source::pan) this keeps the channels separate, see [`Balance`].
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rodio::buffer::SamplesBuffer;
+    /// use rodio::source::Source;
+    ///
+    /// let stereo = SamplesBuffer::new(2, 44100, vec![1.0f32, 1.0]);
+    /// let output: Vec<f32> = stereo.balance(0.25).collect();
+    /// assert_eq!(output, [0.75, 1.0]);
+    /// ```
+    #[inline]
+    fn balance(self, value: f32) -> Balance<Self>
+    where
+        Self: Sized,
+        Self::Item: Sample,
+    {
+        balance::balance(self, value)
+    }
+
+    /// Converts a stereo source from left/right to mid/side.
+    ///
+    /// The first channel of the result is the mid signal `(left + right) / 2` and the second
+    /// the side signal `(left - right) / 2`. Effects applied in between this and
+    /// [`from_mid_side`](Source::from_mid_side) then only affect either the center or the
+    /// width of the stereo image.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the source does not have 2 channels.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rodio::buffer::SamplesBuffer;
+    /// use rodio::Source;
+    ///
+    /// let stereo = SamplesBuffer::new(2, 44100, vec![0.5f32, -0.5, 0.25, 0.0]);
+    /// let processed = stereo
+    ///     .to_mid_side()
+    ///     // process the mid and side signals here
+    ///     .low_pass(8000)
+    ///     .from_mid_side();
+    /// ```
+    #[inline]
+    fn to_mid_side(self) -> MidSide<Self>
+    where
+        Self: Sized + Source<Item = f32>,
+    {
+        mid_side::to_mid_side(self)
+    }
+
+    /// Converts a mid/side source, as produced by [`to_mid_side`](Source::to_mid_side), back
+    /// to left/right stereo.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the source does not have 2 channels.
+    // Named after the conversion it undoes rather than a constructor.
+    #[allow(clippy::wrong_self_convention)]
+    #[inline]
+    fn from_mid_side(self) -> MidSide<Self>
+    where
+        Self: Sized + Source<Item = f32>,
+    {
+        mid_side::from_mid_side(self)
+    }
+
     // There is no `can_seek()` method as it is impossible to use correctly. Between
     // checking if a source supports seeking and actually seeking the sink can
     // switch to a new source.
@@ -582,6 +1531,17 @@ where
             underlying_source: std::any::type_name::<Self>(),
         })
     }
+
+    /// Attempts to seek to the frame at index `frame`, at the sample rate of this source. A
+    /// frame holds one sample for every channel.
+    ///
+    /// By default the frame is converted to a [`Duration`] for [`try_seek`](Source::try_seek),
+    /// sources that can position on an exact frame, such as WAV and raw PCM decoders, do so
+    /// instead. Adapters pass it on when they do not change the timing of their input.
+    #[inline]
+    fn try_seek_frames(&mut self, frame: u64) -> Result<(), SeekError> {
+        self.try_seek(frames_to_duration(frame, self.sample_rate()))
+    }
 }
 
 // We might add decoders requiring new error types, without non_exhaustive
@@ -683,10 +1643,30 @@ macro_rules! source_pointer_impl {
                 (**self).total_duration()
             }
 
+            #[inline]
+            fn total_samples(&self) -> Option<u64> {
+                (**self).total_samples()
+            }
+
+            #[inline]
+            fn tail_duration(&self) -> Duration {
+                (**self).tail_duration()
+            }
+
+            #[inline]
+            fn latency(&self) -> Duration {
+                (**self).latency()
+            }
+
             #[inline]
             fn try_seek(&mut self, pos: Duration) -> Result<(), SeekError> {
                 (**self).try_seek(pos)
             }
+
+            #[inline]
+            fn try_seek_frames(&mut self, frame: u64) -> Result<(), SeekError> {
+                (**self).try_seek_frames(frame)
+            }
         }
     };
 }
@@ -0,0 +1,210 @@
+use std::time::Duration;
+
+use dasp_sample::FromSample;
+
+use super::{SeekError, UniformSourceIterator};
+use crate::common::{ChannelCount, SampleRate};
+use crate::{Sample, Source};
+
+/// Internal function that builds a `ModulateAmplitude` object.
+pub fn modulate_amplitude<I, C>(input: I, control: C) -> ModulateAmplitude<I, C>
+where
+    I: Source,
+    I::Item: Sample,
+    C: Source,
+    C::Item: Sample,
+    f32: FromSample<C::Item>,
+{
+    let sample_rate = input.sample_rate();
+    ModulateAmplitude {
+        input,
+        control: UniformSourceIterator::new(control, 1, sample_rate),
+        gain: 1.0,
+        channel: 0,
+        min: -1.0,
+        max: 1.0,
+    }
+}
+
+/// Multiplies every frame of a source with the next sample of a control source.
+///
+/// The control source is mixed down to mono and resampled to the sample rate the main source
+/// had when this was created, so one control sample applies to one frame. Once the control
+/// source ends the last gain is kept.
+///
+/// By default the control sample is used as the gain directly, see
+/// [`set_range`](ModulateAmplitude::set_range) to map it onto a different range.
+#[derive(Clone)]
+pub struct ModulateAmplitude<I, C>
+where
+    C: Source,
+    C::Item: Sample,
+{
+    input: I,
+    control: UniformSourceIterator<C, f32>,
+    gain: f32,
+    // Channel of the next sample, a new control sample is read at the start of the frame.
+    channel: ChannelCount,
+    min: f32,
+    max: f32,
+}
+
+impl<I, C> ModulateAmplitude<I, C>
+where
+    C: Source,
+    C::Item: Sample,
+{
+    /// Maps control samples from `[-1.0, 1.0]` linearly onto gains from `min` to `max`.
+    ///
+    /// For example a sine wave control with a range of `0.0` to `1.0` results in a tremolo
+    /// that fully silences the source at its troughs.
+    #[inline]
+    pub fn set_range(&mut self, min: f32, max: f32) {
+        self.min = min;
+        self.max = max;
+    }
+
+    /// Returns a reference to the inner source.
+    #[inline]
+    pub fn inner(&self) -> &I {
+        &self.input
+    }
+
+    /// Returns a mutable reference to the inner source.
+    #[inline]
+    pub fn inner_mut(&mut self) -> &mut I {
+        &mut self.input
+    }
+
+    /// Returns the inner source.
+    #[inline]
+    pub fn into_inner(self) -> I {
+        self.input
+    }
+}
+
+impl<I, C> Iterator for ModulateAmplitude<I, C>
+where
+    I: Source,
+    I::Item: Sample,
+    C: Source,
+    C::Item: Sample,
+    f32: FromSample<C::Item>,
+{
+    type Item = I::Item;
+
+    #[inline]
+    fn next(&mut self) -> Option<I::Item> {
+        let sample = self.input.next()?;
+
+        if self.channel == 0 {
+            if let Some(control) = self.control.next() {
+                self.gain = self.min + (control + 1.0) / 2.0 * (self.max - self.min);
+            }
+        }
+        self.channel += 1;
+        if self.channel >= self.input.channels() {
+            self.channel = 0;
+        }
+
+        Some(sample.amplify(self.gain))
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.input.size_hint()
+    }
+}
+
+impl<I, C> Source for ModulateAmplitude<I, C>
+where
+    I: Source,
+    I::Item: Sample,
+    C: Source,
+    C::Item: Sample,
+    f32: FromSample<C::Item>,
+{
+    #[inline]
+    fn current_span_len(&self) -> Option<usize> {
+        self.input.current_span_len()
+    }
+
+    #[inline]
+    fn channels(&self) -> ChannelCount {
+        self.input.channels()
+    }
+
+    #[inline]
+    fn sample_rate(&self) -> SampleRate {
+        self.input.sample_rate()
+    }
+
+    #[inline]
+    fn total_duration(&self) -> Option<Duration> {
+        self.input.total_duration()
+    }
+
+    #[inline]
+    fn tail_duration(&self) -> Duration {
+        self.input.tail_duration()
+    }
+
+    #[inline]
+    fn latency(&self) -> Duration {
+        self.input.latency()
+    }
+
+    /// Also seeks the control source if possible, otherwise it continues where it was.
+    #[inline]
+    fn try_seek(&mut self, pos: Duration) -> Result<(), SeekError> {
+        self.input.try_seek(pos)?;
+        let _ = self.control.try_seek(pos);
+        self.channel = 0;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use crate::buffer::SamplesBuffer;
+    use crate::source::{SineWave, Source};
+
+    #[test]
+    fn tremolo_from_slow_sine() {
+        // a 2 Hz control at 48 kHz on a 1 kHz tone, peaks after 125 ms and troughs after 375 ms
+        let mut tremolo = SineWave::new(1000.0).modulate_amplitude(SineWave::new(2.0));
+        tremolo.set_range(0.0, 1.0);
+        let samples: Vec<f32> = tremolo.take(24_000).collect();
+
+        let peak = |range: std::ops::Range<usize>| {
+            samples[range]
+                .iter()
+                .fold(0.0f32, |max, sample| max.max(sample.abs()))
+        };
+        assert!(peak(5_800..6_200) > 0.95);
+        assert!(peak(17_800..18_200) < 0.05);
+        // the control is zero at the start, half the full gain
+        assert!((peak(0..48) - 0.5).abs() < 0.02);
+    }
+
+    #[test]
+    fn one_control_sample_per_frame() {
+        let stereo = SamplesBuffer::new(2, 1000, vec![1.0f32; 6]);
+        let control = SamplesBuffer::new(1, 1000, vec![0.5f32, 0.25, 0.0]);
+        let output: Vec<f32> = stereo.modulate_amplitude(control).collect();
+        assert_eq!(output, [0.5, 0.5, 0.25, 0.25, 0.0, 0.0]);
+    }
+
+    #[test]
+    fn resamples_control() {
+        let main = SamplesBuffer::new(1, 4, vec![1.0f32; 4]);
+        let control = SamplesBuffer::new(1, 2, vec![0.5f32, 0.5]);
+        let output: Vec<f32> = main
+            .modulate_amplitude(control)
+            .take_duration(Duration::from_secs(1))
+            .collect();
+        assert_eq!(output, [0.5; 4]);
+    }
+}
@@ -0,0 +1,192 @@
+use std::time::Duration;
+
+use super::SeekError;
+use crate::common::{ChannelCount, SampleRate};
+use crate::{Sample, Source};
+
+/// Internal function that builds a `MuteChannels` object.
+pub fn mute_channels<I>(input: I, mask: &[bool]) -> MuteChannels<I>
+where
+    I: Source,
+    I::Item: Sample,
+{
+    assert_eq!(
+        mask.len(),
+        input.channels() as usize,
+        "there must be one mask entry per channel"
+    );
+    MuteChannels {
+        input,
+        mask: mask.to_vec(),
+        channel: 0,
+    }
+}
+
+/// Internal function that builds a `MuteChannels` object that only plays one channel.
+pub fn solo_channel<I>(input: I, index: usize) -> MuteChannels<I>
+where
+    I: Source,
+    I::Item: Sample,
+{
+    let channels = input.channels() as usize;
+    assert!(index < channels, "there is no channel {index}");
+    let mask: Vec<bool> = (0..channels).map(|channel| channel != index).collect();
+    mute_channels(input, &mask)
+}
+
+/// Silences some of the channels of a source and leaves the others untouched.
+///
+/// The channels keep their place, muted ones play silence. The channel count of the source at
+/// the time this was created is used throughout.
+#[derive(Clone, Debug)]
+pub struct MuteChannels<I> {
+    input: I,
+    // Whether each channel is muted.
+    mask: Vec<bool>,
+    // The channel the next sample belongs to.
+    channel: usize,
+}
+
+impl<I> MuteChannels<I>
+where
+    I: Source,
+    I::Item: Sample,
+{
+    /// Returns for every channel whether it is muted.
+    #[inline]
+    pub fn mask(&self) -> &[bool] {
+        &self.mask
+    }
+
+    /// Mutes or unmutes a channel while playing.
+    ///
+    /// # Panics
+    ///
+    /// If there is no channel `index`.
+    #[inline]
+    pub fn set_muted(&mut self, index: usize, muted: bool) {
+        self.mask[index] = muted;
+    }
+
+    /// Returns a reference to the inner source.
+    #[inline]
+    pub fn inner(&self) -> &I {
+        &self.input
+    }
+
+    /// Returns a mutable reference to the inner source.
+    #[inline]
+    pub fn inner_mut(&mut self) -> &mut I {
+        &mut self.input
+    }
+
+    /// Returns the inner source.
+    #[inline]
+    pub fn into_inner(self) -> I {
+        self.input
+    }
+}
+
+impl<I> Iterator for MuteChannels<I>
+where
+    I: Source,
+    I::Item: Sample,
+{
+    type Item = I::Item;
+
+    #[inline]
+    fn next(&mut self) -> Option<I::Item> {
+        let sample = self.input.next()?;
+        let muted = self.mask.get(self.channel).copied().unwrap_or(false);
+        self.channel += 1;
+        if self.channel >= self.mask.len() {
+            self.channel = 0;
+        }
+        if muted {
+            Some(I::Item::ZERO_VALUE)
+        } else {
+            Some(sample)
+        }
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.input.size_hint()
+    }
+}
+
+impl<I> ExactSizeIterator for MuteChannels<I>
+where
+    I: Source + ExactSizeIterator,
+    I::Item: Sample,
+{
+}
+
+impl<I> Source for MuteChannels<I>
+where
+    I: Source,
+    I::Item: Sample,
+{
+    #[inline]
+    fn current_span_len(&self) -> Option<usize> {
+        self.input.current_span_len()
+    }
+
+    #[inline]
+    fn channels(&self) -> ChannelCount {
+        self.input.channels()
+    }
+
+    #[inline]
+    fn sample_rate(&self) -> SampleRate {
+        self.input.sample_rate()
+    }
+
+    #[inline]
+    fn total_duration(&self) -> Option<Duration> {
+        self.input.total_duration()
+    }
+
+    #[inline]
+    fn tail_duration(&self) -> Duration {
+        self.input.tail_duration()
+    }
+
+    #[inline]
+    fn latency(&self) -> Duration {
+        self.input.latency()
+    }
+
+    #[inline]
+    fn try_seek(&mut self, pos: Duration) -> Result<(), SeekError> {
+        self.input.try_seek(pos)?;
+        self.channel = 0;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::buffer::SamplesBuffer;
+    use crate::source::Source;
+
+    #[test]
+    fn mutes_the_right_channel() {
+        let data: Vec<f32> = (1..=8).map(|i| i as f32).collect();
+        let muted = SamplesBuffer::new(2, 1000, data).mute_channels(&[false, true]);
+        let output: Vec<f32> = muted.collect();
+        assert_eq!(output, [1., 0., 3., 0., 5., 0., 7., 0.]);
+    }
+
+    #[test]
+    fn solo_keeps_one_channel() {
+        let data: Vec<i16> = (1..=6).collect();
+        let mut solo = SamplesBuffer::new(3, 1000, data).solo_channel(1);
+        assert_eq!(solo.mask(), [true, false, true]);
+        assert_eq!(solo.next(), Some(0));
+        assert_eq!(solo.next(), Some(2));
+        solo.set_muted(2, false);
+        let output: Vec<i16> = solo.collect();
+        assert_eq!(output, [3, 0, 5, 6]);
+    }
+}
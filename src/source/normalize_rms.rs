@@ -0,0 +1,241 @@
+use std::time::Duration;
+
+use super::SeekError;
+use crate::common::{ChannelCount, SampleRate};
+use crate::{Sample, Source};
+
+/// Internal function that builds a `NormalizeRmsStreaming` object.
+pub fn normalize_rms_streaming<I>(
+    input: I,
+    target_db: f32,
+    window: Duration,
+    max_gain_db: f32,
+) -> NormalizeRmsStreaming<I>
+where
+    I: Source,
+    I::Item: Sample,
+{
+    let target = db_to_linear(target_db);
+    let mut normalize = NormalizeRmsStreaming {
+        input,
+        target,
+        max_gain: db_to_linear(max_gain_db),
+        window,
+        // Starts out as if the source was at the target, so the gain starts at 1.
+        mean_square: target * target,
+        coeff: 0.0,
+        sample_rate: 0,
+        channels: 0,
+    };
+    normalize.update_coeff();
+    normalize
+}
+
+/// Brings a source to a target level while it plays, by measuring its RMS level over a
+/// moving window. See [`Source::normalize_rms_streaming`].
+///
+/// Works on infinite sources such as live streams since nothing is measured ahead of time,
+/// the price is that the gain lags behind changes of the level by about the length of the
+/// window. After a sudden jump in level the output is too loud until the measurement has
+/// caught up, a [`limit`](Source::limit) after it catches the peaks.
+///
+/// Unlike the [`AutomaticGainControl`](super::AutomaticGainControl), which reacts quickly to
+/// keep speech at an even level, this is meant to be slow and to only follow the overall
+/// level of the source.
+#[derive(Clone, Debug)]
+pub struct NormalizeRmsStreaming<I> {
+    input: I,
+    target: f32,
+    max_gain: f32,
+    window: Duration,
+    // Moving average of the squared samples.
+    mean_square: f32,
+    // Weight of a new sample in the average.
+    coeff: f32,
+    // Format `coeff` was computed for.
+    sample_rate: SampleRate,
+    channels: ChannelCount,
+}
+
+impl<I> NormalizeRmsStreaming<I>
+where
+    I: Source,
+    I::Item: Sample,
+{
+    /// Changes the level, in dBFS RMS, the source is brought to.
+    #[inline]
+    pub fn set_target_db(&mut self, target_db: f32) {
+        self.target = db_to_linear(target_db);
+    }
+
+    /// Changes the highest gain, in dB, applied to quiet passages.
+    #[inline]
+    pub fn set_max_gain_db(&mut self, max_gain_db: f32) {
+        self.max_gain = db_to_linear(max_gain_db);
+    }
+
+    /// Returns the gain currently applied, as a factor.
+    #[inline]
+    pub fn gain(&self) -> f32 {
+        let rms = self.mean_square.sqrt();
+        if rms * self.max_gain <= self.target {
+            self.max_gain
+        } else {
+            self.target / rms
+        }
+    }
+
+    /// Returns a reference to the inner source.
+    #[inline]
+    pub fn inner(&self) -> &I {
+        &self.input
+    }
+
+    /// Returns a mutable reference to the inner source.
+    #[inline]
+    pub fn inner_mut(&mut self) -> &mut I {
+        &mut self.input
+    }
+
+    /// Returns the inner source.
+    #[inline]
+    pub fn into_inner(self) -> I {
+        self.input
+    }
+
+    fn update_coeff(&mut self) {
+        self.sample_rate = self.input.sample_rate();
+        self.channels = self.input.channels();
+        let samples = self.window.as_secs_f32() * self.sample_rate as f32 * self.channels as f32;
+        self.coeff = 1.0 - (-1.0 / samples.max(1.0)).exp();
+    }
+}
+
+impl<I> Iterator for NormalizeRmsStreaming<I>
+where
+    I: Source,
+    I::Item: Sample,
+{
+    type Item = I::Item;
+
+    #[inline]
+    fn next(&mut self) -> Option<I::Item> {
+        if self.input.sample_rate() != self.sample_rate || self.input.channels() != self.channels {
+            self.update_coeff();
+        }
+        let sample = self.input.next()?;
+        let value = sample.to_f32();
+        self.mean_square += (value * value - self.mean_square) * self.coeff;
+        Some(sample.amplify(self.gain()))
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.input.size_hint()
+    }
+}
+
+impl<I> ExactSizeIterator for NormalizeRmsStreaming<I>
+where
+    I: Source + ExactSizeIterator,
+    I::Item: Sample,
+{
+}
+
+impl<I> Source for NormalizeRmsStreaming<I>
+where
+    I: Source,
+    I::Item: Sample,
+{
+    #[inline]
+    fn current_span_len(&self) -> Option<usize> {
+        self.input.current_span_len()
+    }
+
+    #[inline]
+    fn channels(&self) -> ChannelCount {
+        self.input.channels()
+    }
+
+    #[inline]
+    fn sample_rate(&self) -> SampleRate {
+        self.input.sample_rate()
+    }
+
+    #[inline]
+    fn total_duration(&self) -> Option<Duration> {
+        self.input.total_duration()
+    }
+
+    #[inline]
+    fn tail_duration(&self) -> Duration {
+        self.input.tail_duration()
+    }
+
+    #[inline]
+    fn latency(&self) -> Duration {
+        self.input.latency()
+    }
+
+    /// The measured level is kept, it settles on the level at the new position within the
+    /// window.
+    #[inline]
+    fn try_seek(&mut self, pos: Duration) -> Result<(), SeekError> {
+        self.input.try_seek(pos)
+    }
+}
+
+#[inline]
+fn db_to_linear(db: f32) -> f32 {
+    10f32.powf(db / 20.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use crate::source::{SineWave, Source};
+
+    fn rms(samples: &[f32]) -> f32 {
+        (samples.iter().map(|s| s * s).sum::<f32>() / samples.len() as f32).sqrt()
+    }
+
+    fn to_db(value: f32) -> f32 {
+        20.0 * value.log10()
+    }
+
+    #[test]
+    fn level_step_converges_to_the_target() {
+        // 2 seconds at -26 dBFS RMS, then -6 dBFS RMS, at 48 kHz
+        let rate = 48_000;
+        let quiet = SineWave::new(440.0)
+            .amplify(0.071)
+            .take_duration(Duration::from_secs(2));
+        let loud = SineWave::new(440.0)
+            .amplify(0.71)
+            .take_duration(Duration::from_secs(2));
+        let output: Vec<f32> = quiet
+            .mix(loud.delay(Duration::from_secs(2)))
+            .normalize_rms_streaming(-16.0, Duration::from_millis(200), 20.0)
+            .collect();
+
+        // 5 windows after the start and after the step the output is at the target
+        let settled = |at: usize| to_db(rms(&output[at..at + rate / 10]));
+        assert!((settled(rate) - -16.0).abs() < 0.3);
+        assert!((settled(rate * 3) - -16.0).abs() < 0.3);
+        // right after the step it is still too loud
+        assert!(settled(rate * 2) > -10.0);
+    }
+
+    #[test]
+    fn gain_is_limited() {
+        // -46 dBFS RMS would need 30 dB
+        let output: Vec<f32> = SineWave::new(440.0)
+            .amplify(0.0071)
+            .normalize_rms_streaming(-16.0, Duration::from_millis(50), 12.0)
+            .take(48_000)
+            .collect();
+        let level = to_db(rms(&output[24_000..]));
+        assert!((level - (-46.0 + 12.0)).abs() < 0.3);
+    }
+}
@@ -0,0 +1,228 @@
+use std::f32::consts::FRAC_PI_4;
+use std::time::Duration;
+
+use super::SeekError;
+use crate::common::{ChannelCount, SampleRate};
+use crate::source::ChannelVolume;
+use crate::{Sample, Source};
+
+/// How the gain of a sound is distributed between the left and right channel as it is
+/// panned. The laws differ in how much a sound in the center is attenuated.
+///
+/// Different engines and mixing desks assume different laws, pick the one matching your
+/// reference to get the same balance.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum PanLaw {
+    /// Constant power, the center is attenuated by 3 dB (a gain of `1/√2` per channel).
+    /// The perceived loudness stays the same while panning.
+    #[default]
+    EqualPower,
+    /// Constant sum of the gains, the center is attenuated by 6 dB (a gain of `0.5` per
+    /// channel). Sounds in the center appear quieter, but the law is mono compatible.
+    Linear,
+    /// A compromise between the other two laws, the center is attenuated by 4.5 dB.
+    Compromise,
+}
+
+impl PanLaw {
+    /// Returns the gains of the left and right channel for `position`, which ranges from
+    /// `-1.0` (left) over `0.0` (center) to `1.0` (right). Positions out of that range are
+    /// clamped.
+    pub fn gains(self, position: f32) -> [f32; 2] {
+        let position = position.clamp(-1.0, 1.0);
+        let linear = [(1.0 - position) / 2.0, (1.0 + position) / 2.0];
+        let angle = (position + 1.0) * FRAC_PI_4;
+        let equal_power = [angle.cos(), angle.sin()];
+
+        match self {
+            PanLaw::EqualPower => equal_power,
+            PanLaw::Linear => linear,
+            PanLaw::Compromise => [
+                (linear[0] * equal_power[0]).sqrt(),
+                (linear[1] * equal_power[1]).sqrt(),
+            ],
+        }
+    }
+}
+
+/// Internal function that builds a `Pan` object.
+pub fn pan<I>(input: I, position: f32) -> Pan<I>
+where
+    I: Source,
+    I::Item: Sample,
+{
+    let mut pan = Pan {
+        input: ChannelVolume::new(input, vec![0.0, 0.0]),
+        position,
+        law: PanLaw::default(),
+    };
+    pan.update_gains();
+    pan
+}
+
+/// Places a source in the stereo field. The source is mixed down to mono and played on two
+/// channels with gains following a [`PanLaw`].
+#[derive(Clone)]
+pub struct Pan<I>
+where
+    I: Source,
+    I::Item: Sample,
+{
+    input: ChannelVolume<I>,
+    position: f32,
+    law: PanLaw,
+}
+
+impl<I> Pan<I>
+where
+    I: Source,
+    I::Item: Sample,
+{
+    /// Moves the source, `-1.0` is fully left, `0.0` the center and `1.0` fully right.
+    #[inline]
+    pub fn set_position(&mut self, position: f32) {
+        self.position = position;
+        self.update_gains();
+    }
+
+    /// Returns the current position.
+    #[inline]
+    pub fn position(&self) -> f32 {
+        self.position
+    }
+
+    /// Changes the pan law, the default is [`PanLaw::EqualPower`].
+    #[inline]
+    pub fn set_pan_law(&mut self, law: PanLaw) {
+        self.law = law;
+        self.update_gains();
+    }
+
+    /// Returns a reference to the inner source.
+    #[inline]
+    pub fn inner(&self) -> &I {
+        self.input.inner()
+    }
+
+    /// Returns a mutable reference to the inner source.
+    #[inline]
+    pub fn inner_mut(&mut self) -> &mut I {
+        self.input.inner_mut()
+    }
+
+    /// Returns the inner source.
+    #[inline]
+    pub fn into_inner(self) -> I {
+        self.input.into_inner()
+    }
+
+    fn update_gains(&mut self) {
+        let [left, right] = self.law.gains(self.position);
+        self.input.set_volume(0, left);
+        self.input.set_volume(1, right);
+    }
+}
+
+impl<I> Iterator for Pan<I>
+where
+    I: Source,
+    I::Item: Sample,
+{
+    type Item = I::Item;
+
+    #[inline]
+    fn next(&mut self) -> Option<I::Item> {
+        self.input.next()
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.input.size_hint()
+    }
+}
+
+impl<I> Source for Pan<I>
+where
+    I: Source,
+    I::Item: Sample,
+{
+    #[inline]
+    fn current_span_len(&self) -> Option<usize> {
+        self.input.current_span_len()
+    }
+
+    #[inline]
+    fn channels(&self) -> ChannelCount {
+        self.input.channels()
+    }
+
+    #[inline]
+    fn sample_rate(&self) -> SampleRate {
+        self.input.sample_rate()
+    }
+
+    #[inline]
+    fn total_duration(&self) -> Option<Duration> {
+        self.input.total_duration()
+    }
+
+    #[inline]
+    fn tail_duration(&self) -> Duration {
+        self.input.tail_duration()
+    }
+
+    #[inline]
+    fn latency(&self) -> Duration {
+        self.input.latency()
+    }
+
+    #[inline]
+    fn try_seek(&mut self, pos: Duration) -> Result<(), SeekError> {
+        self.input.try_seek(pos)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use approx::assert_abs_diff_eq;
+
+    use super::PanLaw;
+    use crate::buffer::SamplesBuffer;
+    use crate::source::Source;
+
+    fn db(gain: f32) -> f32 {
+        20.0 * gain.log10()
+    }
+
+    #[test]
+    fn center_gain() {
+        for (law, expected_db) in [
+            (PanLaw::EqualPower, -3.0),
+            (PanLaw::Linear, -6.0),
+            (PanLaw::Compromise, -4.5),
+        ] {
+            let [left, right] = law.gains(0.0);
+            assert_eq!(left, right);
+            assert_abs_diff_eq!(db(left), expected_db, epsilon = 0.05);
+        }
+    }
+
+    #[test]
+    fn hard_panned() {
+        for law in [PanLaw::EqualPower, PanLaw::Linear, PanLaw::Compromise] {
+            let [left, right] = law.gains(-1.0);
+            assert_abs_diff_eq!(left, 1.0);
+            assert_abs_diff_eq!(right, 0.0);
+            assert_eq!(law.gains(5.0), law.gains(1.0));
+        }
+    }
+
+    #[test]
+    fn pans_mono_source() {
+        let source = SamplesBuffer::new(1, 44100, vec![1.0f32, 0.5]);
+        let mut panned = source.pan(0.0);
+        panned.set_pan_law(PanLaw::Linear);
+        assert_eq!(panned.channels(), 2);
+        assert_eq!(panned.collect::<Vec<_>>(), [0.5, 0.5, 0.25, 0.25]);
+    }
+}
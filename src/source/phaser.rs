@@ -0,0 +1,264 @@
+use std::f32::consts::{PI, TAU};
+use std::time::Duration;
+
+use super::blt::flush_denormal;
+use super::SeekError;
+use crate::common::{ChannelCount, SampleRate};
+use crate::Source;
+
+/// Lowest and highest frequency the all-pass stages are swept between at full depth.
+const MIN_FREQ: f32 = 200.0;
+const MAX_FREQ: f32 = 2000.0;
+
+/// Internal function that builds a `Phaser` object.
+pub fn phaser<I>(
+    input: I,
+    stages: usize,
+    rate_hz: f32,
+    depth: f32,
+    feedback: f32,
+    mix: f32,
+) -> Phaser<I>
+where
+    I: Source<Item = f32>,
+{
+    Phaser {
+        input,
+        stages: stages.max(1),
+        rate_hz,
+        depth: depth.clamp(0.0, 1.0),
+        feedback: feedback.clamp(-0.95, 0.95),
+        mix: mix.clamp(0.0, 1.0),
+        lfo_phase: 0.0,
+        coefficient: 0.0,
+        states: Vec::new(),
+        channel: 0,
+    }
+}
+
+/// Classic phaser effect, sweeps notches up and down the spectrum.
+///
+/// The input goes through a chain of first order all-pass filters whose frequency is swept
+/// by a sine LFO, and is then mixed with the original signal. Frequencies that come out of the
+/// chain with half a period of phase shift cancel out, every two stages add one notch. Unlike
+/// a flanger, which mixes in a delayed copy, the notches are not evenly spaced.
+///
+/// The stages are swept between 200 Hz and 2 kHz, `depth` from 0 to 1 sets how much of that
+/// range is used around its center. `feedback` from -0.95 to 0.95 feeds the output of the
+/// chain back into it to make the notches sharper, and `mix` from 0 to 1 sets how much of the
+/// filtered signal is heard. The deepest notches are at a mix of 0.5.
+#[derive(Clone, Debug)]
+pub struct Phaser<I> {
+    input: I,
+    stages: usize,
+    rate_hz: f32,
+    depth: f32,
+    feedback: f32,
+    mix: f32,
+    // Position of the LFO in the range `0.0..1.0`.
+    lfo_phase: f32,
+    // Coefficient of all stages for the current frame.
+    coefficient: f32,
+    // The filter history of each channel, channels must not influence each other.
+    states: Vec<PhaserState>,
+    // The channel the next sample belongs to.
+    channel: usize,
+}
+
+#[derive(Clone, Debug)]
+struct PhaserState {
+    // Previous input and output of every stage.
+    x_n1: Vec<f32>,
+    y_n1: Vec<f32>,
+    // Previous output of the chain, fed back into it.
+    last: f32,
+}
+
+impl<I> Phaser<I>
+where
+    I: Source<Item = f32>,
+{
+    /// Returns a reference to the inner source.
+    #[inline]
+    pub fn inner(&self) -> &I {
+        &self.input
+    }
+
+    /// Returns a mutable reference to the inner source.
+    #[inline]
+    pub fn inner_mut(&mut self) -> &mut I {
+        &mut self.input
+    }
+
+    /// Returns the inner source.
+    #[inline]
+    pub fn into_inner(self) -> I {
+        self.input
+    }
+
+    fn reset(&mut self) {
+        let channels = self.input.channels().max(1) as usize;
+        let state = PhaserState {
+            x_n1: vec![0.0; self.stages],
+            y_n1: vec![0.0; self.stages],
+            last: 0.0,
+        };
+        self.states.clear();
+        self.states.resize(channels, state);
+        self.channel = 0;
+    }
+
+    // Advances the LFO by one frame of the current sample rate and updates the coefficient.
+    fn next_frame(&mut self) {
+        if self.states.len() != self.input.channels().max(1) as usize {
+            self.reset();
+        }
+        let sample_rate = self.input.sample_rate().max(1) as f32;
+
+        let lfo = (TAU * self.lfo_phase).sin();
+        self.lfo_phase = (self.lfo_phase + self.rate_hz / sample_rate).rem_euclid(1.0);
+
+        let center = (MIN_FREQ * MAX_FREQ).sqrt();
+        let half_range = (MAX_FREQ / MIN_FREQ).sqrt();
+        let freq = (center * half_range.powf(self.depth * lfo)).min(sample_rate * 0.45);
+        // Phase shift of a quarter period at `freq`.
+        let tan = (PI * freq / sample_rate).tan();
+        self.coefficient = (tan - 1.0) / (tan + 1.0);
+    }
+}
+
+impl<I> Iterator for Phaser<I>
+where
+    I: Source<Item = f32>,
+{
+    type Item = f32;
+
+    #[inline]
+    fn next(&mut self) -> Option<f32> {
+        if self.channel == 0 {
+            self.next_frame();
+        }
+        let sample = self.input.next()?;
+
+        let a = self.coefficient;
+        let state = &mut self.states[self.channel];
+        let mut value = sample + self.feedback * state.last;
+        for (x_n1, y_n1) in state.x_n1.iter_mut().zip(state.y_n1.iter_mut()) {
+            // Flushed so that the stages do not compute with denormals while playing silence.
+            let y = flush_denormal(a * value + *x_n1 - a * *y_n1);
+            *x_n1 = value;
+            *y_n1 = y;
+            value = y;
+        }
+        state.last = value;
+
+        self.channel += 1;
+        if self.channel >= self.states.len() {
+            self.channel = 0;
+        }
+        Some(sample * (1.0 - self.mix) + value * self.mix)
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.input.size_hint()
+    }
+}
+
+impl<I> ExactSizeIterator for Phaser<I> where I: Source<Item = f32> + ExactSizeIterator {}
+
+impl<I> Source for Phaser<I>
+where
+    I: Source<Item = f32>,
+{
+    #[inline]
+    fn current_span_len(&self) -> Option<usize> {
+        self.input.current_span_len()
+    }
+
+    #[inline]
+    fn channels(&self) -> ChannelCount {
+        self.input.channels()
+    }
+
+    #[inline]
+    fn sample_rate(&self) -> SampleRate {
+        self.input.sample_rate()
+    }
+
+    #[inline]
+    fn total_duration(&self) -> Option<Duration> {
+        self.input.total_duration()
+    }
+
+    #[inline]
+    fn tail_duration(&self) -> Duration {
+        self.input.tail_duration()
+    }
+
+    #[inline]
+    fn latency(&self) -> Duration {
+        self.input.latency()
+    }
+
+    /// Clears the filters, the LFO keeps its position.
+    #[inline]
+    fn try_seek(&mut self, pos: Duration) -> Result<(), SeekError> {
+        self.input.try_seek(pos)?;
+        self.reset();
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f32::consts::TAU;
+
+    use crate::buffer::SamplesBuffer;
+    use crate::source::Source;
+
+    // Level of a sine at `freq` through the phaser, in windows of 10 milliseconds.
+    fn levels(freq: f32) -> Vec<f32> {
+        let input: Vec<f32> = (0..16_000)
+            .map(|i| (TAU * freq * i as f32 / 8000.0).sin())
+            .collect();
+        let output: Vec<f32> = SamplesBuffer::new(1, 8000, input)
+            .phaser(4, 0.5, 1.0, 0.0, 0.5)
+            .collect();
+        output
+            .chunks(80)
+            .map(|window| window.iter().fold(0.0f32, |peak, s| peak.max(s.abs())))
+            .collect()
+    }
+
+    #[test]
+    fn notches_sweep_over_time() {
+        // The stages sweep from 632 Hz up to 2 kHz and down to 200 Hz within two seconds and
+        // the lowest notch is at 0.41 times their frequency. It passes 600 Hz after about a
+        // quarter of a second and 200 Hz just after a second.
+        let quietest = |levels: &[f32]| levels.iter().copied().fold(f32::MAX, f32::min);
+        let high = levels(600.0);
+        let low = levels(200.0);
+        assert!(quietest(&high[15..30]) < 0.1);
+        assert!(quietest(&low[15..30]) > 0.7);
+        assert!(quietest(&low[100..115]) < 0.1);
+        assert!(quietest(&high[100..115]) > 0.5);
+        // at the top of the sweep the lowest notch is at 830 Hz
+        assert!(high[50] > 0.5 && low[50] > 0.9);
+    }
+
+    #[test]
+    fn channels_are_filtered_independently() {
+        let mono: Vec<f32> = (0..400).map(|i| (i as f32 * 0.3).sin()).collect();
+        let stereo: Vec<f32> = mono.iter().flat_map(|&s| [s, 0.0]).collect();
+        let expected: Vec<f32> = SamplesBuffer::new(1, 8000, mono)
+            .phaser(6, 2.0, 0.8, 0.5, 0.5)
+            .collect();
+        let output: Vec<f32> = SamplesBuffer::new(2, 8000, stereo)
+            .phaser(6, 2.0, 0.8, 0.5, 0.5)
+            .collect();
+        for (frame, expected) in output.chunks(2).zip(expected) {
+            assert_eq!(frame, [expected, 0.0]);
+        }
+    }
+}
@@ -0,0 +1,173 @@
+use std::time::Duration;
+
+use super::blt::flush_denormal;
+use super::SeekError;
+use crate::common::{ChannelCount, SampleRate};
+use crate::Source;
+
+/// Seed of the noise bursts, so a string sounds the same on every run.
+const SEED: u64 = 0x2545_f491_4f6c_dd1d;
+
+/// Level the tone has fallen to after `decay`, -60 dB.
+const DECAY_LEVEL: f32 = 0.001;
+
+/// An infinite source that synthesizes a plucked string with the Karplus-Strong algorithm.
+///
+/// A burst of noise circulates in a delay line one period of the note long, a feedback comb
+/// filter like [`CombFilter`](super::CombFilter). On every round trip neighbouring samples are
+/// averaged, which dampens the high harmonics faster than the low ones, and the whole signal
+/// is scaled so that it has faded by 60 dB after `decay` seconds. The length of the delay line
+/// is rounded to whole samples, so high notes can be slightly out of tune. Has one channel.
+///
+/// # Example
+///
+/// ```
+/// use rodio::source::PluckedString;
+///
+/// // an A string that rings for about two seconds
+/// let mut string = PluckedString::new(110.0, 2.0, 48000);
+/// // later, play the note again
+/// string.pluck();
+/// ```
+#[derive(Clone, Debug)]
+pub struct PluckedString {
+    line: Vec<f32>,
+    pos: usize,
+    // Gain applied on every round trip through the delay line.
+    gain: f32,
+    freq: f32,
+    sample_rate: SampleRate,
+    rng: u64,
+}
+
+impl PluckedString {
+    /// Plucks a string tuned to `freq` that fades out over `decay` seconds, at `sample_rate`.
+    ///
+    /// # Panics
+    ///
+    /// If `freq` is not positive or `sample_rate` is zero.
+    pub fn new(freq: f32, decay: f32, sample_rate: SampleRate) -> PluckedString {
+        assert!(freq > 0.0, "frequency must be greater than zero");
+        assert!(sample_rate > 0, "sample rate must be greater than zero");
+        // The averaging delays the signal by half a sample.
+        let len = (sample_rate as f32 / freq - 0.5).round().max(2.0) as usize;
+        let periods = freq * decay.max(f32::MIN_POSITIVE);
+        let mut string = PluckedString {
+            line: vec![0.0; len],
+            pos: 0,
+            gain: DECAY_LEVEL.powf(1.0 / periods),
+            freq,
+            sample_rate,
+            rng: SEED,
+        };
+        string.pluck();
+        string
+    }
+
+    /// Plucks the string again, the tone starts over at full strength.
+    pub fn pluck(&mut self) {
+        for sample in &mut self.line {
+            self.rng ^= self.rng << 13;
+            self.rng ^= self.rng >> 7;
+            self.rng ^= self.rng << 17;
+            // The top 24 bits mapped to -1.0..1.0.
+            *sample = (self.rng >> 40) as f32 / (1u64 << 23) as f32 - 1.0;
+        }
+        self.pos = 0;
+    }
+
+    /// Returns the frequency the string is tuned to.
+    #[inline]
+    pub fn freq(&self) -> f32 {
+        self.freq
+    }
+}
+
+impl Iterator for PluckedString {
+    type Item = f32;
+
+    #[inline]
+    fn next(&mut self) -> Option<f32> {
+        let len = self.line.len();
+        let output = self.line[self.pos];
+        let next = self.line[(self.pos + 1) % len];
+        self.line[self.pos] = flush_denormal(self.gain * 0.5 * (output + next));
+        self.pos = (self.pos + 1) % len;
+        Some(output)
+    }
+}
+
+impl Source for PluckedString {
+    #[inline]
+    fn current_span_len(&self) -> Option<usize> {
+        None
+    }
+
+    #[inline]
+    fn channels(&self) -> ChannelCount {
+        1
+    }
+
+    #[inline]
+    fn sample_rate(&self) -> SampleRate {
+        self.sample_rate
+    }
+
+    #[inline]
+    fn total_duration(&self) -> Option<Duration> {
+        None
+    }
+
+    #[inline]
+    fn try_seek(&mut self, _: Duration) -> Result<(), SeekError> {
+        Err(SeekError::NotSupported {
+            underlying_source: std::any::type_name::<Self>(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::PluckedString;
+
+    fn rms(samples: &[f32]) -> f32 {
+        (samples.iter().map(|s| s * s).sum::<f32>() / samples.len() as f32).sqrt()
+    }
+
+    // The lag in `range` at which the signal is most similar to itself.
+    fn period(samples: &[f32], range: std::ops::Range<usize>) -> usize {
+        let correlation =
+            |lag: usize| -> f32 { samples[lag..].iter().zip(samples).map(|(a, b)| a * b).sum() };
+        range
+            .max_by(|&a, &b| correlation(a).total_cmp(&correlation(b)))
+            .unwrap()
+    }
+
+    #[test]
+    fn plays_the_requested_note_and_fades() {
+        let rate = 44_100;
+        let output: Vec<f32> = PluckedString::new(220.0, 1.0, rate)
+            .take(rate as usize)
+            .collect();
+
+        let period = period(&output[4410..8820], 100..400);
+        assert!((rate as f32 / period as f32 - 220.0).abs() < 2.0);
+
+        let levels: Vec<f32> = output.chunks(4410).map(rms).collect();
+        assert!(levels.windows(2).all(|w| w[1] < w[0]));
+        // by the end of the decay time the tone has faded far below its first tenth of a second
+        assert!(levels[9] < levels[0] * 0.01);
+    }
+
+    #[test]
+    fn pluck_retriggers_the_tone() {
+        let mut string = PluckedString::new(440.0, 0.1, 8000);
+        let first = rms(&string.by_ref().take(100).collect::<Vec<_>>());
+        string.by_ref().take(8000).for_each(drop);
+        assert!(rms(&string.by_ref().take(100).collect::<Vec<_>>()) < first * 0.001);
+
+        string.pluck();
+        let again = rms(&string.take(100).collect::<Vec<_>>());
+        assert!((again - first).abs() < first * 0.5);
+    }
+}
@@ -1,7 +1,7 @@
 use std::time::Duration;
 
 use super::SeekError;
-use crate::common::{ChannelCount, SampleRate};
+use crate::common::{frames_to_duration, ChannelCount, SampleRate};
 use crate::{Sample, Source};
 
 /// Internal function that builds a `TrackPosition` object. See trait docs for
@@ -65,13 +65,26 @@ where
     /// track_position after speedup's and delay's.
     #[inline]
     pub fn get_pos(&self) -> Duration {
-        let seconds = self.samples_counted as f64
-            / self.input.sample_rate() as f64
-            / self.input.channels() as f64
-            + self.offset_duration;
+        let seconds = if self.samples_counted == 0 {
+            self.offset_duration
+        } else {
+            // Use the format the samples were counted with, the sample rate of the input can
+            // change at any moment, for example when a speedup is applied.
+            self.samples_counted as f64
+                / self.current_span_sample_rate as f64
+                / self.current_span_channels as f64
+                + self.offset_duration
+        };
         Duration::from_secs_f64(seconds)
     }
 
+    /// Overrides the position without seeking, for when the input jumped by itself.
+    #[inline]
+    pub(crate) fn set_pos(&mut self, pos: Duration) {
+        self.offset_duration = pos.as_secs_f64();
+        self.samples_counted = 0;
+    }
+
     #[inline]
     fn set_current_span(&mut self) {
         self.current_span_len = self.current_span_len();
@@ -89,6 +102,16 @@ where
 
     #[inline]
     fn next(&mut self) -> Option<I::Item> {
+        // The sample rate can change in the middle of a span, for example when the
+        // playback speed changes. Account for what was played at the previous rate.
+        if self.samples_counted > 0 && self.input.sample_rate() != self.current_span_sample_rate {
+            self.offset_duration += self.samples_counted as f64
+                / self.current_span_sample_rate as f64
+                / self.current_span_channels as f64;
+            self.samples_counted = 0;
+            self.set_current_span();
+        }
+
         // This should only be executed once at the first call to next.
         if self.current_span_len.is_none() {
             self.set_current_span();
@@ -144,6 +167,16 @@ where
         self.input.total_duration()
     }
 
+    #[inline]
+    fn tail_duration(&self) -> Duration {
+        self.input.tail_duration()
+    }
+
+    #[inline]
+    fn latency(&self) -> Duration {
+        self.input.latency()
+    }
+
     #[inline]
     fn try_seek(&mut self, pos: Duration) -> Result<(), SeekError> {
         let result = self.input.try_seek(pos);
@@ -156,6 +189,14 @@ where
         }
         result
     }
+
+    #[inline]
+    fn try_seek_frames(&mut self, frame: u64) -> Result<(), SeekError> {
+        self.input.try_seek_frames(frame)?;
+        let pos = frames_to_duration(frame, self.input.sample_rate());
+        self.set_pos(pos);
+        Ok(())
+    }
 }
 
 #[cfg(test)]
@@ -196,4 +237,23 @@ mod tests {
         assert_eq!(source.try_seek(Duration::new(1, 0)).is_ok(), true);
         assert_eq!(source.get_pos().as_secs_f32(), 1.0);
     }
+
+    #[test]
+    fn test_position_when_speed_changes() {
+        let inner = SamplesBuffer::new(1, 1, vec![10i16, -10, 10, -10, 20, -20]);
+        let mut source = inner.speed(1.0).track_position();
+
+        source.next();
+        source.next();
+        assert_eq!(source.get_pos().as_secs_f32(), 2.0);
+
+        // what has been played so far is not affected
+        source.inner_mut().set_factor(2.0);
+        assert_eq!(source.get_pos().as_secs_f32(), 2.0);
+
+        source.next();
+        assert_eq!(source.get_pos().as_secs_f32(), 2.5);
+        source.next();
+        assert_eq!(source.get_pos().as_secs_f32(), 3.0);
+    }
 }
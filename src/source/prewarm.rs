@@ -0,0 +1,274 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use super::SeekError;
+use crate::common::{ChannelCount, SampleRate};
+use crate::{Sample, Source};
+
+/// Internal function that builds a `Prewarm` object.
+pub fn prewarm<I, F, E>(input: I, duration: Duration, effect: F) -> Prewarm<E>
+where
+    I: Source,
+    I::Item: Sample,
+    F: FnOnce(PrewarmInput<I>) -> E,
+    E: Source<Item = I::Item>,
+{
+    let channels = input.channels().max(1) as usize;
+    let frames = (duration.as_secs_f64() * input.sample_rate() as f64).round() as usize;
+    let silence = frames * channels;
+    let warmed = Arc::new(AtomicBool::new(silence == 0));
+    let mut effect = effect(PrewarmInput {
+        input,
+        silence,
+        warmed: warmed.clone(),
+    });
+    // Runs the effect right away, off the audio thread, until it has taken all of the
+    // silence. What it made of it is thrown away, however many samples that are.
+    while !warmed.load(Ordering::Relaxed) {
+        if effect.next().is_none() {
+            break;
+        }
+    }
+    Prewarm { effect }
+}
+
+/// Runs an effect on silence before the source starts, so it has settled by the time the
+/// first sample of the source arrives. See [`Source::prewarm`].
+///
+/// The silence is played through the effect and thrown away when the `Prewarm` is created,
+/// so the source starts right away. This helps effects whose state changes over time or with
+/// the level of the signal, such as fades, gain controls and meters. Filters, delays and
+/// reverbs start out in the state silence leaves them in, for them it changes nothing.
+pub struct Prewarm<E> {
+    effect: E,
+}
+
+/// The source an effect passed to [`Source::prewarm`] is built on, it plays silence and then
+/// the source.
+pub struct PrewarmInput<I> {
+    input: I,
+    // Samples of silence left to play.
+    silence: usize,
+    // Set once the last sample of silence was taken.
+    warmed: Arc<AtomicBool>,
+}
+
+impl<I> Iterator for PrewarmInput<I>
+where
+    I: Source,
+    I::Item: Sample,
+{
+    type Item = I::Item;
+
+    #[inline]
+    fn next(&mut self) -> Option<I::Item> {
+        if self.silence > 0 {
+            self.silence -= 1;
+            if self.silence == 0 {
+                self.warmed.store(true, Ordering::Relaxed);
+            }
+            return Some(I::Item::ZERO_VALUE);
+        }
+        self.input.next()
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let (lower, upper) = self.input.size_hint();
+        (
+            lower.saturating_add(self.silence),
+            upper.and_then(|upper| upper.checked_add(self.silence)),
+        )
+    }
+}
+
+impl<I> Source for PrewarmInput<I>
+where
+    I: Source,
+    I::Item: Sample,
+{
+    #[inline]
+    fn current_span_len(&self) -> Option<usize> {
+        if self.silence > 0 {
+            Some(self.silence)
+        } else {
+            self.input.current_span_len()
+        }
+    }
+
+    #[inline]
+    fn channels(&self) -> ChannelCount {
+        self.input.channels()
+    }
+
+    #[inline]
+    fn sample_rate(&self) -> SampleRate {
+        self.input.sample_rate()
+    }
+
+    #[inline]
+    fn total_duration(&self) -> Option<Duration> {
+        let frames = self.silence / self.input.channels().max(1) as usize;
+        let silence = Duration::from_secs_f64(frames as f64 / self.sample_rate().max(1) as f64);
+        self.input
+            .total_duration()
+            .map(|duration| duration + silence)
+    }
+
+    #[inline]
+    fn tail_duration(&self) -> Duration {
+        self.input.tail_duration()
+    }
+
+    #[inline]
+    fn latency(&self) -> Duration {
+        self.input.latency()
+    }
+
+    #[inline]
+    fn try_seek(&mut self, pos: Duration) -> Result<(), SeekError> {
+        self.input.try_seek(pos)?;
+        self.silence = 0;
+        self.warmed.store(true, Ordering::Relaxed);
+        Ok(())
+    }
+}
+
+impl<E> Prewarm<E>
+where
+    E: Source,
+    E::Item: Sample,
+{
+    /// Returns a reference to the effect.
+    #[inline]
+    pub fn inner(&self) -> &E {
+        &self.effect
+    }
+
+    /// Returns a mutable reference to the effect.
+    #[inline]
+    pub fn inner_mut(&mut self) -> &mut E {
+        &mut self.effect
+    }
+
+    /// Returns the effect.
+    #[inline]
+    pub fn into_inner(self) -> E {
+        self.effect
+    }
+}
+
+impl<E> Iterator for Prewarm<E>
+where
+    E: Source,
+    E::Item: Sample,
+{
+    type Item = E::Item;
+
+    #[inline]
+    fn next(&mut self) -> Option<E::Item> {
+        self.effect.next()
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.effect.size_hint()
+    }
+}
+
+impl<E> Source for Prewarm<E>
+where
+    E: Source,
+    E::Item: Sample,
+{
+    #[inline]
+    fn current_span_len(&self) -> Option<usize> {
+        self.effect.current_span_len()
+    }
+
+    #[inline]
+    fn channels(&self) -> ChannelCount {
+        self.effect.channels()
+    }
+
+    #[inline]
+    fn sample_rate(&self) -> SampleRate {
+        self.effect.sample_rate()
+    }
+
+    #[inline]
+    fn total_duration(&self) -> Option<Duration> {
+        self.effect.total_duration()
+    }
+
+    #[inline]
+    fn tail_duration(&self) -> Duration {
+        self.effect.tail_duration()
+    }
+
+    #[inline]
+    fn latency(&self) -> Duration {
+        self.effect.latency()
+    }
+
+    /// The effect is not run on silence again.
+    #[inline]
+    fn try_seek(&mut self, pos: Duration) -> Result<(), SeekError> {
+        self.effect.try_seek(pos)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use crate::buffer::SamplesBuffer;
+    use crate::source::Source;
+
+    fn tone() -> SamplesBuffer<f32> {
+        SamplesBuffer::new(1, 1000, vec![0.5f32; 100])
+    }
+
+    #[test]
+    fn effect_has_settled_when_the_source_starts() {
+        // the gain control turns up a quiet signal, it starts at unity gain
+        let normalize = |input: crate::source::PrewarmInput<SamplesBuffer<f32>>| {
+            input.normalize_rms_streaming(-6.0, Duration::from_millis(10), 6.0)
+        };
+        let cold: Vec<f32> = tone().prewarm(Duration::ZERO, normalize).collect();
+        let warm: Vec<f32> = tone()
+            .prewarm(Duration::from_millis(100), normalize)
+            .collect();
+        assert_eq!(cold.len(), 100);
+        assert_eq!(warm.len(), 100);
+
+        // after silence the gain is at its maximum of 6 dB right away
+        assert!((cold[0] - 0.5).abs() < 0.01);
+        assert!((warm[0] - 0.5 * 1.995).abs() < 0.01);
+    }
+
+    #[test]
+    fn durations_leave_out_the_silence() {
+        let source = tone().prewarm(Duration::from_millis(50), |input| {
+            input.fade_in(Duration::from_millis(50))
+        });
+        assert_eq!(source.total_duration(), Some(Duration::from_millis(100)));
+        // the fade is over by the time the source starts
+        assert!(source.take(10).all(|s| s == 0.5));
+    }
+
+    #[test]
+    fn silence_is_dropped_when_the_effect_changes_the_format() {
+        // the effect plays the silence at twice the rate and in stereo
+        let source = tone().prewarm(Duration::from_millis(50), |input| {
+            crate::source::UniformSourceIterator::<_, f32>::new(input, 2, 2000)
+        });
+        let played: Vec<f32> = source.collect();
+        // only the few samples the resampler reads ahead are left of the silence
+        let start = played.iter().position(|&s| s != 0.0).unwrap();
+        assert!(start < 10);
+        assert_eq!(played.len() - start, 400);
+        assert!(played[start..].iter().all(|&s| (s - 0.5).abs() < 1e-6));
+    }
+}
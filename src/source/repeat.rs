@@ -1,3 +1,5 @@
+use std::collections::VecDeque;
+use std::mem;
 use std::time::Duration;
 
 use crate::source::buffered::Buffered;
@@ -16,10 +18,17 @@ where
     Repeat {
         inner: input.clone(),
         next: input,
+        body_len: None,
+        played: 0,
+        tails: Vec::new(),
     }
 }
 
 /// A source that repeats the given source.
+///
+/// By default every repetition starts once the previous one has ended, see
+/// [`with_tail_overlap`](Repeat::with_tail_overlap) to let the tail of an effect ring on
+/// into the next one.
 pub struct Repeat<I>
 where
     I: Source,
@@ -27,6 +36,42 @@ where
 {
     inner: Buffered<I>,
     next: Buffered<I>,
+    // Samples after which the next repetition starts while the current one plays its tail,
+    // if the tail overlaps.
+    body_len: Option<usize>,
+    // Samples played of the current repetition.
+    played: usize,
+    // Earlier repetitions that are still playing their tail.
+    tails: Vec<Buffered<I>>,
+}
+
+impl<I> Repeat<I>
+where
+    I: Source,
+    I::Item: Sample,
+{
+    /// Plays the tail of every repetition, as reported by [`Source::tail_duration`], over
+    /// the start of the next repetition instead of before it. The tails are added to the
+    /// samples.
+    ///
+    /// This keeps the rhythm of the loop when the source ends with a tail, such as an echo
+    /// or a reverb, instead of cutting the tail off or waiting for it. Needs the total
+    /// duration of the source to be known and the tail to be shorter than the source,
+    /// otherwise it has no effect. Meant to be called before playback starts.
+    pub fn with_tail_overlap(mut self, overlap: bool) -> Self {
+        self.body_len = None;
+        if overlap {
+            let start = &self.next;
+            let channels = start.channels().max(1) as u64;
+            let frames = start.tail_duration().as_secs_f64() * start.sample_rate() as f64;
+            let tail_len = frames.round() as u64 * channels;
+            self.body_len = start
+                .total_samples()
+                .filter(|&total| tail_len > 0 && total > tail_len)
+                .map(|total| (total - tail_len) as usize);
+        }
+        self
+    }
 }
 
 impl<I> Iterator for Repeat<I>
@@ -38,12 +83,34 @@ where
 
     #[inline]
     fn next(&mut self) -> Option<<I as Iterator>::Item> {
-        if let Some(value) = self.inner.next() {
-            return Some(value);
+        if self
+            .body_len
+            .is_some_and(|body_len| self.played >= body_len)
+        {
+            let ringing = mem::replace(&mut self.inner, self.next.clone());
+            self.tails.push(ringing);
+            self.played = 0;
         }
 
-        self.inner = self.next.clone();
-        self.inner.next()
+        let mut value = match self.inner.next() {
+            Some(value) => value,
+            None => {
+                self.inner = self.next.clone();
+                self.played = 0;
+                self.inner.next()?
+            }
+        };
+        self.played += 1;
+        if !self.tails.is_empty() {
+            self.tails.retain_mut(|tail| match tail.next() {
+                Some(sample) => {
+                    value = value.saturating_add(sample);
+                    true
+                }
+                None => false,
+            });
+        }
+        Some(value)
     }
 
     #[inline]
@@ -87,6 +154,11 @@ where
         None
     }
 
+    #[inline]
+    fn latency(&self) -> Duration {
+        self.inner.latency()
+    }
+
     #[inline]
     fn try_seek(&mut self, pos: Duration) -> Result<(), SeekError> {
         self.inner.try_seek(pos)
@@ -103,6 +175,372 @@ where
         Repeat {
             inner: self.inner.clone(),
             next: self.next.clone(),
+            body_len: self.body_len,
+            played: self.played,
+            tails: self.tails.clone(),
         }
     }
 }
+
+/// Internal function that builds a `RepeatCrossfade` object.
+pub fn repeat_crossfade<I>(input: I, fade: Duration) -> RepeatCrossfade<I>
+where
+    I: Source,
+    I::Item: Sample,
+{
+    let mut frames = (fade.as_secs_f64() * input.sample_rate() as f64) as usize;
+    if let Some(total) = input.total_duration() {
+        let total_frames = (total.as_secs_f64() * input.sample_rate() as f64) as usize;
+        frames = frames.min(total_frames / 2);
+    }
+    let fade_samples = frames * input.channels() as usize;
+    let input = input.buffered();
+    RepeatCrossfade {
+        current: input.clone(),
+        start: input,
+        tail: VecDeque::with_capacity(fade_samples + 1),
+        fade_samples,
+        blend_len: 0,
+        blend_remaining: 0,
+        read_since_restart: false,
+        body_len: None,
+        played: 0,
+        tails: Vec::new(),
+    }
+}
+
+/// A source that repeats the given source, crossfading the end of every repetition into the
+/// start of the next one.
+///
+/// Each repetition after the first one starts where the crossfade ended, so one repetition
+/// is shorter than the source by the length of the crossfade. See
+/// [`with_tail_overlap`](RepeatCrossfade::with_tail_overlap) to crossfade before the tail of
+/// an effect instead.
+pub struct RepeatCrossfade<I>
+where
+    I: Source,
+    I::Item: Sample,
+{
+    start: Buffered<I>,
+    current: Buffered<I>,
+    // The last samples read from `current`, held back until it is known whether they are
+    // part of the crossfade at the end.
+    tail: VecDeque<I::Item>,
+    fade_samples: usize,
+    // Length of the ongoing crossfade and how many samples of it are left.
+    blend_len: usize,
+    blend_remaining: usize,
+    // Used to stop on an empty source instead of restarting it forever.
+    read_since_restart: bool,
+    // Samples after which a repetition is crossfaded into the next one while its tail plays
+    // on, if the tail overlaps.
+    body_len: Option<usize>,
+    // Samples read from `current`.
+    played: usize,
+    // Earlier repetitions that are still playing their tail, with the number of samples to
+    // wait before the tail starts.
+    tails: Vec<(usize, Buffered<I>)>,
+}
+
+impl<I> RepeatCrossfade<I>
+where
+    I: Source,
+    I::Item: Sample,
+{
+    /// Crossfades the end of every repetition without its tail, as reported by
+    /// [`Source::tail_duration`], into the start of the next repetition. The tail plays on
+    /// over the next repetition from where the crossfade ends and is added to the samples.
+    ///
+    /// Like [`Repeat::with_tail_overlap`] this keeps an echo or a reverb at the end of the
+    /// source from being faded out at the seam. Needs the total duration of the source to be
+    /// known and the tail to be shorter than the source, otherwise it has no effect. The
+    /// crossfade is shortened to half of the source without its tail if needed. Meant to be
+    /// called before playback starts.
+    pub fn with_tail_overlap(mut self, overlap: bool) -> Self {
+        self.body_len = None;
+        if overlap {
+            let start = &self.start;
+            let channels = start.channels().max(1) as u64;
+            let frames = start.tail_duration().as_secs_f64() * start.sample_rate() as f64;
+            let tail_len = frames.round() as u64 * channels;
+            self.body_len = start
+                .total_samples()
+                .filter(|&total| tail_len > 0 && total > tail_len)
+                .map(|total| (total - tail_len) as usize);
+            if let Some(body_len) = self.body_len {
+                let frames = body_len / channels as usize / 2;
+                self.fade_samples = self.fade_samples.min(frames * channels as usize);
+            }
+        }
+        self
+    }
+
+    // Reads from the current repetition, which ends before its tail if the tail overlaps.
+    fn next_current(&mut self) -> Option<I::Item> {
+        if self
+            .body_len
+            .is_some_and(|body_len| self.played >= body_len)
+        {
+            return None;
+        }
+        let sample = self.current.next()?;
+        self.played += 1;
+        self.read_since_restart = true;
+        Some(sample)
+    }
+
+    fn next_blended(&mut self) -> Option<I::Item> {
+        loop {
+            if self.blend_remaining > 0 {
+                return Some(self.blend());
+            }
+
+            match self.next_current() {
+                Some(sample) => {
+                    self.tail.push_back(sample);
+                    if self.tail.len() > self.fade_samples {
+                        return self.tail.pop_front();
+                    }
+                }
+                None if !self.read_since_restart => return None,
+                None => {
+                    let ended = mem::replace(&mut self.current, self.start.clone());
+                    if self.body_len.is_some() {
+                        // the tail starts where the body ends, after the crossfade
+                        self.tails.push((self.tail.len(), ended));
+                    }
+                    self.played = 0;
+                    self.read_since_restart = false;
+                    self.blend_len = self.tail.len();
+                    self.blend_remaining = self.tail.len();
+                }
+            }
+        }
+    }
+
+    fn blend(&mut self) -> I::Item {
+        let channels = self.current.channels().max(1) as usize;
+        let frames = self.blend_len / channels;
+        let frame = (self.blend_len - self.blend_remaining) / channels;
+        self.blend_remaining -= 1;
+
+        // Neither end of the fade is at full gain, so the crossfade does not start or end
+        // with a jump.
+        let t = (frame + 1) as f32 / (frames + 1) as f32;
+        let old = self.tail.pop_front().unwrap_or(I::Item::ZERO_VALUE);
+        let new = self.next_current().unwrap_or(I::Item::ZERO_VALUE);
+        old.amplify(1.0 - t).saturating_add(new.amplify(t))
+    }
+}
+
+impl<I> Iterator for RepeatCrossfade<I>
+where
+    I: Source,
+    I::Item: Sample,
+{
+    type Item = I::Item;
+
+    #[inline]
+    fn next(&mut self) -> Option<I::Item> {
+        let mut value = self.next_blended()?;
+        if !self.tails.is_empty() {
+            self.tails.retain_mut(|(wait, tail)| {
+                if *wait > 0 {
+                    *wait -= 1;
+                    return true;
+                }
+                match tail.next() {
+                    Some(sample) => {
+                        value = value.saturating_add(sample);
+                        true
+                    }
+                    None => false,
+                }
+            });
+        }
+        Some(value)
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        // infinite
+        (0, None)
+    }
+}
+
+impl<I> Source for RepeatCrossfade<I>
+where
+    I: Iterator + Source,
+    I::Item: Sample,
+{
+    #[inline]
+    fn current_span_len(&self) -> Option<usize> {
+        // The held back samples and the crossfades shift the spans of the source.
+        None
+    }
+
+    #[inline]
+    fn channels(&self) -> ChannelCount {
+        self.current.channels()
+    }
+
+    #[inline]
+    fn sample_rate(&self) -> SampleRate {
+        self.current.sample_rate()
+    }
+
+    #[inline]
+    fn total_duration(&self) -> Option<Duration> {
+        None
+    }
+
+    /// The latency of the source, plus the samples held back for the crossfade.
+    #[inline]
+    fn latency(&self) -> Duration {
+        let channels = self.current.channels().max(1) as usize;
+        let rate = self.current.sample_rate().max(1) as f64;
+        let held = Duration::from_secs_f64((self.fade_samples / channels) as f64 / rate);
+        self.current.latency() + held
+    }
+
+    #[inline]
+    fn try_seek(&mut self, pos: Duration) -> Result<(), SeekError> {
+        self.current.try_seek(pos)?;
+        self.tail.clear();
+        self.blend_len = 0;
+        self.blend_remaining = 0;
+        self.tails.clear();
+        Ok(())
+    }
+}
+
+impl<I> Clone for RepeatCrossfade<I>
+where
+    I: Source,
+    I::Item: Sample,
+{
+    #[inline]
+    fn clone(&self) -> RepeatCrossfade<I> {
+        RepeatCrossfade {
+            start: self.start.clone(),
+            current: self.current.clone(),
+            tail: self.tail.clone(),
+            fade_samples: self.fade_samples,
+            blend_len: self.blend_len,
+            blend_remaining: self.blend_remaining,
+            read_since_restart: self.read_since_restart,
+            body_len: self.body_len,
+            played: self.played,
+            tails: self.tails.clone(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use approx::assert_abs_diff_eq;
+
+    use crate::buffer::SamplesBuffer;
+    use crate::source::Source;
+
+    #[test]
+    fn echo_rings_into_next_repetition() {
+        // a click every 10 milliseconds with an echo 15 milliseconds later
+        let mut click = vec![0.0f32; 10];
+        click[0] = 1.0;
+        let source = SamplesBuffer::new(1, 1000, click).reverb(Duration::from_millis(15), 0.5);
+        assert_eq!(source.tail_duration(), Duration::from_millis(15));
+
+        let output: Vec<f32> = source
+            .clone()
+            .repeat_infinite()
+            .with_tail_overlap(true)
+            .take(50)
+            .collect();
+        for (i, sample) in output.iter().enumerate() {
+            let click = if i % 10 == 0 { 1.0 } else { 0.0 };
+            let echo = if i >= 15 && i % 10 == 5 { 0.5 } else { 0.0 };
+            assert_eq!(*sample, click + echo, "sample {i}");
+        }
+
+        // otherwise the echo plays before the next repetition starts
+        let output: Vec<f32> = source.repeat_infinite().take(50).collect();
+        assert_eq!((output[10], output[15], output[25]), (0.0, 0.5, 1.0));
+    }
+
+    #[test]
+    fn echo_rings_across_the_crossfade() {
+        // a click every 10 milliseconds with an echo 15 milliseconds later
+        let mut click = vec![0.0f32; 10];
+        click[0] = 1.0;
+        let source = SamplesBuffer::new(1, 1000, click).reverb(Duration::from_millis(15), 0.5);
+
+        let output: Vec<f32> = source
+            .repeat_infinite_crossfade(Duration::from_millis(2))
+            .with_tail_overlap(true)
+            .take(40)
+            .collect();
+        // every repetition is 8 samples long, the clicks after the first one are at the
+        // start of a crossfade
+        for (i, sample) in output.iter().enumerate() {
+            let click = match i {
+                0 => 1.0,
+                _ if i % 8 == 0 => 1.0 / 3.0,
+                _ => 0.0,
+            };
+            let echo = if i >= 15 && i % 8 == 7 { 0.5 } else { 0.0 };
+            assert_abs_diff_eq!(*sample, click + echo, epsilon = 1e-6);
+        }
+    }
+
+    #[test]
+    fn seam_is_blended() {
+        let data = vec![1.0f32, 1.0, 1.0, 1.0, -1.0, -1.0, -1.0, -1.0];
+        let source =
+            SamplesBuffer::new(1, 4, data).repeat_infinite_crossfade(Duration::from_millis(500));
+        let output: Vec<f32> = source.take(18).collect();
+
+        let third = 1.0 / 3.0;
+        let expected = [
+            1.0, 1.0, 1.0, 1.0, -1.0, -1.0, // first repetition up to the fade
+            -third, third, // the crossfade
+            1.0, 1.0, -1.0, -1.0, // the rest of the second repetition
+            -third, third, 1.0, 1.0, -1.0, -1.0,
+        ];
+        for (sample, expected) in output.iter().zip(expected) {
+            assert_abs_diff_eq!(*sample, expected, epsilon = 1e-6);
+        }
+    }
+
+    #[test]
+    fn fade_longer_than_the_source_keeps_repeating() {
+        let data = vec![1.0f32, 1.0, 1.0, 1.0, -1.0, -1.0, -1.0, -1.0];
+        let source =
+            SamplesBuffer::new(1, 4, data).repeat_infinite_crossfade(Duration::from_secs(3));
+        let output: Vec<f32> = source.take(40).collect();
+        assert_eq!(output.len(), 40);
+
+        // the fade is limited to half of the source, every repetition is one crossfade
+        assert_eq!(output[..4], [1.0; 4]);
+        for (i, sample) in output[4..].iter().enumerate() {
+            let expected = [-0.6, -0.2, 0.2, 0.6][i % 4];
+            assert_abs_diff_eq!(*sample, expected, epsilon = 1e-6);
+        }
+    }
+
+    #[test]
+    fn without_fade_repeats_plainly() {
+        let source =
+            SamplesBuffer::new(2, 1, vec![1i16, 2, 3, 4]).repeat_infinite_crossfade(Duration::ZERO);
+        assert_eq!(source.take(8).collect::<Vec<_>>(), [1, 2, 3, 4, 1, 2, 3, 4]);
+    }
+
+    #[test]
+    fn empty_source_ends() {
+        let source = SamplesBuffer::new(1, 1, Vec::<f32>::new())
+            .repeat_infinite_crossfade(Duration::from_secs(1));
+        assert_eq!(source.count(), 0);
+    }
+}
@@ -0,0 +1,197 @@
+use std::time::Duration;
+
+use super::SeekError;
+use crate::common::{ChannelCount, SampleRate};
+use crate::{Sample, Source};
+
+/// Internal function that builds a `RouteChannels` object.
+pub fn route_to_channels<I>(input: I, indices: &[usize]) -> RouteChannels<I>
+where
+    I: Source,
+    I::Item: Sample,
+{
+    assert_eq!(
+        indices.len(),
+        input.channels() as usize,
+        "there must be one output channel per channel"
+    );
+    let highest = indices.iter().copied().max().unwrap_or(0);
+    // At least two, so that a mixer does not copy a single channel to the second one.
+    let channels = (highest + 1).max(2);
+    RouteChannels {
+        input,
+        indices: indices.to_vec(),
+        frame: vec![I::Item::ZERO_VALUE; channels],
+        pos: channels,
+    }
+}
+
+/// Places the channels of a source on chosen channels of a wider output, for example to play
+/// one source on outputs 3 and 4 of an audio interface and another one on outputs 1 and 2.
+///
+/// Channel `n` of the source plays on output channel `indices[n]`, channels that several
+/// indices point to play their sum. The output has as many channels as needed to hold the
+/// highest index, but at least two, the others are silent. A [`Mixer`](crate::mixer::Mixer)
+/// with more channels pads the ones above with silence. The channel count of the source at
+/// the time this was created is used throughout.
+#[derive(Clone, Debug)]
+pub struct RouteChannels<I>
+where
+    I: Source,
+    I::Item: Sample,
+{
+    input: I,
+    indices: Vec<usize>,
+    // The output frame being played and the position of the next sample in it.
+    frame: Vec<I::Item>,
+    pos: usize,
+}
+
+impl<I> RouteChannels<I>
+where
+    I: Source,
+    I::Item: Sample,
+{
+    /// Returns the output channel of every channel of the source.
+    #[inline]
+    pub fn indices(&self) -> &[usize] {
+        &self.indices
+    }
+
+    /// Returns a reference to the inner source.
+    #[inline]
+    pub fn inner(&self) -> &I {
+        &self.input
+    }
+
+    /// Returns a mutable reference to the inner source.
+    #[inline]
+    pub fn inner_mut(&mut self) -> &mut I {
+        &mut self.input
+    }
+
+    /// Returns the inner source.
+    #[inline]
+    pub fn into_inner(self) -> I {
+        self.input
+    }
+
+    // Reads the next frame of the source, returns false if it has ended.
+    fn next_frame(&mut self) -> bool {
+        self.frame.fill(I::Item::ZERO_VALUE);
+        let mut read = false;
+        for &index in &self.indices {
+            // An incomplete frame at the end is played as if the missing channels were silent.
+            let Some(sample) = self.input.next() else {
+                break;
+            };
+            self.frame[index] = self.frame[index].saturating_add(sample);
+            read = true;
+        }
+        if read {
+            self.pos = 0;
+        }
+        read
+    }
+}
+
+impl<I> Iterator for RouteChannels<I>
+where
+    I: Source,
+    I::Item: Sample,
+{
+    type Item = I::Item;
+
+    #[inline]
+    fn next(&mut self) -> Option<I::Item> {
+        if self.pos >= self.frame.len() && !self.next_frame() {
+            return None;
+        }
+        let sample = self.frame[self.pos];
+        self.pos += 1;
+        Some(sample)
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let pending = self.frame.len() - self.pos;
+        let from = self.indices.len().max(1);
+        let to = self.frame.len();
+        let (lower, upper) = self.input.size_hint();
+        let convert = |len: usize| len.div_ceil(from) * to + pending;
+        (lower / from * to + pending, upper.map(convert))
+    }
+}
+
+impl<I> Source for RouteChannels<I>
+where
+    I: Source,
+    I::Item: Sample,
+{
+    #[inline]
+    fn current_span_len(&self) -> Option<usize> {
+        let pending = self.frame.len() - self.pos;
+        self.input
+            .current_span_len()
+            .map(|len| len.div_ceil(self.indices.len().max(1)) * self.frame.len() + pending)
+    }
+
+    #[inline]
+    fn channels(&self) -> ChannelCount {
+        self.frame.len() as ChannelCount
+    }
+
+    #[inline]
+    fn sample_rate(&self) -> SampleRate {
+        self.input.sample_rate()
+    }
+
+    #[inline]
+    fn total_duration(&self) -> Option<Duration> {
+        self.input.total_duration()
+    }
+
+    #[inline]
+    fn tail_duration(&self) -> Duration {
+        self.input.tail_duration()
+    }
+
+    #[inline]
+    fn latency(&self) -> Duration {
+        self.input.latency()
+    }
+
+    #[inline]
+    fn try_seek(&mut self, pos: Duration) -> Result<(), SeekError> {
+        self.input.try_seek(pos)?;
+        self.pos = self.frame.len();
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::buffer::SamplesBuffer;
+    use crate::mixer;
+    use crate::source::Source;
+
+    #[test]
+    fn stereo_plays_on_the_upper_channels_of_four() {
+        let stereo = SamplesBuffer::new(2, 1000, vec![1.0f32, 2.0, 3.0, 4.0]);
+        let (mixer, output) = mixer::mixer(4, 1000);
+        mixer.add(stereo.route_to_channels(&[2, 3]));
+        let output: Vec<f32> = output.collect();
+        assert_eq!(output, [0.0, 0.0, 1.0, 2.0, 0.0, 0.0, 3.0, 4.0]);
+    }
+
+    #[test]
+    fn channels_can_be_swapped_and_summed() {
+        let stereo = SamplesBuffer::new(2, 1000, vec![1i16, 2, 3, 4]);
+        let swapped = stereo.clone().route_to_channels(&[1, 0]);
+        assert_eq!(swapped.channels(), 2);
+        assert_eq!(swapped.collect::<Vec<_>>(), [2, 1, 4, 3]);
+
+        let summed = stereo.route_to_channels(&[0, 0]);
+        assert_eq!(summed.collect::<Vec<_>>(), [3, 0, 7, 0]);
+    }
+}
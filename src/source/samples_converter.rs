@@ -97,6 +97,16 @@ where
         self.inner.total_duration()
     }
 
+    #[inline]
+    fn tail_duration(&self) -> Duration {
+        self.inner.tail_duration()
+    }
+
+    #[inline]
+    fn latency(&self) -> Duration {
+        self.inner.latency()
+    }
+
     #[inline]
     fn try_seek(&mut self, pos: Duration) -> Result<(), SeekError> {
         self.inner.try_seek(pos)
@@ -0,0 +1,324 @@
+use std::f32::consts::TAU;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use super::SeekError;
+use crate::common::{frames_to_duration, ChannelCount, SampleRate};
+use crate::{Sample, Source};
+
+/// Grain length used unless [`Scrub::with_grain_duration`] is called.
+const DEFAULT_GRAIN: Duration = Duration::from_millis(40);
+
+/// Fastest a grain is played, in either direction. Faster movements play the grain at this
+/// rate, still centered on the target position.
+const MAX_RATE: f32 = 4.0;
+
+/// Internal function that builds a `Scrub` object.
+pub fn scrub<I>(input: I) -> Scrub<I>
+where
+    I: Source,
+    I::Item: Sample,
+{
+    let mut scrub = Scrub {
+        channels: input.channels().max(1),
+        sample_rate: input.sample_rate().max(1),
+        input,
+        scrubber: Scrubber {
+            target: Arc::new(AtomicU64::new(0)),
+        },
+        last_target: None,
+        hop: 0,
+        window: Vec::new(),
+        output: Vec::new(),
+        read: Vec::new(),
+        pos: 0,
+    };
+    scrub.set_grain_duration(DEFAULT_GRAIN);
+    scrub
+}
+
+/// Plays the classic scrubbing sound of a source while its playhead is dragged around, as
+/// in a waveform editor.
+///
+/// Move the playhead with a [`Scrubber`] from [`Scrub::scrubber`]. The output is made of
+/// short overlapping grains of the input centered on the playhead. Every grain is played at
+/// the speed and in the direction the playhead moved since the previous grain, so dragging
+/// it slowly plays the sound slowly and dragging it backwards plays the sound backwards. A
+/// playhead that stands still is silent. Consecutive grains are crossfaded with a Hann
+/// window, a new grain starts every half grain.
+///
+/// The input is seeked to every grain, so it has to support seeking. Grains it fails to seek
+/// to are silent. The output never ends.
+#[derive(Clone, Debug)]
+pub struct Scrub<I> {
+    input: I,
+    channels: ChannelCount,
+    sample_rate: SampleRate,
+    scrubber: Scrubber,
+    // Playhead at the start of the previous grain, in frames.
+    last_target: Option<f64>,
+    // Frames between the start of two grains, half a grain.
+    hop: usize,
+    window: Vec<f32>,
+    // Overlap-added grains, one grain long. The first hop is being played.
+    output: Vec<f32>,
+    // Input frames read for the next grain.
+    read: Vec<f32>,
+    // Position of the next sample to play in `output`.
+    pos: usize,
+}
+
+/// Moves the playhead of a [`Scrub`], for example from a UI thread.
+///
+/// Can be cloned and sent to another thread, get one with [`Scrub::scrubber`].
+#[derive(Clone, Debug)]
+pub struct Scrubber {
+    // Position in nanoseconds.
+    target: Arc<AtomicU64>,
+}
+
+impl Scrubber {
+    /// Moves the playhead to `pos` in the source. The next grain is centered on it.
+    #[inline]
+    pub fn set_position(&self, pos: Duration) {
+        let nanos = pos.as_nanos().min(u64::MAX as u128) as u64;
+        self.target.store(nanos, Ordering::Relaxed);
+    }
+
+    /// Returns the position of the playhead.
+    #[inline]
+    pub fn position(&self) -> Duration {
+        Duration::from_nanos(self.target.load(Ordering::Relaxed))
+    }
+}
+
+impl<I> Scrub<I>
+where
+    I: Source,
+    I::Item: Sample,
+{
+    /// Sets the length of the grains, 40 milliseconds by default. Shorter grains follow the
+    /// playhead more closely but sound rougher.
+    ///
+    /// Meant to be called before playback starts, the grains already playing are dropped.
+    #[inline]
+    pub fn with_grain_duration(mut self, grain: Duration) -> Self {
+        self.set_grain_duration(grain);
+        self
+    }
+
+    /// Returns a handle to move the playhead from another thread.
+    #[inline]
+    pub fn scrubber(&self) -> Scrubber {
+        self.scrubber.clone()
+    }
+
+    /// Moves the playhead to `pos` in the source, see [`Scrubber::set_position`].
+    #[inline]
+    pub fn set_position(&mut self, pos: Duration) {
+        self.scrubber.set_position(pos);
+    }
+
+    /// Returns a reference to the inner source.
+    #[inline]
+    pub fn inner(&self) -> &I {
+        &self.input
+    }
+
+    /// Returns a mutable reference to the inner source.
+    #[inline]
+    pub fn inner_mut(&mut self) -> &mut I {
+        &mut self.input
+    }
+
+    /// Returns the inner source.
+    #[inline]
+    pub fn into_inner(self) -> I {
+        self.input
+    }
+
+    fn set_grain_duration(&mut self, grain: Duration) {
+        let frames = (grain.as_secs_f64() * self.sample_rate as f64).round() as usize;
+        self.hop = (frames / 2).max(1);
+        let len = self.hop * 2;
+        // A periodic Hann window, two of them overlapping by half add up to one.
+        self.window = (0..len)
+            .map(|k| 0.5 - 0.5 * (TAU * k as f32 / len as f32).cos())
+            .collect();
+        let samples = len * self.channels as usize;
+        self.output.clear();
+        self.output.resize(samples, 0.0);
+        self.pos = self.hop * self.channels as usize;
+    }
+
+    // Drops the hop that was just played and adds the next grain.
+    fn next_grain(&mut self) {
+        let channels = self.channels as usize;
+        let hop_samples = self.hop * channels;
+        self.output.copy_within(hop_samples.., 0);
+        self.output[hop_samples..].fill(0.0);
+        self.pos = 0;
+
+        let nanos = self.scrubber.target.load(Ordering::Relaxed);
+        let target = nanos as f64 * self.sample_rate as f64 / 1_000_000_000.0;
+        let moved = target - self.last_target.unwrap_or(target);
+        self.last_target = Some(target);
+        let rate = (moved / self.hop as f64).clamp(-MAX_RATE as f64, MAX_RATE as f64);
+        if rate == 0.0 {
+            return;
+        }
+
+        // Frame `k` of the grain plays the input at `target + rate * (k - hop)`.
+        let len = self.window.len();
+        let first = target - rate.abs() * self.hop as f64;
+        let start = first.max(0.0).floor();
+        let frames = (rate.abs() * len as f64).ceil() as usize + 2;
+        if !self.read_frames(start as u64, frames) {
+            return;
+        }
+        let read_frames = self.read.len() / channels;
+
+        for (k, &window) in self.window.iter().enumerate() {
+            let at = target + rate * (k as f64 - self.hop as f64) - start;
+            if at < 0.0 {
+                continue;
+            }
+            let index = at.floor() as usize;
+            if index + 1 >= read_frames {
+                continue;
+            }
+            let fract = (at - index as f64) as f32;
+            for channel in 0..channels {
+                let a = self.read[index * channels + channel];
+                let b = self.read[(index + 1) * channels + channel];
+                self.output[k * channels + channel] += (a + (b - a) * fract) * window;
+            }
+        }
+    }
+
+    // Reads up to `frames` frames of the input from `start`. Returns false if seeking failed.
+    fn read_frames(&mut self, start: u64, frames: usize) -> bool {
+        self.read.clear();
+        if self
+            .input
+            .try_seek(frames_to_duration(start, self.sample_rate))
+            .is_err()
+        {
+            return false;
+        }
+        let samples = frames * self.channels as usize;
+        self.read
+            .extend(self.input.by_ref().take(samples).map(|s| s.to_f32()));
+        true
+    }
+}
+
+impl<I> Iterator for Scrub<I>
+where
+    I: Source,
+    I::Item: Sample,
+{
+    type Item = f32;
+
+    #[inline]
+    fn next(&mut self) -> Option<f32> {
+        if self.pos >= self.hop * self.channels as usize {
+            self.next_grain();
+        }
+        let sample = self.output[self.pos];
+        self.pos += 1;
+        Some(sample)
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (usize::MAX, None)
+    }
+}
+
+impl<I> Source for Scrub<I>
+where
+    I: Source,
+    I::Item: Sample,
+{
+    #[inline]
+    fn current_span_len(&self) -> Option<usize> {
+        None
+    }
+
+    #[inline]
+    fn channels(&self) -> ChannelCount {
+        self.channels
+    }
+
+    #[inline]
+    fn sample_rate(&self) -> SampleRate {
+        self.sample_rate
+    }
+
+    #[inline]
+    fn total_duration(&self) -> Option<Duration> {
+        None
+    }
+
+    /// Moves the playhead to `pos` without playing the way there.
+    #[inline]
+    fn try_seek(&mut self, pos: Duration) -> Result<(), SeekError> {
+        self.set_position(pos);
+        self.last_target = None;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use crate::buffer::SamplesBuffer;
+    use crate::source::Source;
+
+    fn ramp() -> SamplesBuffer<f32> {
+        // stereo at 1 kHz, the left channel counts frames, the right one counts backwards
+        let data: Vec<f32> = (0..2000)
+            .flat_map(|frame| [frame as f32, -(frame as f32)])
+            .collect();
+        SamplesBuffer::new(2, 1000, data)
+    }
+
+    #[test]
+    fn forward_movement_plays_forward() {
+        // grains of 40 frames, a new one every 20 frames
+        let mut scrub = ramp().scrub();
+        let scrubber = scrub.scrubber();
+
+        let mut output = Vec::new();
+        for hop in 0..20 {
+            scrubber.set_position(Duration::from_millis(500 + 20 * hop));
+            output.extend(scrub.by_ref().take(40));
+        }
+
+        // once grains overlap, moving at the normal speed reproduces the input
+        for (frame, samples) in output.chunks(2).enumerate().skip(40) {
+            let expected = 500.0 + frame as f32 - 20.0;
+            assert!((samples[0] - expected).abs() < 1e-3, "frame {frame}");
+            assert!((samples[1] + expected).abs() < 1e-3, "frame {frame}");
+        }
+    }
+
+    #[test]
+    fn still_playhead_is_silent() {
+        let mut scrub = ramp().scrub();
+        scrub.set_position(Duration::from_millis(700));
+        assert!(scrub.by_ref().take(400).all(|s| s == 0.0));
+
+        // moving backwards plays backwards
+        let scrubber = scrub.scrubber();
+        let mut output = Vec::new();
+        for hop in 0..10 {
+            scrubber.set_position(Duration::from_millis(700 - 20 * hop));
+            output.extend(scrub.by_ref().take(40).step_by(2));
+        }
+        assert!(output[40..].windows(2).all(|w| w[1] < w[0]));
+    }
+}
@@ -160,6 +160,17 @@ where
         })
     }
 
+    #[inline]
+    fn tail_duration(&self) -> Duration {
+        let tail = self.input.tail_duration();
+        self.total_duration().map_or(tail, |total| tail.min(total))
+    }
+
+    #[inline]
+    fn latency(&self) -> Duration {
+        self.input.latency()
+    }
+
     #[inline]
     fn try_seek(&mut self, pos: Duration) -> Result<(), SeekError> {
         self.input.try_seek(pos)
@@ -3,6 +3,7 @@ use crate::Sample;
 use crate::Source;
 use std::time::Duration;
 
+use super::zero_crossing::is_crossing;
 use super::SeekError;
 
 /// Wrap the source in a skippable. It allows ending the current source early by
@@ -12,6 +13,10 @@ pub fn skippable<I>(source: I) -> Skippable<I> {
     Skippable {
         input: source,
         do_skip: false,
+        zero_crossing: None,
+        channel: 0,
+        frame_sum: 0.0,
+        previous_frame: None,
     }
 }
 
@@ -22,6 +27,12 @@ pub fn skippable<I>(source: I) -> Skippable<I> {
 pub struct Skippable<I> {
     input: I,
     do_skip: bool,
+    // Frames left to wait for a zero crossing before skipping anyway.
+    zero_crossing: Option<usize>,
+    channel: ChannelCount,
+    // Sum of the samples of the current frame across channels.
+    frame_sum: f32,
+    previous_frame: Option<f32>,
 }
 
 impl<I> Skippable<I> {
@@ -31,6 +42,22 @@ impl<I> Skippable<I> {
         self.do_skip = true;
     }
 
+    /// Skips the current source once it reaches a zero crossing, which avoids the click of
+    /// cutting the sound off at an arbitrary sample.
+    ///
+    /// The source keeps playing for at most `max_frames` frames while waiting for the
+    /// crossing, that is the maximum latency this adds to the skip. Channels are mixed down
+    /// to mono to find the crossing. Calling this again or `max_frames` being zero skips
+    /// immediately.
+    #[inline]
+    pub fn skip_at_zero_crossing(&mut self, max_frames: usize) {
+        if max_frames == 0 || self.zero_crossing.is_some() {
+            self.do_skip = true;
+        } else {
+            self.zero_crossing = Some(max_frames);
+        }
+    }
+
     /// Returns a reference to the inner source.
     #[inline]
     pub fn inner(&self) -> &I {
@@ -60,10 +87,28 @@ where
     #[inline]
     fn next(&mut self) -> Option<I::Item> {
         if self.do_skip {
-            None
-        } else {
-            self.input.next()
+            return None;
+        }
+
+        let sample = self.input.next()?;
+        self.frame_sum += sample.to_f32();
+        self.channel += 1;
+        if self.channel >= self.input.channels().max(1) {
+            let frame = self.frame_sum;
+            self.frame_sum = 0.0;
+            self.channel = 0;
+            if let Some(frames_left) = self.zero_crossing.as_mut() {
+                *frames_left -= 1;
+                let crossed = self
+                    .previous_frame
+                    .is_some_and(|previous| is_crossing(previous, frame));
+                if *frames_left == 0 || crossed {
+                    self.do_skip = true;
+                }
+            }
+            self.previous_frame = Some(frame);
         }
+        Some(sample)
     }
 
     #[inline]
@@ -97,8 +142,22 @@ where
         self.input.total_duration()
     }
 
+    #[inline]
+    fn tail_duration(&self) -> Duration {
+        self.input.tail_duration()
+    }
+
+    #[inline]
+    fn latency(&self) -> Duration {
+        self.input.latency()
+    }
+
     #[inline]
     fn try_seek(&mut self, pos: Duration) -> Result<(), SeekError> {
-        self.input.try_seek(pos)
+        self.input.try_seek(pos)?;
+        self.channel = 0;
+        self.frame_sum = 0.0;
+        self.previous_frame = None;
+        Ok(())
     }
 }
@@ -2,12 +2,32 @@ use std::time::Duration;
 
 use super::SeekError;
 use crate::common::{ChannelCount, SampleRate};
-use crate::source::ChannelVolume;
+use crate::source::{ChannelVolume, PanLaw};
 use crate::{Sample, Source};
 
+/// Speed of sound in air, in units of the positions per second.
+const SPEED_OF_SOUND: f32 = 343.0;
+
+/// Longest delay of one ear relative to the other, larger delays are clamped.
+const MAX_DELAY: f32 = 0.02;
+
+/// Time constant with which the gains and delays follow changes of the positions.
+const SMOOTHING: f32 = 0.01;
+
+/// Steps in which the delayed samples are interpolated.
+const INTERPOLATION_STEPS: u32 = 1024;
+
 /// A simple spatial audio source. The underlying source is transformed to Mono
 /// and then played in stereo. The left and right channel's volume are amplified
 /// differently depending on the distance of the left and right ear to the source.
+///
+/// The balance between the ears follows a [`PanLaw`], the ear facing away from the emitter is
+/// attenuated but never fully silenced. The sound also reaches the far ear later, by the
+/// difference in distance divided by the speed of sound, up to 20 milliseconds.
+///
+/// When the positions change the volumes and the delay glide to their new values over about
+/// 10 milliseconds, reading between samples while the delay changes, so moving emitters do
+/// not click.
 #[derive(Clone)]
 pub struct Spatial<I>
 where
@@ -15,6 +35,23 @@ where
     I::Item: Sample,
 {
     input: ChannelVolume<I>,
+    pan_law: PanLaw,
+    // Last positions set, to recompute the gains when the pan law changes.
+    positions: [[f32; 3]; 3],
+    // Gains and delays in seconds of the left and right ear, and the values they glide to.
+    gains: [f32; 2],
+    delays: [f32; 2],
+    target_gains: [f32; 2],
+    target_delays: [f32; 2],
+    // Last mono samples, `history[pos]` is the oldest.
+    history: Vec<I::Item>,
+    pos: usize,
+    // The frame being played and the channel of the next sample.
+    frame: [I::Item; 2],
+    channel: usize,
+    // Sample rate the smoothing coefficient was computed for.
+    smoothing_rate: SampleRate,
+    smoothing: f32,
 }
 
 fn dist_sq(a: [f32; 3], b: [f32; 3]) -> f32 {
@@ -40,10 +77,25 @@ where
         I: Source,
         I::Item: Sample,
     {
+        let history_len = (MAX_DELAY * input.sample_rate() as f32).ceil() as usize + 2;
         let mut ret = Spatial {
-            input: ChannelVolume::new(input, vec![0.0, 0.0]),
+            input: ChannelVolume::new(input, vec![1.0]),
+            pan_law: PanLaw::default(),
+            positions: [emitter_position, left_ear, right_ear],
+            gains: [0.0; 2],
+            delays: [0.0; 2],
+            target_gains: [0.0; 2],
+            target_delays: [0.0; 2],
+            history: vec![I::Item::ZERO_VALUE; history_len],
+            pos: 0,
+            frame: [I::Item::ZERO_VALUE; 2],
+            channel: 0,
+            smoothing_rate: 0,
+            smoothing: 1.0,
         };
         ret.set_positions(emitter_position, left_ear, right_ear);
+        ret.gains = ret.target_gains;
+        ret.delays = ret.target_delays;
         ret
     }
 
@@ -55,20 +107,84 @@ where
         right_ear: [f32; 3],
     ) {
         debug_assert!(left_ear != right_ear);
+        self.positions = [emitter_pos, left_ear, right_ear];
+
         let left_dist_sq = dist_sq(left_ear, emitter_pos);
         let right_dist_sq = dist_sq(right_ear, emitter_pos);
         let max_diff = dist_sq(left_ear, right_ear).sqrt();
         let left_dist = left_dist_sq.sqrt();
         let right_dist = right_dist_sq.sqrt();
-        let left_diff_modifier = (((left_dist - right_dist) / max_diff + 1.0) / 4.0 + 0.5).min(1.0);
-        let right_diff_modifier =
-            (((right_dist - left_dist) / max_diff + 1.0) / 4.0 + 0.5).min(1.0);
+        // Only use half of the pan range so that the far ear still hears the emitter.
+        let pan = ((left_dist - right_dist) / max_diff).clamp(-1.0, 1.0) / 2.0;
+        let [left_diff_modifier, right_diff_modifier] = self.pan_law.gains(pan);
         let left_dist_modifier = (1.0 / left_dist_sq).min(1.0);
         let right_dist_modifier = (1.0 / right_dist_sq).min(1.0);
-        self.input
-            .set_volume(0, left_diff_modifier * left_dist_modifier);
-        self.input
-            .set_volume(1, right_diff_modifier * right_dist_modifier);
+        self.target_gains = [
+            left_diff_modifier * left_dist_modifier,
+            right_diff_modifier * right_dist_modifier,
+        ];
+        let nearest = left_dist.min(right_dist);
+        self.target_delays =
+            [left_dist, right_dist].map(|dist| ((dist - nearest) / SPEED_OF_SOUND).min(MAX_DELAY));
+    }
+
+    /// Changes how the volume is distributed between the ears, the default is
+    /// [`PanLaw::EqualPower`].
+    pub fn set_pan_law(&mut self, pan_law: PanLaw) {
+        self.pan_law = pan_law;
+        let [emitter_pos, left_ear, right_ear] = self.positions;
+        self.set_positions(emitter_pos, left_ear, right_ear);
+    }
+
+    /// Returns a reference to the inner source.
+    #[inline]
+    pub fn inner(&self) -> &I {
+        self.input.inner()
+    }
+
+    /// Returns a mutable reference to the inner source.
+    #[inline]
+    pub fn inner_mut(&mut self) -> &mut I {
+        self.input.inner_mut()
+    }
+
+    /// Returns the inner source. The samples of the current frame that were not read yet are
+    /// lost.
+    #[inline]
+    pub fn into_inner(self) -> I {
+        self.input.into_inner()
+    }
+
+    // Reads the next mono sample and computes the frame it is played in.
+    fn next_frame(&mut self) -> Option<()> {
+        let sample = self.input.next()?;
+        self.history[self.pos] = sample;
+        self.pos = (self.pos + 1) % self.history.len();
+
+        let sample_rate = self.input.sample_rate().max(1);
+        if sample_rate != self.smoothing_rate {
+            self.smoothing_rate = sample_rate;
+            self.smoothing = 1.0 - (-1.0 / (SMOOTHING * sample_rate as f32)).exp();
+        }
+        let max_delay = (self.history.len() - 2) as f32;
+        for ear in 0..2 {
+            self.gains[ear] += (self.target_gains[ear] - self.gains[ear]) * self.smoothing;
+            self.delays[ear] += (self.target_delays[ear] - self.delays[ear]) * self.smoothing;
+            let delay = (self.delays[ear] * sample_rate as f32).min(max_delay);
+            self.frame[ear] = self.delayed(delay).amplify(self.gains[ear]);
+        }
+        Some(())
+    }
+
+    // The mono sample played `delay` samples ago, interpolated between the two closest.
+    fn delayed(&self, delay: f32) -> I::Item {
+        let len = self.history.len();
+        let whole = delay as usize;
+        let newest = self.pos + len - 1;
+        let first = self.history[(newest - whole) % len];
+        let second = self.history[(newest - whole - 1) % len];
+        let fraction = (delay - whole as f32) * INTERPOLATION_STEPS as f32;
+        I::Item::lerp(first, second, fraction as u32, INTERPOLATION_STEPS)
     }
 }
 
@@ -81,7 +197,12 @@ where
 
     #[inline]
     fn next(&mut self) -> Option<I::Item> {
-        self.input.next()
+        if self.channel == 0 {
+            self.next_frame()?;
+        }
+        let sample = self.frame[self.channel];
+        self.channel = 1 - self.channel;
+        Some(sample)
     }
 
     #[inline]
@@ -109,7 +230,7 @@ where
 
     #[inline]
     fn channels(&self) -> ChannelCount {
-        self.input.channels()
+        2
     }
 
     #[inline]
@@ -122,8 +243,108 @@ where
         self.input.total_duration()
     }
 
+    #[inline]
+    fn tail_duration(&self) -> Duration {
+        self.input.tail_duration()
+    }
+
+    #[inline]
+    fn latency(&self) -> Duration {
+        self.input.latency()
+    }
+
+    /// Clears the delay line, the gains and delays jump to their current targets.
     #[inline]
     fn try_seek(&mut self, pos: Duration) -> Result<(), SeekError> {
-        self.input.try_seek(pos)
+        self.input.try_seek(pos)?;
+        self.history.fill(I::Item::ZERO_VALUE);
+        self.gains = self.target_gains;
+        self.delays = self.target_delays;
+        self.channel = 0;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use approx::assert_abs_diff_eq;
+
+    use super::Spatial;
+    use crate::buffer::SamplesBuffer;
+    use crate::source::PanLaw;
+
+    // The last frame, once the gains have settled and the delayed ear plays the source too.
+    fn gains(emitter: [f32; 3], law: PanLaw) -> Vec<f32> {
+        let source = SamplesBuffer::new(1, 44100, vec![1.0f32; 44100]);
+        let mut spatial = Spatial::new(source, emitter, [-1.0, 0.0, 0.0], [1.0, 0.0, 0.0]);
+        spatial.set_pan_law(law);
+        let output: Vec<f32> = spatial.collect();
+        output[output.len() - 2..].to_vec()
+    }
+
+    #[test]
+    fn center_follows_pan_law() {
+        for (law, expected) in [
+            (PanLaw::EqualPower, std::f32::consts::FRAC_1_SQRT_2),
+            (PanLaw::Linear, 0.5),
+            (PanLaw::Compromise, 0.5946),
+        ] {
+            let output = gains([0.0, 1.0, 0.0], law);
+            assert_abs_diff_eq!(output[0], output[1]);
+            // both ears are at a distance of sqrt(2)
+            assert_abs_diff_eq!(output[0], expected / 2.0, epsilon = 1e-3);
+        }
+    }
+
+    #[test]
+    fn nearest_ear_is_louder() {
+        let output = gains([2.0, 0.0, 0.0], PanLaw::EqualPower);
+        assert!(output[1] > output[0]);
+
+        // moving past the ear does not silence the other side
+        let [left, right] = PanLaw::EqualPower.gains(0.5);
+        assert_abs_diff_eq!(output[1] / output[0], right / left * 9.0, epsilon = 1e-3);
+    }
+
+    #[test]
+    fn far_ear_is_delayed() {
+        let mut pulse = vec![0.0f32; 1000];
+        pulse[0] = 1.0;
+        let output: Vec<f32> = Spatial::new(
+            SamplesBuffer::new(1, 34300, pulse),
+            [2.0, 0.0, 0.0],
+            [-1.0, 0.0, 0.0],
+            [1.0, 0.0, 0.0],
+        )
+        .collect();
+        // the left ear is 2 further away, 200 samples at 34.3 kHz
+        assert!(output[1] > 0.0);
+        assert!(output[..400].iter().step_by(2).all(|&s| s == 0.0));
+        assert!(output[400] > 0.0);
+    }
+
+    #[test]
+    fn moving_emitter_does_not_click() {
+        let sample_rate = 44100;
+        let tone: Vec<f32> = (0..sample_rate)
+            .map(|i| (std::f32::consts::TAU * 220.0 * i as f32 / sample_rate as f32).sin())
+            .collect();
+        let max_step = std::f32::consts::TAU * 220.0 / sample_rate as f32;
+        let ears = ([-1.0, 0.0, 0.0], [1.0, 0.0, 0.0]);
+        let source = SamplesBuffer::new(1, sample_rate, tone);
+        let mut spatial = Spatial::new(source, [-3.0, 0.0, 0.0], ears.0, ears.1);
+
+        // the emitter crosses from left to right in jumps of 0.3 every 10 milliseconds
+        let mut output = Vec::new();
+        for step in 0..20 {
+            spatial.set_positions([-3.0 + step as f32 * 0.3, 0.0, 0.0], ears.0, ears.1);
+            output.extend(spatial.by_ref().take(2 * 441));
+        }
+        for channel in 0..2 {
+            let samples: Vec<f32> = output.iter().skip(channel).step_by(2).copied().collect();
+            for pair in samples.windows(2) {
+                assert!((pair[1] - pair[0]).abs() < 1.2 * max_step);
+            }
+        }
     }
 }
@@ -0,0 +1,364 @@
+use std::f32::consts::PI;
+use std::sync::atomic::{AtomicU32, AtomicU64, Ordering};
+use std::sync::mpsc::{self, Receiver, SyncSender};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+use super::SeekError;
+use crate::common::{ChannelCount, SampleRate};
+use crate::{Sample, Source};
+
+/// Number of frames that can be waiting for analysis. Frames are dropped when the analysis
+/// thread falls further behind.
+const FRAMES_IN_FLIGHT: usize = 2;
+
+/// Internal function that builds a `Spectrum` object.
+pub fn spectrum<I>(input: I, fft_size: usize) -> Spectrum<I>
+where
+    I: Source,
+    I::Item: Sample,
+{
+    assert!(
+        fft_size >= 2 && fft_size.is_power_of_two(),
+        "fft_size must be a power of two"
+    );
+
+    let shared = Arc::new(Shared {
+        magnitudes: Mutex::new(Vec::new()),
+        sample_rate: AtomicU32::new(input.sample_rate()),
+        frames: AtomicU64::new(0),
+        fft_size,
+    });
+
+    let (to_analyze, analyze_rx) = mpsc::sync_channel(FRAMES_IN_FLIGHT);
+    let (recycle_tx, recycled) = mpsc::sync_channel(FRAMES_IN_FLIGHT);
+    for _ in 0..FRAMES_IN_FLIGHT {
+        recycle_tx
+            .send(vec![0.0; fft_size])
+            .expect("receiver is alive");
+    }
+
+    let worker_shared = shared.clone();
+    thread::Builder::new()
+        .name("rodio spectrum".to_owned())
+        .spawn(move || analyze(worker_shared, analyze_rx, recycle_tx))
+        .expect("failed to spawn spectrum analysis thread");
+
+    Spectrum {
+        input,
+        shared,
+        history: vec![0.0; fft_size],
+        write_pos: 0,
+        until_next_frame: fft_size,
+        frame_sum: 0.0,
+        channel: 0,
+        to_analyze,
+        recycled,
+    }
+}
+
+/// Passes a source through unchanged while computing its magnitude spectrum on a background
+/// thread.
+///
+/// The channels are mixed down to mono and analyzed in frames of `fft_size` samples with a
+/// Hann window. Consecutive frames overlap by half. Query the result with a
+/// [`SpectrumHandle`] obtained from [`Spectrum::handle`].
+pub struct Spectrum<I> {
+    input: I,
+    shared: Arc<Shared>,
+    // The last `fft_size` mono samples, circular.
+    history: Vec<f32>,
+    write_pos: usize,
+    // Mono samples until the next frame is sent for analysis.
+    until_next_frame: usize,
+    // Sum of the samples of the current frame across channels.
+    frame_sum: f32,
+    channel: ChannelCount,
+    to_analyze: SyncSender<(Vec<f32>, SampleRate)>,
+    // Empty buffers handed back by the analysis thread, avoids allocating on the audio thread.
+    recycled: Receiver<Vec<f32>>,
+}
+
+struct Shared {
+    magnitudes: Mutex<Vec<f32>>,
+    sample_rate: AtomicU32,
+    frames: AtomicU64,
+    fft_size: usize,
+}
+
+/// Gives access to the spectrum computed by a [`Spectrum`] source from any thread.
+#[derive(Clone)]
+pub struct SpectrumHandle {
+    shared: Arc<Shared>,
+}
+
+impl SpectrumHandle {
+    /// Returns the magnitudes of the most recently analyzed frame, one per bin from 0 Hz up
+    /// to the Nyquist frequency (`fft_size / 2 + 1` bins).
+    ///
+    /// A sine wave with amplitude `a` results in a peak of about `a` in its bin. This is empty
+    /// until the first frame has been analyzed.
+    pub fn magnitudes(&self) -> Vec<f32> {
+        self.shared.magnitudes.lock().unwrap().clone()
+    }
+
+    /// Returns the center frequency of `bin` in Hz, based on the sample rate of the source
+    /// at the time the latest frame was captured.
+    #[inline]
+    pub fn bin_frequency(&self, bin: usize) -> f32 {
+        let sample_rate = self.shared.sample_rate.load(Ordering::Relaxed);
+        bin as f32 * sample_rate as f32 / self.shared.fft_size as f32
+    }
+
+    /// Returns the number of frames analyzed so far.
+    #[inline]
+    pub fn frames_analyzed(&self) -> u64 {
+        self.shared.frames.load(Ordering::Acquire)
+    }
+}
+
+impl<I> Spectrum<I>
+where
+    I: Source,
+    I::Item: Sample,
+{
+    /// Returns a handle to read the spectrum with. Get it before handing the source to a
+    /// sink or mixer.
+    #[inline]
+    pub fn handle(&self) -> SpectrumHandle {
+        SpectrumHandle {
+            shared: self.shared.clone(),
+        }
+    }
+
+    /// Returns a reference to the inner source.
+    #[inline]
+    pub fn inner(&self) -> &I {
+        &self.input
+    }
+
+    /// Returns a mutable reference to the inner source.
+    #[inline]
+    pub fn inner_mut(&mut self) -> &mut I {
+        &mut self.input
+    }
+
+    /// Returns the inner source.
+    #[inline]
+    pub fn into_inner(self) -> I {
+        self.input
+    }
+
+    fn push_mono(&mut self, sample: f32) {
+        let fft_size = self.history.len();
+        self.history[self.write_pos] = sample;
+        self.write_pos = (self.write_pos + 1) % fft_size;
+
+        self.until_next_frame -= 1;
+        if self.until_next_frame > 0 {
+            return;
+        }
+        self.until_next_frame = fft_size / 2;
+
+        // If no buffer is free the analysis is lagging behind, skip this frame.
+        if let Ok(mut frame) = self.recycled.try_recv() {
+            let (newer, older) = self.history.split_at(self.write_pos);
+            frame[..older.len()].copy_from_slice(older);
+            frame[older.len()..].copy_from_slice(newer);
+            let _ = self.to_analyze.try_send((frame, self.input.sample_rate()));
+        }
+    }
+}
+
+impl<I> Iterator for Spectrum<I>
+where
+    I: Source,
+    I::Item: Sample,
+{
+    type Item = I::Item;
+
+    #[inline]
+    fn next(&mut self) -> Option<I::Item> {
+        let sample = self.input.next()?;
+
+        let channels = self.input.channels().max(1);
+        self.frame_sum += sample.to_f32();
+        self.channel += 1;
+        if self.channel >= channels {
+            let mono = self.frame_sum / channels as f32;
+            self.frame_sum = 0.0;
+            self.channel = 0;
+            self.push_mono(mono);
+        }
+
+        Some(sample)
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.input.size_hint()
+    }
+}
+
+impl<I> Source for Spectrum<I>
+where
+    I: Source,
+    I::Item: Sample,
+{
+    #[inline]
+    fn current_span_len(&self) -> Option<usize> {
+        self.input.current_span_len()
+    }
+
+    #[inline]
+    fn channels(&self) -> ChannelCount {
+        self.input.channels()
+    }
+
+    #[inline]
+    fn sample_rate(&self) -> SampleRate {
+        self.input.sample_rate()
+    }
+
+    #[inline]
+    fn total_duration(&self) -> Option<Duration> {
+        self.input.total_duration()
+    }
+
+    #[inline]
+    fn tail_duration(&self) -> Duration {
+        self.input.tail_duration()
+    }
+
+    #[inline]
+    fn latency(&self) -> Duration {
+        self.input.latency()
+    }
+
+    #[inline]
+    fn try_seek(&mut self, pos: Duration) -> Result<(), SeekError> {
+        self.input.try_seek(pos)?;
+        self.frame_sum = 0.0;
+        self.channel = 0;
+        Ok(())
+    }
+}
+
+/// Runs on the analysis thread until the `Spectrum` is dropped.
+fn analyze(
+    shared: Arc<Shared>,
+    frames: Receiver<(Vec<f32>, SampleRate)>,
+    recycle: SyncSender<Vec<f32>>,
+) {
+    let fft_size = shared.fft_size;
+    let window: Vec<f32> = (0..fft_size)
+        .map(|i| 0.5 - 0.5 * (2.0 * PI * i as f32 / fft_size as f32).cos())
+        .collect();
+    // Scales the magnitudes so that a sine wave peaks at its amplitude.
+    let scale = 2.0 / window.iter().sum::<f32>();
+
+    let mut re = vec![0.0; fft_size];
+    let mut im = vec![0.0; fft_size];
+    let mut magnitudes = Vec::with_capacity(fft_size / 2 + 1);
+
+    for (frame, sample_rate) in frames {
+        for (i, sample) in frame.iter().enumerate() {
+            re[i] = sample * window[i];
+            im[i] = 0.0;
+        }
+        let _ = recycle.send(frame);
+
+        fft(&mut re, &mut im);
+        magnitudes.clear();
+        magnitudes.extend(
+            re.iter()
+                .zip(&im)
+                .take(fft_size / 2 + 1)
+                .map(|(re, im)| (re * re + im * im).sqrt() * scale),
+        );
+
+        std::mem::swap(&mut *shared.magnitudes.lock().unwrap(), &mut magnitudes);
+        shared.sample_rate.store(sample_rate, Ordering::Relaxed);
+        shared.frames.fetch_add(1, Ordering::Release);
+    }
+}
+
+/// In place radix-2 Cooley-Tukey FFT, the length must be a power of two.
+fn fft(re: &mut [f32], im: &mut [f32]) {
+    let n = re.len();
+
+    // bit reversal permutation
+    let mut j = 0;
+    for i in 1..n {
+        let mut bit = n >> 1;
+        while j & bit != 0 {
+            j ^= bit;
+            bit >>= 1;
+        }
+        j |= bit;
+        if i < j {
+            re.swap(i, j);
+            im.swap(i, j);
+        }
+    }
+
+    let mut len = 2;
+    while len <= n {
+        let angle = -2.0 * PI / len as f32;
+        for start in (0..n).step_by(len) {
+            for k in 0..len / 2 {
+                let (sin, cos) = (angle * k as f32).sin_cos();
+                let a = start + k;
+                let b = a + len / 2;
+                let t_re = re[b] * cos - im[b] * sin;
+                let t_im = re[b] * sin + im[b] * cos;
+                re[b] = re[a] - t_re;
+                im[b] = im[a] - t_im;
+                re[a] += t_re;
+                im[a] += t_im;
+            }
+        }
+        len <<= 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::thread;
+    use std::time::Duration;
+
+    use crate::buffer::SamplesBuffer;
+    use crate::source::{SineWave, Source};
+
+    #[test]
+    fn peak_bin_matches_tone() {
+        // 1000 Hz at 48 kHz with 1024 bins lands between bins, which the window must handle
+        let source = SineWave::new(1000.0).amplify(0.5).spectrum(1024);
+        let handle = source.handle();
+        let _ = source.take(4096).count();
+
+        let mut waited = Duration::ZERO;
+        while handle.frames_analyzed() == 0 && waited < Duration::from_secs(5) {
+            thread::sleep(Duration::from_millis(5));
+            waited += Duration::from_millis(5);
+        }
+
+        let magnitudes = handle.magnitudes();
+        assert_eq!(magnitudes.len(), 513);
+        let (peak, value) = magnitudes
+            .iter()
+            .enumerate()
+            .max_by(|a, b| a.1.total_cmp(b.1))
+            .unwrap();
+        assert!((handle.bin_frequency(peak) - 1000.0).abs() <= handle.bin_frequency(1) / 2.0);
+        assert!(*value > 0.3 && *value <= 0.5, "{value}");
+    }
+
+    #[test]
+    fn passes_samples_through() {
+        let data = vec![1i16, 2, 3, 4, 5, 6];
+        let source = SamplesBuffer::new(2, 100, data.clone()).spectrum(4);
+        assert_eq!(source.collect::<Vec<_>>(), data);
+    }
+}
@@ -45,22 +45,66 @@
 //!
 //! Since the samples are played faster the audio wave get shorter increasing their frequencies
 
+use std::collections::VecDeque;
 use std::time::Duration;
 
 use super::SeekError;
-use crate::common::{ChannelCount, SampleRate};
+use crate::common::{frames_to_duration, ChannelCount, SampleRate};
+use crate::conversions::{from_f32, Interpolation};
 use crate::{Sample, Source};
 
 /// Internal function that builds a `Speed` object.
 pub fn speed<I>(input: I, factor: f32) -> Speed<I> {
-    Speed { input, factor }
+    Speed {
+        input,
+        factor,
+        interpolator: None,
+    }
+}
+
+/// Converts an interval in cents to a speed factor, 1200 cents are one octave.
+#[inline]
+fn cents_to_factor(cents: f32) -> f32 {
+    2f32.powf(cents / 1200.0)
+}
+
+/// Internal function that builds a `Speed` object from an interval in cents.
+pub fn detune<I>(input: I, cents: f32) -> Speed<I> {
+    speed(input, cents_to_factor(cents))
 }
 
 /// Filter that modifies each sample by a given value.
+///
+/// By default the samples are passed on unchanged with a sample rate multiplied by the
+/// factor, and whatever plays the source converts them to its own rate, linearly. With
+/// [`with_interpolation`](Speed::with_interpolation) the source is resampled back to the
+/// sample rate of the input instead, with the chosen interpolation. The output then has no
+/// spans, the input should keep the same format throughout, a change of format is followed
+/// but causes a short glitch.
 #[derive(Clone, Debug)]
 pub struct Speed<I> {
     input: I,
     factor: f32,
+    interpolator: Option<Interpolator>,
+}
+
+// Resamples the input to play it at another speed.
+#[derive(Clone, Debug)]
+struct Interpolator {
+    interpolation: Interpolation,
+    channels: ChannelCount,
+    sample_rate: SampleRate,
+    // Input frames, interleaved. The first ones are silence before the input starts, so the
+    // interpolation has samples on both sides of the first position.
+    frames: VecDeque<f32>,
+    // Position of the next output frame in `frames`, in frames.
+    pos: f64,
+    // Frames in `frames` once the input ended.
+    end: Option<usize>,
+    weights: Vec<f32>,
+    // The output frame being returned and the position of the next sample in it.
+    output: Vec<f32>,
+    output_pos: usize,
 }
 
 impl<I> Speed<I>
@@ -74,6 +118,55 @@ where
         self.factor = factor;
     }
 
+    /// Sets the speed factor to shift the pitch by `cents`, see [`Source::detune`].
+    #[inline]
+    pub fn set_detune(&mut self, cents: f32) {
+        self.factor = cents_to_factor(cents);
+    }
+
+    /// Resamples the source back to the sample rate of the input with `interpolation`,
+    /// instead of leaving the conversion to whatever plays it. [`Interpolation::Cubic`] sounds
+    /// noticeably cleaner than the linear conversion for large changes of pitch.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rodio::source::{Interpolation, SineWave, Source};
+    ///
+    /// let fifth_down = SineWave::new(440.0)
+    ///     .detune(-700.0)
+    ///     .with_interpolation(Interpolation::Cubic);
+    /// ```
+    #[inline]
+    pub fn with_interpolation(mut self, interpolation: Interpolation) -> Speed<I> {
+        self.set_interpolation(interpolation);
+        self
+    }
+
+    /// Changes the interpolation while playing, see
+    /// [`with_interpolation`](Speed::with_interpolation).
+    pub fn set_interpolation(&mut self, interpolation: Interpolation) {
+        match &mut self.interpolator {
+            Some(interpolator) => interpolator.set_interpolation(interpolation),
+            None => {
+                self.interpolator = Some(Interpolator::new(
+                    interpolation,
+                    self.input.channels(),
+                    self.input.sample_rate(),
+                ))
+            }
+        }
+    }
+
+    /// Returns the interpolation the source is resampled with, `None` if the sample rate is
+    /// changed instead.
+    #[inline]
+    pub fn interpolation(&self) -> Option<Interpolation> {
+        self.interpolator
+            .as_ref()
+            .map(|interpolator| interpolator.interpolation)
+    }
+
     /// Returns a reference to the inner source.
     #[inline]
     pub fn inner(&self) -> &I {
@@ -102,12 +195,21 @@ where
 
     #[inline]
     fn next(&mut self) -> Option<I::Item> {
-        self.input.next()
+        match &mut self.interpolator {
+            None => self.input.next(),
+            Some(interpolator) => interpolator
+                .next(&mut self.input, self.factor)
+                .map(from_f32),
+        }
     }
 
     #[inline]
     fn size_hint(&self) -> (usize, Option<usize>) {
-        self.input.size_hint()
+        match &self.interpolator {
+            None => self.input.size_hint(),
+            // Only the rest of the current frame is certain.
+            Some(interpolator) => (interpolator.output.len() - interpolator.output_pos, None),
+        }
     }
 }
 
@@ -125,17 +227,26 @@ where
 {
     #[inline]
     fn current_span_len(&self) -> Option<usize> {
-        self.input.current_span_len()
+        match &self.interpolator {
+            None => self.input.current_span_len(),
+            Some(_) => None,
+        }
     }
 
     #[inline]
     fn channels(&self) -> ChannelCount {
-        self.input.channels()
+        match &self.interpolator {
+            None => self.input.channels(),
+            Some(interpolator) => interpolator.channels,
+        }
     }
 
     #[inline]
     fn sample_rate(&self) -> SampleRate {
-        (self.input.sample_rate() as f32 * self.factor) as u32
+        match &self.interpolator {
+            None => (self.input.sample_rate() as f32 * self.factor) as u32,
+            Some(interpolator) => interpolator.sample_rate,
+        }
     }
 
     #[inline]
@@ -143,9 +254,303 @@ where
         self.input.total_duration().map(|d| d.div_f32(self.factor))
     }
 
+    #[inline]
+    fn tail_duration(&self) -> Duration {
+        if self.factor > 0.0 {
+            self.input.tail_duration().div_f32(self.factor)
+        } else {
+            self.input.tail_duration()
+        }
+    }
+
+    #[inline]
+    fn latency(&self) -> Duration {
+        if self.factor > 0.0 {
+            self.input.latency().div_f32(self.factor)
+        } else {
+            self.input.latency()
+        }
+    }
+
     #[inline]
     fn try_seek(&mut self, pos: Duration) -> Result<(), SeekError> {
-        let pos_accounting_for_speedup = pos.mul_f32(self.factor);
-        self.input.try_seek(pos_accounting_for_speedup)
+        let pos_accounting_for_speedup = pos.mul_f64(self.factor as f64);
+        self.input.try_seek(pos_accounting_for_speedup)?;
+        if let Some(interpolator) = &mut self.interpolator {
+            interpolator.reset(self.input.channels(), self.input.sample_rate());
+        }
+        Ok(())
+    }
+
+    #[inline]
+    fn try_seek_frames(&mut self, frame: u64) -> Result<(), SeekError> {
+        // Frames of the input only line up with the output at the normal speed.
+        if self.factor != 1.0 {
+            return self.try_seek(frames_to_duration(frame, self.sample_rate()));
+        }
+        self.input.try_seek_frames(frame)?;
+        if let Some(interpolator) = &mut self.interpolator {
+            interpolator.reset(self.input.channels(), self.input.sample_rate());
+        }
+        Ok(())
+    }
+}
+
+impl Interpolator {
+    fn new(
+        interpolation: Interpolation,
+        channels: ChannelCount,
+        sample_rate: SampleRate,
+    ) -> Interpolator {
+        let mut interpolator = Interpolator {
+            interpolation,
+            channels: 0,
+            sample_rate: 0,
+            frames: VecDeque::new(),
+            pos: 0.0,
+            end: None,
+            weights: vec![0.0; interpolation.taps()],
+            output: Vec::new(),
+            output_pos: 0,
+        };
+        interpolator.reset(channels, sample_rate);
+        interpolator
+    }
+
+    // Input frames needed before the position of an output frame.
+    #[inline]
+    fn before(&self) -> usize {
+        self.interpolation.taps() / 2 - 1
+    }
+
+    fn set_interpolation(&mut self, interpolation: Interpolation) {
+        // Pads or drops history at the front, so the position keeps pointing at the same
+        // frame.
+        let extra = (interpolation.taps() / 2 - 1) as isize - self.before() as isize;
+        let channels = self.channels as usize;
+        if extra > 0 {
+            for _ in 0..extra as usize * channels {
+                self.frames.push_front(0.0);
+            }
+        } else {
+            let drop = ((-extra) as usize * channels).min(self.frames.len());
+            self.frames.drain(..drop);
+        }
+        self.pos += extra as f64;
+        self.end = self.end.map(|end| (end as isize + extra).max(0) as usize);
+        self.interpolation = interpolation;
+        self.weights.resize(interpolation.taps(), 0.0);
+    }
+
+    // Forgets the input read so far and starts over with a new format.
+    fn reset(&mut self, channels: ChannelCount, sample_rate: SampleRate) {
+        self.channels = channels.max(1);
+        self.sample_rate = sample_rate;
+        self.frames.clear();
+        self.frames
+            .resize(self.before() * self.channels as usize, 0.0);
+        self.pos = self.before() as f64;
+        self.end = None;
+        self.output.clear();
+        self.output.resize(self.channels as usize, 0.0);
+        self.output_pos = self.output.len();
+    }
+
+    #[inline]
+    fn next<I>(&mut self, input: &mut I, factor: f32) -> Option<f32>
+    where
+        I: Source,
+        I::Item: Sample,
+    {
+        if self.output_pos >= self.output.len() && !self.next_frame(input, factor) {
+            return None;
+        }
+        let sample = self.output[self.output_pos];
+        self.output_pos += 1;
+        Some(sample)
+    }
+
+    // Computes the next output frame, returns false once the input has been played.
+    fn next_frame<I>(&mut self, input: &mut I, factor: f32) -> bool
+    where
+        I: Source,
+        I::Item: Sample,
+    {
+        let taps = self.interpolation.taps();
+        let mut index = self.pos as usize;
+        let mut needed = index - self.before() + taps;
+        let mut channels = self.channels as usize;
+        while self.frames.len() < needed * channels {
+            if self.end.is_some() {
+                self.frames.push_back(0.0);
+                continue;
+            }
+            if self.frames.len().is_multiple_of(channels)
+                && (input.channels().max(1) != self.channels
+                    || input.sample_rate() != self.sample_rate)
+            {
+                self.reset(input.channels(), input.sample_rate());
+                index = self.pos as usize;
+                needed = index - self.before() + taps;
+                channels = self.channels as usize;
+                continue;
+            }
+            match input.next() {
+                Some(sample) => self.frames.push_back(sample.to_f32()),
+                None => {
+                    // An incomplete frame at the end is dropped.
+                    let whole = self.frames.len() / channels;
+                    self.frames.truncate(whole * channels);
+                    self.end = Some(whole);
+                }
+            }
+        }
+        if self.end.is_some_and(|end| index >= end) {
+            return false;
+        }
+
+        let fraction = (self.pos - index as f64) as f32;
+        let cutoff = if factor > 1.0 { 1.0 / factor } else { 1.0 };
+        self.interpolation
+            .weights(fraction, cutoff, &mut self.weights);
+        let first = (index - self.before()) * channels;
+        for (channel, output) in self.output.iter_mut().enumerate() {
+            *output = self
+                .weights
+                .iter()
+                .enumerate()
+                .map(|(tap, weight)| self.frames[first + tap * channels + channel] * weight)
+                .sum();
+        }
+        self.output_pos = 0;
+
+        // Drops the frames that are no longer needed.
+        self.pos += factor.max(0.0) as f64;
+        let unneeded = (self.pos as usize).saturating_sub(self.before());
+        let unneeded = unneeded.min(self.frames.len() / channels);
+        self.frames.drain(..unneeded * channels);
+        self.pos -= unneeded as f64;
+        self.end = self.end.map(|end| end.saturating_sub(unneeded));
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use crate::buffer::SamplesBuffer;
+    use crate::source::{Interpolation, SineWave, Source, UniformSourceIterator};
+
+    fn ten_seconds() -> SamplesBuffer<f32> {
+        SamplesBuffer::new(2, 100, vec![0.0; 2000])
+    }
+
+    #[test]
+    fn duration_is_scaled() {
+        let source = ten_seconds().speed(2.0);
+        assert_eq!(source.total_duration(), Some(Duration::from_secs(5)));
+
+        let source = ten_seconds().speed(0.5);
+        assert_eq!(source.total_duration(), Some(Duration::from_secs(20)));
+    }
+
+    #[test]
+    fn duration_is_scaled_through_resampler() {
+        let source: UniformSourceIterator<_, f32> =
+            UniformSourceIterator::new(ten_seconds().speed(2.0), 2, 44100);
+        assert_eq!(source.total_duration(), Some(Duration::from_secs(5)));
+    }
+
+    #[test]
+    fn take_and_skip_compose_with_speed() {
+        let source = ten_seconds()
+            .take_duration(Duration::from_secs(4))
+            .speed(2.0);
+        assert_eq!(source.total_duration(), Some(Duration::from_secs(2)));
+
+        // duration taken after speeding up is in sped up time
+        let source = ten_seconds()
+            .speed(2.0)
+            .take_duration(Duration::from_secs(4));
+        assert_eq!(source.total_duration(), Some(Duration::from_secs(4)));
+        assert_eq!(source.count(), 1600);
+
+        let source = ten_seconds()
+            .speed(2.0)
+            .skip_duration(Duration::from_secs(1));
+        assert_eq!(source.total_duration(), Some(Duration::from_secs(4)));
+        assert_eq!(source.count(), 1600);
+    }
+
+    #[test]
+    fn octave_up_doubles_frequency() {
+        fn zero_crossings(source: impl Source<Item = f32>) -> usize {
+            let resampled: Vec<f32> = UniformSourceIterator::new(source, 1, 48000)
+                .take(48000)
+                .collect();
+            resampled
+                .windows(2)
+                .filter(|pair| (pair[0] < 0.0) != (pair[1] < 0.0))
+                .count()
+        }
+
+        let original = zero_crossings(SineWave::new(220.0));
+        let detuned = zero_crossings(SineWave::new(220.0).detune(1200.0));
+        assert!(original.abs_diff(440) <= 1, "{original}");
+        assert!(detuned.abs_diff(880) <= 1, "{detuned}");
+
+        let source = ten_seconds().detune(-1200.0);
+        assert_eq!(source.total_duration(), Some(Duration::from_secs(20)));
+    }
+
+    #[test]
+    fn better_interpolation_is_closer_to_the_slowed_down_sine() {
+        use std::f32::consts::TAU;
+
+        // a 1 kHz sine at 8 kHz played at half speed is a 500 Hz sine
+        let sine: Vec<f32> = (0..800).map(|n| (TAU * n as f32 / 8.0).sin()).collect();
+        let error = |interpolation| {
+            let output: Vec<f32> = SamplesBuffer::new(1, 8000, sine.clone())
+                .speed(0.5)
+                .with_interpolation(interpolation)
+                .collect();
+            assert_eq!(output.len(), 1600);
+            // away from the edges, where the interpolation sees silence
+            let squared: f32 = output[100..1500]
+                .iter()
+                .enumerate()
+                .map(|(n, s)| s - (TAU * (n + 100) as f32 / 16.0).sin())
+                .map(|e| e * e)
+                .sum();
+            (squared / 1400.0).sqrt()
+        };
+
+        let nearest = error(Interpolation::Nearest);
+        let linear = error(Interpolation::Linear);
+        let cubic = error(Interpolation::Cubic);
+        let sinc = error(Interpolation::Sinc);
+        assert!(linear < nearest / 2.0, "{linear} {nearest}");
+        assert!(cubic < linear / 2.0, "{cubic} {linear}");
+        assert!(sinc < cubic, "{sinc} {cubic}");
+    }
+
+    #[test]
+    fn interpolation_keeps_the_format_of_the_input() {
+        let source = SamplesBuffer::new(2, 1000, (0..8).map(|i| i as f32).collect::<Vec<_>>())
+            .speed(2.0)
+            .with_interpolation(Interpolation::Linear);
+        assert_eq!(source.sample_rate(), 1000);
+        assert_eq!(source.total_duration(), Some(Duration::from_millis(2)));
+        assert_eq!(source.collect::<Vec<_>>(), [0.0, 1.0, 4.0, 5.0]);
+
+        // unchanged at the original speed
+        let output: Vec<f32> = SineWave::new(440.0)
+            .speed(1.0)
+            .with_interpolation(Interpolation::Cubic)
+            .take(100)
+            .collect();
+        let expected: Vec<f32> = SineWave::new(440.0).take(100).collect();
+        assert_eq!(output, expected);
     }
 }
@@ -121,7 +121,7 @@ where
             }
         }
 
-        if self.remaining_duration <= self.duration_per_sample {
+        if self.remaining_duration < self.duration_per_sample {
             None
         } else if let Some(sample) = self.input.next() {
             let sample = match &self.filter {
@@ -182,6 +182,23 @@ where
         }
     }
 
+    /// The part of the tail of the input that is not cut off.
+    #[inline]
+    fn tail_duration(&self) -> Duration {
+        match self.input.total_duration() {
+            Some(total) => {
+                let cut = total.saturating_sub(self.requested_duration);
+                self.input.tail_duration().saturating_sub(cut)
+            }
+            None => Duration::ZERO,
+        }
+    }
+
+    #[inline]
+    fn latency(&self) -> Duration {
+        self.input.latency()
+    }
+
     #[inline]
     fn try_seek(&mut self, pos: Duration) -> Result<(), SeekError> {
         self.input.try_seek(pos)
@@ -0,0 +1,283 @@
+use std::collections::VecDeque;
+use std::time::Duration;
+
+use super::SeekError;
+use crate::common::{ChannelCount, SampleRate};
+use crate::{Sample, Source};
+
+// Longest quiet passage that is held back, longer ones are let through from their start.
+const MAX_HELD: Duration = Duration::from_secs(10);
+
+/// Internal function that builds a `TrimSilence` object.
+pub fn trim_silence<I>(input: I, threshold_db: f32) -> TrimSilence<I>
+where
+    I: Source,
+    I::Item: Sample,
+{
+    TrimSilence {
+        input,
+        threshold: 10f32.powf(threshold_db / 20.0),
+        started: false,
+        ready: VecDeque::new(),
+        held: VecDeque::new(),
+        frame: Vec::new(),
+        leading: Duration::ZERO,
+        trailing: Duration::ZERO,
+        input_ended: false,
+    }
+}
+
+/// Removes the silence at the start and the end of a finite source.
+///
+/// A frame (one sample for every channel) is considered silent if none of its samples exceed
+/// the threshold. Silent frames in the middle of the source are held back until it is known
+/// whether the source continues after them. At most ten seconds are held back, so of a longer
+/// silence at the end only the last ten seconds are removed.
+#[derive(Clone, Debug)]
+pub struct TrimSilence<I>
+where
+    I: Source,
+    I::Item: Sample,
+{
+    input: I,
+    // Linear amplitude a sample must exceed to count as sound.
+    threshold: f32,
+    // Whether the first non-silent frame has been found.
+    started: bool,
+    // Samples that will be returned.
+    ready: VecDeque<I::Item>,
+    // Silent samples that are only returned if something audible follows them.
+    held: VecDeque<I::Item>,
+    // Reused buffer for reading a frame.
+    frame: Vec<I::Item>,
+    // Duration of the silence removed at the start.
+    leading: Duration,
+    // Duration of the silence removed at the end, only set once the input has ended.
+    trailing: Duration,
+    input_ended: bool,
+}
+
+impl<I> TrimSilence<I>
+where
+    I: Source,
+    I::Item: Sample,
+{
+    /// Returns a reference to the inner source.
+    #[inline]
+    pub fn inner(&self) -> &I {
+        &self.input
+    }
+
+    /// Returns a mutable reference to the inner source.
+    #[inline]
+    pub fn inner_mut(&mut self) -> &mut I {
+        &mut self.input
+    }
+
+    /// Returns the inner source.
+    #[inline]
+    pub fn into_inner(self) -> I {
+        self.input
+    }
+
+    /// Reads one frame into `self.frame`. Returns the duration of that frame and whether any
+    /// of its samples is louder than the threshold, or `None` at the end of the input.
+    fn read_frame(&mut self) -> Option<(Duration, bool)> {
+        let channels = self.input.channels();
+        let duration = Duration::from_secs(1) / self.input.sample_rate().max(1);
+
+        self.frame.clear();
+        self.frame
+            .extend(self.input.by_ref().take(channels.max(1) as usize));
+        if self.frame.is_empty() {
+            return None;
+        }
+
+        let threshold = self.threshold;
+        let audible = self.frame.iter().any(|s| s.to_f32().abs() > threshold);
+        Some((duration, audible))
+    }
+}
+
+impl<I> Iterator for TrimSilence<I>
+where
+    I: Source,
+    I::Item: Sample,
+{
+    type Item = I::Item;
+
+    #[inline]
+    fn next(&mut self) -> Option<I::Item> {
+        loop {
+            if let Some(sample) = self.ready.pop_front() {
+                return Some(sample);
+            }
+            if self.input_ended {
+                return None;
+            }
+
+            match self.read_frame() {
+                None => {
+                    let channels = self.input.channels().max(1) as u32;
+                    let frames = (self.held.len() as u32).div_ceil(channels);
+                    self.trailing =
+                        Duration::from_secs(1) / self.input.sample_rate().max(1) * frames;
+                    self.held.clear();
+                    self.input_ended = true;
+                }
+                Some((_, true)) => {
+                    self.started = true;
+                    self.ready.append(&mut self.held);
+                    self.ready.extend(self.frame.drain(..));
+                }
+                Some((duration, false)) if !self.started => self.leading += duration,
+                Some((_, false)) => {
+                    self.held.extend(self.frame.drain(..));
+                    let channels = self.input.channels().max(1) as usize;
+                    let max_held = (MAX_HELD.as_secs() as usize)
+                        .saturating_mul(self.input.sample_rate() as usize)
+                        .saturating_mul(channels);
+                    if self.held.len() > max_held {
+                        let excess = (self.held.len() - max_held).div_ceil(channels) * channels;
+                        self.ready
+                            .extend(self.held.drain(..excess.min(self.held.len())));
+                    }
+                }
+            }
+        }
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let (_, upper) = self.input.size_hint();
+        let buffered = self.ready.len() + self.held.len();
+        (self.ready.len(), upper.map(|upper| upper + buffered))
+    }
+}
+
+impl<I> Source for TrimSilence<I>
+where
+    I: Source,
+    I::Item: Sample,
+{
+    #[inline]
+    fn current_span_len(&self) -> Option<usize> {
+        // Samples are held back for an unknown amount of time so the boundaries of the input
+        // spans do not line up with ours.
+        None
+    }
+
+    #[inline]
+    fn channels(&self) -> ChannelCount {
+        self.input.channels()
+    }
+
+    #[inline]
+    fn sample_rate(&self) -> SampleRate {
+        self.input.sample_rate()
+    }
+
+    /// The silence at the end can only be measured once it has been reached. Until then this
+    /// only accounts for the silence that was removed at the start.
+    #[inline]
+    fn total_duration(&self) -> Option<Duration> {
+        self.input
+            .total_duration()
+            .map(|d| d.saturating_sub(self.leading + self.trailing))
+    }
+
+    #[inline]
+    fn tail_duration(&self) -> Duration {
+        self.input.tail_duration().saturating_sub(self.trailing)
+    }
+
+    #[inline]
+    fn latency(&self) -> Duration {
+        self.input.latency()
+    }
+
+    /// Positions are relative to the start of the trimmed source. The silence at the end is
+    /// measured again once it is reached.
+    #[inline]
+    fn try_seek(&mut self, pos: Duration) -> Result<(), SeekError> {
+        self.input.try_seek(pos + self.leading)?;
+        self.ready.clear();
+        self.held.clear();
+        self.frame.clear();
+        self.trailing = Duration::ZERO;
+        self.input_ended = false;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use crate::buffer::SamplesBuffer;
+    use crate::source::Source;
+
+    fn padded_tone() -> Vec<f32> {
+        let tone = (0..200).map(|i| if i % 2 == 0 { 0.5 } else { -0.5 });
+        let mut data = vec![0.0; 100];
+        data.extend(tone.clone());
+        // a brief quiet passage in the middle must be kept
+        data.extend([0.001; 50]);
+        data.extend(tone);
+        data.extend([0.0; 300]);
+        data
+    }
+
+    #[test]
+    fn trims_both_ends() {
+        let source = SamplesBuffer::new(1, 1000, padded_tone());
+        let mut trimmed = source.trim_silence(-40.0);
+
+        let output: Vec<f32> = trimmed.by_ref().collect();
+        assert_eq!(output.len(), 450);
+        assert_eq!(output[0], 0.5);
+        assert_eq!(output[200], 0.001);
+        assert_eq!(output[449], -0.5);
+        assert_eq!(trimmed.total_duration(), Some(Duration::from_millis(450)));
+    }
+
+    #[test]
+    fn keeps_frames_intact() {
+        let data = vec![0.0, 0.0, 0.0, 0.9, 0.1, 0.2, 0.0, 0.0];
+        let source = SamplesBuffer::new(2, 1000, data);
+        let output: Vec<f32> = source.trim_silence(-20.0).collect();
+        assert_eq!(output, [0.0, 0.9, 0.1, 0.2]);
+    }
+
+    #[test]
+    fn seeking_measures_the_end_again() {
+        let source = SamplesBuffer::new(1, 1000, padded_tone());
+        let mut trimmed = source.trim_silence(-40.0);
+
+        assert_eq!(trimmed.by_ref().count(), 450);
+        trimmed.try_seek(Duration::from_millis(400)).unwrap();
+        assert_eq!(trimmed.total_duration(), Some(Duration::from_millis(750)));
+        assert_eq!(trimmed.by_ref().count(), 50);
+        assert_eq!(trimmed.total_duration(), Some(Duration::from_millis(450)));
+    }
+
+    #[test]
+    fn long_silence_is_only_held_back_up_to_a_limit() {
+        let mut data = vec![0.5; 100];
+        data.extend([0.0; 1500]);
+        let source = SamplesBuffer::new(1, 100, data);
+        let mut trimmed = source.trim_silence(-40.0);
+
+        assert_eq!(trimmed.by_ref().take(100).count(), 100);
+        // the first five seconds of the silence are let through as soon as they are read
+        assert_eq!(trimmed.next(), Some(0.0));
+        assert!(trimmed.held.len() <= 1000);
+        assert_eq!(trimmed.count(), 499);
+    }
+
+    #[test]
+    fn all_silence() {
+        let source = SamplesBuffer::new(1, 1000, vec![0.0f32; 100]);
+        assert_eq!(source.trim_silence(-60.0).count(), 0);
+    }
+}
@@ -5,7 +5,9 @@ use dasp_sample::FromSample;
 
 use super::SeekError;
 use crate::common::{ChannelCount, SampleRate};
-use crate::conversions::{ChannelCountConverter, DataConverter, SampleRateConverter};
+use crate::conversions::{
+    BufferPool, ChannelCountConverter, Custom, DataConverter, Resampler, Resampling,
+};
 use crate::{Sample, Source};
 
 /// An iterator that reads from a `Source` and converts the samples to a
@@ -13,6 +15,9 @@ use crate::{Sample, Source};
 ///
 /// It implements `Source` as well, but all the data is guaranteed to be in a
 /// single span whose channels and samples rate have been passed to `new`.
+///
+/// The sample rate is converted with linear interpolation, use
+/// [`with_resampler`](UniformSourceIterator::with_resampler) to plug in another converter.
 #[derive(Clone)]
 pub struct UniformSourceIterator<I, D>
 where
@@ -20,10 +25,11 @@ where
     I::Item: Sample,
     D: Sample,
 {
-    inner: Option<DataConverter<ChannelCountConverter<SampleRateConverter<Take<I>>>, D>>,
+    inner: Option<DataConverter<ChannelCountConverter<Resampling<Take<I>>>, D>>,
     target_channels: ChannelCount,
     target_sample_rate: SampleRate,
-    total_duration: Option<Duration>,
+    // Buffers of the sample rate converter, reused for the next span.
+    pool: BufferPool<I::Item>,
 }
 
 impl<I, D> UniformSourceIterator<I, D>
@@ -40,36 +46,189 @@ where
         target_channels: ChannelCount,
         target_sample_rate: SampleRate,
     ) -> UniformSourceIterator<I, D> {
-        let total_duration = input.total_duration();
-        let input = UniformSourceIterator::bootstrap(input, target_channels, target_sample_rate);
+        UniformSourceIterator::build(input, target_channels, target_sample_rate, None)
+    }
+
+    /// Same as [`new`](UniformSourceIterator::new), but converts the sample rate with
+    /// `resampler` instead of the built-in linear interpolation.
+    ///
+    /// Cloning the returned source panics unless the resampler implements
+    /// [`try_clone`](Resampler::try_clone).
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rodio::source::{Resampler, SineWave, UniformSourceIterator};
+    /// use rodio::{ChannelCount, SampleRate};
+    ///
+    /// // Plays the input frame closest to every output frame.
+    /// #[derive(Default)]
+    /// struct Nearest {
+    ///     from: SampleRate,
+    ///     to: SampleRate,
+    ///     written: u64,
+    ///     read: u64,
+    ///     frame: Vec<f32>,
+    /// }
+    ///
+    /// impl Resampler for Nearest {
+    ///     fn reset(&mut self, from: SampleRate, to: SampleRate, channels: ChannelCount) {
+    ///         *self = Nearest { from, to, frame: vec![0.0; channels as usize], ..Nearest::default() };
+    ///     }
+    ///
+    ///     fn next_frame(
+    ///         &mut self,
+    ///         input: &mut dyn FnMut(&mut [f32]) -> bool,
+    ///         output: &mut [f32],
+    ///     ) -> bool {
+    ///         let wanted = self.written * self.from as u64 / self.to as u64;
+    ///         while self.read <= wanted {
+    ///             if !input(&mut self.frame) {
+    ///                 return false;
+    ///             }
+    ///             self.read += 1;
+    ///         }
+    ///         output.copy_from_slice(&self.frame);
+    ///         self.written += 1;
+    ///         true
+    ///     }
+    /// }
+    ///
+    /// let source = UniformSourceIterator::<_, f32>::with_resampler(
+    ///     SineWave::new(440.0),
+    ///     2,
+    ///     44100,
+    ///     Nearest::default(),
+    /// );
+    /// ```
+    pub fn with_resampler<R>(
+        input: I,
+        target_channels: ChannelCount,
+        target_sample_rate: SampleRate,
+        resampler: R,
+    ) -> UniformSourceIterator<I, D>
+    where
+        R: Resampler + 'static,
+    {
+        UniformSourceIterator::build(
+            input,
+            target_channels,
+            target_sample_rate,
+            Some(Custom::new(Box::new(resampler))),
+        )
+    }
+
+    fn build(
+        input: I,
+        target_channels: ChannelCount,
+        target_sample_rate: SampleRate,
+        resampler: Option<Custom>,
+    ) -> UniformSourceIterator<I, D> {
+        let mut pool = BufferPool::default();
+        let input = UniformSourceIterator::bootstrap(
+            input,
+            target_channels,
+            target_sample_rate,
+            &mut pool,
+            resampler,
+        );
 
         UniformSourceIterator {
             inner: Some(input),
             target_channels,
             target_sample_rate,
-            total_duration,
+            pool,
         }
     }
 
+    /// Number of input frames the sample rate conversion has read ahead of the output. Part
+    /// of the [`latency`](Source::latency) of this source.
+    #[inline]
+    pub fn resampler_delay_frames(&self) -> usize {
+        self.inner
+            .as_ref()
+            .map_or(0, |input| input.inner().inner().latency())
+    }
+
+    /// Returns a reference to the inner source.
+    #[inline]
+    pub fn inner(&self) -> &I {
+        self.inner.as_ref().unwrap().inner().inner().inner().inner()
+    }
+
+    /// Returns a mutable reference to the inner source. Samples that were already read ahead
+    /// for the sample rate conversion are still played, prefer seeking through
+    /// [`try_seek`](Source::try_seek) of this source.
+    #[inline]
+    pub fn inner_mut(&mut self) -> &mut I {
+        self.inner
+            .as_mut()
+            .unwrap()
+            .inner_mut()
+            .inner_mut()
+            .inner_mut()
+            .inner_mut()
+    }
+
+    /// Returns the inner source. Samples that were read ahead for the sample rate conversion
+    /// are lost.
+    #[inline]
+    pub fn into_inner(mut self) -> I {
+        let (input, _) = self
+            .inner
+            .take()
+            .unwrap()
+            .into_inner()
+            .into_inner()
+            .recycle(&mut self.pool);
+        input.iter
+    }
+
     #[inline]
     fn bootstrap(
-        input: I,
+        mut input: I,
         target_channels: ChannelCount,
         target_sample_rate: SampleRate,
-    ) -> DataConverter<ChannelCountConverter<SampleRateConverter<Take<I>>>, D> {
-        // Limit the span length to something reasonable
-        let span_len = input.current_span_len().map(|x| x.min(32768));
+        pool: &mut BufferPool<I::Item>,
+        resampler: Option<Custom>,
+    ) -> DataConverter<ChannelCountConverter<Resampling<Take<I>>>, D> {
+        // Spans without channels or without a sample rate (as produced by some broken files)
+        // can not be converted. They are skipped, or if their length is unknown the source is
+        // treated as finished.
+        while is_malformed(&input) {
+            match input.current_span_len() {
+                Some(len) if len > 0 => {
+                    if input.by_ref().take(len).count() < len {
+                        break;
+                    }
+                }
+                _ => break,
+            }
+        }
 
         let from_channels = input.channels();
         let from_sample_rate = input.sample_rate();
 
+        // Limit the span length to something reasonable
+        let span_len = if is_malformed(&input) {
+            Some(0)
+        } else {
+            input.current_span_len().map(|x| x.min(32768))
+        };
+
         let input = Take {
             iter: input,
             n: span_len,
         };
-        let input =
-            SampleRateConverter::new(input, from_sample_rate, target_sample_rate, from_channels);
-        let input = ChannelCountConverter::new(input, from_channels, target_channels);
+        let input = Resampling::with_pool(
+            input,
+            from_sample_rate.max(1),
+            target_sample_rate,
+            from_channels.max(1),
+            resampler,
+            pool,
+        );
+        let input = ChannelCountConverter::new(input, from_channels.max(1), target_channels);
 
         DataConverter::new(input)
     }
@@ -89,17 +248,21 @@ where
             return Some(value);
         }
 
-        let input = self
+        let (input, resampler) = self
             .inner
             .take()
             .unwrap()
             .into_inner()
             .into_inner()
-            .into_inner()
-            .iter;
+            .recycle(&mut self.pool);
 
-        let mut input =
-            UniformSourceIterator::bootstrap(input, self.target_channels, self.target_sample_rate);
+        let mut input = UniformSourceIterator::bootstrap(
+            input.iter,
+            self.target_channels,
+            self.target_sample_rate,
+            &mut self.pool,
+            resampler,
+        );
 
         let value = input.next();
         self.inner = Some(input);
@@ -135,7 +298,26 @@ where
 
     #[inline]
     fn total_duration(&self) -> Option<Duration> {
-        self.total_duration
+        // Not cached, the inner source may change its duration while playing (see `speed`).
+        self.inner
+            .as_ref()
+            .and_then(|input| input.inner().inner().inner().inner().total_duration())
+    }
+
+    #[inline]
+    fn tail_duration(&self) -> Duration {
+        self.inner.as_ref().map_or(Duration::ZERO, |input| {
+            input.inner().inner().inner().inner().tail_duration()
+        })
+    }
+
+    #[inline]
+    fn latency(&self) -> Duration {
+        self.inner.as_ref().map_or(Duration::ZERO, |input| {
+            let source = input.inner().inner().inner().inner();
+            let delay = input.inner().inner().latency() as f64 / source.sample_rate().max(1) as f64;
+            source.latency() + Duration::from_secs_f64(delay)
+        })
     }
 
     #[inline]
@@ -153,6 +335,14 @@ where
     }
 }
 
+#[inline]
+fn is_malformed<I: Source>(input: &I) -> bool
+where
+    I::Item: Sample,
+{
+    input.channels() == 0 || input.sample_rate() == 0
+}
+
 #[derive(Clone, Debug)]
 struct Take<I> {
     iter: I,
@@ -160,6 +350,11 @@ struct Take<I> {
 }
 
 impl<I> Take<I> {
+    #[inline]
+    pub fn inner(&self) -> &I {
+        &self.iter
+    }
+
     #[inline]
     pub fn inner_mut(&mut self) -> &mut I {
         &mut self.iter
@@ -206,3 +401,75 @@ where
 }
 
 impl<I> ExactSizeIterator for Take<I> where I: ExactSizeIterator {}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    use super::UniformSourceIterator;
+    use crate::buffer::SamplesBuffer;
+    use crate::common::{ChannelCount, SampleRate};
+    use crate::conversions::Resampler;
+    use crate::source::from_iter;
+    use crate::Source;
+
+    // Copies the input and counts the frames it produced.
+    struct Passthrough {
+        frames: Arc<AtomicUsize>,
+        resets: Arc<AtomicUsize>,
+    }
+
+    impl Resampler for Passthrough {
+        fn reset(&mut self, from: SampleRate, to: SampleRate, _: ChannelCount) {
+            assert_eq!(from, to);
+            self.resets.fetch_add(1, Ordering::Relaxed);
+        }
+
+        fn next_frame(
+            &mut self,
+            input: &mut dyn FnMut(&mut [f32]) -> bool,
+            output: &mut [f32],
+        ) -> bool {
+            let read = input(output);
+            if read {
+                self.frames.fetch_add(1, Ordering::Relaxed);
+            }
+            read
+        }
+    }
+
+    #[test]
+    fn custom_resampler_converts_every_span() {
+        let frames = Arc::new(AtomicUsize::new(0));
+        let resets = Arc::new(AtomicUsize::new(0));
+        let resampler = Passthrough {
+            frames: frames.clone(),
+            resets: resets.clone(),
+        };
+        let input = from_iter([
+            SamplesBuffer::new(2, 1000, vec![0.5f32, -0.5, 0.25, -0.25]),
+            SamplesBuffer::new(2, 1000, vec![1.0f32, -1.0]),
+        ]);
+        let output: Vec<f32> =
+            UniformSourceIterator::with_resampler(input, 2, 1000, resampler).collect();
+
+        assert_eq!(output, [0.5, -0.5, 0.25, -0.25, 1.0, -1.0]);
+        assert_eq!(frames.load(Ordering::Relaxed), 3);
+        // once per span, and once more when looking for a span after the last one
+        assert_eq!(resets.load(Ordering::Relaxed), 3);
+    }
+
+    #[test]
+    fn latency_includes_the_frame_read_ahead() {
+        let input = SamplesBuffer::new(1, 1000, vec![0.5f32; 10]);
+        let source = UniformSourceIterator::<_, f32>::new(input.clone(), 1, 3000);
+        assert_eq!(source.resampler_delay_frames(), 1);
+        assert_eq!(source.latency(), Duration::from_millis(1));
+
+        let source = UniformSourceIterator::<_, f32>::new(input, 1, 1000);
+        assert_eq!(source.resampler_delay_frames(), 0);
+        assert_eq!(source.latency(), Duration::ZERO);
+    }
+}
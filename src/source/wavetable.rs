@@ -0,0 +1,165 @@
+use std::time::Duration;
+
+use super::SeekError;
+use crate::common::{ChannelCount, SampleRate};
+use crate::Source;
+
+/// An infinite source that plays a single cycle of a waveform on repeat at any frequency, a
+/// wavetable oscillator.
+///
+/// Unlike the generators of [`SignalGenerator`](super::SignalGenerator), the shape of the wave
+/// is given as a table of samples, for example one period cut from a recording or drawn by
+/// hand. The table is read at the speed that makes it repeat `freq` times per second,
+/// positions between two samples of the table are linearly interpolated. Has one channel.
+///
+/// # Example
+///
+/// ```
+/// use rodio::source::Wavetable;
+///
+/// // a rough square wave
+/// let table = vec![1.0, 1.0, 1.0, 1.0, -1.0, -1.0, -1.0, -1.0];
+/// let mut oscillator = Wavetable::new(table, 220.0, 48000);
+/// oscillator.set_freq(440.0);
+/// ```
+#[derive(Clone, Debug)]
+pub struct Wavetable {
+    table: Vec<f32>,
+    freq: f32,
+    sample_rate: SampleRate,
+    // Position in the table in the range `0.0..table.len()`.
+    pos: f64,
+    // Table samples to advance per output sample.
+    step: f64,
+}
+
+impl Wavetable {
+    /// Plays `table`, one period of the waveform, `freq` times per second at `sample_rate`.
+    ///
+    /// # Panics
+    ///
+    /// If `table` is empty or `sample_rate` is zero.
+    pub fn new(table: Vec<f32>, freq: f32, sample_rate: SampleRate) -> Wavetable {
+        assert!(!table.is_empty(), "the wavetable must not be empty");
+        assert!(sample_rate > 0, "sample rate must be greater than zero");
+        let mut wavetable = Wavetable {
+            table,
+            freq: 0.0,
+            sample_rate,
+            pos: 0.0,
+            step: 0.0,
+        };
+        wavetable.set_freq(freq);
+        wavetable
+    }
+
+    /// Changes the frequency while playing. The wave carries on from where it is, so the
+    /// change does not click. Negative frequencies play the table backwards.
+    #[inline]
+    pub fn set_freq(&mut self, freq: f32) {
+        self.freq = freq;
+        self.step = freq as f64 * self.table.len() as f64 / self.sample_rate as f64;
+    }
+
+    /// Returns the frequency the table is played at.
+    #[inline]
+    pub fn freq(&self) -> f32 {
+        self.freq
+    }
+
+    /// Returns the table that is played.
+    #[inline]
+    pub fn table(&self) -> &[f32] {
+        &self.table
+    }
+}
+
+impl Iterator for Wavetable {
+    type Item = f32;
+
+    #[inline]
+    fn next(&mut self) -> Option<f32> {
+        let len = self.table.len();
+        let index = self.pos as usize;
+        let fraction = (self.pos - index as f64) as f32;
+        let current = self.table[index];
+        let next = self.table[(index + 1) % len];
+        self.pos = (self.pos + self.step).rem_euclid(len as f64);
+        // `rem_euclid` can round up to `len` for tiny negative positions.
+        if self.pos >= len as f64 {
+            self.pos = 0.0;
+        }
+        Some(current + (next - current) * fraction)
+    }
+}
+
+impl Source for Wavetable {
+    #[inline]
+    fn current_span_len(&self) -> Option<usize> {
+        None
+    }
+
+    #[inline]
+    fn channels(&self) -> ChannelCount {
+        1
+    }
+
+    #[inline]
+    fn sample_rate(&self) -> SampleRate {
+        self.sample_rate
+    }
+
+    #[inline]
+    fn total_duration(&self) -> Option<Duration> {
+        None
+    }
+
+    /// Moves to where the wave would be after playing for `pos` at the current frequency.
+    #[inline]
+    fn try_seek(&mut self, pos: Duration) -> Result<(), SeekError> {
+        let len = self.table.len() as f64;
+        self.pos = (pos.as_secs_f64() * self.freq as f64 * len).rem_euclid(len);
+        if self.pos >= len {
+            self.pos = 0.0;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f32::consts::TAU;
+
+    use super::Wavetable;
+
+    // Counts the times the signal goes from negative to positive.
+    fn rising_crossings(samples: &[f32]) -> usize {
+        samples
+            .windows(2)
+            .filter(|w| w[0] < 0.0 && w[1] >= 0.0)
+            .count()
+    }
+
+    #[test]
+    fn plays_the_requested_frequency() {
+        let table: Vec<f32> = (0..64).map(|i| (TAU * i as f32 / 64.0).sin()).collect();
+        let mut wavetable = Wavetable::new(table, 440.0, 48_000);
+        let second: Vec<f32> = wavetable.by_ref().take(48_000).collect();
+        assert!(rising_crossings(&second).abs_diff(440) <= 1);
+
+        wavetable.set_freq(1234.5);
+        let second: Vec<f32> = wavetable.take(48_000).collect();
+        assert!(rising_crossings(&second).abs_diff(1234) <= 1);
+    }
+
+    #[test]
+    fn fractional_positions_are_interpolated() {
+        // the table is read at a quarter of a sample per output sample
+        let wavetable = Wavetable::new(vec![0.0, 1.0], 1.0, 8);
+        let output: Vec<f32> = wavetable.take(10).collect();
+        assert_eq!(
+            output,
+            [0.0, 0.25, 0.5, 0.75, 1.0, 0.75, 0.5, 0.25, 0.0, 0.25]
+        );
+    }
+}
@@ -0,0 +1,254 @@
+use std::time::Duration;
+
+use super::{BoxedSource, SeekError};
+use crate::common::{ChannelCount, SampleRate};
+use crate::source::uniform::UniformSourceIterator;
+use crate::Source;
+
+/// Format of a mix without any sources.
+const EMPTY_CHANNELS: ChannelCount = 1;
+const EMPTY_SAMPLE_RATE: SampleRate = 44100;
+
+/// Mixes any number of sources, each with its own weight, in a single source.
+///
+/// Unlike chaining [`mix`](Source::mix), which nests a level deeper for every source, all
+/// sources are summed in one place. They are converted to the channel count and sample rate
+/// of the first one. The mix ends when the longest source ends, the others are silent once
+/// they have ended.
+///
+/// # Example
+///
+/// ```
+/// use rodio::source::{SineWave, Source, WeightedMix};
+///
+/// let pad = WeightedMix::new(vec![
+///     (SineWave::new(220.0).boxed(), 0.5),
+///     (SineWave::new(277.2).boxed(), 0.3),
+///     (SineWave::new(329.6).boxed(), 0.2),
+/// ]);
+/// ```
+pub struct WeightedMix {
+    inputs: Vec<Input>,
+    channels: ChannelCount,
+    sample_rate: SampleRate,
+    // The channel of the next sample.
+    channel: ChannelCount,
+}
+
+struct Input {
+    source: UniformSourceIterator<BoxedSource, f32>,
+    weight: f32,
+    ended: bool,
+}
+
+impl WeightedMix {
+    /// Mixes `sources`, multiplying the samples of each source by its weight.
+    pub fn new(sources: Vec<(BoxedSource, f32)>) -> WeightedMix {
+        let (channels, sample_rate) = match sources.first() {
+            Some((source, _)) => (source.channels().max(1), source.sample_rate().max(1)),
+            None => (EMPTY_CHANNELS, EMPTY_SAMPLE_RATE),
+        };
+        let inputs = sources
+            .into_iter()
+            .map(|(source, weight)| Input {
+                source: UniformSourceIterator::new(source, channels, sample_rate),
+                weight,
+                ended: false,
+            })
+            .collect();
+        WeightedMix {
+            inputs,
+            channels,
+            sample_rate,
+            channel: 0,
+        }
+    }
+
+    /// Returns the number of sources in the mix.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.inputs.len()
+    }
+
+    /// Returns true if the mix has no sources.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.inputs.is_empty()
+    }
+
+    /// Returns the weight of the source at `index`.
+    ///
+    /// # Panics
+    ///
+    /// If `index` is out of bounds.
+    #[inline]
+    pub fn weight(&self, index: usize) -> f32 {
+        self.inputs[index].weight
+    }
+
+    /// Changes the weight of the source at `index` while playing.
+    ///
+    /// # Panics
+    ///
+    /// If `index` is out of bounds.
+    #[inline]
+    pub fn set_weight(&mut self, index: usize, weight: f32) {
+        self.inputs[index].weight = weight;
+    }
+}
+
+impl Iterator for WeightedMix {
+    type Item = f32;
+
+    #[inline]
+    fn next(&mut self) -> Option<f32> {
+        let mut sum = 0.0;
+        let mut playing = false;
+        for input in &mut self.inputs {
+            if input.ended {
+                continue;
+            }
+            match input.source.next() {
+                Some(sample) => {
+                    sum += sample * input.weight;
+                    playing = true;
+                }
+                None => input.ended = true,
+            }
+        }
+        // A frame that was started is completed.
+        if !playing && self.channel == 0 {
+            return None;
+        }
+        self.channel = (self.channel + 1) % self.channels;
+        Some(sum)
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let lower = self
+            .inputs
+            .iter()
+            .filter(|input| !input.ended)
+            .map(|input| input.source.size_hint().0)
+            .max()
+            .unwrap_or(0);
+        (lower, None)
+    }
+}
+
+impl Source for WeightedMix {
+    #[inline]
+    fn current_span_len(&self) -> Option<usize> {
+        None
+    }
+
+    #[inline]
+    fn channels(&self) -> ChannelCount {
+        self.channels
+    }
+
+    #[inline]
+    fn sample_rate(&self) -> SampleRate {
+        self.sample_rate
+    }
+
+    #[inline]
+    fn total_duration(&self) -> Option<Duration> {
+        self.inputs
+            .iter()
+            .map(|input| input.source.total_duration())
+            .try_fold(Duration::ZERO, |longest, duration| {
+                duration.map(|duration| longest.max(duration))
+            })
+    }
+
+    /// Everything that plays on after the last source without its tail ends, if all the
+    /// durations are known. Otherwise the longest tail.
+    fn tail_duration(&self) -> Duration {
+        let longest_tail = self
+            .inputs
+            .iter()
+            .map(|input| input.source.tail_duration())
+            .max()
+            .unwrap_or_default();
+        let Some(end) = self.total_duration() else {
+            return longest_tail;
+        };
+        let last_dry_end = self
+            .inputs
+            .iter()
+            .filter_map(|input| {
+                let total = input.source.total_duration()?;
+                Some(total.saturating_sub(input.source.tail_duration()))
+            })
+            .max()
+            .unwrap_or_default();
+        end.saturating_sub(last_dry_end)
+    }
+
+    fn latency(&self) -> Duration {
+        self.inputs
+            .iter()
+            .map(|input| input.source.latency())
+            .max()
+            .unwrap_or_default()
+    }
+
+    /// Seeks every source, sources that had ended play again if they are long enough.
+    fn try_seek(&mut self, pos: Duration) -> Result<(), SeekError> {
+        for input in &mut self.inputs {
+            input.source.try_seek(pos)?;
+            input.ended = false;
+        }
+        self.channel = 0;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::WeightedMix;
+    use crate::buffer::SamplesBuffer;
+    use crate::source::{SineWave, Source};
+
+    #[test]
+    fn output_is_the_weighted_sum() {
+        let tone = |freq: f32| {
+            SineWave::new(freq)
+                .take_duration(Duration::from_millis(100))
+                .collect::<Vec<f32>>()
+        };
+        let (a, b, c) = (tone(220.0), tone(330.0), tone(440.0));
+        let mix = WeightedMix::new(vec![
+            (SineWave::new(220.0).boxed(), 0.5),
+            (SineWave::new(330.0).boxed(), 0.25),
+            (SineWave::new(440.0).boxed(), -0.125),
+        ]);
+        let output: Vec<f32> = mix.take(a.len()).collect();
+
+        for (i, sample) in output.iter().enumerate() {
+            let expected = a[i] * 0.5 + b[i] * 0.25 - c[i] * 0.125;
+            assert!((sample - expected).abs() < 1e-6);
+        }
+    }
+
+    #[test]
+    fn ends_with_the_longest_source() {
+        let mut mix = WeightedMix::new(vec![
+            (SamplesBuffer::new(2, 1000, vec![1.0f32; 4]).boxed(), 1.0),
+            (SamplesBuffer::new(2, 1000, vec![1.0f32; 8]).boxed(), 0.5),
+            // converted to stereo
+            (SamplesBuffer::new(1, 1000, vec![1.0f32; 1]).boxed(), 0.25),
+        ]);
+        assert_eq!(mix.total_duration(), Some(Duration::from_millis(4)));
+        let output: Vec<f32> = mix.by_ref().collect();
+        assert_eq!(output, [1.75, 1.75, 1.5, 1.5, 0.5, 0.5, 0.5, 0.5]);
+
+        mix.set_weight(1, 2.0);
+        mix.try_seek(Duration::from_millis(3)).unwrap();
+        assert_eq!(mix.collect::<Vec<_>>(), [2.0, 2.0]);
+    }
+}
@@ -2,7 +2,7 @@ use std::marker::PhantomData;
 use std::time::Duration;
 
 use super::SeekError;
-use crate::common::{ChannelCount, SampleRate};
+use crate::common::{frames_to_duration, ChannelCount, SampleRate};
 use crate::{Sample, Source};
 
 /// An source that produces samples with value zero (silence). Depending on if
@@ -27,7 +27,8 @@ impl<S> Zero<S> {
             marker: PhantomData,
         }
     }
-    /// Create a new source that never ends and produces total silence.
+    /// Create a new source that produces `num_samples` samples of silence, counted over all
+    /// channels.
     #[inline]
     pub fn new_samples(
         channels: ChannelCount,
@@ -62,6 +63,14 @@ where
             Some(S::ZERO_VALUE)
         }
     }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        match self.num_samples {
+            Some(num_samples) => (num_samples, Some(num_samples)),
+            None => (usize::MAX, None),
+        }
+    }
 }
 
 impl<S> Source for Zero<S>
@@ -85,7 +94,8 @@ where
 
     #[inline]
     fn total_duration(&self) -> Option<Duration> {
-        None
+        let frames = self.num_samples? as u64 / self.channels.max(1) as u64;
+        Some(frames_to_duration(frames, self.sample_rate))
     }
 
     #[inline]
@@ -93,3 +103,28 @@ where
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::Zero;
+    use crate::Source;
+
+    #[test]
+    fn infinite_silence() {
+        let source = Zero::<f32>::new(2, 44100);
+        assert_eq!(source.channels(), 2);
+        assert_eq!(source.sample_rate(), 44100);
+        assert_eq!(source.total_duration(), None);
+        assert!(source.take(100_000).all(|s| s == 0.0));
+    }
+
+    #[test]
+    fn finite_silence() {
+        let source = Zero::<f32>::new_samples(2, 1000, 500);
+        assert_eq!(source.size_hint(), (500, Some(500)));
+        assert_eq!(source.total_duration(), Some(Duration::from_millis(250)));
+        assert_eq!(source.collect::<Vec<_>>(), [0.0; 500]);
+    }
+}
@@ -0,0 +1,254 @@
+use std::time::Duration;
+
+use super::SeekError;
+use crate::common::{ChannelCount, SampleRate};
+use crate::{Sample, Source};
+
+/// Returns true if a signal going from `previous` to `current` crosses or touches zero.
+#[inline]
+pub(crate) fn is_crossing(previous: f32, current: f32) -> bool {
+    (previous <= 0.0 && current >= 0.0) || (previous >= 0.0 && current <= 0.0)
+}
+
+/// Returns the frame of `samples` to splice at, the quieter side of the first zero crossing,
+/// or the quietest frame if there is none. Channels are mixed down to mono.
+pub(crate) fn splice_point<S>(samples: &[S], channels: usize) -> usize
+where
+    S: Sample,
+{
+    let mono: Vec<f32> = samples
+        .chunks(channels)
+        .map(|frame| frame.iter().map(|s| s.to_f32()).sum::<f32>() / channels as f32)
+        .collect();
+
+    let quietest = |a: usize, b: usize| {
+        if mono[b].abs() < mono[a].abs() {
+            b
+        } else {
+            a
+        }
+    };
+    (1..mono.len())
+        .find(|&i| is_crossing(mono[i - 1], mono[i]))
+        .map(|i| quietest(i - 1, i))
+        .or_else(|| (0..mono.len()).reduce(quietest))
+        .unwrap_or(0)
+}
+
+/// Internal function that builds an `AlignToZeroCrossing` object.
+pub fn align_to_zero_crossing<I>(input: I, max_offset: Duration) -> AlignToZeroCrossing<I>
+where
+    I: Source,
+    I::Item: Sample,
+{
+    AlignToZeroCrossing {
+        input,
+        max_offset,
+        aligned: false,
+        buffer: Vec::new(),
+        pos: 0,
+        dropped: Duration::ZERO,
+    }
+}
+
+/// Starts a source at its first zero crossing so that it can be spliced onto another sound
+/// without a click.
+///
+/// Up to `max_offset` of the input is read ahead when playback starts and after every seek.
+/// The frames before the zero crossing that is found are dropped, so at most `max_offset` of
+/// audio is lost. If there is no zero crossing within that window the source starts at its
+/// quietest frame instead. Channels are mixed down to mono to find the crossing.
+#[derive(Clone, Debug)]
+pub struct AlignToZeroCrossing<I>
+where
+    I: Source,
+    I::Item: Sample,
+{
+    input: I,
+    max_offset: Duration,
+    aligned: bool,
+    // Samples read ahead while searching, returned before reading from the input again.
+    buffer: Vec<I::Item>,
+    pos: usize,
+    // Duration dropped by the last alignment.
+    dropped: Duration,
+}
+
+impl<I> AlignToZeroCrossing<I>
+where
+    I: Source,
+    I::Item: Sample,
+{
+    /// Returns a reference to the inner source.
+    #[inline]
+    pub fn inner(&self) -> &I {
+        &self.input
+    }
+
+    /// Returns a mutable reference to the inner source.
+    #[inline]
+    pub fn inner_mut(&mut self) -> &mut I {
+        &mut self.input
+    }
+
+    /// Returns the inner source.
+    #[inline]
+    pub fn into_inner(self) -> I {
+        self.input
+    }
+
+    /// Returns the duration that was dropped to reach the zero crossing.
+    #[inline]
+    pub fn dropped(&self) -> Duration {
+        self.dropped
+    }
+
+    fn align(&mut self) {
+        self.aligned = true;
+        let channels = self.input.channels().max(1) as usize;
+        let sample_rate = self.input.sample_rate().max(1);
+        let max_frames = (self.max_offset.as_secs_f64() * sample_rate as f64) as usize;
+
+        // One frame more than the window so a crossing right at its end can be found.
+        self.buffer.clear();
+        self.buffer
+            .extend(self.input.by_ref().take((max_frames + 1) * channels));
+        let start = splice_point(&self.buffer, channels);
+
+        self.pos = start * channels;
+        self.dropped = Duration::from_secs(1) * start as u32 / sample_rate;
+    }
+}
+
+impl<I> Iterator for AlignToZeroCrossing<I>
+where
+    I: Source,
+    I::Item: Sample,
+{
+    type Item = I::Item;
+
+    #[inline]
+    fn next(&mut self) -> Option<I::Item> {
+        if !self.aligned {
+            self.align();
+        }
+
+        if let Some(sample) = self.buffer.get(self.pos) {
+            self.pos += 1;
+            return Some(*sample);
+        }
+        self.input.next()
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let (lower, upper) = self.input.size_hint();
+        let buffered = self.buffer.len() - self.pos.min(self.buffer.len());
+        if self.aligned {
+            (lower + buffered, upper.map(|upper| upper + buffered))
+        } else {
+            (0, upper)
+        }
+    }
+}
+
+impl<I> Source for AlignToZeroCrossing<I>
+where
+    I: Source,
+    I::Item: Sample,
+{
+    #[inline]
+    fn current_span_len(&self) -> Option<usize> {
+        if self.aligned && self.pos >= self.buffer.len() {
+            self.input.current_span_len()
+        } else {
+            None
+        }
+    }
+
+    #[inline]
+    fn channels(&self) -> ChannelCount {
+        self.input.channels()
+    }
+
+    #[inline]
+    fn sample_rate(&self) -> SampleRate {
+        self.input.sample_rate()
+    }
+
+    #[inline]
+    fn total_duration(&self) -> Option<Duration> {
+        self.input
+            .total_duration()
+            .map(|d| d.saturating_sub(self.dropped))
+    }
+
+    #[inline]
+    fn tail_duration(&self) -> Duration {
+        self.input.tail_duration()
+    }
+
+    #[inline]
+    fn latency(&self) -> Duration {
+        self.input.latency()
+    }
+
+    /// Aligns again after seeking, so playback may resume up to `max_offset` after `pos`.
+    #[inline]
+    fn try_seek(&mut self, pos: Duration) -> Result<(), SeekError> {
+        self.input.try_seek(pos)?;
+        self.aligned = false;
+        self.buffer.clear();
+        self.pos = 0;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use crate::buffer::SamplesBuffer;
+    use crate::source::{SineWave, Source};
+
+    // At 48 kHz a 100 Hz sine moves by at most 0.0131 per sample.
+    const MAX_STEP: f32 = 0.0131;
+
+    #[test]
+    fn starts_near_zero() {
+        // start a quarter period in, at the peak of the wave
+        let mut source = SineWave::new(100.0)
+            .skip_duration(Duration::from_micros(2500))
+            .align_to_zero_crossing(Duration::from_millis(10));
+
+        let first = source.next().unwrap();
+        assert!(first.abs() <= MAX_STEP / 2.0, "{first}");
+        // the next crossing is another quarter period away
+        assert!(source.dropped() > Duration::from_millis(2));
+        assert!(source.dropped() < Duration::from_millis(3));
+    }
+
+    #[test]
+    fn skip_cuts_at_zero_crossing() {
+        let mut source = SineWave::new(100.0).skippable();
+        let _ = source.by_ref().take(120).count();
+
+        source.skip_at_zero_crossing(480);
+        let rest: Vec<f32> = source.collect();
+        assert!(rest.len() <= 480);
+        let last = rest.last().unwrap();
+        assert!(last.abs() <= MAX_STEP, "{last}");
+    }
+
+    #[test]
+    fn no_crossing_within_window() {
+        let data = vec![0.5f32, 0.4, 0.1, 0.2, 0.3, 0.6];
+        let aligned = SamplesBuffer::new(1, 1000, data.clone())
+            .align_to_zero_crossing(Duration::from_millis(3));
+        assert_eq!(aligned.collect::<Vec<_>>(), [0.1, 0.2, 0.3, 0.6]);
+
+        let mut skippable = SamplesBuffer::new(1, 1000, data).skippable();
+        skippable.skip_at_zero_crossing(2);
+        assert_eq!(skippable.collect::<Vec<_>>(), [0.5, 0.4]);
+    }
+}
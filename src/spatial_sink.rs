@@ -5,7 +5,7 @@ use std::time::Duration;
 use dasp_sample::FromSample;
 
 use crate::mixer::Mixer;
-use crate::source::{SeekError, Spatial};
+use crate::source::{PanLaw, SeekError, Spatial};
 use crate::{Sample, Sink, Source};
 
 /// A sink that allows changing the position of the source and the listeners
@@ -20,6 +20,7 @@ struct SoundPositions {
     emitter_position: [f32; 3],
     left_ear: [f32; 3],
     right_ear: [f32; 3],
+    pan_law: PanLaw,
 }
 
 impl SpatialSink {
@@ -36,6 +37,7 @@ impl SpatialSink {
                 emitter_position,
                 left_ear,
                 right_ear,
+                pan_law: PanLaw::default(),
             })),
         }
     }
@@ -55,6 +57,12 @@ impl SpatialSink {
         self.positions.lock().unwrap().right_ear = pos;
     }
 
+    /// Changes how the volume is distributed between the ears, the default is
+    /// [`PanLaw::EqualPower`].
+    pub fn set_pan_law(&self, pan_law: PanLaw) {
+        self.positions.lock().unwrap().pan_law = pan_law;
+    }
+
     /// Appends a sound to the queue of sounds to play.
     #[inline]
     pub fn append<S>(&self, source: S)
@@ -65,14 +73,16 @@ impl SpatialSink {
     {
         let positions = self.positions.clone();
         let pos_lock = self.positions.lock().unwrap();
-        let source = Spatial::new(
+        let mut source = Spatial::new(
             source,
             pos_lock.emitter_position,
             pos_lock.left_ear,
             pos_lock.right_ear,
-        )
-        .periodic_access(Duration::from_millis(10), move |i| {
+        );
+        source.set_pan_law(pos_lock.pan_law);
+        let source = source.periodic_access(Duration::from_millis(10), move |i| {
             let pos = positions.lock().unwrap();
+            i.set_pan_law(pos.pan_law);
             i.set_positions(pos.emitter_position, pos.left_ear, pos.right_ear);
         });
         self.sink.append(source);
@@ -0,0 +1,286 @@
+use std::cmp::Ordering;
+use std::sync::Arc;
+
+use dasp_sample::FromSample;
+
+use crate::mixer::Mixer;
+use crate::{Sample, Source, SpatialSink};
+
+/// Identifies a voice played by a [`SpatialVoicePool`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct VoiceId(u64);
+
+/// Plays sounds from many emitters at once, each on its own [`SpatialSink`], with a limit on
+/// the number of voices that play at the same time.
+///
+/// When all voices are taken the least audible one is stopped to make room for a new sound,
+/// unless the new sound would be even less audible, then it is not played at all. How audible
+/// a voice is follows the attenuation of [`Spatial`](crate::source::Spatial): its volume
+/// divided by the squared distance from the emitter to the nearest ear, no louder than its
+/// volume within a distance of one. Among equally audible voices the farthest one is stopped.
+///
+/// Voices that have finished playing free their place on their own.
+///
+/// # Example
+///
+/// ```
+/// use rodio::source::SineWave;
+/// use rodio::{SpatialVoicePool, Source};
+/// use std::time::Duration;
+///
+/// // or the mixer of an output stream
+/// let (mixer, _output) = rodio::mixer::mixer(2, 44100);
+/// let mut pool = SpatialVoicePool::new(mixer, 16, [-0.1, 0.0, 0.0], [0.1, 0.0, 0.0]);
+/// let tone = SineWave::new(440.0).take_duration(Duration::from_secs(1));
+/// let voice = pool.play(tone, [2.0, 0.0, 5.0], 1.0);
+/// // the listener walks towards the sound
+/// pool.set_listener([-0.1, 0.0, 3.0], [0.1, 0.0, 3.0]);
+/// ```
+pub struct SpatialVoicePool {
+    mixer: Arc<Mixer<f32>>,
+    max_voices: usize,
+    left_ear: [f32; 3],
+    right_ear: [f32; 3],
+    voices: Vec<Voice>,
+    next_id: u64,
+}
+
+struct Voice {
+    id: VoiceId,
+    sink: SpatialSink,
+    position: [f32; 3],
+    volume: f32,
+}
+
+impl SpatialVoicePool {
+    /// Builds a pool that plays at most `max_voices` sounds on `mixer` at the same time, heard
+    /// by a listener with the given ear positions.
+    ///
+    /// # Panics
+    ///
+    /// If `max_voices` is zero.
+    pub fn new(
+        mixer: Arc<Mixer<f32>>,
+        max_voices: usize,
+        left_ear: [f32; 3],
+        right_ear: [f32; 3],
+    ) -> SpatialVoicePool {
+        assert!(max_voices > 0, "a pool needs at least one voice");
+        SpatialVoicePool {
+            mixer,
+            max_voices,
+            left_ear,
+            right_ear,
+            voices: Vec::with_capacity(max_voices),
+            next_id: 0,
+        }
+    }
+
+    /// Plays `source` from an emitter at `position` with `volume`, stopping the least audible
+    /// voice if the pool is full. Returns `None` if the pool is full and the new sound would
+    /// be the least audible one.
+    pub fn play<S>(&mut self, source: S, position: [f32; 3], volume: f32) -> Option<VoiceId>
+    where
+        S: Source + Send + 'static,
+        f32: FromSample<S::Item>,
+        S::Item: Sample + Send,
+    {
+        self.voices.retain(|voice| !voice.sink.empty());
+        if self.voices.len() >= self.max_voices {
+            let weakest = self.weakest()?;
+            let candidate = (self.audibility(position, volume), self.distance(position));
+            let voice = &self.voices[weakest];
+            let existing = (
+                self.audibility(voice.position, voice.volume),
+                self.distance(voice.position),
+            );
+            if compare(candidate, existing) != Ordering::Greater {
+                return None;
+            }
+            self.voices.swap_remove(weakest);
+        }
+
+        let sink = SpatialSink::connect_new(&self.mixer, position, self.left_ear, self.right_ear);
+        sink.set_volume(volume);
+        sink.append(source);
+        let id = VoiceId(self.next_id);
+        self.next_id += 1;
+        self.voices.push(Voice {
+            id,
+            sink,
+            position,
+            volume,
+        });
+        Some(id)
+    }
+
+    /// Moves the emitter of a voice. Returns false if the voice is no longer playing.
+    pub fn set_emitter_position(&mut self, id: VoiceId, position: [f32; 3]) -> bool {
+        match self.voice(id) {
+            Some(voice) => {
+                voice.sink.set_emitter_position(position);
+                voice.position = position;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Changes the volume of a voice. Returns false if the voice is no longer playing.
+    pub fn set_volume(&mut self, id: VoiceId, volume: f32) -> bool {
+        match self.voice(id) {
+            Some(voice) => {
+                voice.sink.set_volume(volume);
+                voice.volume = volume;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Moves the ears of the listener, for all voices.
+    pub fn set_listener(&mut self, left_ear: [f32; 3], right_ear: [f32; 3]) {
+        self.left_ear = left_ear;
+        self.right_ear = right_ear;
+        for voice in &self.voices {
+            voice.sink.set_left_ear_position(left_ear);
+            voice.sink.set_right_ear_position(right_ear);
+        }
+    }
+
+    /// Stops a voice and frees its place.
+    pub fn stop(&mut self, id: VoiceId) {
+        self.voices.retain(|voice| voice.id != id);
+    }
+
+    /// Stops all voices.
+    pub fn stop_all(&mut self) {
+        self.voices.clear();
+    }
+
+    /// Returns true if the voice is still playing, false once it has finished or was stopped.
+    pub fn is_playing(&self, id: VoiceId) -> bool {
+        self.voices
+            .iter()
+            .any(|voice| voice.id == id && !voice.sink.empty())
+    }
+
+    /// Returns the number of voices that are playing.
+    pub fn active_voices(&self) -> usize {
+        self.voices
+            .iter()
+            .filter(|voice| !voice.sink.empty())
+            .count()
+    }
+
+    /// Returns the number of voices that can play at the same time.
+    #[inline]
+    pub fn max_voices(&self) -> usize {
+        self.max_voices
+    }
+
+    fn voice(&mut self, id: VoiceId) -> Option<&mut Voice> {
+        self.voices
+            .iter_mut()
+            .find(|voice| voice.id == id && !voice.sink.empty())
+    }
+
+    // Index of the voice that is stopped first.
+    fn weakest(&self) -> Option<usize> {
+        self.voices
+            .iter()
+            .map(|voice| {
+                (
+                    self.audibility(voice.position, voice.volume),
+                    self.distance(voice.position),
+                )
+            })
+            .enumerate()
+            .min_by(|(_, a), (_, b)| compare(*a, *b))
+            .map(|(index, _)| index)
+    }
+
+    // Distance from `position` to the nearest ear.
+    fn distance(&self, position: [f32; 3]) -> f32 {
+        let dist = |ear: [f32; 3]| {
+            (0..3)
+                .map(|i| (ear[i] - position[i]).powi(2))
+                .sum::<f32>()
+                .sqrt()
+        };
+        dist(self.left_ear).min(dist(self.right_ear))
+    }
+
+    fn audibility(&self, position: [f32; 3], volume: f32) -> f32 {
+        volume.abs() * (1.0 / self.distance(position).powi(2)).min(1.0)
+    }
+}
+
+// Orders (audibility, distance) pairs from the voice to stop first to the one to keep longest.
+fn compare(a: (f32, f32), b: (f32, f32)) -> Ordering {
+    a.0.total_cmp(&b.0).then(b.1.total_cmp(&a.1))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::SpatialVoicePool;
+    use crate::mixer::mixer;
+    use crate::source::SineWave;
+
+    const LEFT_EAR: [f32; 3] = [-0.1, 0.0, 0.0];
+    const RIGHT_EAR: [f32; 3] = [0.1, 0.0, 0.0];
+
+    #[test]
+    fn farthest_voice_is_stolen_when_full() {
+        let (controller, _output) = mixer(2, 44100);
+        let mut pool = SpatialVoicePool::new(controller, 3, LEFT_EAR, RIGHT_EAR);
+        let near = pool
+            .play(SineWave::new(220.0), [0.0, 0.0, 2.0], 1.0)
+            .unwrap();
+        let far = pool
+            .play(SineWave::new(330.0), [0.0, 0.0, 20.0], 1.0)
+            .unwrap();
+        let middle = pool
+            .play(SineWave::new(440.0), [0.0, 0.0, 8.0], 1.0)
+            .unwrap();
+        assert_eq!(pool.active_voices(), 3);
+
+        let new = pool
+            .play(SineWave::new(550.0), [5.0, 0.0, 0.0], 1.0)
+            .unwrap();
+        assert!(!pool.is_playing(far));
+        assert!(pool.is_playing(near) && pool.is_playing(middle) && pool.is_playing(new));
+        assert_eq!(pool.active_voices(), 3);
+
+        // a sound farther away than all voices is not played
+        assert!(pool
+            .play(SineWave::new(660.0), [0.0, 0.0, 50.0], 1.0)
+            .is_none());
+        assert!(!pool.set_volume(far, 0.5));
+    }
+
+    #[test]
+    fn quiet_or_stopped_voices_make_room() {
+        let (controller, _output) = mixer(2, 44100);
+        let mut pool = SpatialVoicePool::new(controller, 2, LEFT_EAR, RIGHT_EAR);
+        let quiet = pool
+            .play(SineWave::new(220.0), [0.0, 0.0, 2.0], 0.1)
+            .unwrap();
+        let loud = pool
+            .play(SineWave::new(330.0), [0.0, 0.0, 4.0], 1.0)
+            .unwrap();
+
+        // a tenth of the volume at 2 is quieter than full volume at 4
+        let new = pool
+            .play(SineWave::new(440.0), [0.0, 0.0, 4.0], 1.0)
+            .unwrap();
+        assert!(!pool.is_playing(quiet));
+
+        pool.stop(loud);
+        assert_eq!(pool.active_voices(), 1);
+        assert!(pool
+            .play(SineWave::new(550.0), [0.0, 0.0, 50.0], 1.0)
+            .is_some());
+        assert!(pool.is_playing(new));
+    }
+}
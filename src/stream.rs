@@ -1,14 +1,29 @@
-use std::io::{Read, Seek};
+use std::fs::File;
+use std::io::{self, BufReader, Read, Seek};
 use std::marker::Sync;
-use std::sync::Arc;
+use std::path::Path;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::{mpsc, Arc, Mutex, TryLockError};
+use std::thread;
+use std::time::{Duration, Instant};
 use std::{error, fmt};
 
 use crate::common::{ChannelCount, SampleRate};
+use crate::conversions::{DitherMode, Ditherer};
 use crate::decoder;
 use crate::mixer::{mixer, Mixer, MixerSource};
 use crate::sink::Sink;
+use crate::source::UniformSourceIterator;
+use crate::Source;
 use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
-use cpal::{BufferSize, FrameCount, Sample, SampleFormat, StreamConfig, SupportedBufferSize};
+use cpal::{
+    BufferSize, FrameCount, FromSample, Sample, SampleFormat, StreamConfig, SupportedBufferSize,
+};
+
+mod audio_thread;
+
+use self::audio_thread::AudioThread;
+pub use self::audio_thread::ThreadPriority;
 
 const HZ_44100: SampleRate = 44_100;
 
@@ -17,7 +32,77 @@ const HZ_44100: SampleRate = 44_100;
 /// If this is dropped, playback will end, and the associated output stream will be disposed.
 pub struct OutputStream {
     mixer: Arc<Mixer<f32>>,
-    _stream: cpal::Stream,
+    underruns: Arc<AtomicUsize>,
+    latency: Arc<DeviceLatency>,
+    // Shared with the callback of the stream, moved to a new slot when switching devices.
+    output: OutputSlot,
+    config: OutputStreamConfig,
+    device: cpal::Device,
+    thread: AudioThread,
+    stream: cpal::Stream,
+}
+
+/// The output of the mixer, converted to the configuration of the device.
+type DeviceOutput = UniformSourceIterator<MixerSource<f32>, f32>;
+/// Empty while the output is being moved to another device.
+type OutputSlot = Arc<Mutex<Option<DeviceOutput>>>;
+
+/// Latency of the device, as last seen by the callback of its stream.
+#[derive(Debug)]
+struct DeviceLatency {
+    // Time cpal reports between a callback and playing the first sample it writes, in
+    // nanoseconds.
+    reported: AtomicU64,
+    // Frames the callback writes at once, taken from the configuration until it is first
+    // called.
+    buffer_frames: AtomicUsize,
+    // Latency of the sources that are playing as of the last callback, in nanoseconds.
+    sources: AtomicU64,
+    channels: usize,
+    sample_rate: SampleRate,
+}
+
+impl DeviceLatency {
+    fn new(config: &OutputStreamConfig) -> DeviceLatency {
+        let buffer_frames = match config.buffer_size {
+            BufferSize::Fixed(frames) => frames as usize,
+            BufferSize::Default => 0,
+        };
+        DeviceLatency {
+            reported: AtomicU64::new(0),
+            buffer_frames: AtomicUsize::new(buffer_frames),
+            sources: AtomicU64::new(0),
+            channels: config.channel_count.max(1) as usize,
+            sample_rate: config.sample_rate.max(1),
+        }
+    }
+
+    fn record(&self, reported: Duration, buffer_len: usize) {
+        self.reported
+            .store(reported.as_nanos() as u64, Ordering::Relaxed);
+        self.buffer_frames
+            .store(buffer_len / self.channels, Ordering::Relaxed);
+    }
+
+    // How long a buffer of `buffer_len` samples plays for.
+    fn buffer_period(&self, buffer_len: usize) -> Duration {
+        Duration::from_secs((buffer_len / self.channels) as u64) / self.sample_rate
+    }
+
+    fn record_sources(&self, sources: Duration) {
+        self.sources
+            .store(sources.as_nanos() as u64, Ordering::Relaxed);
+    }
+
+    /// Estimates the time from the mixer reading a sample from its sources until the device
+    /// plays it, plus the latency of the sources that are playing. The last sample of a
+    /// buffer is played a whole buffer after the first one.
+    fn estimate(&self) -> Duration {
+        let reported = Duration::from_nanos(self.reported.load(Ordering::Relaxed));
+        let frames = self.buffer_frames.load(Ordering::Relaxed);
+        let sources = Duration::from_nanos(self.sources.load(Ordering::Relaxed));
+        reported + Duration::from_secs_f64(frames as f64 / self.sample_rate as f64) + sources
+    }
 }
 
 impl OutputStream {
@@ -25,6 +110,344 @@ impl OutputStream {
     pub fn mixer(&self) -> Arc<Mixer<f32>> {
         self.mixer.clone()
     }
+
+    /// Returns true while the stream only plays silence, see [`Mixer::is_silent`].
+    #[inline]
+    pub fn is_silent(&self) -> bool {
+        self.mixer.is_silent()
+    }
+
+    /// Number of times the device most likely ran out of samples, causing an audible dropout.
+    ///
+    /// Counts the buffers that took rodio longer to fill than they last, so the device had
+    /// to wait, and the underruns the audio backend reports through its error callback.
+    /// Few backends report them, so the count is a lower bound. Silence because nothing is
+    /// playing or because the last source ended is not counted.
+    ///
+    /// This is only meant as a diagnostic. A count that keeps growing means the sources are
+    /// too slow to decode or process in real time.
+    pub fn underrun_count(&self) -> usize {
+        self.underruns.load(Ordering::Relaxed)
+    }
+
+    /// Estimates how long it takes until a sample added to the mixer now is heard, for
+    /// example to delay video so that it stays in sync with the audio.
+    ///
+    /// This adds up the latency the device reports to cpal, the length of the buffers the
+    /// device requests and the [`latency`](crate::Source::latency) of the sources that are
+    /// playing at the last callback, such as the look-ahead of a
+    /// [`limit`](crate::Source::limit). The callback publishes these through atomics, so
+    /// polling this never holds up the audio thread. The value is an estimate: not every
+    /// platform reports the latency of its device, in which case only the buffers are
+    /// counted, and until the device has requested its first buffer the configured buffer
+    /// size is used. Query it again after a while, or after
+    /// [`switch_device`](OutputStream::switch_device), to get a better estimate.
+    pub fn output_latency(&self) -> Duration {
+        self.latency.estimate()
+    }
+
+    /// Opens a stream on the default output device, falling back to other configurations and
+    /// devices if that fails. See [`OutputStreamBuilder::open_default_stream`].
+    ///
+    /// Never panics, on systems without any audio device such as servers and CI machines this
+    /// returns [`StreamError::NoDevice`].
+    #[inline]
+    pub fn try_default() -> Result<OutputStream, StreamError> {
+        OutputStreamBuilder::open_default_stream()
+    }
+
+    /// Opens a stream on `device` that runs at exactly `sample_rate`, for bit-perfect playback
+    /// of audio recorded at that rate.
+    ///
+    /// The mixer and the device then both run at `sample_rate`, so sources at that rate are
+    /// not resampled by rodio. The default configuration of the device is used if it supports
+    /// the rate, otherwise the supported configuration it prefers that does. Returns
+    /// [`StreamError::UnsupportedSampleRate`] if the device does not support the rate at all.
+    ///
+    /// Whether the operating system resamples on its way to the hardware, as shared mode
+    /// audio servers may do, cannot be seen from here. Use
+    /// [`is_resampling`](OutputStream::is_resampling) to check that rodio does not resample.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use cpal::traits::HostTrait;
+    ///
+    /// let device = cpal::default_host().default_output_device().unwrap();
+    /// let stream = rodio::OutputStream::try_exact_rate(device, 96_000)?;
+    /// # Ok::<(), rodio::StreamError>(())
+    /// ```
+    pub fn try_exact_rate(
+        device: cpal::Device,
+        sample_rate: SampleRate,
+    ) -> Result<OutputStream, StreamError> {
+        let default_config = device
+            .default_output_config()
+            .map_err(StreamError::DefaultStreamConfigError)?;
+        let rate = cpal::SampleRate(sample_rate);
+        let config = if default_config.sample_rate() == rate {
+            default_config
+        } else {
+            let mut supported: Vec<_> = device
+                .supported_output_configs()
+                .map_err(StreamError::SupportedStreamConfigsError)?
+                .filter(|config| {
+                    (config.min_sample_rate()..=config.max_sample_rate()).contains(&rate)
+                })
+                .collect();
+            // Alike to the default one first, then by the preference of cpal.
+            supported.sort_by(|a, b| {
+                let alike = |config: &cpal::SupportedStreamConfigRange| {
+                    (
+                        config.channels() == default_config.channels(),
+                        config.sample_format() == default_config.sample_format(),
+                    )
+                };
+                alike(b)
+                    .cmp(&alike(a))
+                    .then_with(|| b.cmp_default_heuristics(a))
+            });
+            supported
+                .into_iter()
+                .next()
+                .ok_or(StreamError::UnsupportedSampleRate(sample_rate))?
+                .with_sample_rate(rate)
+        };
+        OutputStreamBuilder::default()
+            .with_device(device)
+            .with_supported_config(&config)
+            .open_stream()
+    }
+
+    /// Returns true while rodio resamples the audio that is playing, either because a source
+    /// plays at another rate than the mixer, see [`Mixer::is_resampling`], or because the
+    /// stream [moved](OutputStream::switch_device) to a device with another rate.
+    pub fn is_resampling(&self) -> bool {
+        self.mixer.sample_rate() != self.config.sample_rate || self.mixer.is_resampling()
+    }
+
+    /// Moves playback to another device with its default configuration, for example when
+    /// headphones are plugged in. The mixer stays the same, so sinks keep playing.
+    ///
+    /// If the device uses a different sample rate or channel count than the mixer the output
+    /// of the mixer is converted. Expect a short gap or glitch in the audio while switching.
+    /// On error the stream keeps playing on the current device.
+    pub fn switch_device(&mut self, device: cpal::Device) -> Result<(), StreamError> {
+        let config = OutputStreamBuilder::from_device(device.clone())?
+            .with_dither(self.config.dither)
+            .with_clipping(self.config.clip)
+            .config;
+        let output = Arc::new(Mutex::new(None));
+        let latency = Arc::new(DeviceLatency::new(&config));
+        let stream = Self::init_stream(
+            &device,
+            &config,
+            &self.thread,
+            output.clone(),
+            self.underruns.clone(),
+            latency.clone(),
+        )
+        .map_err(build_stream_error)?;
+        stream.play().map_err(StreamError::PlayStreamError)?;
+
+        move_output(&self.output, &output, &config);
+        self.stream = stream;
+        self.latency = latency;
+        self.output = output;
+        self.config = config;
+        self.device = device;
+        Ok(())
+    }
+
+    /// Returns the underlying cpal stream, to call platform specific cpal methods that rodio
+    /// does not wrap.
+    ///
+    /// This is an advanced escape hatch and depends on the version of cpal that rodio uses, so
+    /// it may change with any cpal upgrade. Playing or pausing the stream through it leaves
+    /// rodio unaware, the sinks keep their state.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use rodio::cpal::traits::StreamTrait;
+    ///
+    /// let stream = rodio::OutputStream::try_default()?;
+    /// // stops the device callback without rodio noticing, see above
+    /// stream.cpal_stream().pause()?;
+    /// stream.cpal_stream().play()?;
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    #[inline]
+    pub fn cpal_stream(&self) -> &cpal::Stream {
+        &self.stream
+    }
+
+    /// Returns the device the stream plays on. Changes after
+    /// [`switch_device`](OutputStream::switch_device).
+    ///
+    /// Like [`cpal_stream`](OutputStream::cpal_stream) this exposes cpal directly.
+    #[inline]
+    pub fn device(&self) -> &cpal::Device {
+        &self.device
+    }
+
+    /// Returns the configuration the stream was opened with, together with
+    /// [`sample_format`](OutputStream::sample_format) this describes what the device is fed.
+    #[inline]
+    pub fn stream_config(&self) -> StreamConfig {
+        (&self.config).into()
+    }
+
+    /// Returns the sample format the device is fed with.
+    #[inline]
+    pub fn sample_format(&self) -> SampleFormat {
+        self.config.sample_format
+    }
+}
+
+/// Moves the mixer's output from one stream to another, converting it to `config`.
+fn move_output(from: &OutputSlot, to: &OutputSlot, config: &OutputStreamConfig) {
+    if let Some(output) = from.lock().unwrap().take() {
+        let converted = UniformSourceIterator::new(
+            output.into_inner(),
+            config.channel_count,
+            config.sample_rate,
+        );
+        *to.lock().unwrap() = Some(converted);
+    }
+}
+
+fn build_stream_error(err: cpal::BuildStreamError) -> StreamError {
+    match err {
+        cpal::BuildStreamError::StreamConfigNotSupported => StreamError::UnsupportedConfig,
+        err => StreamError::BuildStreamError(err),
+    }
+}
+
+/// Plays audio for as long as any clone of it, or any [`Sink`] created from it, is alive.
+///
+/// An [`OutputStream`] stops playing as soon as it is dropped, which is easy to do by accident
+/// when only its mixer is kept around. A `Player` owns the stream instead, on a dedicated
+/// thread because streams can not be sent between threads on every platform. The `Player`
+/// itself can be cloned and sent freely. Once the last clone and the last sink created with
+/// [`sink`](Player::sink) are dropped the stream is closed.
+///
+/// # Example
+///
+/// ```no_run
+/// use rodio::source::{SineWave, Source};
+/// use std::time::Duration;
+///
+/// let sink = rodio::Player::open_default()?.sink();
+/// sink.append(SineWave::new(440.0).take_duration(Duration::from_secs(1)));
+/// sink.sleep_until_end();
+/// # Ok::<(), rodio::StreamError>(())
+/// ```
+#[derive(Clone)]
+pub struct Player {
+    mixer: Arc<Mixer<f32>>,
+    underruns: Arc<AtomicUsize>,
+    // The output thread closes the stream once the last clone of this is dropped.
+    keep_alive: Arc<mpsc::Sender<()>>,
+}
+
+impl Player {
+    /// Opens the default output stream, see [`OutputStreamBuilder::open_default_stream`].
+    pub fn open_default() -> Result<Player, StreamError> {
+        Player::open_with(OutputStreamBuilder::open_default_stream)
+    }
+
+    /// Opens a stream on `device` with its default configuration, falling back to other
+    /// configurations it supports, see [`OutputStreamBuilder::open_stream_or_fallback`].
+    ///
+    /// Every player has its own stream, mixer and clock, so the sinks of players on different
+    /// devices play independently.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use rodio::cpal::traits::HostTrait;
+    /// use rodio::source::{SineWave, Source};
+    /// use rodio::{cpal, Player};
+    /// use std::time::Duration;
+    ///
+    /// // play a different tone on every output device
+    /// let mut sinks = Vec::new();
+    /// for (i, device) in cpal::default_host().output_devices()?.enumerate() {
+    ///     let sink = Player::open_device(device)?.sink();
+    ///     let tone = SineWave::new(220.0 * (i + 1) as f32);
+    ///     sink.append(tone.take_duration(Duration::from_secs(1)));
+    ///     sinks.push(sink);
+    /// }
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn open_device(device: cpal::Device) -> Result<Player, StreamError> {
+        Player::open_with(move || {
+            OutputStreamBuilder::from_device(device)?.open_stream_or_fallback()
+        })
+    }
+
+    /// Opens a stream by calling `open` on the output thread, use this to configure the
+    /// stream with an [`OutputStreamBuilder`].
+    ///
+    /// # Panics
+    ///
+    /// If the output thread can not be spawned or `open` panics.
+    pub fn open_with<F>(open: F) -> Result<Player, StreamError>
+    where
+        F: FnOnce() -> Result<OutputStream, StreamError> + Send + 'static,
+    {
+        let (opened_tx, opened_rx) = mpsc::channel();
+        let (keep_alive, closed) = mpsc::channel::<()>();
+        thread::Builder::new()
+            .name("rodio output".to_owned())
+            .spawn(move || match open() {
+                Ok(stream) => {
+                    let _ = opened_tx.send(Ok((stream.mixer(), stream.underruns.clone())));
+                    // Returns an error once every sender is dropped.
+                    let _ = closed.recv();
+                }
+                Err(err) => {
+                    let _ = opened_tx.send(Err(err));
+                }
+            })
+            .expect("failed to spawn output thread");
+
+        let (mixer, underruns) = opened_rx.recv().expect("output thread panicked")?;
+        Ok(Player {
+            mixer,
+            underruns,
+            keep_alive: Arc::new(keep_alive),
+        })
+    }
+
+    /// Access the output stream's mixer.
+    ///
+    /// The mixer does not keep the stream alive, keep the `Player` around too.
+    pub fn mixer(&self) -> Arc<Mixer<f32>> {
+        self.mixer.clone()
+    }
+
+    /// Creates a new [`Sink`] playing on this stream that keeps the stream alive.
+    ///
+    /// A [detached](Sink::detach) sink no longer keeps the stream alive.
+    pub fn sink(&self) -> Sink {
+        let mut sink = Sink::connect_new(&self.mixer);
+        sink.keep_alive(self.keep_alive.clone());
+        sink
+    }
+
+    /// Number of times the audio thread could not keep up with the device, see
+    /// [`OutputStream::underrun_count`].
+    pub fn underrun_count(&self) -> usize {
+        self.underruns.load(Ordering::Relaxed)
+    }
+
+    /// Returns true while the stream only plays silence, see [`Mixer::is_silent`].
+    #[inline]
+    pub fn is_silent(&self) -> bool {
+        self.mixer.is_silent()
+    }
 }
 
 #[derive(Copy, Clone, Debug)]
@@ -34,6 +457,8 @@ pub struct OutputStreamConfig {
     sample_rate: SampleRate,
     buffer_size: BufferSize,
     sample_format: SampleFormat,
+    dither: DitherMode,
+    clip: bool,
 }
 
 /// Convenience builder for audio output stream.
@@ -44,6 +469,9 @@ pub struct OutputStreamBuilder {
     device: Option<cpal::Device>,
     /// luptidoo, docs required...
     pub config: OutputStreamConfig,
+    thread: AudioThread,
+    // The sample format was chosen with `with_sample_format` and must not be replaced.
+    sample_format_forced: bool,
 }
 
 impl Default for OutputStreamConfig {
@@ -53,6 +481,8 @@ impl Default for OutputStreamConfig {
             sample_rate: HZ_44100,
             buffer_size: BufferSize::Default,
             sample_format: SampleFormat::I8,
+            dither: DitherMode::None,
+            clip: true,
         }
     }
 }
@@ -105,9 +535,61 @@ impl OutputStreamBuilder {
         self
     }
 
-    /// Select scalar type that will carry a sample.
+    /// Forces the scalar type that carries a sample, for example `F32` for quality or `I16`
+    /// for compatibility. The mixed output is converted to it right before it is handed to the
+    /// device.
+    ///
+    /// Opening the stream fails with [`StreamError::UnsupportedSampleFormat`] if the device
+    /// does not support the format at the configured channel count and sample rate, and
+    /// [`open_stream_or_fallback`](OutputStreamBuilder::open_stream_or_fallback) only falls
+    /// back to configurations with this format.
     pub fn with_sample_format(mut self, sample_format: SampleFormat) -> OutputStreamBuilder {
         self.config.sample_format = sample_format;
+        self.sample_format_forced = true;
+        self
+    }
+
+    /// Sets the dithering applied when converting to an integer sample format, see
+    /// [`DitherMode`]. Off by default so that the output is bit-exact.
+    pub fn with_dither(mut self, dither: DitherMode) -> OutputStreamBuilder {
+        self.config.dither = dither;
+        self
+    }
+
+    /// Sets whether samples outside of `-1.0..=1.0` are clamped to the limits of an integer
+    /// sample format. On by default.
+    ///
+    /// Turning it off saves a comparison per sample and is only safe if the mixed output never
+    /// exceeds full scale, the conversion of out of range samples is not specified otherwise.
+    /// Float formats are never clamped.
+    ///
+    /// This only covers the samples the stream hands to the device. Converting a source with
+    /// [`Source::convert_samples`](crate::Source::convert_samples) or the slice conversions does
+    /// not clamp, keep such sources in range yourself.
+    pub fn with_clipping(mut self, clip: bool) -> OutputStreamBuilder {
+        self.config.clip = clip;
+        self
+    }
+
+    /// Names the thread that runs the audio callback, so that it can be told apart in profilers
+    /// and debuggers. Names longer than 15 bytes are cut.
+    ///
+    /// cpal creates the thread, so the name is set from inside it the first time it asks for
+    /// audio. Only supported on Linux, elsewhere a warning is printed and the name is ignored.
+    pub fn with_thread_name(mut self, name: &str) -> OutputStreamBuilder {
+        self.thread.name = Some(name.into());
+        self
+    }
+
+    /// Sets the scheduling priority of the thread that runs the audio callback. A higher
+    /// priority reduces dropouts when the system is under load, see [`ThreadPriority`].
+    ///
+    /// Like [`with_thread_name`](OutputStreamBuilder::with_thread_name) this is applied from
+    /// inside the thread and only supported on Linux. Raising the priority usually requires
+    /// extra permissions, if it fails a warning is printed and playback continues at the
+    /// priority cpal chose.
+    pub fn with_thread_priority(mut self, priority: ThreadPriority) -> OutputStreamBuilder {
+        self.thread.priority = priority;
         self
     }
 
@@ -123,6 +605,7 @@ impl OutputStreamBuilder {
             // In case of supported range limit buffer size to avoid unexpectedly long playback delays.
             buffer_size: clamp_supported_buffer_size(config.buffer_size(), 1024),
             sample_format: config.sample_format(),
+            ..self.config
         };
         self
     }
@@ -139,9 +622,34 @@ impl OutputStreamBuilder {
     }
 
     /// Open output stream using parameters configured so far.
+    ///
+    /// Returns [`StreamError::NoDevice`] if no device was set.
     pub fn open_stream(&self) -> Result<OutputStream, StreamError> {
-        let device = self.device.as_ref().expect("output device specified");
-        OutputStream::open(device, &self.config)
+        let device = self.device.as_ref().ok_or(StreamError::NoDevice)?;
+        if self.sample_format_forced {
+            self.check_sample_format(device)?;
+        }
+        OutputStream::open(device, &self.config, &self.thread)
+    }
+
+    // Errors unless the device supports the sample format at the configured channel count
+    // and sample rate.
+    fn check_sample_format(&self, device: &cpal::Device) -> Result<(), StreamError> {
+        let config = &self.config;
+        let rate = cpal::SampleRate(config.sample_rate);
+        let supported = device
+            .supported_output_configs()
+            .map_err(StreamError::SupportedStreamConfigsError)?
+            .any(|supported| {
+                supported.sample_format() == config.sample_format
+                    && supported.channels() == config.channel_count as cpal::ChannelCount
+                    && (supported.min_sample_rate()..=supported.max_sample_rate()).contains(&rate)
+            });
+        if supported {
+            Ok(())
+        } else {
+            Err(StreamError::UnsupportedSampleFormat(config.sample_format))
+        }
     }
 
     /// Try opening a new output stream with the builder's current stream configuration.
@@ -149,12 +657,17 @@ impl OutputStreamBuilder {
     /// supported by the device.
     /// If all attempts fail returns initial error.
     pub fn open_stream_or_fallback(&self) -> Result<OutputStream, StreamError> {
-        let device = self.device.as_ref().expect("output device specified");
-        OutputStream::open(device, &self.config).or_else(|err| {
+        let device = self.device.as_ref().ok_or(StreamError::NoDevice)?;
+        self.open_stream().or_else(|err| {
             for supported_config in supported_output_configs(device)? {
-                if let Ok(handle) = Self::default()
+                if self.sample_format_forced
+                    && supported_config.sample_format() != self.config.sample_format
+                {
+                    continue;
+                }
+                if let Ok(handle) = self
+                    .fallback(&supported_config)
                     .with_device(device.clone())
-                    .with_supported_config(&supported_config)
                     .open_stream()
                 {
                     return Ok(handle);
@@ -164,6 +677,18 @@ impl OutputStreamBuilder {
         })
     }
 
+    // A builder for `config` that keeps the settings of this one which are not part of the
+    // device configuration.
+    fn fallback(&self, config: &cpal::SupportedStreamConfig) -> OutputStreamBuilder {
+        Self {
+            thread: self.thread.clone(),
+            ..Self::default()
+        }
+        .with_supported_config(config)
+        .with_dither(self.config.dither)
+        .with_clipping(self.config.clip)
+    }
+
     /// Try to open a new output stream for the default output device with its default configuration.
     /// Failing that attempt to open output stream with alternative configuration and/or non default
     /// output devices. Returns stream for first of the tried configurations that succeeds.
@@ -217,6 +742,60 @@ where
     Ok(sink)
 }
 
+/// Plays the file at `path` once on the default output device, without setting up a stream
+/// and a sink first.
+///
+/// Opens a [`Player`] for the default device, decodes the file and plays it on a new sink.
+/// The returned [`PlayHandle`] keeps all of that alive, dropping it stops the sound. Use
+/// [`Player`] instead to play several sounds on the same stream.
+///
+/// # Example
+///
+/// ```no_run
+/// let sound = rodio::play_once("music.ogg")?;
+/// sound.wait();
+/// # Ok::<(), rodio::PlayError>(())
+/// ```
+pub fn play_once<P>(path: P) -> Result<PlayHandle, PlayError>
+where
+    P: AsRef<Path>,
+{
+    let file = BufReader::new(File::open(path).map_err(PlayError::IoError)?);
+    let input = decoder::Decoder::new(file)?;
+    let player = Player::open_default().map_err(PlayError::StreamError)?;
+    let sink = player.sink();
+    sink.append(input);
+    Ok(PlayHandle { sink })
+}
+
+/// A sound started with [`play_once`], which plays for as long as this is kept around.
+pub struct PlayHandle {
+    // Keeps the output stream alive.
+    sink: Sink,
+}
+
+impl PlayHandle {
+    /// Blocks the current thread until the sound has played to the end or was stopped.
+    pub fn wait(&self) {
+        self.sink.sleep_until_end();
+    }
+
+    /// Stops the sound, it can not be resumed.
+    pub fn stop(&self) {
+        self.sink.stop();
+    }
+
+    /// Returns true once the sound has played to the end or was stopped.
+    pub fn is_finished(&self) -> bool {
+        self.sink.empty()
+    }
+
+    /// Returns the sink the sound plays on, for example to change its volume or pause it.
+    pub fn sink(&self) -> &Sink {
+        &self.sink
+    }
+}
+
 impl From<&OutputStreamConfig> for StreamConfig {
     fn from(config: &OutputStreamConfig) -> Self {
         cpal::StreamConfig {
@@ -234,6 +813,10 @@ pub enum PlayError {
     DecoderError(decoder::DecoderError),
     /// The output device was lost.
     NoDevice,
+    /// The file passed to [`play_once`] could not be opened.
+    IoError(io::Error),
+    /// The output stream used by [`play_once`] could not be opened.
+    StreamError(StreamError),
 }
 
 impl From<decoder::DecoderError> for PlayError {
@@ -247,6 +830,8 @@ impl fmt::Display for PlayError {
         match self {
             Self::DecoderError(e) => e.fmt(f),
             Self::NoDevice => write!(f, "NoDevice"),
+            Self::IoError(e) => e.fmt(f),
+            Self::StreamError(e) => e.fmt(f),
         }
     }
 }
@@ -256,6 +841,8 @@ impl error::Error for PlayError {
         match self {
             Self::DecoderError(e) => Some(e),
             Self::NoDevice => None,
+            Self::IoError(e) => Some(e),
+            Self::StreamError(e) => Some(e),
         }
     }
 }
@@ -276,6 +863,16 @@ pub enum StreamError {
     SupportedStreamConfigsError(cpal::SupportedStreamConfigsError),
     /// Could not find any output device
     NoDevice,
+    /// The device does not support the requested channel count, sample rate or sample
+    /// format.
+    UnsupportedConfig,
+    /// The device does not support the sample format forced with
+    /// [`OutputStreamBuilder::with_sample_format`] at the configured channel count and sample
+    /// rate.
+    UnsupportedSampleFormat(SampleFormat),
+    /// The device does not support the sample rate requested with
+    /// [`OutputStream::try_exact_rate`].
+    UnsupportedSampleRate(SampleRate),
 }
 
 impl fmt::Display for StreamError {
@@ -286,6 +883,15 @@ impl fmt::Display for StreamError {
             Self::DefaultStreamConfigError(e) => e.fmt(f),
             Self::SupportedStreamConfigsError(e) => e.fmt(f),
             Self::NoDevice => write!(f, "NoDevice"),
+            Self::UnsupportedConfig => {
+                write!(f, "stream configuration not supported by the device")
+            }
+            Self::UnsupportedSampleFormat(format) => {
+                write!(f, "sample format {format} not supported by the device")
+            }
+            Self::UnsupportedSampleRate(rate) => {
+                write!(f, "sample rate of {rate} Hz not supported by the device")
+            }
         }
     }
 }
@@ -297,7 +903,10 @@ impl error::Error for StreamError {
             Self::BuildStreamError(e) => Some(e),
             Self::DefaultStreamConfigError(e) => Some(e),
             Self::SupportedStreamConfigsError(e) => Some(e),
-            Self::NoDevice => None,
+            Self::NoDevice
+            | Self::UnsupportedConfig
+            | Self::UnsupportedSampleFormat(_)
+            | Self::UnsupportedSampleRate(_) => None,
         }
     }
 }
@@ -306,144 +915,198 @@ impl OutputStream {
     fn open(
         device: &cpal::Device,
         config: &OutputStreamConfig,
+        thread: &AudioThread,
     ) -> Result<OutputStream, StreamError> {
         let (controller, source) = mixer(config.channel_count, config.sample_rate);
-        Self::init_stream(device, config, source)
-            .map_err(StreamError::BuildStreamError)
-            .and_then(|stream| {
-                stream.play().map_err(StreamError::PlayStreamError)?;
-                Ok(Self {
-                    _stream: stream,
-                    mixer: controller,
-                })
+        let output = UniformSourceIterator::new(source, config.channel_count, config.sample_rate);
+        let output = Arc::new(Mutex::new(Some(output)));
+        let underruns = Arc::new(AtomicUsize::new(0));
+        let latency = Arc::new(DeviceLatency::new(config));
+        Self::init_stream(
+            device,
+            config,
+            thread,
+            output.clone(),
+            underruns.clone(),
+            latency.clone(),
+        )
+        .map_err(build_stream_error)
+        .and_then(|stream| {
+            stream.play().map_err(StreamError::PlayStreamError)?;
+            Ok(Self {
+                stream,
+                mixer: controller,
+                underruns,
+                latency,
+                output,
+                config: *config,
+                device: device.clone(),
+                thread: thread.clone(),
             })
+        })
     }
 
     fn init_stream(
         device: &cpal::Device,
         config: &OutputStreamConfig,
-        mut samples: MixerSource<f32>,
+        thread: &AudioThread,
+        output: OutputSlot,
+        underruns: Arc<AtomicUsize>,
+        latency: Arc<DeviceLatency>,
     ) -> Result<cpal::Stream, cpal::BuildStreamError> {
-        let error_callback = |err| {
-            #[cfg(feature = "tracing")]
-            tracing::error!("error initializing output stream: {err}");
-            #[cfg(not(feature = "tracing"))]
-            eprintln!("error initializing output stream: {err}");
+        let error_callback = {
+            let underruns = underruns.clone();
+            move |err| {
+                if reports_underrun(&err) {
+                    underruns.fetch_add(1, Ordering::Relaxed);
+                }
+                log_stream_error(err);
+            }
         };
         let sample_format = config.sample_format;
+        let dither = if sample_format.is_float() {
+            DitherMode::None
+        } else {
+            config.dither
+        };
+        let ditherer = Ditherer::new(
+            dither,
+            sample_format.sample_size() as u32 * 8,
+            config.channel_count as usize,
+        );
+        let clip = config.clip && !sample_format.is_float();
         let config = config.into();
+        macro_rules! build {
+            ($sample:ty, $silence:expr) => {
+                device.build_output_stream::<$sample, _, _>(
+                    &config,
+                    data_callback(
+                        output,
+                        thread.clone(),
+                        ditherer,
+                        clip,
+                        $silence,
+                        underruns,
+                        latency,
+                    ),
+                    error_callback,
+                    None,
+                )
+            };
+        }
         match sample_format {
-            cpal::SampleFormat::F32 => device.build_output_stream::<f32, _, _>(
-                &config,
-                move |data, _| {
-                    data.iter_mut()
-                        .for_each(|d| *d = samples.next().unwrap_or(0f32))
-                },
-                error_callback,
-                None,
-            ),
-            cpal::SampleFormat::F64 => device.build_output_stream::<f64, _, _>(
-                &config,
-                move |data, _| {
-                    data.iter_mut()
-                        .for_each(|d| *d = samples.next().map(Sample::from_sample).unwrap_or(0f64))
-                },
-                error_callback,
-                None,
-            ),
-            cpal::SampleFormat::I8 => device.build_output_stream::<i8, _, _>(
-                &config,
-                move |data, _| {
-                    data.iter_mut()
-                        .for_each(|d| *d = samples.next().map(Sample::from_sample).unwrap_or(0i8))
-                },
-                error_callback,
-                None,
-            ),
-            cpal::SampleFormat::I16 => device.build_output_stream::<i16, _, _>(
-                &config,
-                move |data, _| {
-                    data.iter_mut()
-                        .for_each(|d| *d = samples.next().map(Sample::from_sample).unwrap_or(0i16))
-                },
-                error_callback,
-                None,
-            ),
-            cpal::SampleFormat::I32 => device.build_output_stream::<i32, _, _>(
-                &config,
-                move |data, _| {
-                    data.iter_mut()
-                        .for_each(|d| *d = samples.next().map(Sample::from_sample).unwrap_or(0i32))
-                },
-                error_callback,
-                None,
-            ),
-            cpal::SampleFormat::I64 => device.build_output_stream::<i64, _, _>(
-                &config,
-                move |data, _| {
-                    data.iter_mut()
-                        .for_each(|d| *d = samples.next().map(Sample::from_sample).unwrap_or(0i64))
-                },
-                error_callback,
-                None,
-            ),
-            cpal::SampleFormat::U8 => device.build_output_stream::<u8, _, _>(
-                &config,
-                move |data, _| {
-                    data.iter_mut().for_each(|d| {
-                        *d = samples
-                            .next()
-                            .map(Sample::from_sample)
-                            .unwrap_or(u8::MAX / 2)
-                    })
-                },
-                error_callback,
-                None,
-            ),
-            cpal::SampleFormat::U16 => device.build_output_stream::<u16, _, _>(
-                &config,
-                move |data, _| {
-                    data.iter_mut().for_each(|d| {
-                        *d = samples
-                            .next()
-                            .map(Sample::from_sample)
-                            .unwrap_or(u16::MAX / 2)
-                    })
-                },
-                error_callback,
-                None,
-            ),
-            cpal::SampleFormat::U32 => device.build_output_stream::<u32, _, _>(
-                &config,
-                move |data, _| {
-                    data.iter_mut().for_each(|d| {
-                        *d = samples
-                            .next()
-                            .map(Sample::from_sample)
-                            .unwrap_or(u32::MAX / 2)
-                    })
-                },
-                error_callback,
-                None,
-            ),
-            cpal::SampleFormat::U64 => device.build_output_stream::<u64, _, _>(
-                &config,
-                move |data, _| {
-                    data.iter_mut().for_each(|d| {
-                        *d = samples
-                            .next()
-                            .map(Sample::from_sample)
-                            .unwrap_or(u64::MAX / 2)
-                    })
-                },
-                error_callback,
-                None,
-            ),
+            cpal::SampleFormat::F32 => build!(f32, 0f32),
+            cpal::SampleFormat::F64 => build!(f64, 0f64),
+            cpal::SampleFormat::I8 => build!(i8, 0i8),
+            cpal::SampleFormat::I16 => build!(i16, 0i16),
+            cpal::SampleFormat::I32 => build!(i32, 0i32),
+            cpal::SampleFormat::I64 => build!(i64, 0i64),
+            cpal::SampleFormat::U8 => build!(u8, u8::MAX / 2),
+            cpal::SampleFormat::U16 => build!(u16, u16::MAX / 2),
+            cpal::SampleFormat::U32 => build!(u32, u32::MAX / 2),
+            cpal::SampleFormat::U64 => build!(u64, u64::MAX / 2),
             _ => Err(cpal::BuildStreamError::StreamConfigNotSupported),
         }
     }
 }
 
+/// Builds the callback of a stream that plays whatever is in `output`, or silence while
+/// it is empty or locked by another thread. The first call applies the `thread` settings to the audio thread.
+fn data_callback<T>(
+    output: OutputSlot,
+    thread: AudioThread,
+    mut ditherer: Ditherer,
+    clip: bool,
+    silence: T,
+    underruns: Arc<AtomicUsize>,
+    latency: Arc<DeviceLatency>,
+) -> impl FnMut(&mut [T], &cpal::OutputCallbackInfo) + Send + 'static
+where
+    T: Sample + FromSample<f32> + Send + 'static,
+{
+    let mut thread = Some(thread);
+    move |data, info| {
+        if let Some(thread) = thread.take() {
+            thread.apply();
+        }
+        let started = Instant::now();
+        let timestamp = info.timestamp();
+        let reported = timestamp.playback.duration_since(&timestamp.callback);
+        latency.record(reported.unwrap_or_default(), data.len());
+        // Never waits on the thread that swaps the output, and keeps playing if a panic
+        // poisoned the lock.
+        let mut output = match output.try_lock() {
+            Ok(output) => output,
+            Err(TryLockError::Poisoned(poisoned)) => poisoned.into_inner(),
+            Err(TryLockError::WouldBlock) => return data.fill(silence),
+        };
+        match output.as_mut() {
+            Some(samples) => {
+                fill_buffer(data, samples, &mut ditherer, clip, silence);
+                latency.record_sources(samples.latency());
+                let period = latency.buffer_period(data.len());
+                count_slow_fill(started.elapsed(), period, &underruns);
+            }
+            None => data.fill(silence),
+        }
+    }
+}
+
+/// Fills a buffer requested by the device with samples from the mixer, padding with `silence`
+/// once the mixer runs out. Samples out of range are clamped first if `clip` is set.
+fn fill_buffer<T, S>(
+    data: &mut [T],
+    samples: &mut S,
+    ditherer: &mut Ditherer,
+    clip: bool,
+    silence: T,
+) where
+    T: Sample + FromSample<f32>,
+    S: crate::Source<Item = f32>,
+{
+    let mut filled = 0;
+    for d in data.iter_mut() {
+        let Some(sample) = samples.next() else {
+            break;
+        };
+        let sample = if clip {
+            sample.clamp(-1.0, 1.0)
+        } else {
+            sample
+        };
+        *d = Sample::from_sample(ditherer.process(sample));
+        filled += 1;
+    }
+    data[filled..].fill(silence);
+}
+
+/// Counts an underrun if filling a buffer took longer than the `period` it plays for, then
+/// the device had nothing left to play in the meantime.
+fn count_slow_fill(elapsed: Duration, period: Duration, underruns: &AtomicUsize) {
+    if elapsed > period {
+        underruns.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+/// Whether an error passed to the error callback of a stream reports an underrun. cpal has
+/// no dedicated error for it, the backends that report one describe it in the message.
+fn reports_underrun(err: &cpal::StreamError) -> bool {
+    match err {
+        cpal::StreamError::BackendSpecific { err } => {
+            let description = err.description.to_lowercase();
+            description.contains("underrun") || description.contains("xrun")
+        }
+        _ => false,
+    }
+}
+
+fn log_stream_error(err: cpal::StreamError) {
+    #[cfg(feature = "tracing")]
+    tracing::error!("error initializing output stream: {err}");
+    #[cfg(not(feature = "tracing"))]
+    eprintln!("error initializing output stream: {err}");
+}
+
 /// Return all formats supported by the device.
 fn supported_output_configs(
     device: &cpal::Device,
@@ -466,3 +1129,334 @@ fn supported_output_configs(
         formats
     }))
 }
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::time::{Duration, Instant};
+
+    use cpal::traits::{DeviceTrait, HostTrait};
+    use cpal::SampleFormat;
+
+    use std::sync::{Arc, Mutex};
+
+    use super::{
+        count_slow_fill, fill_buffer, move_output, reports_underrun, DeviceLatency, OutputSlot,
+        OutputStream, OutputStreamBuilder, OutputStreamConfig, Player, StreamError,
+    };
+    use crate::conversions::{DitherMode, Ditherer};
+    use crate::mixer::mixer;
+    use crate::source::{SineWave, Source, UniformSourceIterator};
+    use crate::Sink;
+
+    #[test]
+    fn sources_ending_mid_buffer_are_not_underruns() {
+        let underruns = AtomicUsize::new(0);
+        let mut ditherer = Ditherer::new(DitherMode::None, 16, 1);
+        let (controller, mut samples) = mixer::<f32>(1, 48_000);
+        let mut buffer = [0i16; 480];
+        let period = Duration::from_millis(10);
+
+        // the source runs out half way through the second buffer
+        controller.add(SineWave::new(440.0).take_duration(Duration::from_millis(15)));
+        for _ in 0..3 {
+            let started = Instant::now();
+            fill_buffer(&mut buffer, &mut samples, &mut ditherer, true, 0);
+            count_slow_fill(started.elapsed(), period, &underruns);
+        }
+        assert_eq!(underruns.load(Ordering::Relaxed), 0);
+    }
+
+    #[test]
+    fn slow_fills_and_reported_underruns_are_counted() {
+        let underruns = AtomicUsize::new(0);
+        count_slow_fill(
+            Duration::from_millis(9),
+            Duration::from_millis(10),
+            &underruns,
+        );
+        assert_eq!(underruns.load(Ordering::Relaxed), 0);
+        count_slow_fill(
+            Duration::from_millis(11),
+            Duration::from_millis(10),
+            &underruns,
+        );
+        assert_eq!(underruns.load(Ordering::Relaxed), 1);
+
+        let backend = |description: &str| cpal::StreamError::BackendSpecific {
+            err: cpal::BackendSpecificError {
+                description: description.into(),
+            },
+        };
+        assert!(reports_underrun(&backend("ALSA XRUN detected")));
+        assert!(!reports_underrun(&backend("device busy")));
+        assert!(!reports_underrun(&cpal::StreamError::DeviceNotAvailable));
+    }
+
+    #[test]
+    fn silence_after_mixer_ends() {
+        let mut ditherer = Ditherer::new(DitherMode::None, 16, 1);
+        let (controller, mut samples) = mixer::<f32>(1, 48_000);
+        let mut buffer = [1u16; 4];
+
+        controller.add(SineWave::new(440.0).take_duration(Duration::ZERO));
+        fill_buffer(&mut buffer, &mut samples, &mut ditherer, true, u16::MAX / 2);
+        assert_eq!(buffer, [u16::MAX / 2; 4]);
+    }
+
+    #[test]
+    fn loud_samples_clip_instead_of_wrapping() {
+        let mut ditherer = Ditherer::new(DitherMode::None, 16, 1);
+        let (controller, mut samples) = mixer::<f32>(1, 48_000);
+        let mut buffer = [0i16; 4];
+
+        controller.add(crate::buffer::SamplesBuffer::new(
+            1,
+            48_000,
+            vec![1.5f32, -3.0, 1.0, f32::INFINITY],
+        ));
+        fill_buffer(&mut buffer, &mut samples, &mut ditherer, true, 0);
+        assert_eq!(buffer, [i16::MAX, i16::MIN, i16::MAX, i16::MAX]);
+    }
+
+    #[test]
+    fn samples_in_range_are_the_same_without_clipping() {
+        let mut ditherer = Ditherer::new(DitherMode::None, 16, 1);
+        let data = vec![0.5f32, -0.5, 0.25, -1.0];
+
+        let mut fill = |clip: bool| {
+            let (controller, mut samples) = mixer::<f32>(1, 48_000);
+            controller.add(crate::buffer::SamplesBuffer::new(1, 48_000, data.clone()));
+            let mut buffer = [0i16; 4];
+            fill_buffer(&mut buffer, &mut samples, &mut ditherer, clip, 0);
+            buffer
+        };
+        assert_eq!(fill(false), [16384, -16384, 8192, i16::MIN]);
+        assert_eq!(fill(true), fill(false));
+    }
+
+    #[test]
+    fn mono_plays_on_both_channels() {
+        let mut ditherer = Ditherer::new(DitherMode::None, 32, 2);
+        let (controller, mut samples) = mixer::<f32>(2, 48_000);
+        let mut buffer = [0f32; 4800];
+
+        let sink = Sink::connect_new(&controller);
+        sink.append(SineWave::new(440.0).take_duration(Duration::from_secs(1)));
+        fill_buffer(&mut buffer, &mut samples, &mut ditherer, true, 0.0);
+
+        assert!(buffer.iter().any(|&sample| sample.abs() > 0.5));
+        for frame in buffer.chunks(2) {
+            assert_eq!(frame[0], frame[1]);
+        }
+    }
+
+    #[test]
+    fn sinks_survive_device_switch() {
+        let mut ditherer = Ditherer::new(DitherMode::None, 32, 2);
+        let (controller, source) = mixer::<f32>(2, 44_100);
+        let first: OutputSlot = Arc::new(Mutex::new(Some(UniformSourceIterator::new(
+            source, 2, 44_100,
+        ))));
+        let sink = Sink::connect_new(&controller);
+        sink.append(SineWave::new(440.0).take_duration(Duration::from_secs(1)));
+
+        let mut buffer = [0f32; 4410];
+        let mut output = first.lock().unwrap();
+        fill_buffer(
+            &mut buffer,
+            output.as_mut().unwrap(),
+            &mut ditherer,
+            true,
+            0.0,
+        );
+        drop(output);
+        assert!(buffer.iter().any(|&sample| sample.abs() > 0.5));
+
+        // a mono device running at 48 kHz
+        let second = Arc::new(Mutex::new(None));
+        let config = OutputStreamConfig {
+            channel_count: 1,
+            sample_rate: 48_000,
+            ..OutputStreamConfig::default()
+        };
+        move_output(&first, &second, &config);
+        assert!(first.lock().unwrap().is_none());
+
+        let mut output = second.lock().unwrap();
+        let output = output.as_mut().unwrap();
+        assert_eq!(output.channels(), 1);
+        assert_eq!(output.sample_rate(), 48_000);
+        let mut buffer = [0f32; 480];
+        fill_buffer(&mut buffer, output, &mut ditherer, true, 0.0);
+        assert!(buffer.iter().any(|&sample| sample.abs() > 0.5));
+        assert_eq!(sink.len(), 1);
+    }
+
+    #[test]
+    fn latency_adds_up_device_and_sources() {
+        let (controller, source) = mixer::<f32>(2, 48_000);
+        let output: OutputSlot = Arc::new(Mutex::new(Some(UniformSourceIterator::new(
+            source, 2, 48_000,
+        ))));
+        let config = OutputStreamConfig {
+            channel_count: 2,
+            sample_rate: 48_000,
+            ..OutputStreamConfig::default()
+        };
+        let device = DeviceLatency::new(&config);
+        // what the callback records for a buffer of 480 frames
+        let callback = |output: &OutputSlot| {
+            let mut output = output.lock().unwrap();
+            let output = output.as_mut().unwrap();
+            output.nth(1);
+            device.record(Duration::from_millis(3), 960);
+            device.record_sources(Source::latency(output));
+        };
+        callback(&output);
+        assert_eq!(device.estimate(), Duration::from_millis(13));
+
+        // the mixer starts sources at the next frame
+        controller.add(SineWave::new(440.0));
+        callback(&output);
+        let plain = device.estimate();
+        assert_eq!(plain, Duration::from_millis(13));
+
+        controller.add(SineWave::new(440.0).limit(0.5));
+        callback(&output);
+        let limited = device.estimate();
+        let look_ahead = (limited - plain).as_secs_f64();
+        assert!((look_ahead - 0.005).abs() < 0.0001, "{look_ahead}");
+    }
+
+    #[test]
+    fn missing_device_is_an_error() {
+        assert!(matches!(
+            OutputStreamBuilder::default().open_stream(),
+            Err(StreamError::NoDevice)
+        ));
+        assert!(matches!(
+            OutputStreamBuilder::default().open_stream_or_fallback(),
+            Err(StreamError::NoDevice)
+        ));
+    }
+
+    #[test]
+    fn try_default_without_device() {
+        // Only meaningful on machines without audio hardware, elsewhere this merely checks
+        // that opening the default stream does not panic.
+        let has_device = cpal::default_host().default_output_device().is_some();
+        let result = OutputStream::try_default();
+        if !has_device {
+            assert!(matches!(result, Err(StreamError::NoDevice)));
+        }
+    }
+
+    #[test]
+    fn forced_sample_format_is_used_or_an_error() {
+        // Only meaningful on machines with audio hardware.
+        let Ok(builder) = OutputStreamBuilder::from_default_device() else {
+            return;
+        };
+        let device = builder.device.clone().unwrap();
+        let Ok(supported) = device.supported_output_configs() else {
+            return;
+        };
+        let supported: Vec<_> = supported.collect();
+        for format in [SampleFormat::F32, SampleFormat::I16, SampleFormat::U64] {
+            let builder = OutputStreamBuilder::from_device(device.clone())
+                .unwrap()
+                .with_sample_format(format);
+            let is_supported = supported.iter().any(|config| {
+                config.sample_format() == format
+                    && config.channels() == builder.config.channel_count
+                    && (config.min_sample_rate()..=config.max_sample_rate())
+                        .contains(&cpal::SampleRate(builder.config.sample_rate))
+            });
+            match builder.open_stream() {
+                Ok(stream) => assert_eq!(stream.sample_format(), format),
+                Err(StreamError::UnsupportedSampleFormat(rejected)) => {
+                    assert!(!is_supported);
+                    assert_eq!(rejected, format);
+                }
+                // The device may be busy.
+                Err(_) => assert!(is_supported),
+            }
+        }
+    }
+
+    #[test]
+    fn exact_rate_plays_without_resampling() {
+        // Only meaningful on machines with audio hardware.
+        let Some(device) = cpal::default_host().default_output_device() else {
+            return;
+        };
+        let Ok(supported) = device.supported_output_configs() else {
+            return;
+        };
+        let supported: Vec<_> = supported.collect();
+        for rate in [44_100, 48_000, 96_000] {
+            let is_supported = supported.iter().any(|config| {
+                (config.min_sample_rate()..=config.max_sample_rate())
+                    .contains(&cpal::SampleRate(rate))
+            });
+            match OutputStream::try_exact_rate(device.clone(), rate) {
+                Ok(stream) => {
+                    assert_eq!(stream.stream_config().sample_rate.0, rate);
+                    let sink = Sink::connect_new(&stream.mixer());
+                    let tone = vec![0.5f32; rate as usize / 10];
+                    sink.append(crate::buffer::SamplesBuffer::new(1, rate, tone));
+                    std::thread::sleep(Duration::from_millis(50));
+                    assert!(!stream.is_resampling());
+                }
+                Err(StreamError::UnsupportedSampleRate(rejected)) => {
+                    assert!(!is_supported);
+                    assert_eq!(rejected, rate);
+                }
+                // The device may be busy.
+                Err(_) => assert!(is_supported),
+            }
+        }
+    }
+
+    #[test]
+    fn fallback_keeps_dither_and_clipping() {
+        let builder = OutputStreamBuilder::default()
+            .with_dither(DitherMode::Tpdf)
+            .with_clipping(false);
+        let supported = cpal::SupportedStreamConfig::new(
+            1,
+            cpal::SampleRate(22_050),
+            cpal::SupportedBufferSize::Unknown,
+            SampleFormat::I16,
+        );
+        let fallback = builder.fallback(&supported);
+        assert_eq!(fallback.config.sample_rate, 22_050);
+        assert_eq!(fallback.config.sample_format, SampleFormat::I16);
+        assert_eq!(fallback.config.dither, DitherMode::Tpdf);
+        assert!(!fallback.config.clip);
+    }
+
+    #[test]
+    fn player_reports_open_errors() {
+        let result = Player::open_with(|| Err(StreamError::NoDevice));
+        assert!(matches!(result, Err(StreamError::NoDevice)));
+    }
+
+    #[test]
+    #[cfg(feature = "wav")]
+    fn play_once_plays_to_the_end() {
+        assert!(matches!(
+            super::play_once("assets/missing.wav"),
+            Err(super::PlayError::IoError(_))
+        ));
+        let sound = match super::play_once("assets/float64.wav") {
+            Ok(sound) => sound,
+            // Playing needs a working output device.
+            Err(super::PlayError::StreamError(_)) => return,
+            Err(err) => panic!("{err}"),
+        };
+        sound.wait();
+        assert!(sound.is_finished());
+    }
+}
@@ -0,0 +1,173 @@
+//! Applies the settings of [`OutputStreamBuilder::with_thread_name`] and
+//! [`OutputStreamBuilder::with_thread_priority`] to the thread cpal runs the callback on.
+//!
+//! [`OutputStreamBuilder::with_thread_name`]: super::OutputStreamBuilder::with_thread_name
+//! [`OutputStreamBuilder::with_thread_priority`]: super::OutputStreamBuilder::with_thread_priority
+
+use std::io;
+use std::sync::Arc;
+
+/// Real-time priority used for [`ThreadPriority::RealTime`], in the range of `SCHED_FIFO`.
+#[cfg(target_os = "linux")]
+const REALTIME_PRIORITY: i32 = 70;
+
+/// Niceness used for [`ThreadPriority::High`].
+#[cfg(target_os = "linux")]
+const HIGH_NICENESS: i32 = -10;
+
+/// Longest thread name Linux accepts, in bytes.
+#[cfg(target_os = "linux")]
+const MAX_NAME_LEN: usize = 15;
+
+/// Scheduling priority of the thread that runs the audio callback, see
+/// [`OutputStreamBuilder::with_thread_priority`](super::OutputStreamBuilder::with_thread_priority).
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum ThreadPriority {
+    /// Keeps the priority cpal gave the thread.
+    #[default]
+    Default,
+    /// Runs the thread before normal threads, but still shares the processor fairly. On Linux
+    /// it gets a niceness of -10, which usually requires `CAP_SYS_NICE` or a `nice` limit
+    /// in `limits.conf`.
+    High,
+    /// Real-time scheduling, the thread runs as soon as it is ready. On Linux this uses
+    /// `SCHED_FIFO`, which requires `CAP_SYS_NICE`, an `rtprio` limit or RealtimeKit.
+    RealTime,
+}
+
+/// Settings for the audio thread, applied the first time it calls the callback.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct AudioThread {
+    pub(crate) name: Option<Arc<str>>,
+    pub(crate) priority: ThreadPriority,
+}
+
+impl AudioThread {
+    /// Applies the settings to the calling thread. Failures are reported but otherwise
+    /// ignored, playback works either way.
+    pub(crate) fn apply(&self) {
+        if let Some(name) = &self.name {
+            if let Err(err) = set_name(name) {
+                #[cfg(feature = "tracing")]
+                tracing::warn!("could not name the audio thread: {err}");
+                #[cfg(not(feature = "tracing"))]
+                eprintln!("could not name the audio thread: {err}");
+            }
+        }
+        if let Err(err) = set_priority(self.priority) {
+            #[cfg(feature = "tracing")]
+            tracing::warn!("could not set the priority of the audio thread: {err}");
+            #[cfg(not(feature = "tracing"))]
+            eprintln!("could not set the priority of the audio thread: {err}");
+        }
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn set_name(name: &str) -> io::Result<()> {
+    // Longer names are rejected, cut them at a character boundary instead.
+    let mut len = name.len().min(MAX_NAME_LEN);
+    while !name.is_char_boundary(len) {
+        len -= 1;
+    }
+    let name = std::ffi::CString::new(&name[..len])
+        .map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, "name contains a nul byte"))?;
+    // SAFETY: `name` is a nul terminated string that outlives the call.
+    let result = unsafe { libc::pthread_setname_np(libc::pthread_self(), name.as_ptr()) };
+    match result {
+        0 => Ok(()),
+        errno => Err(io::Error::from_raw_os_error(errno)),
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+fn set_name(_name: &str) -> io::Result<()> {
+    Err(io::Error::new(
+        io::ErrorKind::Unsupported,
+        "naming the audio thread is only supported on Linux",
+    ))
+}
+
+#[cfg(target_os = "linux")]
+fn set_priority(priority: ThreadPriority) -> io::Result<()> {
+    match priority {
+        ThreadPriority::Default => Ok(()),
+        ThreadPriority::High => {
+            // On Linux the niceness of a thread can be set on its own through its id.
+            // SAFETY: both calls only take plain values.
+            let result = unsafe {
+                libc::setpriority(
+                    libc::PRIO_PROCESS,
+                    libc::gettid() as libc::id_t,
+                    HIGH_NICENESS,
+                )
+            };
+            match result {
+                0 => Ok(()),
+                _ => Err(io::Error::last_os_error()),
+            }
+        }
+        ThreadPriority::RealTime => {
+            let param = libc::sched_param {
+                sched_priority: REALTIME_PRIORITY,
+            };
+            // SAFETY: `param` is a valid `sched_param` that outlives the call.
+            let result = unsafe {
+                libc::pthread_setschedparam(libc::pthread_self(), libc::SCHED_FIFO, &param)
+            };
+            match result {
+                0 => Ok(()),
+                errno => Err(io::Error::from_raw_os_error(errno)),
+            }
+        }
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+fn set_priority(priority: ThreadPriority) -> io::Result<()> {
+    match priority {
+        ThreadPriority::Default => Ok(()),
+        _ => Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "setting the priority of the audio thread is only supported on Linux",
+        )),
+    }
+}
+
+#[cfg(all(test, target_os = "linux"))]
+mod tests {
+    use super::{AudioThread, ThreadPriority};
+
+    fn current_name() -> String {
+        let mut name = [0u8; 16];
+        // SAFETY: the buffer is large enough for any thread name and its terminator.
+        let result = unsafe {
+            libc::pthread_getname_np(libc::pthread_self(), name.as_mut_ptr().cast(), name.len())
+        };
+        assert_eq!(result, 0);
+        let len = name.iter().position(|&b| b == 0).unwrap();
+        String::from_utf8(name[..len].to_vec()).unwrap()
+    }
+
+    #[test]
+    fn names_the_thread() {
+        std::thread::spawn(|| {
+            let settings = AudioThread {
+                name: Some("rodio-audio".into()),
+                priority: ThreadPriority::Default,
+            };
+            settings.apply();
+            assert_eq!(current_name(), "rodio-audio");
+
+            // names are cut to what Linux allows
+            let settings = AudioThread {
+                name: Some("rodio-audio-output-thread".into()),
+                ..AudioThread::default()
+            };
+            settings.apply();
+            assert_eq!(current_name(), "rodio-audio-out");
+        })
+        .join()
+        .unwrap();
+    }
+}
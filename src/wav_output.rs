@@ -1,6 +1,9 @@
 use crate::{ChannelCount, Sample, Source};
-use hound::{SampleFormat, WavSpec};
+use hound::{SampleFormat, WavSpec, WavWriter};
+use std::fs::File;
+use std::io::BufWriter;
 use std::path;
+use std::time::Duration;
 
 /// This procedure saves Source's output into a wav file. The output samples format is 32-bit float.
 /// This function is intended primarily for testing and diagnostics. It can be used to see
@@ -9,23 +12,101 @@ pub fn output_to_wav<S: Sample>(
     source: &mut impl Source<Item = S>,
     wav_file: impl AsRef<path::Path>,
 ) -> Result<(), Box<dyn std::error::Error>> {
+    let mut writer = create_writer(source, wav_file)?;
+    write_samples(&mut writer, source)?;
+    writer.finalize()?;
+    Ok(())
+}
+
+fn create_writer<S: Sample>(
+    source: &impl Source<Item = S>,
+    wav_file: impl AsRef<path::Path>,
+) -> Result<WavWriter<BufWriter<File>>, hound::Error> {
     let format = WavSpec {
         channels: source.channels() as ChannelCount,
         sample_rate: source.sample_rate(),
         bits_per_sample: 32,
         sample_format: SampleFormat::Float,
     };
-    let mut writer = hound::WavWriter::create(wav_file, format)?;
+    hound::WavWriter::create(wav_file, format)
+}
+
+fn write_samples<S: Sample>(
+    writer: &mut WavWriter<BufWriter<File>>,
+    source: &mut impl Source<Item = S>,
+) -> Result<(), hound::Error> {
     for sample in source {
         writer.write_sample(sample.to_f32())?;
     }
+    Ok(())
+}
+
+/// Saves the part of `source` between `start` and `end` into a wav file, for example a region
+/// selected in an editor. Seeks to `start` and writes until `end` or until the source ends,
+/// with the sample rate and channel count of the source, see [`output_to_wav`].
+///
+/// Fails if the source can not seek to `start`. Nothing but the header is written if `end` is
+/// not after `start`.
+pub fn export_region<S: Sample>(
+    mut source: impl Source<Item = S>,
+    start: Duration,
+    end: Duration,
+    wav_file: impl AsRef<path::Path>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    source.try_seek(start)?;
+    let mut region = source.take_duration(end.saturating_sub(start));
+    output_to_wav(&mut region, wav_file)
+}
+
+/// Saves the part of `source` between `start` and `end` into a wav file like [`export_region`],
+/// fading the clip in over `fade_in` and out over `fade_out` so that its edges do not click.
+///
+/// The first frame of the clip and the last one, at `end` or where the source ends if it
+/// reports its duration, are silent and the gain rises linearly to full volume over the fade
+/// lengths, see [`Source::linear_gain_ramp`]. Fades longer than the clip overlap. Assumes
+/// that the channel count and sample rate of the source do not change within the clip.
+pub fn export_region_with_fades<S: Sample>(
+    mut source: impl Source<Item = S>,
+    start: Duration,
+    end: Duration,
+    fade_in: Duration,
+    fade_out: Duration,
+    wav_file: impl AsRef<path::Path>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    source.try_seek(start)?;
+    let end = source
+        .total_duration()
+        .map_or(end, |duration| end.min(duration));
+    let length = end.saturating_sub(start);
+    let frame = Duration::from_secs(1) / source.sample_rate().max(1);
+
+    // A ramp can not be empty, a zero length fade is a ramp that keeps the gain at one.
+    let ramp = |duration: Duration| {
+        let gain = if duration.is_zero() { 1.0 } else { 0.0 };
+        (duration.max(frame), gain)
+    };
+    let (fade_in, silent_start) = ramp(fade_in);
+    let (fade_out, silent_end) = ramp(fade_out);
+
+    let mut writer = create_writer(&source, wav_file)?;
+    // The fade out starts a frame early so that the last frame is the one that is silent.
+    let mut head = source
+        .take_duration(length)
+        .linear_gain_ramp(fade_in, silent_start, 1.0, false)
+        .take_duration(length.saturating_sub(fade_out + frame));
+    write_samples(&mut writer, &mut head)?;
+    let mut tail = head
+        .into_inner()
+        .linear_gain_ramp(fade_out, 1.0, silent_end, true);
+    write_samples(&mut writer, &mut tail)?;
     writer.finalize()?;
     Ok(())
 }
 
 #[cfg(test)]
 mod test {
-    use super::output_to_wav;
+    use super::{export_region, export_region_with_fades, output_to_wav};
+    use crate::buffer::SamplesBuffer;
     use crate::common::ChannelCount;
     use crate::Source;
     use std::io::BufReader;
@@ -56,4 +137,58 @@ mod test {
             "wav samples do not match the source"
         );
     }
+
+    #[test]
+    fn test_export_region() {
+        // a ramp so every sample can be told apart, 3 seconds of stereo at 1 kHz
+        let samples: Vec<f32> = (0..6000).map(|i| i as f32 / 6000.0).collect();
+        let source = SamplesBuffer::new(2, 1000, samples.clone());
+        let wav_file_path = "target/tmp/export-region-test.wav";
+        export_region(
+            source,
+            Duration::from_millis(1500),
+            Duration::from_millis(2500),
+            wav_file_path,
+        )
+        .expect("region can be exported");
+
+        let file = std::fs::File::open(wav_file_path).expect("output file can be opened");
+        let mut reader =
+            hound::WavReader::new(BufReader::new(file)).expect("wav file can be read back");
+        assert_eq!(reader.spec().sample_rate, 1000);
+        assert_eq!(reader.spec().channels, 2);
+        let actual_samples: Vec<f32> = reader.samples::<f32>().map(|x| x.unwrap()).collect();
+        assert_eq!(actual_samples, samples[3000..5000]);
+    }
+
+    #[test]
+    fn test_export_region_with_fades() {
+        // 3 seconds of a constant level at 1 kHz, mono
+        let source = SamplesBuffer::new(1, 1000, vec![1.0f32; 3000]);
+        let wav_file_path = "target/tmp/export-region-fades-test.wav";
+        export_region_with_fades(
+            source,
+            Duration::from_secs(1),
+            Duration::from_secs(2),
+            Duration::from_millis(100),
+            Duration::from_millis(200),
+            wav_file_path,
+        )
+        .expect("region can be exported");
+
+        let file = std::fs::File::open(wav_file_path).expect("output file can be opened");
+        let mut reader =
+            hound::WavReader::new(BufReader::new(file)).expect("wav file can be read back");
+        let clip: Vec<f32> = reader.samples::<f32>().map(|x| x.unwrap()).collect();
+        assert_eq!(clip.len(), 1000);
+
+        // rises over the first 100 frames and falls over the last 200
+        assert_eq!(clip[0], 0.0);
+        assert!((clip[50] - 0.5).abs() < 1e-6);
+        assert!(clip[100..800].iter().all(|&s| s == 1.0));
+        assert!((clip[899] - 0.5).abs() < 1e-6);
+        assert_eq!(clip[999], 0.0);
+        assert!(clip[..100].windows(2).all(|w| w[0] < w[1]));
+        assert!(clip[800..].windows(2).all(|w| w[0] > w[1]));
+    }
 }
@@ -0,0 +1,31 @@
+#![cfg(feature = "symphonia-mp3")]
+
+use std::error::Error;
+use std::io::Cursor;
+
+use rodio::decoder::DecoderError;
+use rodio::Decoder;
+
+#[test]
+fn truncated_mp3_names_format_and_cause() {
+    let data = std::fs::read("assets/music.mp3").unwrap();
+    let truncated = data[..64].to_vec();
+
+    let error = Decoder::new_mp3(Cursor::new(truncated)).err().unwrap();
+    assert!(matches!(error, DecoderError::Backend { format: "MP3", .. }));
+    assert!(error.to_string().contains("MP3"), "{error}");
+    assert!(error.source().is_some());
+}
+
+#[test]
+fn detecting_the_format_chains_the_cause() {
+    let error = Decoder::new(Cursor::new(vec![0u8; 100])).err().unwrap();
+    assert!(matches!(
+        error,
+        DecoderError::Backend {
+            format: "unknown",
+            ..
+        }
+    ));
+    assert!(error.source().is_some());
+}
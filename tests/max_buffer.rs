@@ -0,0 +1,32 @@
+#![cfg(any(feature = "symphonia-mp3", feature = "wav"))]
+
+use std::fs::File;
+use std::io::BufReader;
+
+use rodio::Decoder;
+
+fn decode(path: &str, max_buffer: Option<usize>) -> Vec<rodio::decoder::DecoderSample> {
+    // a small reader buffer so that the data really arrives in small pieces
+    let file = BufReader::with_capacity(512, File::open(path).unwrap());
+    match max_buffer {
+        Some(bytes) => Decoder::with_max_buffer(file, bytes).unwrap().collect(),
+        None => Decoder::new(file).unwrap().collect(),
+    }
+}
+
+#[cfg(feature = "symphonia-mp3")]
+#[test]
+fn capped_mp3_decodes_the_same() {
+    let expected = decode("assets/music.mp3", None);
+    assert!(!expected.is_empty());
+    assert_eq!(decode("assets/music.mp3", Some(1024)), expected);
+    assert_eq!(decode("assets/music.mp3", Some(100_000)), expected);
+}
+
+#[cfg(feature = "wav")]
+#[test]
+fn capped_wav_decodes_the_same() {
+    let expected = decode("assets/music.wav", None);
+    assert!(!expected.is_empty());
+    assert_eq!(decode("assets/music.wav", Some(1024)), expected);
+}
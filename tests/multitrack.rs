@@ -0,0 +1,44 @@
+#![cfg(all(feature = "symphonia-aac", feature = "symphonia-isomp4"))]
+use std::fs::File;
+use std::io::BufReader;
+
+use rodio::decoder::{DecoderError, DecoderSample};
+use rodio::{Decoder, Source};
+
+// Holds the first second of `music.m4a` followed by a track with the first 1.3 seconds of
+// `RL.m4a`.
+fn open() -> BufReader<File> {
+    BufReader::new(File::open("assets/multitrack.m4a").unwrap())
+}
+
+#[test]
+fn lists_all_audio_tracks() {
+    let tracks = Decoder::new(open()).unwrap().tracks();
+    assert_eq!(tracks.len(), 2);
+    assert_ne!(tracks[0].id, tracks[1].id);
+    assert_ne!(tracks[0].total_duration, tracks[1].total_duration);
+}
+
+#[test]
+fn plays_the_selected_track() {
+    let default = Decoder::new(open()).unwrap();
+    let tracks = default.tracks();
+    assert_eq!(default.total_duration(), tracks[0].total_duration);
+
+    let selected = Decoder::new_with_track(open(), tracks[1].id).unwrap();
+    assert_eq!(selected.total_duration(), tracks[1].total_duration);
+    assert_ne!(selected.total_duration(), default.total_duration());
+
+    let default: Vec<DecoderSample> = default.collect();
+    let selected: Vec<DecoderSample> = selected.collect();
+    assert!(selected.len() > default.len());
+    assert_ne!(default[..], selected[..default.len()]);
+}
+
+#[test]
+fn unknown_track_is_an_error() {
+    assert!(matches!(
+        Decoder::new_with_track(open(), 42),
+        Err(DecoderError::UnknownTrack(42))
+    ));
+}
@@ -60,6 +60,42 @@ fn seek_returns_err_if_unsupported(
     assert_eq!(res.is_ok(), supports_seek, "decoder: {decoder_name}");
 }
 
+#[apply(all_decoders)]
+#[trace]
+fn decoding_a_range_needs_seeking(
+    #[case] format: &'static str,
+    #[case] supports_seek: bool,
+    #[case] decoder_name: &'static str,
+) {
+    let asset = Path::new("assets/music").with_extension(format);
+    let file = BufReader::new(std::fs::File::open(asset).unwrap());
+    let res = Decoder::new_range(file, Duration::from_secs(1), Duration::from_secs(2));
+    assert_eq!(res.is_ok(), supports_seek, "decoder: {decoder_name}");
+}
+
+#[apply(supported_decoders)]
+#[trace]
+fn decoded_range_has_expected_duration(
+    #[case] format: &'static str,
+    #[case] decoder_name: &'static str,
+) {
+    let asset = Path::new("assets/music").with_extension(format);
+    let file = BufReader::new(std::fs::File::open(asset).unwrap());
+    let start = Duration::from_millis(1500);
+    let end = Duration::from_millis(4000);
+    let range = Decoder::new_range(file, start, end).unwrap();
+    assert_eq!(range.total_duration(), Some(end - start));
+
+    let rate = range.sample_rate() as f64;
+    let channels = range.channels() as f64;
+    let played = Duration::from_secs_f64(range.count() as f64 / rate / channels);
+    let expected = end - start;
+    assert!(
+        played.abs_diff(expected) < Duration::from_millis(1),
+        "decoder: {decoder_name}, played {played:?}"
+    );
+}
+
 #[apply(supported_decoders)]
 #[trace]
 fn seek_beyond_end_saturates(#[case] format: &'static str, #[case] decoder_name: &'static str) {
@@ -161,6 +197,69 @@ fn seek_does_not_break_channel_order(
     }
 }
 
+#[cfg(all(feature = "wav", not(feature = "symphonia-wav")))]
+#[test]
+fn seek_samples_is_exact_for_wav() {
+    let samples: Vec<_> = get_music("wav").collect();
+    let mut source = get_music("wav");
+    let channels = source.channels() as usize;
+
+    // seek from the second channel to check channel order is kept as well
+    source.next();
+    assert_eq!(source.total_samples(), Some(samples.len() as u64));
+    for frame in [0, 1, 4410, 12345, 44099] {
+        source.try_seek_samples(frame).unwrap();
+        let offset = frame as usize * channels;
+        let expected = &samples[offset + 1..offset + 11];
+        assert_eq!(source.by_ref().take(10).collect::<Vec<_>>(), expected);
+    }
+}
+
+#[test]
+fn seek_samples_is_exact_for_pcm() {
+    use rodio::decoder::SampleFormat;
+
+    // 3 channels at an odd sample rate where durations do not map to whole samples
+    let bytes: Vec<u8> = (0..3000u16).flat_map(|i| i.to_le_bytes()).collect();
+    let open = || Decoder::new_pcm(&bytes[..], 44_099, 3, SampleFormat::I16Le);
+    let samples: Vec<_> = open().unwrap().collect();
+    let mut source = open().unwrap();
+    // the data can only be read so seeking goes forward
+    for frame in [1, 7, 333, 999] {
+        source.try_seek_samples(frame).unwrap();
+        let offset = frame as usize * 3;
+        assert_eq!(
+            source.by_ref().take(3).collect::<Vec<_>>(),
+            samples[offset..offset + 3]
+        );
+    }
+}
+
+#[cfg(all(feature = "wav", not(feature = "symphonia-wav")))]
+#[test]
+fn seeking_reaches_the_decoder_through_effects() {
+    let samples: Vec<_> = get_music("wav").collect();
+    let channels = get_music("wav").channels() as usize;
+    let mut chain = get_music("wav").amplify(0.5).low_pass(1000);
+
+    // seeking the chain seeks the decoder at its root
+    chain.try_seek(Duration::from_secs(1)).unwrap();
+    assert_eq!(chain.inner().inner().tracks().len(), 1);
+
+    // the decoder can be reached to use what only it offers
+    chain
+        .inner_mut()
+        .inner_mut()
+        .try_seek_samples(4410)
+        .unwrap();
+    let mut decoder = chain.into_inner().into_inner();
+    let offset = 4410 * channels;
+    assert_eq!(
+        decoder.by_ref().take(10).collect::<Vec<_>>(),
+        samples[offset..offset + 10]
+    );
+}
+
 fn second_channel_beep_range<R: rodio::Source>(source: &mut R) -> std::ops::Range<usize>
 where
     R: Iterator<Item = f32>,
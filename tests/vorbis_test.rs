@@ -0,0 +1,35 @@
+#![cfg(all(feature = "vorbis", not(feature = "symphonia-vorbis")))]
+
+use std::io::Cursor;
+
+use rodio::{Decoder, Source};
+
+// Reads the channel count of every sample along with it.
+fn decode(data: Vec<u8>) -> Vec<(u16, rodio::decoder::DecoderSample)> {
+    let mut decoder = Decoder::new(Cursor::new(data)).unwrap();
+    let mut samples = Vec::new();
+    loop {
+        let channels = decoder.channels();
+        match decoder.next() {
+            Some(sample) => samples.push((channels, sample)),
+            None => return samples,
+        }
+    }
+}
+
+#[test]
+fn chained_streams_are_all_played() {
+    // a mono stream followed by a stereo one, both at 44.1 kHz
+    let first = std::fs::read("assets/beep3.ogg").unwrap();
+    let second = std::fs::read("assets/RL.ogg").unwrap();
+    let mono = decode(first.clone());
+    let stereo = decode(second.clone());
+    assert!(mono.iter().all(|&(channels, _)| channels == 1));
+    assert!(stereo.iter().all(|&(channels, _)| channels == 2));
+
+    let chained = decode([first, second].concat());
+    assert_eq!(chained.len(), mono.len() + stereo.len());
+    let (head, tail) = chained.split_at(mono.len());
+    assert!(head == mono, "first stream differs");
+    assert!(tail == stereo, "second stream differs");
+}